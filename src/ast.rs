@@ -1,13 +1,26 @@
-use crate::scanner::KeywordType;
+use crate::scanner::{KeywordType, Location};
+use crate::types::{Member, Type};
 use log::debug;
 
 /// Enum for the types of binary operations supported.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum BinaryOpType {
     Add,
     Sub,
     Mul,
     Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    LogAnd,
+    LogOr,
 }
 
 impl BinaryOpType {
@@ -31,40 +44,583 @@ impl BinaryOpType {
                 debug!("Creating BinaryOpType::Div from KeywordType::Div");
                 Some(BinaryOpType::Div)
             }
+            KeywordType::Mod => {
+                debug!("Creating BinaryOpType::Mod from KeywordType::Mod");
+                Some(BinaryOpType::Mod)
+            }
+            KeywordType::And => {
+                debug!("Creating BinaryOpType::BitAnd from KeywordType::And");
+                Some(BinaryOpType::BitAnd)
+            }
+            KeywordType::Or => {
+                debug!("Creating BinaryOpType::BitOr from KeywordType::Or");
+                Some(BinaryOpType::BitOr)
+            }
+            KeywordType::Xor => {
+                debug!("Creating BinaryOpType::BitXor from KeywordType::Xor");
+                Some(BinaryOpType::BitXor)
+            }
+            KeywordType::Shl => {
+                debug!("Creating BinaryOpType::Shl from KeywordType::Shl");
+                Some(BinaryOpType::Shl)
+            }
+            KeywordType::Shr => {
+                debug!("Creating BinaryOpType::Shr from KeywordType::Shr");
+                Some(BinaryOpType::Shr)
+            }
+            KeywordType::Eq => {
+                debug!("Creating BinaryOpType::Eq from KeywordType::Eq");
+                Some(BinaryOpType::Eq)
+            }
+            KeywordType::Ne => {
+                debug!("Creating BinaryOpType::Ne from KeywordType::Ne");
+                Some(BinaryOpType::Ne)
+            }
+            KeywordType::Lt => {
+                debug!("Creating BinaryOpType::Lt from KeywordType::Lt");
+                Some(BinaryOpType::Lt)
+            }
+            KeywordType::Le => {
+                debug!("Creating BinaryOpType::Le from KeywordType::Le");
+                Some(BinaryOpType::Le)
+            }
+            KeywordType::LogAnd => {
+                debug!("Creating BinaryOpType::LogAnd from KeywordType::LogAnd");
+                Some(BinaryOpType::LogAnd)
+            }
+            KeywordType::LogOr => {
+                debug!("Creating BinaryOpType::LogOr from KeywordType::LogOr");
+                Some(BinaryOpType::LogOr)
+            }
             _ => None,
         }
     }
+
+    /// Return true if the operation is a comparison yielding 0 or 1.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOpType::Eq | BinaryOpType::Ne | BinaryOpType::Lt | BinaryOpType::Le
+        )
+    }
+
+    /// Return the C spelling of the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOpType::Add => "+",
+            BinaryOpType::Sub => "-",
+            BinaryOpType::Mul => "*",
+            BinaryOpType::Div => "/",
+            BinaryOpType::Mod => "%",
+            BinaryOpType::BitAnd => "&",
+            BinaryOpType::BitOr => "|",
+            BinaryOpType::BitXor => "^",
+            BinaryOpType::Shl => "<<",
+            BinaryOpType::Shr => ">>",
+            BinaryOpType::Eq => "==",
+            BinaryOpType::Ne => "!=",
+            BinaryOpType::Lt => "<",
+            BinaryOpType::Le => "<=",
+            BinaryOpType::LogAnd => "&&",
+            BinaryOpType::LogOr => "||",
+        }
+    }
+
+    /// Return true if the operation is `&&` or `||`.
+    pub fn is_logical(&self) -> bool {
+        matches!(self, BinaryOpType::LogAnd | BinaryOpType::LogOr)
+    }
+
+    /// Return true if the operation is a shift, whose operands are promoted
+    /// separately instead of being converted to a common type.
+    pub fn is_shift(&self) -> bool {
+        matches!(self, BinaryOpType::Shl | BinaryOpType::Shr)
+    }
+}
+
+/// Enum for the types of unary operations supported.
+#[derive(Debug, Clone)]
+pub enum UnaryOpType {
+    Neg,
+    BitNot,
+    Not,
+    Addr,
+    Deref,
 }
 
-/// Enum to represent nodes in an AST.
-/// Nodes can be numbers or binary operations.
+impl UnaryOpType {
+    /// Function to create UnaryOpType from KeywordType.
+    /// Return None if the keyword does not match any UnaryOpType.
+    pub fn from_keyword(keyword: &KeywordType) -> Option<UnaryOpType> {
+        match keyword {
+            KeywordType::Sub => {
+                debug!("Creating UnaryOpType::Neg from KeywordType::Sub");
+                Some(UnaryOpType::Neg)
+            }
+            KeywordType::Tilde => {
+                debug!("Creating UnaryOpType::BitNot from KeywordType::Tilde");
+                Some(UnaryOpType::BitNot)
+            }
+            KeywordType::Not => {
+                debug!("Creating UnaryOpType::Not from KeywordType::Not");
+                Some(UnaryOpType::Not)
+            }
+            KeywordType::And => {
+                debug!("Creating UnaryOpType::Addr from KeywordType::And");
+                Some(UnaryOpType::Addr)
+            }
+            KeywordType::Mul => {
+                debug!("Creating UnaryOpType::Deref from KeywordType::Mul");
+                Some(UnaryOpType::Deref)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the C spelling of the operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOpType::Neg => "-",
+            UnaryOpType::BitNot => "~",
+            UnaryOpType::Not => "!",
+            UnaryOpType::Addr => "&",
+            UnaryOpType::Deref => "*",
+        }
+    }
+}
+
+/// A local variable of a function.
 #[derive(Debug)]
-pub enum Node {
+pub struct Var {
+    pub name: String,
+    pub ty: Type,
+    /// where the variable is declared, the default location for
+    /// the ones the compiler makes
+    pub location: Location,
+}
+
+/// The kind of format string a function takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatKind {
+    Printf,
+    Scanf,
+}
+
+/// The format string parameter of a function like `printf`, from the
+/// attribute `format(kind, string, first)` or known of the C library.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Format {
+    pub kind: FormatKind,
+    /// the index of the format string among the arguments, from 0
+    pub string: usize,
+    /// the index of the first argument converted by the format,
+    /// None if they are passed as a `va_list`
+    pub first: Option<usize>,
+}
+
+/// Enum to represent the kinds of nodes in an AST.
+/// Expressions carry their C type in `Node::ty`, statements don't.
+#[derive(Debug, Clone)]
+pub enum NodeKind {
     NUM(i64),
+    /// a local variable, the index into `Function::locals`
+    VAR(usize),
+    /// a global variable or a string literal, by its symbol name
+    GVAR(String),
     BINOP {
         op: BinaryOpType,
         lhs: Box<Node>,
         rhs: Box<Node>,
     },
+    UNOP {
+        op: UnaryOpType,
+        operand: Box<Node>,
+    },
+    ASSIGN {
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+    },
+    /// a cast of the operand to the type of the node written in the source
+    CAST(Box<Node>),
+    /// an implicit conversion of the operand to the type of the node,
+    /// inserted where C converts a value without a cast
+    CONV(Box<Node>),
+    /// access to a member of a struct or union
+    MEMBER {
+        base: Box<Node>,
+        member: Member,
+    },
+    /// a call to the function designated by `func`,
+    /// a function or a pointer to a function,
+    /// a returned struct or union is stored in the local `ret_buffer`,
+    /// `noreturn` is true if the function is known never to return,
+    /// `format` tells where its format string is if it takes one
+    CALL {
+        func: Box<Node>,
+        args: Vec<Node>,
+        ret_buffer: Option<usize>,
+        noreturn: bool,
+        format: Option<Format>,
+    },
+    /// an expression statement
+    STMT(Box<Node>),
+    /// a compound literal: the statement `init` initializing the anonymous
+    /// local `var`, which is the value of the expression
+    LITERAL {
+        init: Box<Node>,
+        var: Box<Node>,
+    },
+    /// `va_start`: initialize the `va_list` the operand points to
+    /// for the variadic arguments of the function
+    VASTART(Box<Node>),
+    /// `va_arg`: the address of the next variadic argument of the `va_list`
+    /// the operand points to, which moves past it
+    VAARG(Box<Node>),
+    /// fill the object designated by the lvalue with zero bytes
+    MEMZERO(Box<Node>),
+    /// `__builtin_unreachable()`: the program promises not to get there
+    UNREACHABLE,
+    /// a `return` statement, with the returned value unless the function returns `void`
+    RETURN(Option<Box<Node>>),
+    BLOCK(Vec<Node>),
+    IF {
+        cond: Box<Node>,
+        then: Box<Node>,
+        els: Option<Box<Node>>,
+    },
+    /// `for` and `while` loops, a `while` has neither `init` nor `inc`
+    FOR {
+        init: Option<Box<Node>>,
+        cond: Option<Box<Node>>,
+        inc: Option<Box<Node>>,
+        body: Box<Node>,
+    },
+    /// a `switch` with the values of its `case` labels
+    SWITCH {
+        cond: Box<Node>,
+        body: Box<Node>,
+        cases: Vec<i64>,
+        has_default: bool,
+    },
+    /// a `case` label, the index into the cases of the enclosing switch,
+    /// or the `default` label
+    CASE {
+        index: Option<usize>,
+        body: Box<Node>,
+    },
+    BREAK,
+    CONTINUE,
+    /// a `goto` to the label of the function
+    GOTO(String),
+    /// a statement after a label
+    LABEL {
+        name: String,
+        body: Box<Node>,
+    },
+}
+
+/// A node in an AST.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub ty: Option<Type>,
+    /// where the node is in the source: the operator of an operation,
+    /// the name of a variable or the first token of a statement
+    pub location: Location,
+    /// true if the expression is written in brackets, which tells that
+    /// the precedence of its operators is not mistaken
+    pub parenthesized: bool,
 }
 
 impl Node {
-    /// Function to create a Node from a number.
+    /// Function to create a Node from its kind and type.
+    pub fn new(kind: NodeKind, ty: Option<Type>) -> Node {
+        Node {
+            kind,
+            ty,
+            location: Location::default(),
+            parenthesized: false,
+        }
+    }
+
+    /// Return the node located at `location`.
+    pub fn at(mut self, location: Location) -> Node {
+        self.location = location;
+        self
+    }
+
+    /// Function to create a Node from an `int` number.
     pub fn from_num(num: i64) -> Node {
-        debug!("Creating Node::NUM from i64: {}", num);
-        Self::NUM(num)
+        Self::from_typed_num(num, Type::Int)
+    }
+
+    /// Function to create a Node from a number of the integer type `ty`.
+    pub fn from_typed_num(num: i64, ty: Type) -> Node {
+        debug!("Creating NodeKind::NUM from i64: {} of type {:?}", num, ty);
+        Self::new(NodeKind::NUM(num), Some(ty))
+    }
+
+    /// Function to create a Node referring to the global variable `name`.
+    pub fn from_gvar(name: String, ty: Type) -> Node {
+        debug!("Creating NodeKind::GVAR from {} of type {:?}", name, ty);
+        Self::new(NodeKind::GVAR(name), Some(ty))
+    }
+
+    /// Function to create a Node implicitly converting `node` to `ty`,
+    /// located where `node` is.
+    /// No node is created if `node` already has the type.
+    pub fn from_cast(node: Node, ty: Type) -> Node {
+        if *node.get_type() == ty {
+            return node;
+        }
+        debug!("Creating NodeKind::CONV of {:?} to {:?}", node, ty);
+        let location = node.location;
+        Self::new(NodeKind::CONV(Box::new(node)), Some(ty)).at(location)
+    }
+
+    /// Function to create a Node referring to the local variable `id`.
+    pub fn from_var(id: usize, ty: Type) -> Node {
+        debug!("Creating NodeKind::VAR from local {} of type {:?}", id, ty);
+        Self::new(NodeKind::VAR(id), Some(ty))
+    }
+
+    /// Function to create a Node from a cast expression `(ty)node`.
+    /// Unlike `from_cast` the node is always created, as the result of a
+    /// cast is not an lvalue even if the type doesn't change.
+    /// Whether the operand can be cast to `ty` is checked by the type checker.
+    pub fn from_explicit_cast(node: Node, ty: Type) -> Node {
+        debug!("Creating NodeKind::CAST of {:?} to {:?}", node, ty);
+        Self::new(NodeKind::CAST(Box::new(node)), Some(ty))
     }
 
     /// Function to create a Node from a binary operation.
+    ///
+    /// Integer operands are converted to their common type by the usual
+    /// arithmetic conversions, except for shifts where each operand is promoted
+    /// on its own. Comparisons, `&&` and `||` are `int`, otherwise the common
+    /// type, or a pointer or array on the left hand side, decides the type.
+    /// Whether the operands are valid is checked by the type checker.
     pub fn from_binop(op: BinaryOpType, lhs: Node, rhs: Node) -> Node {
         debug!(
-            "Creating Node::BINOP with op: {:?}, lhs: {:?}, rhs: {:?}",
+            "Creating NodeKind::BINOP with op: {:?}, lhs: {:?}, rhs: {:?}",
             op, lhs, rhs
         );
-        Self::BINOP {
-            op,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
+        let (lhs, rhs, ty) = if op.is_logical() {
+            (lhs, rhs, Type::Int)
+        } else if !lhs.get_type().is_integer() || !rhs.get_type().is_integer() {
+            let ty = if op.is_comparison() {
+                Type::Int
+            } else {
+                lhs.get_type().decay()
+            };
+            (lhs, rhs, ty)
+        } else if op.is_shift() {
+            let (lhs_ty, rhs_ty) = (lhs.promoted_type(), rhs.promoted_type());
+            let lhs = Self::from_cast(lhs, lhs_ty.clone());
+            (lhs, Self::from_cast(rhs, rhs_ty), lhs_ty)
+        } else {
+            let common = Type::usual_arith(&lhs.promoted_type(), &rhs.promoted_type());
+            let ty = if op.is_comparison() {
+                Type::Int
+            } else {
+                common.clone()
+            };
+            let lhs = Self::from_cast(lhs, common.clone());
+            (lhs, Self::from_cast(rhs, common), ty)
+        };
+        Self::new(
+            NodeKind::BINOP {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            },
+            Some(ty),
+        )
+    }
+
+    /// Function to create a Node from a unary operation.
+    /// Whether the operand is valid is checked by the type checker,
+    /// except for a dereference whose type is the one pointed to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a non-pointer is dereferenced.
+    pub fn from_unop(op: UnaryOpType, operand: Node) -> Node {
+        debug!(
+            "Creating NodeKind::UNOP with op: {:?}, operand: {:?}",
+            op, operand
+        );
+        let (operand, ty) = match op {
+            UnaryOpType::Neg | UnaryOpType::BitNot if operand.get_type().is_integer() => {
+                let ty = operand.promoted_type();
+                (Self::from_cast(operand, ty.clone()), ty)
+            }
+            UnaryOpType::Neg | UnaryOpType::BitNot => {
+                let ty = operand.get_type().decay();
+                (operand, ty)
+            }
+            UnaryOpType::Not => (operand, Type::Int),
+            UnaryOpType::Addr => {
+                let ty = Type::pointer_to(operand.get_type().clone());
+                (operand, ty)
+            }
+            // a function designator decays to a pointer, `*f` designates f again
+            UnaryOpType::Deref => match operand.get_type().decay() {
+                Type::Ptr(base) => (operand, *base),
+                _ => panic!("from_unop: invalid pointer dereference {:?}", operand),
+            },
+        };
+        Self::new(
+            NodeKind::UNOP {
+                op,
+                operand: Box::new(operand),
+            },
+            Some(ty),
+        )
+    }
+
+    /// Function to create a Node from an assignment.
+    /// A scalar right hand side is converted to the type of the left hand side.
+    /// Whether the left hand side can be assigned the right hand side
+    /// is checked by the type checker.
+    pub fn from_assign(lhs: Node, rhs: Node) -> Node {
+        debug!(
+            "Creating NodeKind::ASSIGN with lhs: {:?}, rhs: {:?}",
+            lhs, rhs
+        );
+        let ty = lhs.get_type().clone();
+        let rhs = if ty.is_scalar() && rhs.get_type().decay().is_scalar() {
+            Self::from_cast(rhs, ty.clone())
+        } else {
+            rhs
+        };
+        Self::new(
+            NodeKind::ASSIGN {
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            },
+            Some(ty),
+        )
+    }
+
+    /// Function to create a Node accessing the member `name` of `base`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base` is not a complete struct or union with such a member.
+    pub fn from_member(base: Node, name: &str) -> Node {
+        debug!("Creating NodeKind::MEMBER {} of {:?}", name, base);
+        let member = match base.get_type() {
+            Type::Record(record) => match record.borrow().find_member(name) {
+                Some(member) => member.clone(),
+                None => panic!("from_member: no member {} in {:?}", name, record),
+            },
+            ty => panic!("from_member: {:?} is not a struct nor a union", ty),
+        };
+        let ty = member.ty.clone();
+        Self::new(
+            NodeKind::MEMBER {
+                base: Box::new(base),
+                member,
+            },
+            Some(ty),
+        )
+    }
+
+    /// Return the type of an integer expression after the integer promotions.
+    /// A bit-field narrower than `int` promotes to `int`, whatever its type.
+    pub fn promoted_type(&self) -> Type {
+        match self.kind {
+            NodeKind::MEMBER { ref member, .. } => match member.bit_field {
+                Some(ref bit_field) if bit_field.width < 32 && member.ty.size() <= 4 => Type::Int,
+                _ => member.ty.promote(),
+            },
+            _ => self.get_type().promote(),
         }
     }
+
+    /// Return true if the node is a bit-field member.
+    pub fn is_bit_field(&self) -> bool {
+        matches!(self.kind, NodeKind::MEMBER { ref member, .. } if member.bit_field.is_some())
+    }
+
+    /// Return true if the node designates an object in memory,
+    /// i.e. it can be assigned to and its address can be taken.
+    pub fn is_lvalue(&self) -> bool {
+        matches!(
+            self.kind,
+            NodeKind::VAR(_)
+                | NodeKind::GVAR(_)
+                | NodeKind::LITERAL { .. }
+                | NodeKind::MEMBER { .. }
+                | NodeKind::UNOP {
+                    op: UnaryOpType::Deref,
+                    ..
+                }
+        )
+    }
+
+    /// Return the type of an expression node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is a statement.
+    pub fn get_type(&self) -> &Type {
+        match self.ty {
+            Some(ref ty) => ty,
+            None => panic!("get_type: node has no type: {:?}", self),
+        }
+    }
+}
+
+/// A function definition.
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    /// where the function is defined, at its name
+    pub location: Location,
+    /// true if the function is declared `_Noreturn`
+    pub is_noreturn: bool,
+    /// true if the function is not visible to other files
+    pub is_static: bool,
+    /// the parameters, indexes into `locals`
+    pub params: Vec<usize>,
+    pub ret: Type,
+    pub locals: Vec<Var>,
+    /// the local holding the register save area of a variadic function
+    pub va_area: Option<usize>,
+    /// the local holding the address the caller passes to store
+    /// a returned struct or union of the Memory class
+    pub sret: Option<usize>,
+    pub body: Node,
+}
+
+/// An address constant in the initial value of a global variable:
+/// the 8 bytes at `offset` hold the address of `label` plus `addend`.
+#[derive(Debug)]
+pub struct Reloc {
+    pub offset: i64,
+    pub label: String,
+    pub addend: i64,
+}
+
+/// A global variable, a function-local static variable or a string literal.
+#[derive(Debug)]
+pub struct Global {
+    pub name: String,
+    pub ty: Type,
+    /// the initial value, None for a zero initialized object
+    pub init: Option<Vec<u8>>,
+    pub relocs: Vec<Reloc>,
+    /// true if the symbol is not visible to other files
+    pub is_static: bool,
+    /// false if the variable is only declared `extern`, it is defined in another file
+    pub is_definition: bool,
+}
+
+/// A whole translation unit.
+#[derive(Debug)]
+pub struct Program {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
 }
@@ -0,0 +1,481 @@
+// The control-flow graph of a function of the IR: the blocks the
+// terminators go to and come from, and the dominator tree built from them.
+// A block dominates another if every path from the entry to the other one
+// goes through it; the blocks which can't be reached from the entry are
+// dominated by none and dominate nothing.
+//
+// The dominance frontier of a block is where its dominance stops: the blocks
+// it doesn't strictly dominate but one of whose predecessors it dominates,
+// where the values defined in the block meet those coming from elsewhere.
+//
+// An edge going back to a block which dominates its source closes a natural
+// loop, made of the block, its header, and the blocks which reach the edge
+// without going through the header. Two loops are either disjoint or one is
+// inside the other, the loops with the same header are merged, and they form
+// a forest. The loops entered in more than one block, which only `goto` can
+// make, are not natural and not found.
+
+use std::fmt::Write;
+
+use crate::ir::{BlockId, Function, Terminator};
+
+/// The edges between the blocks of a function.
+#[derive(Debug)]
+pub struct Cfg {
+    /// the blocks each block may go to, each once, in the order of its terminator
+    pub succs: Vec<Vec<BlockId>>,
+    /// the blocks which may go to each block, each once, in the order of the blocks
+    pub preds: Vec<Vec<BlockId>>,
+    /// the blocks reachable from the entry in reverse post-order, where a
+    /// block comes before its successors but for the edges closing a loop
+    pub rpo: Vec<BlockId>,
+    /// the index of each block in `rpo`, None if it can't be reached
+    rpo_index: Vec<Option<usize>>,
+}
+
+impl Cfg {
+    /// Builds the graph of the function, whose terminators must only go
+    /// to its blocks
+    pub fn new(function: &Function) -> Cfg {
+        let len = function.blocks.len();
+        let mut succs = Vec::with_capacity(len);
+        let mut preds = vec![Vec::new(); len];
+        for (i, block) in function.blocks.iter().enumerate() {
+            let mut targets = block.term.successors();
+            targets.dedup();
+            for &target in &targets {
+                preds[target.0].push(BlockId(i));
+            }
+            succs.push(targets);
+        }
+        // a depth-first search from the entry, the blocks are numbered
+        // after all their successors
+        let mut post_order = Vec::with_capacity(len);
+        let mut visited = vec![false; len];
+        let mut stack = vec![(BlockId(0), 0)];
+        visited[0] = true;
+        while let Some((block, next)) = stack.pop() {
+            match succs[block.0].get(next) {
+                Some(&succ) => {
+                    stack.push((block, next + 1));
+                    if !visited[succ.0] {
+                        visited[succ.0] = true;
+                        stack.push((succ, 0));
+                    }
+                }
+                None => post_order.push(block),
+            }
+        }
+        let rpo: Vec<BlockId> = post_order.into_iter().rev().collect();
+        let mut rpo_index = vec![None; len];
+        for (i, block) in rpo.iter().enumerate() {
+            rpo_index[block.0] = Some(i);
+        }
+        Cfg {
+            succs,
+            preds,
+            rpo,
+            rpo_index,
+        }
+    }
+
+    /// Return true if the block can be reached from the entry
+    pub fn is_reachable(&self, block: BlockId) -> bool {
+        self.rpo_index[block.0].is_some()
+    }
+}
+
+/// The dominator tree of a function.
+#[derive(Debug)]
+pub struct DomTree {
+    /// the immediate dominator of each block, None for the entry and
+    /// the blocks which can't be reached
+    idom: Vec<Option<BlockId>>,
+    children: Vec<Vec<BlockId>>,
+    /// the numbers of each block when the depth-first search of the tree
+    /// enters and leaves it, the blocks it dominates are numbered in between
+    enter: Vec<usize>,
+    leave: Vec<usize>,
+}
+
+impl DomTree {
+    /// Builds the dominator tree with the algorithm of Cooper, Harvey and
+    /// Kennedy, "A Simple, Fast Dominance Algorithm": the immediate dominator
+    /// of a block is where the paths from the immediate dominators of its
+    /// predecessors meet, computed in reverse post-order until nothing changes.
+    pub fn new(cfg: &Cfg) -> DomTree {
+        let len = cfg.succs.len();
+        let mut idom: Vec<Option<BlockId>> = vec![None; len];
+        let entry = BlockId(0);
+        idom[entry.0] = Some(entry);
+        let index = |block: BlockId| cfg.rpo_index[block.0].unwrap();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &cfg.rpo[1..] {
+                let mut new_idom = None;
+                for &pred in &cfg.preds[block.0] {
+                    if idom[pred.0].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(mut other) => {
+                            // walk up from the later block in reverse
+                            // post-order until both paths meet
+                            let mut finger = pred;
+                            while finger != other {
+                                while index(finger) > index(other) {
+                                    finger = idom[finger.0].unwrap();
+                                }
+                                while index(other) > index(finger) {
+                                    other = idom[other.0].unwrap();
+                                }
+                            }
+                            finger
+                        }
+                    });
+                }
+                if idom[block.0] != new_idom {
+                    idom[block.0] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        idom[entry.0] = None;
+        let mut children = vec![Vec::new(); len];
+        for &block in &cfg.rpo {
+            if let Some(parent) = idom[block.0] {
+                children[parent.0].push(block);
+            }
+        }
+        let mut enter = vec![usize::MAX; len];
+        let mut leave = vec![0; len];
+        let mut counter = 0;
+        let mut stack = vec![(entry, 0)];
+        enter[entry.0] = counter;
+        while let Some((block, next)) = stack.pop() {
+            counter += 1;
+            match children[block.0].get(next) {
+                Some(&child) => {
+                    stack.push((block, next + 1));
+                    enter[child.0] = counter;
+                    stack.push((child, 0));
+                }
+                None => leave[block.0] = counter,
+            }
+        }
+        DomTree {
+            idom,
+            children,
+            enter,
+            leave,
+        }
+    }
+
+    /// Return the immediate dominator of the block, None for the entry
+    /// and the blocks which can't be reached
+    pub fn idom(&self, block: BlockId) -> Option<BlockId> {
+        self.idom[block.0]
+    }
+
+    /// Return the blocks the block immediately dominates
+    pub fn children(&self, block: BlockId) -> &[BlockId] {
+        &self.children[block.0]
+    }
+
+    /// Return true if `a` dominates `b`, a block dominating itself
+    pub fn dominates(&self, a: BlockId, b: BlockId) -> bool {
+        self.enter[b.0] != usize::MAX
+            && self.enter[a.0] <= self.enter[b.0]
+            && self.leave[b.0] <= self.leave[a.0]
+    }
+
+    /// Return the dominance frontier of each block, in the order of the
+    /// blocks. From each predecessor of a block where paths meet, the
+    /// dominators are walked up to the immediate dominator of the block,
+    /// which is the first one to dominate it strictly.
+    pub fn frontiers(&self, cfg: &Cfg) -> Vec<Vec<BlockId>> {
+        let mut frontiers = vec![Vec::new(); cfg.succs.len()];
+        for &block in &cfg.rpo {
+            if cfg.preds[block.0].len() < 2 {
+                continue;
+            }
+            for &pred in &cfg.preds[block.0] {
+                let mut runner = Some(pred);
+                while let Some(current) = runner {
+                    if !cfg.is_reachable(current) || Some(current) == self.idom(block) {
+                        break;
+                    }
+                    if !frontiers[current.0].contains(&block) {
+                        frontiers[current.0].push(block);
+                    }
+                    runner = self.idom(current);
+                }
+            }
+        }
+        for frontier in &mut frontiers {
+            frontier.sort();
+        }
+        frontiers
+    }
+}
+
+/// A natural loop.
+#[derive(Debug)]
+pub struct Loop {
+    /// the block every path into the loop goes through
+    pub header: BlockId,
+    /// the blocks of the loop, its header and those of the inner loops included, in order
+    pub blocks: Vec<BlockId>,
+    /// the blocks going back to the header
+    pub latches: Vec<BlockId>,
+    /// the index of the loop this one is inside of
+    pub parent: Option<usize>,
+    /// the indexes of the loops immediately inside this one
+    pub children: Vec<usize>,
+    /// 1 for the outermost loops
+    pub depth: usize,
+}
+
+/// The natural loops of a function and how they nest.
+#[derive(Debug)]
+pub struct LoopForest {
+    /// the loops in the reverse post-order of their headers, a loop
+    /// coming before those inside it
+    pub loops: Vec<Loop>,
+    /// the index of the innermost loop of each block
+    innermost: Vec<Option<usize>>,
+}
+
+impl LoopForest {
+    /// Finds the natural loops of the function
+    pub fn new(cfg: &Cfg, dom: &DomTree) -> LoopForest {
+        let len = cfg.succs.len();
+        let mut loops: Vec<Loop> = Vec::new();
+        for &header in &cfg.rpo {
+            let latches: Vec<BlockId> = cfg.preds[header.0]
+                .iter()
+                .copied()
+                .filter(|&pred| dom.dominates(header, pred))
+                .collect();
+            if latches.is_empty() {
+                continue;
+            }
+            // walk back from the latches, the header stops the walk
+            let mut in_loop = vec![false; len];
+            in_loop[header.0] = true;
+            let mut stack = latches.clone();
+            while let Some(block) = stack.pop() {
+                if !in_loop[block.0] {
+                    in_loop[block.0] = true;
+                    stack.extend(
+                        cfg.preds[block.0]
+                            .iter()
+                            .filter(|&&pred| cfg.is_reachable(pred)),
+                    );
+                }
+            }
+            let blocks = (0..len).filter(|&i| in_loop[i]).map(BlockId).collect();
+            loops.push(Loop {
+                header,
+                blocks,
+                latches,
+                parent: None,
+                children: Vec::new(),
+                depth: 1,
+            });
+        }
+        // the header of an outer loop dominates those of the inner loops
+        // and comes before them, so the innermost loop of a block is the
+        // last one containing it
+        let mut innermost: Vec<Option<usize>> = vec![None; len];
+        for i in 0..loops.len() {
+            let parent = innermost[loops[i].header.0];
+            if let Some(parent) = parent {
+                loops[i].depth = loops[parent].depth + 1;
+                loops[parent].children.push(i);
+            }
+            loops[i].parent = parent;
+            for &block in &loops[i].blocks {
+                innermost[block.0] = Some(i);
+            }
+        }
+        LoopForest { loops, innermost }
+    }
+
+    /// Return the index of the innermost loop the block is in
+    pub fn loop_of(&self, block: BlockId) -> Option<usize> {
+        self.innermost[block.0]
+    }
+
+    /// Return the number of loops the block is in
+    pub fn depth(&self, block: BlockId) -> usize {
+        self.loop_of(block).map_or(0, |i| self.loops[i].depth)
+    }
+
+    /// Return the indexes of the outermost loops
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.loops.len()).filter(|&i| self.loops[i].parent.is_none())
+    }
+}
+
+/// Return the graph of the function in the dot language of Graphviz. The
+/// blocks show their instructions, the edges closing a loop are dashed and
+/// the blocks which can't be reached are grey.
+pub fn dot(function: &Function) -> String {
+    let cfg = Cfg::new(function);
+    let dom = DomTree::new(&cfg);
+    let forest = LoopForest::new(&cfg, &dom);
+    let mut out = String::new();
+    writeln!(out, "digraph \"{}\" {{", function.name).unwrap();
+    writeln!(out, "  node [shape=box, fontname=\"monospace\"];").unwrap();
+    for (i, block) in function.blocks.iter().enumerate() {
+        let id = BlockId(i);
+        let mut label = format!("{}:", id);
+        if forest.depth(id) > 0 {
+            write!(label, " ; loop depth {}", forest.depth(id)).unwrap();
+        }
+        label.push('\n');
+        for inst in &block.insts {
+            writeln!(label, "  {}", function.display(inst)).unwrap();
+        }
+        writeln!(label, "  {}", block.term).unwrap();
+        let mut escaped = String::new();
+        for c in label.chars() {
+            match c {
+                '"' | '\\' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                // left-justify the lines
+                '\n' => escaped.push_str("\\l"),
+                _ => escaped.push(c),
+            }
+        }
+        write!(out, "  {} [label=\"{}\"", id, escaped).unwrap();
+        if !cfg.is_reachable(id) {
+            write!(out, ", color=gray, fontcolor=gray").unwrap();
+        }
+        writeln!(out, "];").unwrap();
+    }
+    for (i, block) in function.blocks.iter().enumerate() {
+        let id = BlockId(i);
+        let edges = match block.term {
+            Terminator::Branch { then, els, .. } if then != els => vec![(then, "T"), (els, "F")],
+            _ => cfg.succs[i].iter().map(|&succ| (succ, "")).collect(),
+        };
+        for (succ, label) in edges {
+            let mut attrs = Vec::new();
+            if !label.is_empty() {
+                attrs.push(format!("label=\"{}\"", label));
+            }
+            if cfg.is_reachable(id) && dom.dominates(succ, id) {
+                attrs.push("style=dashed".to_owned());
+            }
+            match attrs.is_empty() {
+                true => writeln!(out, "  {} -> {};", id, succ).unwrap(),
+                false => writeln!(out, "  {} -> {} [{}];", id, succ, attrs.join(", ")).unwrap(),
+            }
+        }
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irparse::parse_module;
+
+    /// Return a function whose block `i` goes to the blocks `succs[i]`
+    fn graph(succs: &[&[usize]]) -> Function {
+        let mut text = String::from("function @f() -> void {\n");
+        for (i, targets) in succs.iter().enumerate() {
+            let term = match targets {
+                [] => "ret".to_owned(),
+                [target] => format!("jump bb{}", target),
+                [then, els] => format!("branch i32 1, bb{}, bb{}", then, els),
+                _ => unreachable!(),
+            };
+            text.push_str(&format!("bb{}:\n  {}\n", i, term));
+        }
+        text.push_str("}\n");
+        parse_module(&text).functions.remove(0)
+    }
+
+    fn blocks(ids: &[usize]) -> Vec<BlockId> {
+        ids.iter().copied().map(BlockId).collect()
+    }
+
+    #[test]
+    fn test_nested_loops() {
+        // bb1 heads a loop with bb3 looping on itself inside, bb5 can't be reached
+        let function = graph(&[&[1], &[2, 6], &[3], &[3, 4], &[1], &[1], &[]]);
+        let cfg = Cfg::new(&function);
+        assert_eq!(cfg.succs[3], blocks(&[3, 4]));
+        assert_eq!(cfg.preds[1], blocks(&[0, 4, 5]));
+        assert_eq!(cfg.rpo, blocks(&[0, 1, 6, 2, 3, 4]));
+        assert!(!cfg.is_reachable(BlockId(5)));
+
+        let dom = DomTree::new(&cfg);
+        let idoms: Vec<Option<usize>> = (0..7).map(|i| dom.idom(BlockId(i)).map(|b| b.0)).collect();
+        assert_eq!(
+            idoms,
+            [None, Some(0), Some(1), Some(2), Some(3), None, Some(1)]
+        );
+        assert_eq!(dom.children(BlockId(1)), blocks(&[6, 2]));
+        assert!(dom.dominates(BlockId(1), BlockId(4)));
+        assert!(dom.dominates(BlockId(3), BlockId(3)));
+        assert!(!dom.dominates(BlockId(6), BlockId(2)));
+        assert!(!dom.dominates(BlockId(0), BlockId(5)));
+
+        let frontiers = dom.frontiers(&cfg);
+        let expected = [
+            vec![],
+            blocks(&[1]),
+            blocks(&[1]),
+            blocks(&[1, 3]),
+            blocks(&[1]),
+            vec![],
+            vec![],
+        ];
+        assert_eq!(frontiers, expected);
+
+        let forest = LoopForest::new(&cfg, &dom);
+        assert_eq!(forest.loops.len(), 2);
+        let outer = &forest.loops[0];
+        assert_eq!(
+            (outer.header, outer.parent, outer.depth),
+            (BlockId(1), None, 1)
+        );
+        assert_eq!(outer.blocks, blocks(&[1, 2, 3, 4]));
+        assert_eq!(outer.latches, blocks(&[4]));
+        assert_eq!(outer.children, [1]);
+        let inner = &forest.loops[1];
+        assert_eq!(
+            (inner.header, inner.parent, inner.depth),
+            (BlockId(3), Some(0), 2)
+        );
+        assert_eq!(inner.blocks, blocks(&[3]));
+        assert_eq!(forest.roots().collect::<Vec<_>>(), [0]);
+        let depths: Vec<usize> = (0..7).map(|i| forest.depth(BlockId(i))).collect();
+        assert_eq!(depths, [0, 1, 1, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_irreducible_loop() {
+        // bb1 and bb2 are both entered from bb0, neither heads a natural loop
+        let function = graph(&[&[1, 2], &[2], &[1, 3], &[]]);
+        let cfg = Cfg::new(&function);
+        let dom = DomTree::new(&cfg);
+        assert_eq!(dom.idom(BlockId(1)), Some(BlockId(0)));
+        assert_eq!(dom.idom(BlockId(2)), Some(BlockId(0)));
+        assert_eq!(dom.idom(BlockId(3)), Some(BlockId(2)));
+        let frontiers = dom.frontiers(&cfg);
+        assert_eq!(frontiers, [vec![], blocks(&[2]), blocks(&[1]), vec![]]);
+        let forest = LoopForest::new(&cfg, &dom);
+        assert!(forest.loops.is_empty());
+        assert_eq!(forest.loop_of(BlockId(1)), None);
+    }
+}
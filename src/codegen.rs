@@ -1,44 +1,731 @@
-use crate::ast::{BinaryOpType, Node};
+// The code generator translates the IR into x86-64 assembly for the
+// System V ABI. Every virtual register has 8 bytes of its own in the stack
+// frame: an instruction loads its operands from there into the machine
+// registers and stores its result back. Only the lower bits of a register
+// of a narrow type are defined, the instructions which look at the upper
+// ones, division, right shifts and conversions, extend their operands first.
 
-fn push() {
-    info!("push %rax");
-    println!("    push %rax");
-}
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use log::info;
 
-fn pop(register: &str) {
-    info!("pop {}", register);
-    println!("    pop {}", register);
+use crate::ir::{
+    ArgTy, BinOp, BlockId, Callee, ConvOp, Function, Global, Inst, Module, Operand, Reg,
+    Terminator, Ty, UnOp,
+};
+use crate::types::align_to;
+
+/// Registers passing the integer arguments, in order.
+const ARG_REG64: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+const ARG_REG8: [&str; 6] = ["%dil", "%sil", "%dl", "%cl", "%r8b", "%r9b"];
+
+/// The names of the parts of a register holding 1, 2, 4 and 8 bytes.
+const AX: [&str; 4] = ["%al", "%ax", "%eax", "%rax"];
+const DI: [&str; 4] = ["%dil", "%di", "%edi", "%rdi"];
+
+/// The size of the register save area of a variadic function: the 6
+/// general purpose argument registers and the 8 vector ones.
+const VA_AREA_SIZE: i64 = 6 * 8 + 8 * 16;
+
+/// Return the name of the part of the register `reg` holding a value of type `ty`.
+fn sized(reg: [&'static str; 4], ty: Ty) -> &'static str {
+    match ty.size() {
+        1 => reg[0],
+        2 => reg[1],
+        4 => reg[2],
+        _ => reg[3],
+    }
+}
+
+/// Sign or zero extend the lower bits of `reg` holding a value of type `ty`
+/// to the full register.
+fn extend(reg: [&'static str; 4], ty: Ty, signed: bool) {
+    match (ty, signed) {
+        (Ty::I8, true) => println!("    movsbq {}, {}", reg[0], reg[3]),
+        (Ty::I8, false) => println!("    movzbq {}, {}", reg[0], reg[3]),
+        (Ty::I16, true) => println!("    movswq {}, {}", reg[1], reg[3]),
+        (Ty::I16, false) => println!("    movzwq {}, {}", reg[1], reg[3]),
+        (Ty::I32, true) => println!("    movslq {}, {}", reg[2], reg[3]),
+        // writing a 32-bit register clears the upper half
+        (Ty::I32, false) => println!("    mov {}, {}", reg[2], reg[2]),
+        (Ty::I64 | Ty::Ptr, _) => {}
+    }
 }
 
-pub fn gen_expr(node: &Node) {
-    match node {
-        Node::NUM(n) => {
-            info!("gen expr: {:?}", node);
-            println!("    mov ${}, %rax", n);
+/// The stack frame of the function being generated.
+struct Frame<'a> {
+    function: &'a Function,
+    /// `%rbp` relative offsets of the slots
+    slots: Vec<i64>,
+    /// `%rbp` relative offsets of the registers
+    regs: Vec<i64>,
+    /// the offsets of the copies of the struct parameters passed in
+    /// registers, by the index of the parameter
+    params: Vec<Option<i64>>,
+    /// the offsets of the buffers of the calls returning a struct,
+    /// in the order of the calls
+    ret_buffers: Vec<i64>,
+    stack_size: i64,
+    /// the offset of the register save area of a variadic function
+    va_area: Option<i64>,
+    /// the number of registers and the bytes of the stack used by
+    /// the named parameters, where the variadic arguments start
+    va_regs: usize,
+    va_stack: i64,
+    /// the offset of the address to store a returned struct of the Memory class
+    sret: Option<i64>,
+}
+
+impl<'a> Frame<'a> {
+    /// Assigns the slots, the copies of the struct parameters, the buffers
+    /// of the returned structs and the registers their place below `%rbp`.
+    fn new(function: &'a Function) -> Frame<'a> {
+        let mut offset = 0;
+        let mut allocate = |size: i64, align: i64| {
+            offset = align_to(offset + size, align);
+            -offset
+        };
+        let slots = function
+            .slots
+            .iter()
+            .map(|slot| allocate(slot.size, slot.align))
+            .collect();
+        let sret = is_sret(function.ret);
+        let tys: Vec<ArgTy> = function.params.iter().map(|param| param.ty).collect();
+        let (locations, va_regs, va_stack) = arg_locations(&tys, sret);
+        let params = tys
+            .iter()
+            .zip(&locations)
+            .map(|(ty, loc)| match (ty, loc) {
+                (&ArgTy::Byval(size), ArgLoc::Reg(_)) => Some(allocate(align_to(size, 8), 8)),
+                _ => None,
+            })
+            .collect();
+        let mut ret_buffers = Vec::new();
+        for inst in function.blocks.iter().flat_map(|block| &block.insts) {
+            if let Inst::Call {
+                ret: Some(ArgTy::Byval(size)),
+                ..
+            } = *inst
+            {
+                ret_buffers.push(allocate(align_to(size, 8), 8));
+            }
         }
-        Node::BINOP { op, lhs, rhs } => {
-            info!("gen expr: {:?}", node);
-            gen_expr(rhs);
-            push();
-            gen_expr(lhs);
-            pop("%rdi");
-            match op {
-                BinaryOpType::Add => {
-                    println!("    add %rdi, %rax");
+        let va_area = function.is_variadic.then(|| allocate(VA_AREA_SIZE, 16));
+        let sret = sret.then(|| allocate(8, 8));
+        let regs = function.regs.iter().map(|_| allocate(8, 8)).collect();
+        Frame {
+            function,
+            slots,
+            regs,
+            params,
+            ret_buffers,
+            stack_size: align_to(offset, 16),
+            va_area,
+            va_regs,
+            va_stack,
+            sret,
+        }
+    }
+
+    /// Load the operand into the 64-bit register `reg`
+    fn load(&self, operand: &Operand, reg: &str) {
+        match *operand {
+            Operand::Const(value) => println!("    mov ${}, {}", value, reg),
+            Operand::Reg(Reg(r)) => println!("    mov {}(%rbp), {}", self.regs[r], reg),
+        }
+    }
+
+    /// Store the 64-bit register `reg` into the virtual register `dst`
+    fn store(&self, reg: &str, dst: Reg) {
+        println!("    mov {}, {}(%rbp)", reg, self.regs[dst.0]);
+    }
+
+    /// Return the label of the block
+    fn label(&self, block: BlockId) -> String {
+        format!(".L.bb.{}.{}", self.function.name, block.0)
+    }
+}
+
+/// Return true if a value of type `ret` is returned in memory the caller
+/// passes the address of.
+fn is_sret(ret: Option<ArgTy>) -> bool {
+    matches!(ret, Some(ArgTy::Byval(size)) if size > 16)
+}
+
+/// Return the number of eightbytes passed in registers for a value of
+/// type `ty`, None if it is of the Memory class.
+fn eightbytes(ty: ArgTy) -> Option<usize> {
+    match ty {
+        ArgTy::Scalar(_) => Some(1),
+        ArgTy::Byval(size) if size > 16 => None,
+        ArgTy::Byval(size) => Some(((size + 7) / 8) as usize),
+    }
+}
+
+/// Where an argument is passed.
+#[derive(Debug, Clone, Copy)]
+enum ArgLoc {
+    /// in the general purpose argument registers from this index on
+    Reg(usize),
+    /// on the stack at this offset into the arguments above the return address
+    Stack(i64),
+}
+
+/// Assigns the arguments of types `tys` their locations as the System V ABI
+/// does, after the hidden pointer to a returned struct if `sret` is true.
+/// An argument goes to the stack as a whole if it is of the Memory class or
+/// if there are not enough registers left for all its eightbytes, the next
+/// arguments still take the registers left.
+/// Return the locations, the number of registers and the size of the stack used.
+fn arg_locations(tys: &[ArgTy], sret: bool) -> (Vec<ArgLoc>, usize, i64) {
+    let mut regs = sret as usize;
+    let mut stack = 0;
+    let locations = tys
+        .iter()
+        .map(|&ty| match eightbytes(ty) {
+            Some(n) if regs + n <= ARG_REG64.len() => {
+                let loc = ArgLoc::Reg(regs);
+                regs += n;
+                loc
+            }
+            _ => {
+                let loc = ArgLoc::Stack(stack);
+                stack += match ty {
+                    ArgTy::Scalar(_) => 8,
+                    ArgTy::Byval(size) => align_to(size, 8),
+                };
+                loc
+            }
+        })
+        .collect();
+    (locations, regs, stack)
+}
+
+/// Return the number of bytes of the eightbyte `i` of a value of `size` bytes.
+fn eightbyte_size(size: i64, i: usize) -> i64 {
+    (size - i as i64 * 8).min(8)
+}
+
+/// Load the `size` bytes at `offset` from the address in `addr` into
+/// the register `reg`, whose lowest byte is `reg8`, zeroing the upper bytes.
+/// The bytes are read one by one to never read past the end of the value.
+fn load_eightbyte(reg: &str, reg8: &str, addr: &str, offset: i64, size: i64) {
+    if size == 8 {
+        println!("    mov {}({}), {}", offset, addr, reg);
+        return;
+    }
+    println!("    mov $0, {}", reg);
+    for i in (0..size).rev() {
+        println!("    shl $8, {}", reg);
+        println!("    mov {}({}), {}", offset + i, addr, reg8);
+    }
+}
+
+/// Store the lower `size` bytes of the register `reg` at `offset` from
+/// the address in `addr`, the register is clobbered.
+fn store_eightbyte(reg: &str, reg8: &str, addr: &str, offset: i64, size: i64) {
+    if size == 8 {
+        println!("    mov {}, {}({})", reg, offset, addr);
+        return;
+    }
+    for i in 0..size {
+        println!("    mov {}, {}({})", reg8, offset + i, addr);
+        println!("    shr $8, {}", reg);
+    }
+}
+
+/// Copy `size` bytes from the address in `src` to the address in `dst`
+/// through %r10, which is not used to pass arguments.
+fn copy_bytes(src: &str, src_offset: i64, dst: &str, dst_offset: i64, size: i64) {
+    for i in 0..size {
+        println!("    mov {}({}), %r10b", src_offset + i, src);
+        println!("    mov %r10b, {}({})", dst_offset + i, dst);
+    }
+}
+
+/// Return a unique number for the local labels.
+fn next_label() -> usize {
+    static COUNTER: AtomicUsize = AtomicUsize::new(1);
+    COUNTER.fetch_add(1, Ordering::SeqCst)
+}
+
+/// Generate a binary operation on `%rax` and `%rdi`, the result in `%rax`.
+fn gen_binary(op: BinOp, ty: Ty) {
+    match op {
+        BinOp::Add => println!("    add %rdi, %rax"),
+        BinOp::Sub => println!("    sub %rdi, %rax"),
+        BinOp::Mul => println!("    imul %rdi, %rax"),
+        BinOp::And => println!("    and %rdi, %rax"),
+        BinOp::Or => println!("    or %rdi, %rax"),
+        BinOp::Xor => println!("    xor %rdi, %rax"),
+        // the operands are extended so that the 64-bit division gives the
+        // quotient and the remainder of the narrow ones
+        BinOp::SDiv | BinOp::SRem => {
+            extend(AX, ty, true);
+            extend(DI, ty, true);
+            println!("    cqo");
+            println!("    idiv %rdi");
+            if op == BinOp::SRem {
+                println!("    mov %rdx, %rax");
+            }
+        }
+        BinOp::UDiv | BinOp::URem => {
+            extend(AX, ty, false);
+            extend(DI, ty, false);
+            println!("    xor %edx, %edx");
+            println!("    div %rdi");
+            if op == BinOp::URem {
+                println!("    mov %rdx, %rax");
+            }
+        }
+        BinOp::Shl | BinOp::LShr | BinOp::AShr => {
+            println!("    mov %rdi, %rcx");
+            let shift = match op {
+                BinOp::Shl => "shl",
+                BinOp::LShr => {
+                    extend(AX, ty, false);
+                    "shr"
                 }
-                BinaryOpType::Sub => {
-                    println!("    sub %rdi, %rax");
+                _ => {
+                    extend(AX, ty, true);
+                    "sar"
                 }
-                BinaryOpType::Mul => {
-                    println!("    imul %rdi, %rax");
+            };
+            println!("    {} %cl, %rax", shift);
+        }
+        BinOp::Eq | BinOp::Ne | BinOp::SLt | BinOp::SLe | BinOp::ULt | BinOp::ULe => {
+            println!("    cmp {}, {}", sized(DI, ty), sized(AX, ty));
+            let set = match op {
+                BinOp::Eq => "sete",
+                BinOp::Ne => "setne",
+                BinOp::SLt => "setl",
+                BinOp::SLe => "setle",
+                BinOp::ULt => "setb",
+                _ => "setbe",
+            };
+            println!("    {} %al", set);
+            println!("    movzbq %al, %rax");
+        }
+    }
+}
+
+/// Generate a call, passing the arguments in registers and on the stack,
+/// and store what it returns in `dst`.
+fn gen_call(
+    frame: &Frame,
+    dst: Option<Reg>,
+    callee: &Callee,
+    args: &[(ArgTy, Operand)],
+    ret: Option<ArgTy>,
+    ret_buffer: Option<i64>,
+) {
+    let sret = is_sret(ret);
+    let tys: Vec<ArgTy> = args.iter().map(|&(ty, _)| ty).collect();
+    let (locations, _, stack) = arg_locations(&tys, sret);
+    // the stack arguments start 16-byte aligned at the call, the frame
+    // keeps `%rsp` aligned
+    let stack = align_to(stack, 16);
+    if stack != 0 {
+        println!("    sub ${}, %rsp", stack);
+    }
+    for ((ty, arg), loc) in args.iter().zip(&locations) {
+        match (*ty, *loc) {
+            (ArgTy::Scalar(_), ArgLoc::Stack(offset)) => {
+                frame.load(arg, "%rax");
+                println!("    mov %rax, {}(%rsp)", offset);
+            }
+            (ArgTy::Byval(size), ArgLoc::Stack(offset)) => {
+                frame.load(arg, "%rax");
+                copy_bytes("%rax", 0, "%rsp", offset, size);
+            }
+            (ArgTy::Scalar(_), ArgLoc::Reg(reg)) => frame.load(arg, ARG_REG64[reg]),
+            // a struct goes one eightbyte per register
+            (ArgTy::Byval(size), ArgLoc::Reg(reg)) => {
+                frame.load(arg, "%rax");
+                for i in 0..eightbytes(ArgTy::Byval(size)).unwrap_or(0) {
+                    let (reg64, reg8) = (ARG_REG64[reg + i], ARG_REG8[reg + i]);
+                    load_eightbyte(reg64, reg8, "%rax", i as i64 * 8, eightbyte_size(size, i));
                 }
-                BinaryOpType::Div => {
-                    println!("    cqo");
-                    println!("    idiv %rdi");
+            }
+        }
+    }
+    if sret {
+        let buffer = ret_buffer.expect("gen_call: call without a return buffer");
+        println!("    lea {}(%rbp), %rdi", buffer);
+    }
+    // a function is called by its name, a pointer to a function
+    // through %r11 which is not used to pass arguments
+    let target = match callee {
+        Callee::Direct(name) => name.clone(),
+        Callee::Indirect(operand) => {
+            frame.load(operand, "%r11");
+            "*%r11".to_owned()
+        }
+    };
+    // %al holds the number of vector registers used by a variadic call
+    println!("    mov $0, %eax");
+    println!("    call {}", target);
+    if stack != 0 {
+        println!("    add ${}, %rsp", stack);
+    }
+    let dst = match dst {
+        Some(dst) => dst,
+        None => return,
+    };
+    match ret {
+        // a struct returned in %rax and %rdx is stored in the buffer,
+        // one returned in memory is already there and %rax points to it
+        Some(ArgTy::Byval(size)) if !sret => {
+            let buffer = ret_buffer.expect("gen_call: call without a return buffer");
+            println!("    lea {}(%rbp), %rdi", buffer);
+            store_eightbyte("%rax", "%al", "%rdi", 0, eightbyte_size(size, 0));
+            if size > 8 {
+                store_eightbyte("%rdx", "%dl", "%rdi", 8, eightbyte_size(size, 1));
+            }
+            frame.store("%rdi", dst);
+        }
+        _ => frame.store("%rax", dst),
+    }
+}
+
+/// Generate an instruction. `ret_buffers` gives the buffers of the
+/// calls returning a struct which come next.
+fn gen_inst(inst: &Inst, frame: &Frame, ret_buffers: &mut impl Iterator<Item = i64>) {
+    info!("gen inst: {:?}", inst);
+    match *inst {
+        Inst::Binary {
+            dst,
+            op,
+            ty,
+            ref lhs,
+            ref rhs,
+        } => {
+            frame.load(lhs, "%rax");
+            frame.load(rhs, "%rdi");
+            gen_binary(op, ty);
+            frame.store("%rax", dst);
+        }
+        Inst::Unary {
+            dst,
+            op,
+            ref operand,
+            ..
+        } => {
+            frame.load(operand, "%rax");
+            match op {
+                UnOp::Neg => println!("    neg %rax"),
+                UnOp::Not => println!("    not %rax"),
+            }
+            frame.store("%rax", dst);
+        }
+        Inst::Conv {
+            dst,
+            op,
+            from,
+            ref operand,
+        } => {
+            frame.load(operand, "%rax");
+            match op {
+                ConvOp::SExt => extend(AX, from, true),
+                ConvOp::ZExt => extend(AX, from, false),
+                // the lower bits are the value
+                ConvOp::Trunc | ConvOp::PtrToInt | ConvOp::IntToPtr => {}
+            }
+            frame.store("%rax", dst);
+        }
+        Inst::SlotAddr { dst, slot } => {
+            println!("    lea {}(%rbp), %rax", frame.slots[slot.0]);
+            frame.store("%rax", dst);
+        }
+        Inst::GlobalAddr { dst, ref name } => {
+            println!("    lea {}(%rip), %rax", name);
+            frame.store("%rax", dst);
+        }
+        Inst::PtrAdd {
+            dst,
+            ref base,
+            ref offset,
+        } => {
+            frame.load(base, "%rax");
+            frame.load(offset, "%rdi");
+            println!("    add %rdi, %rax");
+            frame.store("%rax", dst);
+        }
+        Inst::Load { dst, ty, ref addr } => {
+            frame.load(addr, "%rcx");
+            match ty {
+                Ty::I8 => println!("    movzbq (%rcx), %rax"),
+                Ty::I16 => println!("    movzwq (%rcx), %rax"),
+                Ty::I32 => println!("    mov (%rcx), %eax"),
+                Ty::I64 | Ty::Ptr => println!("    mov (%rcx), %rax"),
+            }
+            frame.store("%rax", dst);
+        }
+        Inst::Store {
+            ty,
+            ref value,
+            ref addr,
+        } => {
+            frame.load(value, "%rax");
+            frame.load(addr, "%rcx");
+            println!("    mov {}, (%rcx)", sized(AX, ty));
+        }
+        Inst::MemCopy {
+            ref dst,
+            ref src,
+            size,
+        } => {
+            frame.load(dst, "%rdi");
+            frame.load(src, "%rsi");
+            println!("    mov ${}, %rcx", size);
+            println!("    rep movsb");
+        }
+        Inst::MemZero { ref addr, size } => {
+            frame.load(addr, "%rdi");
+            println!("    mov ${}, %rcx", size);
+            println!("    xor %eax, %eax");
+            println!("    rep stosb");
+        }
+        Inst::Call {
+            dst,
+            ref callee,
+            ref args,
+            ret,
+        } => {
+            let ret_buffer = match ret {
+                Some(ArgTy::Byval(_)) => ret_buffers.next(),
+                _ => None,
+            };
+            gen_call(frame, dst, callee, args, ret, ret_buffer);
+        }
+        Inst::VaStart { ref ap } => {
+            let area = frame
+                .va_area
+                .expect("gen_inst: va_start without a register save area");
+            frame.load(ap, "%rax");
+            // gp_offset skips the registers of the named parameters, there are
+            // no floating point ones, the other arguments are above the return
+            // address after the named ones passed on the stack
+            println!("    movl ${}, (%rax)", frame.va_regs * 8);
+            println!("    movl $48, 4(%rax)");
+            println!("    lea {}(%rbp), %rdx", 16 + frame.va_stack);
+            println!("    mov %rdx, 8(%rax)");
+            println!("    lea {}(%rbp), %rdx", area);
+            println!("    mov %rdx, 16(%rax)");
+        }
+        Inst::VaArg { dst, ref ap, size } => {
+            // take the next registers of the save area while there are enough
+            // left for all the eightbytes of the argument, then the next
+            // eightbytes of the overflow area; a struct or union of more than
+            // 16 bytes is always in the overflow area
+            let label = next_label();
+            let eightbytes = (size + 7) / 8;
+            frame.load(ap, "%rcx");
+            if size <= 16 {
+                println!("    mov (%rcx), %eax");
+                println!("    cmp ${}, %eax", 48 - 8 * eightbytes);
+                println!("    ja .L.va_overflow.{}", label);
+                println!("    lea {}(%rax), %edx", 8 * eightbytes);
+                println!("    mov %edx, (%rcx)");
+                println!("    add 16(%rcx), %rax");
+                println!("    jmp .L.va_end.{}", label);
+            }
+            println!(".L.va_overflow.{}:", label);
+            println!("    mov 8(%rcx), %rax");
+            println!("    lea {}(%rax), %rdx", 8 * eightbytes);
+            println!("    mov %rdx, 8(%rcx)");
+            println!(".L.va_end.{}:", label);
+            frame.store("%rax", dst);
+        }
+        Inst::Copy { dst, ref src, .. } => {
+            frame.load(src, "%rax");
+            frame.store("%rax", dst);
+        }
+        Inst::Phi { .. } => panic!("gen_inst: the phis must be removed before code generation"),
+    }
+}
+
+/// Generate the terminator of the block `block`, which jumps to the block
+/// following it by falling through.
+fn gen_terminator(term: &Terminator, block: BlockId, frame: &Frame) {
+    info!("gen terminator: {:?}", term);
+    let next = BlockId(block.0 + 1);
+    match *term {
+        Terminator::Jump(target) => {
+            if target != next {
+                println!("    jmp {}", frame.label(target));
+            }
+        }
+        Terminator::Branch {
+            ty,
+            ref cond,
+            then,
+            els,
+        } => {
+            frame.load(cond, "%rax");
+            println!("    cmp $0, {}", sized(AX, ty));
+            println!("    je {}", frame.label(els));
+            if then != next {
+                println!("    jmp {}", frame.label(then));
+            }
+        }
+        Terminator::Ret(ref value) => {
+            if let Some(value) = value {
+                frame.load(value, "%rax");
+                // a struct is returned in %rax and %rdx, or copied to the
+                // memory provided by the caller whose address is returned
+                match (frame.function.ret, frame.sret) {
+                    (Some(ArgTy::Byval(size)), Some(sret)) => {
+                        println!("    mov {}(%rbp), %rdi", sret);
+                        copy_bytes("%rax", 0, "%rdi", 0, size);
+                        println!("    mov %rdi, %rax");
+                    }
+                    (Some(ArgTy::Byval(size)), None) => {
+                        println!("    mov %rax, %rcx");
+                        if size > 8 {
+                            load_eightbyte("%rdx", "%dl", "%rcx", 8, eightbyte_size(size, 1));
+                        }
+                        load_eightbyte("%rax", "%al", "%rcx", 0, eightbyte_size(size, 0));
+                    }
+                    _ => {}
+                }
+            }
+            if next.0 != frame.function.blocks.len() {
+                println!("    jmp .L.return.{}", frame.function.name);
+            }
+        }
+        // reaching it is undefined, trap rather than run into what follows
+        Terminator::Unreachable => println!("    ud2"),
+    }
+}
+
+/// Generate the prologue of the function: save the registers a variadic
+/// function reads its arguments from and the address of a returned struct,
+/// then store the parameters into their registers.
+fn gen_prologue(frame: &Frame) {
+    let function = frame.function;
+    println!("    push %rbp");
+    println!("    mov %rsp, %rbp");
+    println!("    sub ${}, %rsp", frame.stack_size);
+    if let Some(area) = frame.va_area {
+        for (i, reg) in ARG_REG64.iter().enumerate() {
+            println!("    mov {}, {}(%rbp)", reg, area + i as i64 * 8);
+        }
+        for i in 0..8 {
+            println!("    movsd %xmm{}, {}(%rbp)", i, area + 48 + i * 16);
+        }
+    }
+    if let Some(sret) = frame.sret {
+        println!("    mov %rdi, {}(%rbp)", sret);
+    }
+    // a struct parameter is the address of its copy, made from the
+    // registers, or the one passed above the return address
+    let tys: Vec<ArgTy> = function.params.iter().map(|param| param.ty).collect();
+    let (locations, _, _) = arg_locations(&tys, frame.sret.is_some());
+    for ((param, loc), copy) in function.params.iter().zip(locations).zip(&frame.params) {
+        match (param.ty, loc) {
+            (ArgTy::Scalar(_), ArgLoc::Reg(reg)) => frame.store(ARG_REG64[reg], param.reg),
+            (ArgTy::Scalar(_), ArgLoc::Stack(offset)) => {
+                println!("    mov {}(%rbp), %rax", 16 + offset);
+                frame.store("%rax", param.reg);
+            }
+            (ArgTy::Byval(size), ArgLoc::Reg(reg)) => {
+                let copy = copy.expect("gen_prologue: struct parameter without a copy");
+                for i in 0..eightbytes(param.ty).unwrap_or(0) {
+                    let (reg64, reg8) = (ARG_REG64[reg + i], ARG_REG8[reg + i]);
+                    let size = eightbyte_size(size, i);
+                    store_eightbyte(reg64, reg8, "%rbp", copy + i as i64 * 8, size);
                 }
+                println!("    lea {}(%rbp), %rax", copy);
+                frame.store("%rax", param.reg);
+            }
+            (ArgTy::Byval(_), ArgLoc::Stack(offset)) => {
+                println!("    lea {}(%rbp), %rax", 16 + offset);
+                frame.store("%rax", param.reg);
+            }
+        }
+    }
+}
+
+/// Generate a function: its blocks in order between the prologue and
+/// the epilogue.
+fn gen_function(function: &Function) {
+    info!("gen function: {}", function.name);
+    let frame = Frame::new(function);
+    if function.is_static {
+        println!("  .local {}", function.name);
+    } else {
+        println!("  .globl {}", function.name);
+    }
+    println!("{}:", function.name);
+    gen_prologue(&frame);
+    let mut ret_buffers = frame.ret_buffers.iter().copied();
+    for (i, block) in function.blocks.iter().enumerate() {
+        println!("{}:", frame.label(BlockId(i)));
+        for inst in &block.insts {
+            gen_inst(inst, &frame, &mut ret_buffers);
+        }
+        gen_terminator(&block.term, BlockId(i), &frame);
+    }
+    println!(".L.return.{}:", function.name);
+    println!("    mov %rbp, %rsp");
+    println!("    pop %rbp");
+    println!("    ret");
+}
+
+/// Emit a global variable into `.data` with its initial value,
+/// or into `.bss` if it is zero initialized.
+/// Nothing is emitted for an `extern` declaration.
+fn gen_global(global: &Global) {
+    info!("gen global: {}", global.name);
+    if !global.is_definition {
+        return;
+    }
+    if global.is_static {
+        println!("  .local {}", global.name);
+    } else {
+        println!("  .globl {}", global.name);
+    }
+    let data = match global.init {
+        Some(ref data) => data,
+        None => {
+            println!("  .bss");
+            println!("  .align {}", global.align);
+            println!("{}:", global.name);
+            println!("  .zero {}", global.size);
+            return;
+        }
+    };
+    println!("  .data");
+    println!("  .align {}", global.align);
+    println!("{}:", global.name);
+    let mut offset = 0;
+    while offset < data.len() {
+        match global
+            .relocs
+            .iter()
+            .find(|reloc| reloc.offset == offset as i64)
+        {
+            Some(reloc) => {
+                println!("  .quad {}{:+}", reloc.label, reloc.addend);
+                offset += 8;
+            }
+            None => {
+                println!("  .byte {}", data[offset]);
+                offset += 1;
             }
         }
     }
 }
+
+/// Generate the assembly code of the whole module to stdout.
+pub fn gen_program(module: &Module) {
+    for global in &module.globals {
+        gen_global(global);
+    }
+    println!("  .text");
+    for function in &module.functions {
+        gen_function(function);
+    }
+}
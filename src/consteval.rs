@@ -0,0 +1,244 @@
+// Constant expressions are evaluated while parsing, where C requires a value
+// at compile time: array sizes, `case` labels, enumerators, bit-field widths,
+// `_Static_assert` and the initial values of objects with static storage.
+//
+// The evaluation follows the types of the AST, which carries the implicit
+// conversions: every operation is done in the type of its operands and its
+// result is converted to the type of the node. Unsigned arithmetic wraps
+// around, while an overflowing signed operation has no defined value, so it
+// is an error like a division by zero. A value is held in an `i64`: sign
+// extended from its type if the type is signed, zero extended otherwise,
+// the bits of an `unsigned long` being reinterpreted.
+//
+// The initial value of a static object may also be an address constant:
+// the address of an object or a function with static storage plus an integer,
+// given as the label of the object and the integer.
+
+use crate::ast::{BinaryOpType, Node, NodeKind, UnaryOpType};
+use crate::diagnostics::error;
+use crate::types::Type;
+
+/// Converts `value` to the integer type `ty`, wrapping it around
+/// the way a conversion at run time does.
+pub fn convert(value: i64, ty: &Type) -> i64 {
+    match ty {
+        Type::Bool => (value != 0) as i64,
+        Type::Char => value as i8 as i64,
+        Type::UChar => value as u8 as i64,
+        Type::Short => value as i16 as i64,
+        Type::UShort => value as u16 as i64,
+        Type::Int => value as i32 as i64,
+        Type::UInt => value as u32 as i64,
+        _ => value,
+    }
+}
+
+/// Evaluates an integer constant expression.
+///
+/// # Panics
+///
+/// Panics if the expression is not an integer constant, divides by zero
+/// or overflows.
+pub fn eval(node: &Node) -> i64 {
+    if !node.get_type().is_integer() {
+        error(
+            node.location,
+            &format!(
+                "expression of type {} is not an integer constant expression",
+                node.get_type()
+            ),
+        );
+    }
+    match eval_reloc(node) {
+        (value, None) => value,
+        (_, Some(label)) => error(
+            node.location,
+            &format!("address of {} is not an integer constant", label),
+        ),
+    }
+}
+
+/// Evaluates a constant expression, which may be an address constant.
+/// Return the value, or the addend and the label of an address constant.
+///
+/// # Panics
+///
+/// Panics if the expression is not constant, divides by zero or overflows.
+pub fn eval_reloc(node: &Node) -> (i64, Option<String>) {
+    let not_constant =
+        || -> ! { error(node.location, "expression is not a compile-time constant") };
+    // the operands of an arithmetic operation can't be addresses
+    let eval = |node: &Node| match eval_reloc(node) {
+        (value, None) => value,
+        _ => not_constant(),
+    };
+    let ty = node.get_type();
+    let (value, label) = match node.kind {
+        NodeKind::NUM(n) => (n, None),
+        // an array or function designator decays to its address
+        NodeKind::GVAR(_) | NodeKind::MEMBER { .. }
+            if matches!(ty, Type::Array(..) | Type::Func { .. }) =>
+        {
+            eval_addr(node)
+        }
+        NodeKind::CAST(ref operand) | NodeKind::CONV(ref operand) => match eval_reloc(operand) {
+            (value, Some(label)) if ty.size() == 8 => (value, Some(label)),
+            (value, None) => (value, None),
+            _ => not_constant(),
+        },
+        NodeKind::UNOP {
+            ref op,
+            ref operand,
+        } => match op {
+            UnaryOpType::Neg => (
+                arith(node, |_, value| value.wrapping_neg(), 0, eval(operand)),
+                None,
+            ),
+            UnaryOpType::BitNot => (!eval(operand), None),
+            UnaryOpType::Not => ((eval(operand) == 0) as i64, None),
+            UnaryOpType::Addr => eval_addr(operand),
+            UnaryOpType::Deref => not_constant(),
+        },
+        // the right operand is not evaluated if the left one decides
+        NodeKind::BINOP {
+            op: BinaryOpType::LogAnd,
+            ref lhs,
+            ref rhs,
+        } => ((eval(lhs) != 0 && eval(rhs) != 0) as i64, None),
+        NodeKind::BINOP {
+            op: BinaryOpType::LogOr,
+            ref lhs,
+            ref rhs,
+        } => ((eval(lhs) != 0 || eval(rhs) != 0) as i64, None),
+        // an address plus or minus a constant
+        NodeKind::BINOP {
+            ref op,
+            ref lhs,
+            ref rhs,
+        } if matches!(op, BinaryOpType::Add | BinaryOpType::Sub) => {
+            match (op, eval_reloc(lhs), eval_reloc(rhs)) {
+                (_, (lhs_value, None), (rhs_value, None)) => {
+                    (binop(node, op, lhs, lhs_value, rhs_value), None)
+                }
+                (BinaryOpType::Add, (lhs, Some(label)), (rhs, None))
+                | (BinaryOpType::Add, (lhs, None), (rhs, Some(label))) => {
+                    (lhs.wrapping_add(rhs), Some(label))
+                }
+                (BinaryOpType::Sub, (lhs, Some(label)), (rhs, None)) => {
+                    (lhs.wrapping_sub(rhs), Some(label))
+                }
+                _ => not_constant(),
+            }
+        }
+        NodeKind::BINOP {
+            ref op,
+            ref lhs,
+            ref rhs,
+        } => (binop(node, op, lhs, eval(lhs), eval(rhs)), None),
+        _ => not_constant(),
+    };
+    match label {
+        Some(label) => (value, Some(label)),
+        None => (convert(value, ty), None),
+    }
+}
+
+/// Return the result of the binary operation `node` on the values of its
+/// operands, which have been converted to the same type unless it is a shift.
+///
+/// # Panics
+///
+/// Panics if the operation overflows, divides by zero or shifts by
+/// a negative count or one not less than the width of the left operand.
+fn binop(node: &Node, op: &BinaryOpType, lhs_node: &Node, lhs: i64, rhs: i64) -> i64 {
+    let ty = lhs_node.get_type().decay();
+    let unsigned = ty.is_unsigned() || ty.base().is_some();
+    let (ulhs, urhs) = (lhs as u64, rhs as u64);
+    match op {
+        BinaryOpType::Add => arith(node, i128::wrapping_add, lhs, rhs),
+        BinaryOpType::Sub => arith(node, i128::wrapping_sub, lhs, rhs),
+        BinaryOpType::Mul => arith(node, i128::wrapping_mul, lhs, rhs),
+        BinaryOpType::Div | BinaryOpType::Mod if rhs == 0 => {
+            error(node.location, "division by zero in constant expression")
+        }
+        BinaryOpType::Div if unsigned => (ulhs / urhs) as i64,
+        BinaryOpType::Div => arith(node, i128::wrapping_div, lhs, rhs),
+        BinaryOpType::Mod if unsigned => (ulhs % urhs) as i64,
+        // the remainder overflows if the quotient does
+        BinaryOpType::Mod => {
+            arith(node, i128::wrapping_div, lhs, rhs);
+            lhs % rhs
+        }
+        BinaryOpType::BitAnd => lhs & rhs,
+        BinaryOpType::BitOr => lhs | rhs,
+        BinaryOpType::BitXor => lhs ^ rhs,
+        BinaryOpType::Shl | BinaryOpType::Shr => {
+            let bits = ty.size() * 8;
+            if rhs < 0 || rhs >= bits {
+                error(
+                    node.location,
+                    &format!("shift count {} is out of range for {}", rhs, ty),
+                );
+            }
+            match op {
+                // shifting a 1 into the sign bit is accepted like gcc does
+                BinaryOpType::Shl if !unsigned && lhs >= 0 && (ulhs >> (bits - 1 - rhs)) > 1 => {
+                    error(node.location, "overflow in constant expression")
+                }
+                BinaryOpType::Shl => ((lhs as i128) << rhs) as i64,
+                BinaryOpType::Shr if unsigned => (ulhs >> rhs) as i64,
+                _ => lhs >> rhs,
+            }
+        }
+        BinaryOpType::Eq => (lhs == rhs) as i64,
+        BinaryOpType::Ne => (lhs != rhs) as i64,
+        BinaryOpType::Lt if unsigned => (ulhs < urhs) as i64,
+        BinaryOpType::Lt => (lhs < rhs) as i64,
+        BinaryOpType::Le if unsigned => (ulhs <= urhs) as i64,
+        BinaryOpType::Le => (lhs <= rhs) as i64,
+        BinaryOpType::LogAnd | BinaryOpType::LogOr => unreachable!(),
+    }
+}
+
+/// Return the result of the arithmetic operation `f` done in the type of
+/// `node`: it wraps around if the type is unsigned.
+///
+/// # Panics
+///
+/// Panics if the type is signed and the result is not representable in it.
+fn arith(node: &Node, f: impl Fn(i128, i128) -> i128, lhs: i64, rhs: i64) -> i64 {
+    let ty = node.get_type();
+    if ty.is_unsigned() {
+        let (lhs, rhs) = (lhs as u64 as i128, rhs as u64 as i128);
+        return f(lhs, rhs) as i64;
+    }
+    let value = f(lhs as i128, rhs as i128);
+    if value as i64 as i128 != value || convert(value as i64, ty) != value as i64 {
+        error(node.location, "overflow in constant expression");
+    }
+    value as i64
+}
+
+/// Evaluates the address of an lvalue designating (a part of) an object
+/// with static storage.
+///
+/// # Panics
+///
+/// Panics if the address is not constant.
+fn eval_addr(node: &Node) -> (i64, Option<String>) {
+    match node.kind {
+        NodeKind::GVAR(ref name) => (0, Some(name.clone())),
+        NodeKind::MEMBER {
+            ref base,
+            ref member,
+        } => {
+            let (offset, label) = eval_addr(base);
+            (offset + member.offset, label)
+        }
+        NodeKind::UNOP {
+            op: UnaryOpType::Deref,
+            ref operand,
+        } => eval_reloc(operand),
+        _ => error(node.location, "address is not a compile-time constant"),
+    }
+}
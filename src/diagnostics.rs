@@ -0,0 +1,335 @@
+// Warnings report code which is valid C but likely not what was meant. Each
+// one has a name, the one of its `-W` option, and a level: ignored, reported
+// as a warning, or reported as an error which stops the compilation like any
+// other error. Only the ones gcc enables by default are enabled without an
+// option; `-Wall` and `-Wextra` enable groups of them as gcc does, while
+// `-Wname` and `-Wno-name` enable or disable one whatever the groups say.
+// `-Werror` makes every enabled warning an error, `-Werror=name` a single
+// one.
+//
+// The levels may also be changed for a part of the source with
+// `#pragma GCC diagnostic`. As the checks after parsing need the levels in
+// effect at the code they look at, the pragmas are recorded with their
+// locations instead of changing a single state.
+//
+// A warning is rendered like an error, its severity and location first, and
+// the option controlling it last:
+//
+//     warning: 3:9: unused variable x [-Wunused-variable]
+//     error: 4:5: use of undeclared identifier y
+
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::scanner::Location;
+
+/// A check reporting code which is valid but likely wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Warning {
+    /// a local variable which is never used
+    UnusedVariable,
+    /// a parameter which is never used in the body of its function
+    UnusedParameter,
+    /// a signed integer converted to unsigned to be compared
+    SignCompare,
+    /// a statement falling through to the next `case` label
+    ImplicitFallthrough,
+    /// a declaration hiding another one of an outer scope
+    Shadow,
+    /// operators whose precedence is often mistaken, without brackets
+    Parentheses,
+    /// the end of a non-void function reached without returning a value
+    ReturnType,
+    /// a statement which is never executed
+    UnreachableCode,
+    /// a function declared `_Noreturn` which may return
+    InvalidNoreturn,
+    /// a local variable read before it is assigned on every path
+    Uninitialized,
+    /// a local variable read before it is assigned on some paths
+    MaybeUninitialized,
+    /// a format string not matching the arguments of a call to `printf` or `scanf`
+    Format,
+    /// a pointer converted to a pointer to an incompatible type without a cast
+    IncompatiblePointerTypes,
+}
+
+impl Warning {
+    /// Every warning
+    pub const ALL: [Warning; 13] = [
+        Warning::UnusedVariable,
+        Warning::UnusedParameter,
+        Warning::SignCompare,
+        Warning::ImplicitFallthrough,
+        Warning::Shadow,
+        Warning::Parentheses,
+        Warning::ReturnType,
+        Warning::UnreachableCode,
+        Warning::InvalidNoreturn,
+        Warning::Uninitialized,
+        Warning::MaybeUninitialized,
+        Warning::Format,
+        Warning::IncompatiblePointerTypes,
+    ];
+
+    /// Return the name of the warning, the one of its option without `-W`
+    pub fn name(self) -> &'static str {
+        match self {
+            Warning::UnusedVariable => "unused-variable",
+            Warning::UnusedParameter => "unused-parameter",
+            Warning::SignCompare => "sign-compare",
+            Warning::ImplicitFallthrough => "implicit-fallthrough",
+            Warning::Shadow => "shadow",
+            Warning::Parentheses => "parentheses",
+            Warning::ReturnType => "return-type",
+            Warning::UnreachableCode => "unreachable-code",
+            Warning::InvalidNoreturn => "invalid-noreturn",
+            Warning::Uninitialized => "uninitialized",
+            Warning::MaybeUninitialized => "maybe-uninitialized",
+            Warning::Format => "format",
+            Warning::IncompatiblePointerTypes => "incompatible-pointer-types",
+        }
+    }
+
+    /// Return the warning named `name`
+    pub fn from_name(name: &str) -> Option<Warning> {
+        Warning::ALL
+            .iter()
+            .find(|warning| warning.name() == name)
+            .copied()
+    }
+
+    /// Return true if the warning is enabled without an option
+    fn by_default(self) -> bool {
+        matches!(self, Warning::IncompatiblePointerTypes)
+    }
+
+    /// Return true if the warning is enabled by `-Wall`
+    fn in_all(self) -> bool {
+        matches!(
+            self,
+            Warning::UnusedVariable
+                | Warning::Parentheses
+                | Warning::ReturnType
+                | Warning::InvalidNoreturn
+                | Warning::Uninitialized
+                | Warning::MaybeUninitialized
+                | Warning::Format
+        )
+    }
+
+    /// Return true if the warning is enabled by `-Wextra`
+    fn in_extra(self) -> bool {
+        matches!(
+            self,
+            Warning::UnusedParameter | Warning::SignCompare | Warning::ImplicitFallthrough
+        )
+    }
+}
+
+/// How a warning is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ignored,
+    Warning,
+    Error,
+}
+
+/// The levels of the warnings and where they change.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    /// `-Wall` and `-Wextra`
+    all: bool,
+    extra: bool,
+    /// `-Werror`
+    errors: bool,
+    /// the warnings enabled or disabled by name, overriding the groups
+    enabled: HashMap<Warning, bool>,
+    /// the warnings made errors or not by name, overriding `-Werror`
+    error: HashMap<Warning, bool>,
+    /// the levels set by pragmas, from their locations on
+    changes: Vec<(Location, HashMap<Warning, Level>)>,
+    /// the levels saved by `#pragma GCC diagnostic push`
+    saved: Vec<HashMap<Warning, Level>>,
+    /// how many warnings have been reported
+    count: usize,
+}
+
+impl Diagnostics {
+    /// Constructs diagnostics with the warnings enabled by default only
+    pub fn new() -> Diagnostics {
+        Diagnostics::default()
+    }
+
+    /// Applies the command line option `option`: `-Wall`, `-Wextra`,
+    /// `-Werror`, or `-W[no-][error=]name`.
+    /// Return an error message if the option is unknown.
+    pub fn option(&mut self, option: &str) -> Result<(), String> {
+        let unknown = || format!("unknown warning option '{}'", option);
+        let name = option.strip_prefix("-W").ok_or_else(unknown)?;
+        let (name, on) = match name.strip_prefix("no-") {
+            Some(name) => (name, false),
+            None => (name, true),
+        };
+        match name {
+            "all" => self.all = on,
+            "extra" => self.extra = on,
+            "error" => self.errors = on,
+            _ => {
+                let (name, is_error) = match name.strip_prefix("error=") {
+                    Some(name) => (name, true),
+                    None => (name, false),
+                };
+                let warning = Warning::from_name(name).ok_or_else(unknown)?;
+                if !is_error {
+                    self.enabled.insert(warning, on);
+                } else {
+                    self.error.insert(warning, on);
+                    // `-Werror=name` also enables the warning
+                    if on {
+                        self.enabled.insert(warning, true);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the level of `warning` set on the command line
+    fn command_line_level(&self, warning: Warning) -> Level {
+        let group = warning.by_default()
+            || (self.all && warning.in_all())
+            || (self.extra && warning.in_extra());
+        if !self.enabled.get(&warning).copied().unwrap_or(group) {
+            Level::Ignored
+        } else if self.error.get(&warning).copied().unwrap_or(self.errors) {
+            Level::Error
+        } else {
+            Level::Warning
+        }
+    }
+
+    /// Return the level of `warning` in effect at `location`
+    pub fn level(&self, warning: Warning, location: Location) -> Level {
+        self.changes
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= location)
+            .and_then(|(_, levels)| levels.get(&warning).copied())
+            .unwrap_or_else(|| self.command_line_level(warning))
+    }
+
+    /// Applies the pragma at `location` whose text after `#pragma` is `text`.
+    /// Only the `GCC diagnostic` ones are understood, the others are ignored
+    /// like gcc does:
+    ///
+    /// pragma = 'GCC' 'diagnostic' ('push' | 'pop' | level '"-Wname"')
+    /// level = 'ignored' | 'warning' | 'error'
+    ///
+    /// # Panics
+    ///
+    /// Panics if a diagnostic pragma is malformed or names an unknown warning.
+    pub fn pragma(&mut self, location: Location, text: &str) {
+        debug!(" pragma at {}: {}", location, text);
+        let words = text.split_whitespace().collect::<Vec<_>>();
+        if !matches!(words.as_slice(), ["GCC" | "clang", "diagnostic", ..]) {
+            return;
+        }
+        let mut levels = self
+            .changes
+            .last()
+            .map(|(_, levels)| levels.clone())
+            .unwrap_or_default();
+        match words[2..] {
+            ["push"] => {
+                self.saved.push(levels);
+                return;
+            }
+            ["pop"] => levels = self.saved.pop().unwrap_or_default(),
+            [kind, option] => {
+                let level = match kind {
+                    "ignored" => Level::Ignored,
+                    "warning" => Level::Warning,
+                    "error" => Level::Error,
+                    _ => error(
+                        location,
+                        &format!(
+                            "unknown diagnostic kind '{}', expected ignored, warning or error",
+                            kind
+                        ),
+                    ),
+                };
+                let warning = option
+                    .strip_prefix("\"-W")
+                    .and_then(|name| name.strip_suffix('"'))
+                    .and_then(Warning::from_name)
+                    .unwrap_or_else(|| {
+                        error(
+                            location,
+                            &format!("unknown warning option {} in diagnostic pragma", option),
+                        )
+                    });
+                levels.insert(warning, level);
+            }
+            _ => error(
+                location,
+                "expected push, pop, or a diagnostic kind and a warning option",
+            ),
+        }
+        self.changes.push((location, levels));
+    }
+
+    /// Reports `warning` at `location` with `message`, if it is enabled there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the warning is an error there.
+    pub fn warn(&mut self, warning: Warning, location: Location, message: &str) {
+        match self.level(warning, location) {
+            Level::Ignored => {}
+            Level::Warning => {
+                self.count += 1;
+                let flag = format!("-W{}", warning.name());
+                eprintln!("{}", render("warning", location, message, &flag));
+            }
+            Level::Error => {
+                let flag = format!("-Werror={}", warning.name());
+                panic!("{}", render("error", location, message, &flag));
+            }
+        }
+    }
+
+    /// Reports a warning at `location` with `message` which no option turns
+    /// off, like those gcc always gives. `-Werror` still makes it an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if warnings are errors.
+    pub fn warn_always(&mut self, location: Location, message: &str) {
+        if self.errors {
+            panic!("{}", render("error", location, message, "-Werror"));
+        }
+        self.count += 1;
+        eprintln!("{}", render("warning", location, message, ""));
+    }
+
+    /// Return how many warnings have been reported
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Return a diagnostic of `severity` at `location`, with the option
+/// controlling it if there is one.
+pub fn render(severity: &str, location: Location, message: &str, flag: &str) -> String {
+    match flag {
+        "" => format!("{}: {}: {}", severity, location, message),
+        _ => format!("{}: {}: {} [{}]", severity, location, message, flag),
+    }
+}
+
+/// Stops the compilation with an error at `location`.
+pub fn error(location: Location, message: &str) -> ! {
+    panic!("{}", render("error", location, message, ""))
+}
@@ -0,0 +1,270 @@
+// The flow analysis follows the control through the statements of each
+// function to tell which statements can be reached and which can complete,
+// the control going on after them. From that it warns about the end of
+// a non-void function being reached without a value returned, statements
+// which are never executed, and a `case` label reached by falling through
+// the statements of the one before. A null statement before the label, such
+// as `__attribute__((fallthrough));`, marks the fall through as intended.
+//
+// Only the structure of the statements is followed, not the values of the
+// conditions: both branches of an `if` are taken as reachable. A loop is
+// infinite if it has no condition or a nonzero constant one, so only a
+// `break` leaves it. A call to a function declared `_Noreturn`, or to one
+// of the C library known never to return, doesn't complete, neither does
+// `__builtin_unreachable()`.
+//
+// A label may be jumped to by a `goto` after it, so the statements are
+// followed again until no more labels are found reachable. The last pass
+// reports the warnings.
+
+use std::collections::HashSet;
+
+use log::debug;
+
+use crate::ast::{Function, Node, NodeKind, Program};
+use crate::diagnostics::{Diagnostics, Warning};
+use crate::scanner::Location;
+use crate::types::Type;
+
+/// Analyzes every function of the program, reporting the warnings to `diagnostics`.
+pub fn check_program(program: &Program, diagnostics: &mut Diagnostics) {
+    for function in &program.functions {
+        check_function(function, diagnostics);
+    }
+}
+
+/// Analyzes the body of a function.
+fn check_function(function: &Function, diagnostics: &mut Diagnostics) {
+    debug!("flow of function: {}", function.name);
+    let mut flow = Flow {
+        function,
+        diagnostics: None,
+        labels: HashSet::new(),
+        breaks: Vec::new(),
+        continues: Vec::new(),
+        switches: Vec::new(),
+        returns: false,
+    };
+    loop {
+        let reached = flow.labels.len();
+        flow.stmt(&function.body, true);
+        if flow.labels.len() == reached {
+            break;
+        }
+    }
+    flow.diagnostics = Some(diagnostics);
+    flow.returns = false;
+    if !flow.stmt(&function.body, true) {
+        return;
+    }
+    if function.is_noreturn {
+        flow.warn(
+            Warning::InvalidNoreturn,
+            function.location,
+            "function declared _Noreturn should not return",
+        );
+    } else if function.ret != Type::Void && function.name != "main" {
+        // reaching the end of `main` returns 0
+        let message = match flow.returns {
+            true => format!(
+                "non-void function {} does not return a value in all control paths",
+                function.name
+            ),
+            false => format!(
+                "non-void function {} does not return a value",
+                function.name
+            ),
+        };
+        flow.warn(Warning::ReturnType, function.location, &message);
+    }
+}
+
+/// The state of the analysis of a function.
+struct Flow<'a> {
+    function: &'a Function,
+    /// where the warnings are reported, None until the last pass
+    diagnostics: Option<&'a mut Diagnostics>,
+    /// the labels a reachable `goto` jumps to
+    labels: HashSet<String>,
+    /// for each enclosing loop or `switch`, innermost last,
+    /// true if a reachable `break` leaves it
+    breaks: Vec<bool>,
+    /// for each enclosing loop, true if a reachable `continue` goes on with it
+    continues: Vec<bool>,
+    /// for each enclosing `switch`, true if it is reachable, so are its labels
+    switches: Vec<bool>,
+    /// true if a reachable `return` has been found
+    returns: bool,
+}
+
+impl Flow<'_> {
+    /// Reports `warning` in the last pass.
+    fn warn(&mut self, warning: Warning, location: Location, message: &str) {
+        if let Some(ref mut diagnostics) = self.diagnostics {
+            diagnostics.warn(warning, location, message);
+        }
+    }
+
+    /// Follows the statement `node`, which the control reaches from the
+    /// statement before it if `reachable` is true.
+    /// Return true if it may complete.
+    fn stmt(&mut self, node: &Node, reachable: bool) -> bool {
+        match node.kind {
+            NodeKind::STMT(ref expr) => reachable && !never_returns(expr),
+            NodeKind::RETURN(_) => {
+                if reachable {
+                    self.returns = true;
+                    if self.function.is_noreturn {
+                        self.warn(
+                            Warning::InvalidNoreturn,
+                            node.location,
+                            "function declared _Noreturn should not return",
+                        );
+                    }
+                }
+                false
+            }
+            NodeKind::BLOCK(ref stmts) => self.block(stmts, reachable),
+            NodeKind::IF {
+                ref then, ref els, ..
+            } => {
+                let then = self.stmt(then, reachable);
+                let els = match els {
+                    Some(els) => self.stmt(els, reachable),
+                    None => reachable,
+                };
+                then || els
+            }
+            NodeKind::FOR {
+                ref cond, ref body, ..
+            } => {
+                self.breaks.push(false);
+                self.continues.push(false);
+                let completes = self.stmt(body, reachable);
+                let continued = self.continues.pop().unwrap();
+                let broken = self.breaks.pop().unwrap();
+                let tested = reachable || completes || continued;
+                let infinite = cond.as_deref().is_none_or(is_true_constant);
+                (tested && !infinite) || broken
+            }
+            // the body is entered at its labels only
+            NodeKind::SWITCH {
+                ref body,
+                has_default,
+                ..
+            } => {
+                self.breaks.push(false);
+                self.switches.push(reachable);
+                let completes = self.stmt(body, false);
+                self.switches.pop();
+                let broken = self.breaks.pop().unwrap();
+                completes || broken || (reachable && !has_default)
+            }
+            NodeKind::CASE { ref body, .. } | NodeKind::LABEL { ref body, .. } => {
+                let entered = self.entered(node, reachable);
+                self.stmt(body, entered)
+            }
+            NodeKind::BREAK => {
+                if let (true, Some(broken)) = (reachable, self.breaks.last_mut()) {
+                    *broken = true;
+                }
+                false
+            }
+            NodeKind::CONTINUE => {
+                if let (true, Some(continued)) = (reachable, self.continues.last_mut()) {
+                    *continued = true;
+                }
+                false
+            }
+            NodeKind::GOTO(ref name) => {
+                if reachable {
+                    self.labels.insert(name.clone());
+                }
+                false
+            }
+            _ => reachable,
+        }
+    }
+
+    /// Follows the statements of a block, warning about the first one of
+    /// each run of statements the control never reaches, and about those
+    /// falling through to a `case` label.
+    fn block(&mut self, stmts: &[Node], mut reachable: bool) -> bool {
+        // an unreachable block is reported by the statement it is part of
+        let mut reported = !reachable;
+        let mut previous: Option<&Node> = None;
+        for stmt in stmts {
+            if let (true, NodeKind::CASE { .. }, Some(previous)) = (reachable, &stmt.kind, previous)
+            {
+                let last = last_stmt(previous);
+                if !is_empty(last) {
+                    self.warn(
+                        Warning::ImplicitFallthrough,
+                        last.location,
+                        "this statement may fall through",
+                    );
+                }
+            }
+            if self.entered(stmt, reachable) {
+                reported = false;
+            } else if !reported && !is_empty(stmt) && !matches!(stmt.kind, NodeKind::BREAK) {
+                // a `break` after a `return` is left alone, it is a common idiom
+                self.warn(
+                    Warning::UnreachableCode,
+                    stmt.location,
+                    "code will never be executed",
+                );
+                reported = true;
+            }
+            reachable = self.stmt(stmt, reachable);
+            previous = Some(stmt);
+        }
+        reachable
+    }
+
+    /// Return true if the control may enter the statement `node`, from the
+    /// statement before it if `reachable` is true, or by jumping to its label.
+    fn entered(&self, node: &Node, reachable: bool) -> bool {
+        reachable
+            || match node.kind {
+                NodeKind::CASE { .. } => self.switches.last().copied().unwrap_or(false),
+                NodeKind::LABEL { ref name, .. } => self.labels.contains(name),
+                _ => false,
+            }
+    }
+}
+
+/// Return the statement of `node` executed last when it completes:
+/// the last one of a block, the one following a label.
+fn last_stmt(node: &Node) -> &Node {
+    match node.kind {
+        NodeKind::BLOCK(ref stmts) if !stmts.is_empty() => last_stmt(stmts.last().unwrap()),
+        NodeKind::CASE { ref body, .. } | NodeKind::LABEL { ref body, .. } => last_stmt(body),
+        _ => node,
+    }
+}
+
+/// Return true if `node` is an empty statement.
+fn is_empty(node: &Node) -> bool {
+    matches!(node.kind, NodeKind::BLOCK(ref stmts) if stmts.is_empty())
+}
+
+/// Return true if evaluating the expression `node` never completes:
+/// a call to a function which never returns, or `__builtin_unreachable()`.
+fn never_returns(node: &Node) -> bool {
+    match node.kind {
+        NodeKind::CALL { noreturn, .. } => noreturn,
+        NodeKind::UNREACHABLE => true,
+        NodeKind::CAST(ref operand) | NodeKind::CONV(ref operand) => never_returns(operand),
+        _ => false,
+    }
+}
+
+/// Return true if the condition `node` is a nonzero constant.
+fn is_true_constant(node: &Node) -> bool {
+    match node.kind {
+        NodeKind::NUM(value) => value != 0,
+        NodeKind::CAST(ref operand) | NodeKind::CONV(ref operand) => is_true_constant(operand),
+        _ => false,
+    }
+}
@@ -0,0 +1,416 @@
+// The format checks validate the calls to the functions taking a format
+// string, such as `printf` and `scanf`, when the format is a string literal.
+// The literal is parsed into its conversion specifications, each of which
+// converts the next arguments: the `*` width and precision of printf take
+// an `int`, then the conversion takes a value of a type set by its length
+// modifier and its conversion character, or a pointer to one for scanf.
+// The types are the ones of the arguments before the default argument
+// promotions. Like gcc, the signedness of an integer is not checked, `%u`
+// may print an `int`.
+//
+// A warning is reported for an invalid specification, for an argument of
+// the wrong type, with the specification fitting the argument suggested,
+// for a missing argument and for arguments left over. A format which is
+// not a literal can't be checked, passing one without arguments is
+// reported as the string may contain conversions. Neither are the arguments
+// of a format numbering them, `%2$s %1$d`, only its specifications.
+
+use log::debug;
+
+use crate::ast::{Format, FormatKind, Node, NodeKind, UnaryOpType};
+use crate::diagnostics::{Diagnostics, Warning};
+use crate::types::Type;
+
+/// A conversion specification of a format string.
+#[derive(Debug)]
+struct Spec {
+    /// the specification as written, from '%' to the conversion character
+    text: String,
+    /// the flags, width and precision as written, kept by the suggestions
+    options: String,
+    /// how many `int` arguments the `*` width and precision of printf take
+    stars: usize,
+    /// true if scanf is told by `*` not to assign the converted value
+    suppressed: bool,
+    /// true if the specification numbers its argument, `%1$d`
+    positional: bool,
+    length: &'static str,
+    conversion: u8,
+}
+
+/// The type of argument a conversion takes.
+#[derive(Debug)]
+enum Expected {
+    /// an integer of this type once promoted, of either signedness
+    Integer(Type),
+    /// a pointer to this type, `void` for any object
+    Pointer(Type),
+    /// a floating type, which the compiler doesn't have
+    Floating(&'static str),
+}
+
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Integer(ty) => write!(f, "{}", ty),
+            Expected::Pointer(ty) => write!(f, "{}", Type::pointer_to(ty.clone())),
+            Expected::Floating(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Checks the arguments `args` of a call to a function taking the format
+/// string `format`, `literal` being the bytes of the format if it is a
+/// string literal, without the terminating null character.
+pub fn check_call(
+    diagnostics: &mut Diagnostics,
+    format: &Format,
+    literal: Option<&[u8]>,
+    args: &[Node],
+) {
+    let Some(string) = args.get(format.string) else {
+        return;
+    };
+    let Some(literal) = literal else {
+        if format.first.is_some_and(|first| args.len() <= first) {
+            diagnostics.warn(
+                Warning::Format,
+                string.location,
+                "format string is not a string literal and there are no format arguments; \
+                 did you mean to print it with \"%s\"?",
+            );
+        }
+        return;
+    };
+    debug!(" check format: {}", String::from_utf8_lossy(literal));
+    let specs = match parse(format.kind, literal) {
+        Ok(specs) => specs,
+        Err(message) => {
+            diagnostics.warn(Warning::Format, string.location, &message);
+            return;
+        }
+    };
+    // the arguments of a `va_list` can't be checked, nor the numbered ones
+    let Some(first) = format.first else {
+        return;
+    };
+    if specs.iter().any(|spec| spec.positional) {
+        return;
+    }
+    let mut next = first;
+    for spec in &specs {
+        // the `*` width and precision take an `int` whatever the conversion is
+        let stars = (0..spec.stars).map(|_| (Expected::Integer(Type::Int), true));
+        let converted = match spec.suppressed || spec.conversion == b'%' {
+            true => None,
+            false => Some((expected(format.kind, spec), false)),
+        };
+        for (expected, star) in stars.chain(converted) {
+            let Some(arg) = args.get(next) else {
+                let message = format!(
+                    "format '{}' expects a matching {} argument",
+                    spec.text, expected
+                );
+                diagnostics.warn(Warning::Format, string.location, &message);
+                return;
+            };
+            if !matches(&expected, &arg.get_type().decay()) {
+                let mut message = format!(
+                    "format '{}' expects an argument of type {}, but argument {} has type {}",
+                    spec.text,
+                    expected,
+                    next + 1,
+                    written_type(arg)
+                );
+                if let Some(fix) = fix_it(format.kind, spec, &expected, arg).filter(|_| !star) {
+                    message = format!("{}; {}", message, fix);
+                }
+                diagnostics.warn(Warning::Format, arg.location, &message);
+            }
+            next += 1;
+        }
+    }
+    if let Some(arg) = args.get(next) {
+        diagnostics.warn(
+            Warning::Format,
+            arg.location,
+            "too many arguments for format",
+        );
+    }
+}
+
+/// Parses the format string `literal` into its conversion specifications
+/// according to the rules:
+/// printf_spec = '%' position? flag* ('*' position? | digit*) ('.' ('*' position? | digit*))?
+///               length conversion
+/// scanf_spec = '%' position? '*'? digit* length (conversion | '[' '^'? ']'? [^\]]* ']')
+/// position = digit+ '$'
+/// flag = '-' | '+' | ' ' | '#' | '0' | '\''
+/// length = ('hh' | 'h' | 'll' | 'l' | 'j' | 'z' | 't' | 'L')?
+/// `%%` is a specification converting no argument.
+/// Return the message of the warning if the format is invalid.
+fn parse(kind: FormatKind, literal: &[u8]) -> Result<Vec<Spec>, String> {
+    if literal.contains(&0) {
+        return Err("embedded '\\0' in format".to_owned());
+    }
+    let mut specs = Vec::new();
+    let mut index = 0;
+    while index < literal.len() {
+        if literal[index] != b'%' {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        index += 1;
+        let mut stars = 0;
+        let mut suppressed = false;
+        let digits = |index: &mut usize| {
+            while literal.get(*index).is_some_and(u8::is_ascii_digit) {
+                *index += 1;
+            }
+        };
+        // the number of an argument, `1$`
+        let position = |index: &mut usize| {
+            let mut end = *index;
+            digits(&mut end);
+            let numbered = end > *index && literal.get(end) == Some(&b'$');
+            if numbered {
+                *index = end + 1;
+            }
+            numbered
+        };
+        let positional = position(&mut index);
+        let options_start = index;
+        match kind {
+            FormatKind::Printf => {
+                while literal.get(index).is_some_and(|c| b"-+ #0'".contains(c)) {
+                    index += 1;
+                }
+                for precision in [false, true] {
+                    if precision {
+                        if literal.get(index) != Some(&b'.') {
+                            break;
+                        }
+                        index += 1;
+                    }
+                    if literal.get(index) == Some(&b'*') {
+                        stars += 1;
+                        index += 1;
+                        position(&mut index);
+                    } else {
+                        digits(&mut index);
+                    }
+                }
+            }
+            FormatKind::Scanf => {
+                if literal.get(index) == Some(&b'*') {
+                    suppressed = true;
+                    index += 1;
+                }
+                digits(&mut index);
+            }
+        }
+        let options = String::from_utf8_lossy(&literal[options_start..index]).into_owned();
+        let length = ["hh", "h", "ll", "l", "j", "z", "t", "L"]
+            .into_iter()
+            .find(|length| literal[index..].starts_with(length.as_bytes()))
+            .unwrap_or("");
+        index += length.len();
+        let Some(&conversion) = literal.get(index) else {
+            return Err(match index - start {
+                1 => "spurious trailing '%' in format".to_owned(),
+                _ => "conversion lacks type at end of format".to_owned(),
+            });
+        };
+        index += 1;
+        let known: &[u8] = match kind {
+            FormatKind::Printf => b"diouxXcspnfFeEgGaA%",
+            FormatKind::Scanf => b"diouxXcspnfFeEgGaA%[",
+        };
+        if !known.contains(&conversion) || (conversion == b'%' && index - start > 2) {
+            return Err(format!(
+                "unknown conversion type character '{}' in format",
+                conversion.escape_ascii()
+            ));
+        }
+        if conversion == b'[' {
+            // a ']' first is part of the scan set
+            let set = index + usize::from(literal.get(index) == Some(&b'^'));
+            let set = set + usize::from(literal.get(set) == Some(&b']'));
+            match literal[set..].iter().position(|&c| c == b']') {
+                Some(end) => index = set + end + 1,
+                None => return Err("no closing ']' for '%[' format".to_owned()),
+            }
+        }
+        let valid_length = match conversion {
+            b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'n' => true,
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A' => {
+                matches!(length, "" | "l" | "L")
+            }
+            // the wide characters and strings
+            b'c' | b's' | b'[' => matches!(length, "" | "l"),
+            _ => length.is_empty(),
+        };
+        let text = String::from_utf8_lossy(&literal[start..index]).into_owned();
+        if !valid_length {
+            return Err(format!(
+                "use of '{}' length modifier with '{}' type character in format '{}'",
+                length, conversion as char, text
+            ));
+        }
+        specs.push(Spec {
+            text,
+            options,
+            stars,
+            suppressed,
+            positional,
+            length,
+            conversion,
+        });
+    }
+    Ok(specs)
+}
+
+/// Return the type of argument the conversion of `spec` takes.
+fn expected(kind: FormatKind, spec: &Spec) -> Expected {
+    let unsigned = matches!(spec.conversion, b'o' | b'u' | b'x' | b'X');
+    let integer = match (spec.length, unsigned) {
+        ("hh", false) => Type::Char,
+        ("hh", true) => Type::UChar,
+        ("h", false) => Type::Short,
+        ("h", true) => Type::UShort,
+        ("l" | "j" | "t", false) => Type::Long,
+        ("l" | "j" | "t", true) | ("z", _) => Type::ULong,
+        ("ll", false) => Type::LongLong,
+        ("ll", true) => Type::ULongLong,
+        (_, false) => Type::Int,
+        (_, true) => Type::UInt,
+    };
+    match (kind, spec.conversion) {
+        (FormatKind::Printf, b'd' | b'i' | b'o' | b'u' | b'x' | b'X') => {
+            Expected::Integer(integer.promote())
+        }
+        // a `wint_t` is an `unsigned int`
+        (FormatKind::Printf, b'c') if spec.length == "l" => Expected::Integer(Type::UInt),
+        (FormatKind::Printf, b'c') => Expected::Integer(Type::Int),
+        (FormatKind::Printf, b'p') => Expected::Pointer(Type::Void),
+        (FormatKind::Printf, b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A') => {
+            match spec.length {
+                "L" => Expected::Floating("long double"),
+                _ => Expected::Floating("double"),
+            }
+        }
+        (FormatKind::Scanf, b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A') => {
+            match spec.length {
+                "l" => Expected::Floating("double *"),
+                "L" => Expected::Floating("long double *"),
+                _ => Expected::Floating("float *"),
+            }
+        }
+        (FormatKind::Scanf, b'p') => Expected::Pointer(Type::pointer_to(Type::Void)),
+        // a `wchar_t` is an `int`
+        (_, b's' | b'c' | b'[') if spec.length == "l" => Expected::Pointer(Type::Int),
+        (_, b's' | b'c' | b'[') => Expected::Pointer(Type::Char),
+        _ => Expected::Pointer(integer),
+    }
+}
+
+/// Return true if an argument of type `ty` is what the conversion expects.
+fn matches(expected: &Expected, ty: &Type) -> bool {
+    match (expected, ty) {
+        (Expected::Integer(expected), _) => {
+            ty.is_integer() && signed(&ty.promote()) == signed(expected)
+        }
+        (Expected::Pointer(expected), Type::Ptr(base)) => match (expected, &**base) {
+            (Type::Void, _) | (_, Type::Void) => true,
+            (Type::Ptr(inner), Type::Ptr(_)) if **inner == Type::Void => true,
+            (expected, base) if expected.is_integer() && base.is_integer() => {
+                signed(base) == signed(expected)
+            }
+            (expected, base) => expected == base,
+        },
+        _ => false,
+    }
+}
+
+/// Return the signed type of the same rank as the integer type `ty`.
+fn signed(ty: &Type) -> Type {
+    match ty {
+        Type::UChar => Type::Char,
+        Type::UShort => Type::Short,
+        Type::UInt => Type::Int,
+        Type::ULong => Type::Long,
+        Type::ULongLong => Type::LongLong,
+        ty => ty.clone(),
+    }
+}
+
+/// Return the argument `arg` as written, before the default argument promotions.
+fn unconverted(arg: &Node) -> &Node {
+    match arg.kind {
+        NodeKind::CONV(ref operand) => operand,
+        _ => arg,
+    }
+}
+
+/// Return the type of the argument `arg` as written, an array decayed.
+fn written_type(arg: &Node) -> Type {
+    unconverted(arg).get_type().decay()
+}
+
+/// Return the suggestion fixing the specification `spec` which doesn't
+/// take the argument `arg`, None if no specification takes it.
+fn fix_it(kind: FormatKind, spec: &Spec, expected: &Expected, arg: &Node) -> Option<String> {
+    let ty = written_type(arg);
+    let integer = |ty: &Type, promoted: bool| {
+        let length = match ty {
+            Type::Char | Type::UChar if !promoted => "hh",
+            Type::Short | Type::UShort if !promoted => "h",
+            Type::Long | Type::ULong => "l",
+            Type::LongLong | Type::ULongLong => "ll",
+            _ => "",
+        };
+        let conversion = match spec.conversion {
+            conversion @ (b'd' | b'i' | b'o' | b'u' | b'x' | b'X') => conversion as char,
+            _ if ty.is_unsigned() => 'u',
+            _ => 'd',
+        };
+        format!("did you mean '%{}{}{}'?", spec.options, length, conversion)
+    };
+    match (kind, &ty) {
+        (FormatKind::Printf, ty) if ty.is_integer() => Some(integer(ty, true)),
+        (FormatKind::Printf, Type::Ptr(base)) => match **base {
+            Type::Char | Type::UChar => Some(format!("did you mean '%{}s'?", spec.options)),
+            _ => Some(format!("did you mean '%{}p'?", spec.options)),
+        },
+        // a string is read in a `char` array
+        (FormatKind::Scanf, Type::Ptr(base))
+            if matches!(unconverted(arg).get_type(), Type::Array(..))
+                && matches!(**base, Type::Char | Type::UChar) =>
+        {
+            Some(format!("did you mean '%{}s'?", spec.options))
+        }
+        (FormatKind::Scanf, Type::Ptr(base)) if base.is_integer() => Some(integer(base, false)),
+        (FormatKind::Scanf, ty)
+            if matches(expected, &Type::pointer_to(ty.clone())) && is_lvalue(arg) =>
+        {
+            Some("did you mean to pass its address with '&'?".to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Return true if the expression `node` designates an object whose
+/// address can be taken.
+fn is_lvalue(node: &Node) -> bool {
+    matches!(
+        unconverted(node).kind,
+        NodeKind::VAR(_)
+            | NodeKind::GVAR(_)
+            | NodeKind::MEMBER { .. }
+            | NodeKind::UNOP {
+                op: UnaryOpType::Deref,
+                ..
+            }
+    )
+}
@@ -0,0 +1,779 @@
+// The intermediate representation sits between the AST and the code
+// generator. A function is a list of basic blocks, each a list of
+// instructions ending in a terminator, the only place where the control
+// leaves a block. Instructions compute values into virtual registers,
+// as many as needed, each assigned once by a single instruction; the local
+// variables live in stack slots, read and written by loads and stores
+// through their addresses.
+//
+// The IR is typed but knows nothing of C: a value is an integer of 8, 16,
+// 32 or 64 bits, or a pointer. The signedness of the C types is in the
+// operations instead (`sdiv` and `udiv`, `sext` and `zext`). A struct or
+// union is handled through its address, and copied as bytes; passed to
+// or returned by a function, it is a `byval` of its size, the target
+// deciding how it goes through registers or memory.
+//
+// The IR prints as text, which `irparse` reads back into the same IR:
+//
+//     function @f(%0: i32) -> i32 {
+//       $0 = slot "x", size 4, align 4
+//     bb0:
+//       %1: ptr = slotaddr $0
+//       store i32 %0, %1
+//       %2: i32 = load i32 %1
+//       %3: i32 = slt i32 %2, 10
+//       branch i32 %3, bb1, bb2
+//     bb1:
+//       ret %2
+//     bb2:
+//       ret 0
+//     }
+//
+// Every register is written with its type where it is assigned, the other
+// types are those of the operands. A phi lists the operand of each
+// predecessor after its block: `%5: i32 = phi i32 bb1: %3, bb2: 0`.
+
+use std::fmt;
+
+/// The type of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ty {
+    I8,
+    I16,
+    I32,
+    I64,
+    Ptr,
+}
+
+impl Ty {
+    /// Every type
+    pub const ALL: [Ty; 5] = [Ty::I8, Ty::I16, Ty::I32, Ty::I64, Ty::Ptr];
+
+    /// Return the name of the type in the text of the IR
+    pub fn name(self) -> &'static str {
+        match self {
+            Ty::I8 => "i8",
+            Ty::I16 => "i16",
+            Ty::I32 => "i32",
+            Ty::I64 => "i64",
+            Ty::Ptr => "ptr",
+        }
+    }
+
+    /// Return the size of a value of the type in bytes
+    pub fn size(self) -> i64 {
+        match self {
+            Ty::I8 => 1,
+            Ty::I16 => 2,
+            Ty::I32 => 4,
+            Ty::I64 | Ty::Ptr => 8,
+        }
+    }
+}
+
+/// A virtual register, the index into `Function::regs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Reg(pub usize);
+
+/// A basic block, the index into `Function::blocks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub usize);
+
+/// A stack slot, the index into `Function::slots`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SlotId(pub usize);
+
+/// An operand of an instruction: a register or a constant, whose type is
+/// given by the instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Reg(Reg),
+    Const(i64),
+}
+
+/// The type of a value passed to or returned by a function: a scalar,
+/// or a struct or union of that many bytes passed by its address.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgTy {
+    Scalar(Ty),
+    Byval(i64),
+}
+
+/// An operation on two values of the same type. The comparisons give
+/// 1 if they hold, 0 otherwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+    UDiv,
+    SRem,
+    URem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    /// logical shift right, filling with zeros
+    LShr,
+    /// arithmetic shift right, filling with the sign bit
+    AShr,
+    Eq,
+    Ne,
+    SLt,
+    SLe,
+    ULt,
+    ULe,
+}
+
+impl BinOp {
+    /// Every binary operation
+    pub const ALL: [BinOp; 19] = [
+        BinOp::Add,
+        BinOp::Sub,
+        BinOp::Mul,
+        BinOp::SDiv,
+        BinOp::UDiv,
+        BinOp::SRem,
+        BinOp::URem,
+        BinOp::And,
+        BinOp::Or,
+        BinOp::Xor,
+        BinOp::Shl,
+        BinOp::LShr,
+        BinOp::AShr,
+        BinOp::Eq,
+        BinOp::Ne,
+        BinOp::SLt,
+        BinOp::SLe,
+        BinOp::ULt,
+        BinOp::ULe,
+    ];
+
+    /// Return the name of the operation in the text of the IR
+    pub fn name(self) -> &'static str {
+        match self {
+            BinOp::Add => "add",
+            BinOp::Sub => "sub",
+            BinOp::Mul => "mul",
+            BinOp::SDiv => "sdiv",
+            BinOp::UDiv => "udiv",
+            BinOp::SRem => "srem",
+            BinOp::URem => "urem",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+            BinOp::Xor => "xor",
+            BinOp::Shl => "shl",
+            BinOp::LShr => "lshr",
+            BinOp::AShr => "ashr",
+            BinOp::Eq => "eq",
+            BinOp::Ne => "ne",
+            BinOp::SLt => "slt",
+            BinOp::SLe => "sle",
+            BinOp::ULt => "ult",
+            BinOp::ULe => "ule",
+        }
+    }
+
+    /// Return true if the operation compares its operands
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            BinOp::Eq | BinOp::Ne | BinOp::SLt | BinOp::SLe | BinOp::ULt | BinOp::ULe
+        )
+    }
+}
+
+/// An operation on one value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnOp {
+    Neg,
+    /// bitwise complement
+    Not,
+}
+
+impl UnOp {
+    /// Every unary operation
+    pub const ALL: [UnOp; 2] = [UnOp::Neg, UnOp::Not];
+
+    /// Return the name of the operation in the text of the IR
+    pub fn name(self) -> &'static str {
+        match self {
+            UnOp::Neg => "neg",
+            UnOp::Not => "not",
+        }
+    }
+}
+
+/// A conversion of a value to the type of the register it is stored in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvOp {
+    /// keep the lower bits of an integer
+    Trunc,
+    /// sign extend an integer
+    SExt,
+    /// zero extend an integer
+    ZExt,
+    /// a pointer as a 64-bit integer
+    PtrToInt,
+    /// a 64-bit integer as a pointer
+    IntToPtr,
+}
+
+impl ConvOp {
+    /// Every conversion
+    pub const ALL: [ConvOp; 5] = [
+        ConvOp::Trunc,
+        ConvOp::SExt,
+        ConvOp::ZExt,
+        ConvOp::PtrToInt,
+        ConvOp::IntToPtr,
+    ];
+
+    /// Return the name of the conversion in the text of the IR
+    pub fn name(self) -> &'static str {
+        match self {
+            ConvOp::Trunc => "trunc",
+            ConvOp::SExt => "sext",
+            ConvOp::ZExt => "zext",
+            ConvOp::PtrToInt => "ptrtoint",
+            ConvOp::IntToPtr => "inttoptr",
+        }
+    }
+}
+
+/// The function a call jumps to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Callee {
+    /// a function by its symbol name
+    Direct(String),
+    /// a pointer to a function
+    Indirect(Operand),
+}
+
+/// An instruction, which doesn't change the flow of control.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inst {
+    /// `dst = lhs op rhs`, with operands of type `ty`, as is `dst`
+    /// unless `op` is a comparison
+    Binary {
+        dst: Reg,
+        op: BinOp,
+        ty: Ty,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    /// `dst = op operand`, both of type `ty`
+    Unary {
+        dst: Reg,
+        op: UnOp,
+        ty: Ty,
+        operand: Operand,
+    },
+    /// `dst = op operand`, converting the operand of type `from`
+    Conv {
+        dst: Reg,
+        op: ConvOp,
+        from: Ty,
+        operand: Operand,
+    },
+    /// `dst = &slot`
+    SlotAddr { dst: Reg, slot: SlotId },
+    /// `dst = &name`, a global variable or a function
+    GlobalAddr { dst: Reg, name: String },
+    /// `dst = base + offset`, the offset in bytes being an `i64`
+    PtrAdd {
+        dst: Reg,
+        base: Operand,
+        offset: Operand,
+    },
+    /// `dst = *addr`, reading a value of type `ty`
+    Load { dst: Reg, ty: Ty, addr: Operand },
+    /// `*addr = value`, writing a value of type `ty`
+    Store {
+        ty: Ty,
+        value: Operand,
+        addr: Operand,
+    },
+    /// copy `size` bytes from `src` to `dst`, which don't overlap
+    MemCopy {
+        dst: Operand,
+        src: Operand,
+        size: i64,
+    },
+    /// fill `size` bytes at `addr` with zeros
+    MemZero { addr: Operand, size: i64 },
+    /// call `callee` with the arguments of their types, storing what it
+    /// returns in `dst` if `ret` is not `void`: the value, or for a `byval`
+    /// the address of a copy
+    Call {
+        dst: Option<Reg>,
+        callee: Callee,
+        args: Vec<(ArgTy, Operand)>,
+        ret: Option<ArgTy>,
+    },
+    /// initialize the `va_list` at `ap` for the variadic arguments of the function
+    VaStart { ap: Operand },
+    /// `dst` = the address of the next variadic argument of the `va_list`
+    /// at `ap`, a scalar or a struct or union of `size` bytes, moving past it
+    VaArg { dst: Reg, ap: Operand, size: i64 },
+    /// `dst = src`, both of type `ty`, which is how the phis are taken
+    /// out of the SSA form
+    Copy { dst: Reg, ty: Ty, src: Operand },
+    /// `dst` = the operand of the block the control comes from, one for
+    /// each predecessor of the block; the phis come first in their block
+    Phi {
+        dst: Reg,
+        ty: Ty,
+        incoming: Vec<(BlockId, Operand)>,
+    },
+}
+
+impl Inst {
+    /// Return the register the instruction assigns
+    pub fn dst(&self) -> Option<Reg> {
+        match *self {
+            Inst::Binary { dst, .. }
+            | Inst::Unary { dst, .. }
+            | Inst::Conv { dst, .. }
+            | Inst::SlotAddr { dst, .. }
+            | Inst::GlobalAddr { dst, .. }
+            | Inst::PtrAdd { dst, .. }
+            | Inst::Load { dst, .. }
+            | Inst::VaArg { dst, .. }
+            | Inst::Copy { dst, .. }
+            | Inst::Phi { dst, .. } => Some(dst),
+            Inst::Call { dst, .. } => dst,
+            Inst::Store { .. }
+            | Inst::MemCopy { .. }
+            | Inst::MemZero { .. }
+            | Inst::VaStart { .. } => None,
+        }
+    }
+
+    /// Return the operands the instruction reads, those of a phi included
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Inst::Binary { lhs, rhs, .. } => vec![lhs, rhs],
+            Inst::Unary { operand, .. } | Inst::Conv { operand, .. } => vec![operand],
+            Inst::SlotAddr { .. } | Inst::GlobalAddr { .. } => Vec::new(),
+            Inst::PtrAdd { base, offset, .. } => vec![base, offset],
+            Inst::Load { addr, .. } | Inst::MemZero { addr, .. } => vec![addr],
+            Inst::Store { value, addr, .. } => vec![value, addr],
+            Inst::MemCopy { dst, src, .. } => vec![dst, src],
+            Inst::Call { callee, args, .. } => {
+                let mut operands = Vec::new();
+                if let Callee::Indirect(operand) = callee {
+                    operands.push(operand);
+                }
+                operands.extend(args.iter().map(|(_, arg)| arg));
+                operands
+            }
+            Inst::VaStart { ap } | Inst::VaArg { ap, .. } => vec![ap],
+            Inst::Copy { src, .. } => vec![src],
+            Inst::Phi { incoming, .. } => incoming.iter().map(|(_, operand)| operand).collect(),
+        }
+    }
+
+    /// Return the operands the instruction reads to change them
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Inst::Binary { lhs, rhs, .. } => vec![lhs, rhs],
+            Inst::Unary { operand, .. } | Inst::Conv { operand, .. } => vec![operand],
+            Inst::SlotAddr { .. } | Inst::GlobalAddr { .. } => Vec::new(),
+            Inst::PtrAdd { base, offset, .. } => vec![base, offset],
+            Inst::Load { addr, .. } | Inst::MemZero { addr, .. } => vec![addr],
+            Inst::Store { value, addr, .. } => vec![value, addr],
+            Inst::MemCopy { dst, src, .. } => vec![dst, src],
+            Inst::Call { callee, args, .. } => {
+                let mut operands = Vec::new();
+                if let Callee::Indirect(operand) = callee {
+                    operands.push(operand);
+                }
+                operands.extend(args.iter_mut().map(|(_, arg)| arg));
+                operands
+            }
+            Inst::VaStart { ap } | Inst::VaArg { ap, .. } => vec![ap],
+            Inst::Copy { src, .. } => vec![src],
+            Inst::Phi { incoming, .. } => incoming.iter_mut().map(|(_, operand)| operand).collect(),
+        }
+    }
+}
+
+/// The instruction ending a basic block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    /// go to `then` if `cond`, of type `ty`, is not zero, otherwise to `els`
+    Branch {
+        ty: Ty,
+        cond: Operand,
+        then: BlockId,
+        els: BlockId,
+    },
+    /// return from the function, with a value unless it returns `void`
+    Ret(Option<Operand>),
+    /// the program never gets there
+    Unreachable,
+}
+
+impl Terminator {
+    /// Return the blocks the terminator may go to
+    pub fn successors(&self) -> Vec<BlockId> {
+        match *self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch { then, els, .. } => vec![then, els],
+            Terminator::Ret(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    /// Return the operand the terminator reads to change it
+    pub fn operand_mut(&mut self) -> Option<&mut Operand> {
+        match self {
+            Terminator::Branch { cond, .. } => Some(cond),
+            Terminator::Ret(value) => value.as_mut(),
+            Terminator::Jump(_) | Terminator::Unreachable => None,
+        }
+    }
+}
+
+/// A basic block, entered at its first instruction only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub insts: Vec<Inst>,
+    pub term: Terminator,
+}
+
+/// A stack slot holding a local variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slot {
+    /// the name of the variable, for reading the IR
+    pub name: String,
+    pub size: i64,
+    pub align: i64,
+}
+
+/// A parameter of a function: the register holding its value, or the
+/// address of a `byval` copy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub reg: Reg,
+    pub ty: ArgTy,
+}
+
+/// A function definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    /// true if the function is not visible to other files
+    pub is_static: bool,
+    pub params: Vec<Param>,
+    /// true if the function takes more arguments after its parameters
+    pub is_variadic: bool,
+    /// the type of the returned value, None for `void`
+    pub ret: Option<ArgTy>,
+    pub slots: Vec<Slot>,
+    /// the types of the virtual registers
+    pub regs: Vec<Ty>,
+    /// the basic blocks, the first one is entered by the call and never branched to
+    pub blocks: Vec<Block>,
+}
+
+/// An address constant in the initial value of a global variable:
+/// the 8 bytes at `offset` hold the address of `label` plus `addend`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reloc {
+    pub offset: i64,
+    pub label: String,
+    pub addend: i64,
+}
+
+/// A global variable, a function-local static variable or a string literal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub size: i64,
+    pub align: i64,
+    /// the initial value, None for a zero initialized object
+    pub init: Option<Vec<u8>>,
+    pub relocs: Vec<Reloc>,
+    /// true if the symbol is not visible to other files
+    pub is_static: bool,
+    /// false if the variable is only declared `extern`, it is defined in another file
+    pub is_definition: bool,
+}
+
+/// A whole translation unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+impl fmt::Display for SlotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Reg(reg) => write!(f, "{}", reg),
+            Operand::Const(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for ArgTy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgTy::Scalar(ty) => write!(f, "{}", ty),
+            ArgTy::Byval(size) => write!(f, "byval {}", size),
+        }
+    }
+}
+
+impl fmt::Display for Callee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Callee::Direct(name) => write!(f, "@{}", name),
+            Callee::Indirect(operand) => write!(f, "{}", operand),
+        }
+    }
+}
+
+/// Writes the type of what a function returns, `void` for nothing.
+fn write_ret(f: &mut fmt::Formatter<'_>, ret: Option<ArgTy>) -> fmt::Result {
+    match ret {
+        Some(ty) => write!(f, "{}", ty),
+        None => write!(f, "void"),
+    }
+}
+
+/// Writes the bytes as a string in double quotes, the printable ASCII
+/// characters as they are except `"` and `\`, the others as `\` and
+/// two hexadecimal digits.
+fn write_bytes(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    write!(f, "\"")?;
+    for &byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(f, "\\{:02x}", byte)?,
+            b' '..=b'~' => write!(f, "{}", byte as char)?,
+            _ => write!(f, "\\{:02x}", byte)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jump {}", target),
+            Terminator::Branch {
+                ty,
+                cond,
+                then,
+                els,
+            } => write!(f, "branch {} {}, {}, {}", ty, cond, then, els),
+            Terminator::Ret(Some(value)) => write!(f, "ret {}", value),
+            Terminator::Ret(None) => write!(f, "ret"),
+            Terminator::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+/// An instruction of a function, displayed with the types of the
+/// registers it assigns.
+pub struct InstDisplay<'a> {
+    function: &'a Function,
+    inst: &'a Inst,
+}
+
+impl Function {
+    /// Return the instruction of the function, to be displayed as in the
+    /// text of the IR
+    pub fn display<'a>(&'a self, inst: &'a Inst) -> InstDisplay<'a> {
+        InstDisplay {
+            function: self,
+            inst,
+        }
+    }
+}
+
+impl fmt::Display for InstDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(dst) = self.inst.dst() {
+            write!(f, "{}: {} = ", dst, self.function.regs[dst.0])?;
+        }
+        match *self.inst {
+            Inst::Binary {
+                op,
+                ty,
+                ref lhs,
+                ref rhs,
+                ..
+            } => write!(f, "{} {} {}, {}", op.name(), ty, lhs, rhs),
+            Inst::Unary {
+                op,
+                ty,
+                ref operand,
+                ..
+            } => write!(f, "{} {} {}", op.name(), ty, operand),
+            Inst::Conv {
+                op,
+                from,
+                ref operand,
+                ..
+            } => write!(f, "{} {} {}", op.name(), from, operand),
+            Inst::SlotAddr { slot, .. } => write!(f, "slotaddr {}", slot),
+            Inst::GlobalAddr { ref name, .. } => write!(f, "globaladdr @{}", name),
+            Inst::PtrAdd {
+                ref base,
+                ref offset,
+                ..
+            } => write!(f, "ptradd {}, {}", base, offset),
+            Inst::Load { ty, ref addr, .. } => write!(f, "load {} {}", ty, addr),
+            Inst::Store {
+                ty,
+                ref value,
+                ref addr,
+            } => write!(f, "store {} {}, {}", ty, value, addr),
+            Inst::MemCopy {
+                ref dst,
+                ref src,
+                size,
+            } => write!(f, "memcopy {}, {}, {}", dst, src, size),
+            Inst::MemZero { ref addr, size } => write!(f, "memzero {}, {}", addr, size),
+            Inst::Call {
+                ref callee,
+                ref args,
+                ret,
+                ..
+            } => {
+                write!(f, "call {}(", callee)?;
+                for (i, (ty, arg)) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} {}", ty, arg)?;
+                }
+                write!(f, ") -> ")?;
+                write_ret(f, ret)
+            }
+            Inst::VaStart { ref ap } => write!(f, "vastart {}", ap),
+            Inst::VaArg { ref ap, size, .. } => write!(f, "vaarg {}, {}", ap, size),
+            Inst::Copy { ty, ref src, .. } => write!(f, "copy {} {}", ty, src),
+            Inst::Phi {
+                ty, ref incoming, ..
+            } => {
+                write!(f, "phi {}", ty)?;
+                for (i, (block, operand)) in incoming.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}: {}", separator, block, operand)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "function ")?;
+        if self.is_static {
+            write!(f, "static ")?;
+        }
+        write!(f, "@{}(", self.name)?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", param.reg, param.ty)?;
+        }
+        if self.is_variadic {
+            if !self.params.is_empty() {
+                write!(f, ", ")?;
+            }
+            write!(f, "...")?;
+        }
+        write!(f, ") -> ")?;
+        write_ret(f, self.ret)?;
+        writeln!(f, " {{")?;
+        for (i, slot) in self.slots.iter().enumerate() {
+            write!(f, "  {} = slot ", SlotId(i))?;
+            write_bytes(f, slot.name.as_bytes())?;
+            writeln!(f, ", size {}, align {}", slot.size, slot.align)?;
+        }
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "{}:", BlockId(i))?;
+            for inst in &block.insts {
+                writeln!(f, "  {}", self.display(inst))?;
+            }
+            writeln!(f, "  {}", block.term)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl fmt::Display for Global {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.is_definition {
+            true => write!(f, "global ")?,
+            false => write!(f, "extern ")?,
+        }
+        if self.is_static {
+            write!(f, "static ")?;
+        }
+        write!(
+            f,
+            "@{}, size {}, align {}",
+            self.name, self.size, self.align
+        )?;
+        if let Some(ref init) = self.init {
+            write!(f, ", init ")?;
+            write_bytes(f, init)?;
+        }
+        for reloc in &self.relocs {
+            write!(
+                f,
+                ", reloc {} @{}{:+}",
+                reloc.offset, reloc.label, reloc.addend
+            )?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Prints the module as the text `irparse` reads: the globals, then the
+/// functions each after an empty line.
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for global in &self.globals {
+            write!(f, "{}", global)?;
+        }
+        for function in &self.functions {
+            writeln!(f)?;
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,766 @@
+// The parser reads the text the IR prints as back into the IR, so that
+// the IR of a program can be saved, edited and compiled again. Printing
+// the IR it reads gives back the same text.
+//
+// Blank lines and the spaces between the tokens don't matter, a `;` starts
+// a comment running to the end of the line. The slots and the blocks of a
+// function are numbered in the order they are written, from 0; the
+// registers can be numbered as one likes, a register nothing assigns is
+// an `i64`.
+//
+//     module   := (global | function)*
+//     global   := ("global" | "extern") ["static"] @name "," "size" int "," "align" int
+//                 ["," "init" string] ("," "reloc" int @name ("+" int | -int))*
+//     function := "function" ["static"] @name "(" [params] ")" "->" ret "{" slot* block* "}"
+//     params   := (param ("," param)* ["," "..."]) | "..."
+//     param    := %reg ":" argty
+//     slot     := $slot "=" "slot" string "," "size" int "," "align" int
+//     block    := bbN ":" inst* term
+//     inst     := [%reg ":" ty "="] opcode operands
+//     phi      := %reg ":" ty "=" "phi" ty bbN ":" operand ("," bbN ":" operand)*
+//     argty    := ty | "byval" int
+//     ret      := argty | "void"
+//     operand  := %reg | int
+
+use std::fmt;
+
+use log::debug;
+
+use crate::ir::{
+    ArgTy, BinOp, Block, BlockId, Callee, ConvOp, Function, Global, Inst, Module, Operand, Param,
+    Reg, Reloc, Slot, SlotId, Terminator, Ty, UnOp,
+};
+use crate::scanner::Location;
+
+/// A token of the text of the IR.
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    /// a keyword, an opcode, a type or a block label
+    Word(String),
+    Reg(usize),
+    Slot(usize),
+    /// a symbol name after `@`
+    Global(String),
+    Int(i64),
+    /// the bytes of a string in double quotes
+    Str(Vec<u8>),
+    Punct(&'static str),
+    Eof,
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tok::Word(word) => write!(f, "'{}'", word),
+            Tok::Reg(reg) => write!(f, "'{}'", Reg(*reg)),
+            Tok::Slot(slot) => write!(f, "'{}'", SlotId(*slot)),
+            Tok::Global(name) => write!(f, "'@{}'", name),
+            Tok::Int(value) => write!(f, "'{}'", value),
+            Tok::Str(_) => write!(f, "a string"),
+            Tok::Punct(punct) => write!(f, "'{}'", punct),
+            Tok::Eof => write!(f, "the end of the input"),
+        }
+    }
+}
+
+/// The punctuation, the longest first.
+const PUNCTS: [&str; 9] = ["->", "...", ",", ":", "=", "(", ")", "{", "}"];
+
+/// Return true if the character can be part of a word or a symbol name.
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Splits the text into tokens with their locations.
+fn tokenize(text: &str) -> Vec<(Tok, Location)> {
+    let mut tokens = Vec::new();
+    for (line, content) in text.lines().enumerate() {
+        let chars: Vec<char> = content.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let location = Location {
+                line: line + 1,
+                column: i + 1,
+            };
+            let c = chars[i];
+            // the characters from `i` on satisfying `pred`, and where they end
+            let take = |i: usize, pred: fn(char) -> bool| {
+                let mut j = i;
+                while j < chars.len() && pred(chars[j]) {
+                    j += 1;
+                }
+                (chars[i..j].iter().collect::<String>(), j)
+            };
+            let tok = match c {
+                ' ' | '\t' | '\r' => {
+                    i += 1;
+                    continue;
+                }
+                ';' => break,
+                '%' | '$' => {
+                    let (digits, end) = take(i + 1, |c| c.is_ascii_digit());
+                    let number = digits.parse().unwrap_or_else(|_| {
+                        panic!(
+                            "parse_module: {}: expected a number after '{}'",
+                            location, c
+                        )
+                    });
+                    i = end;
+                    match c {
+                        '%' => Tok::Reg(number),
+                        _ => Tok::Slot(number),
+                    }
+                }
+                '@' => {
+                    let (name, end) = take(i + 1, is_name_char);
+                    if name.is_empty() {
+                        panic!("parse_module: {}: expected a name after '@'", location);
+                    }
+                    i = end;
+                    Tok::Global(name)
+                }
+                '"' => {
+                    let mut bytes = Vec::new();
+                    i += 1;
+                    loop {
+                        match chars.get(i) {
+                            None => panic!("parse_module: {}: unterminated string", location),
+                            Some('"') => break,
+                            Some('\\') => {
+                                let hex: String = chars.iter().skip(i + 1).take(2).collect();
+                                let byte = u8::from_str_radix(&hex, 16).unwrap_or_else(|_| {
+                                    panic!(
+                                        "parse_module: {}: expected two hexadecimal digits after '\\'",
+                                        location
+                                    )
+                                });
+                                bytes.push(byte);
+                                i += 3;
+                            }
+                            Some(&c) => {
+                                let mut buffer = [0; 4];
+                                bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                                i += 1;
+                            }
+                        }
+                    }
+                    i += 1;
+                    Tok::Str(bytes)
+                }
+                c if c.is_ascii_digit()
+                    || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) =>
+                {
+                    let (digits, end) = take(i + 1, |c| c.is_ascii_digit());
+                    let number = format!("{}{}", c, digits);
+                    i = end;
+                    Tok::Int(number.parse().unwrap_or_else(|_| {
+                        panic!(
+                            "parse_module: {}: integer {} out of range",
+                            location, number
+                        )
+                    }))
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let (word, end) = take(i, is_name_char);
+                    i = end;
+                    Tok::Word(word)
+                }
+                // `+` only comes before the addend of a relocation
+                '+' => {
+                    i += 1;
+                    Tok::Punct("+")
+                }
+                _ => {
+                    let rest: String = chars[i..].iter().collect();
+                    let punct = PUNCTS
+                        .iter()
+                        .find(|punct| rest.starts_with(*punct))
+                        .unwrap_or_else(|| {
+                            panic!("parse_module: {}: unexpected character '{}'", location, c)
+                        });
+                    i += punct.len();
+                    Tok::Punct(punct)
+                }
+            };
+            tokens.push((tok, location));
+        }
+    }
+    let end = Location {
+        line: text.lines().count() + 1,
+        column: 1,
+    };
+    tokens.push((Tok::Eof, end));
+    tokens
+}
+
+/// Reads the text of the IR of a module.
+/// Panics with the location of the first error.
+pub fn parse_module(text: &str) -> Module {
+    let mut parser = Parser {
+        tokens: tokenize(text),
+        pos: 0,
+    };
+    let mut module = Module {
+        globals: Vec::new(),
+        functions: Vec::new(),
+    };
+    loop {
+        match parser.peek() {
+            Tok::Word(word) if word == "global" || word == "extern" => {
+                module.globals.push(parser.global())
+            }
+            Tok::Word(word) if word == "function" => module.functions.push(parser.function()),
+            Tok::Eof => return module,
+            tok => parser.error(&format!("expected a global or a function, not {}", tok)),
+        }
+    }
+}
+
+/// The state of the parser: the tokens and the next one to read.
+struct Parser {
+    tokens: Vec<(Tok, Location)>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Return the next token without reading it
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    /// Reads the next token
+    fn next(&mut self) -> Tok {
+        let tok = self.tokens[self.pos].0.clone();
+        if tok != Tok::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    /// Return the location of the next token
+    fn location(&self) -> Location {
+        self.tokens[self.pos].1
+    }
+
+    /// Panics with the location of the next token
+    fn error(&self, message: &str) -> ! {
+        self.error_at(self.location(), message)
+    }
+
+    /// Panics with the location
+    fn error_at(&self, location: Location, message: &str) -> ! {
+        panic!("parse_module: {}: {}", location, message)
+    }
+
+    /// Return true if the next token is the word, reading it if it is
+    fn consume_word(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Tok::Word(w) if w == word => {
+                self.next();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Return true if the next token is the punctuation, reading it if it is
+    fn consume(&mut self, punct: &str) -> bool {
+        match self.peek() {
+            Tok::Punct(p) if *p == punct => {
+                self.next();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Reads the punctuation
+    fn expect(&mut self, punct: &str) {
+        if !self.consume(punct) {
+            self.error(&format!("expected '{}', not {}", punct, self.peek()));
+        }
+    }
+
+    /// Reads the word
+    fn expect_word(&mut self, word: &str) {
+        if !self.consume_word(word) {
+            self.error(&format!("expected '{}', not {}", word, self.peek()));
+        }
+    }
+
+    /// Reads a word
+    fn word(&mut self) -> String {
+        match self.peek().clone() {
+            Tok::Word(word) => {
+                self.next();
+                word
+            }
+            tok => self.error(&format!("expected a word, not {}", tok)),
+        }
+    }
+
+    /// Reads an integer
+    fn int(&mut self) -> i64 {
+        match *self.peek() {
+            Tok::Int(value) => {
+                self.next();
+                value
+            }
+            ref tok => self.error(&format!("expected an integer, not {}", tok)),
+        }
+    }
+
+    /// Reads a symbol name
+    fn global_name(&mut self) -> String {
+        match self.peek().clone() {
+            Tok::Global(name) => {
+                self.next();
+                name
+            }
+            tok => self.error(&format!("expected a symbol name, not {}", tok)),
+        }
+    }
+
+    /// Reads a string
+    fn string(&mut self) -> Vec<u8> {
+        match self.peek().clone() {
+            Tok::Str(bytes) => {
+                self.next();
+                bytes
+            }
+            tok => self.error(&format!("expected a string, not {}", tok)),
+        }
+    }
+
+    /// Reads `, name int`
+    fn attribute(&mut self, name: &str) -> i64 {
+        self.expect(",");
+        self.expect_word(name);
+        self.int()
+    }
+
+    /// Reads a type
+    fn ty(&mut self) -> Ty {
+        match self.peek() {
+            Tok::Word(word) => match Ty::ALL.iter().find(|ty| ty.name() == word) {
+                Some(&ty) => {
+                    self.next();
+                    ty
+                }
+                None => self.error(&format!("unknown type '{}'", word)),
+            },
+            tok => self.error(&format!("expected a type, not {}", tok)),
+        }
+    }
+
+    /// Reads the type of a value passed to or returned by a function
+    fn arg_ty(&mut self) -> ArgTy {
+        match self.consume_word("byval") {
+            true => ArgTy::Byval(self.int()),
+            false => ArgTy::Scalar(self.ty()),
+        }
+    }
+
+    /// Reads the type a function returns
+    fn ret(&mut self) -> Option<ArgTy> {
+        match self.consume_word("void") {
+            true => None,
+            false => Some(self.arg_ty()),
+        }
+    }
+
+    /// Reads a register
+    fn reg(&mut self) -> Reg {
+        match *self.peek() {
+            Tok::Reg(reg) => {
+                self.next();
+                Reg(reg)
+            }
+            ref tok => self.error(&format!("expected a register, not {}", tok)),
+        }
+    }
+
+    /// Reads an operand
+    fn operand(&mut self) -> Operand {
+        match *self.peek() {
+            Tok::Reg(reg) => {
+                self.next();
+                Operand::Reg(Reg(reg))
+            }
+            Tok::Int(value) => {
+                self.next();
+                Operand::Const(value)
+            }
+            ref tok => self.error(&format!("expected an operand, not {}", tok)),
+        }
+    }
+
+    /// Reads a block label
+    fn block_id(&mut self) -> BlockId {
+        match self.peek() {
+            Tok::Word(word) => match word.strip_prefix("bb").and_then(|n| n.parse().ok()) {
+                Some(n) => {
+                    self.next();
+                    BlockId(n)
+                }
+                None => self.error(&format!("expected a block, not '{}'", word)),
+            },
+            tok => self.error(&format!("expected a block, not {}", tok)),
+        }
+    }
+
+    /// Reads a global variable
+    fn global(&mut self) -> Global {
+        let is_definition = self.word() == "global";
+        let is_static = self.consume_word("static");
+        let name = self.global_name();
+        debug!("parse global: {}", name);
+        let size = self.attribute("size");
+        let align = self.attribute("align");
+        let mut init = None;
+        let mut relocs = Vec::new();
+        while self.consume(",") {
+            match self.word().as_str() {
+                "init" if init.is_none() && relocs.is_empty() => init = Some(self.string()),
+                "reloc" => {
+                    let offset = self.int();
+                    let label = self.global_name();
+                    let addend = match self.consume("+") {
+                        true => self.int(),
+                        false => match self.int() {
+                            addend if addend < 0 => addend,
+                            _ => self.error("expected the sign of the addend"),
+                        },
+                    };
+                    relocs.push(Reloc {
+                        offset,
+                        label,
+                        addend,
+                    });
+                }
+                word => self.error(&format!("unexpected '{}' in global @{}", word, name)),
+            }
+        }
+        Global {
+            name,
+            size,
+            align,
+            init,
+            relocs,
+            is_static,
+            is_definition,
+        }
+    }
+
+    /// Reads a function
+    fn function(&mut self) -> Function {
+        self.expect_word("function");
+        let is_static = self.consume_word("static");
+        let name = self.global_name();
+        debug!("parse function: {}", name);
+        let mut function = Function {
+            name,
+            is_static,
+            params: Vec::new(),
+            is_variadic: false,
+            ret: None,
+            slots: Vec::new(),
+            regs: Vec::new(),
+            blocks: Vec::new(),
+        };
+        // the types of the registers as they are assigned
+        let mut regs = Vec::new();
+        self.expect("(");
+        if !self.consume(")") {
+            loop {
+                if self.consume("...") {
+                    function.is_variadic = true;
+                    self.expect(")");
+                    break;
+                }
+                let location = self.location();
+                let reg = self.reg();
+                self.expect(":");
+                let ty = self.arg_ty();
+                let reg_ty = match ty {
+                    ArgTy::Scalar(ty) => ty,
+                    ArgTy::Byval(_) => Ty::Ptr,
+                };
+                self.define(&mut regs, reg, reg_ty, location);
+                function.params.push(Param { reg, ty });
+                if self.consume(")") {
+                    break;
+                }
+                self.expect(",");
+            }
+        }
+        self.expect("->");
+        function.ret = self.ret();
+        self.expect("{");
+        while let Tok::Slot(slot) = *self.peek() {
+            if slot != function.slots.len() {
+                self.error(&format!("expected {}", SlotId(function.slots.len())));
+            }
+            self.next();
+            self.expect("=");
+            self.expect_word("slot");
+            let name = String::from_utf8(self.string())
+                .unwrap_or_else(|_| self.error("the name of a slot must be valid UTF-8"));
+            let size = self.attribute("size");
+            let align = self.attribute("align");
+            function.slots.push(Slot { name, size, align });
+        }
+        while !self.consume("}") {
+            let location = self.location();
+            let block = self.block_id();
+            if block.0 != function.blocks.len() {
+                let expected = BlockId(function.blocks.len());
+                self.error_at(location, &format!("expected {}", expected));
+            }
+            self.expect(":");
+            let mut insts = Vec::new();
+            let term = loop {
+                if let Some(term) = self.terminator() {
+                    break term;
+                }
+                insts.push(self.inst(&mut regs));
+            };
+            function.blocks.push(Block { insts, term });
+        }
+        function.regs = regs.into_iter().map(|ty| ty.unwrap_or(Ty::I64)).collect();
+        function
+    }
+
+    /// Records that the register at `location` is assigned a value of type `ty`
+    fn define(&self, regs: &mut Vec<Option<Ty>>, reg: Reg, ty: Ty, location: Location) {
+        if regs.len() <= reg.0 {
+            regs.resize(reg.0 + 1, None);
+        }
+        match regs[reg.0] {
+            Some(defined) if defined != ty => self.error_at(
+                location,
+                &format!("register {} is assigned both {} and {}", reg, defined, ty),
+            ),
+            _ => regs[reg.0] = Some(ty),
+        }
+    }
+
+    /// Reads a terminator, None if the next token doesn't start one
+    fn terminator(&mut self) -> Option<Terminator> {
+        let word = match self.peek() {
+            Tok::Word(word) => word.clone(),
+            _ => return None,
+        };
+        let term = match word.as_str() {
+            "jump" => {
+                self.next();
+                Terminator::Jump(self.block_id())
+            }
+            "branch" => {
+                self.next();
+                let ty = self.ty();
+                let cond = self.operand();
+                self.expect(",");
+                let then = self.block_id();
+                self.expect(",");
+                let els = self.block_id();
+                Terminator::Branch {
+                    ty,
+                    cond,
+                    then,
+                    els,
+                }
+            }
+            "ret" => {
+                self.next();
+                match self.peek() {
+                    Tok::Reg(_) | Tok::Int(_) => Terminator::Ret(Some(self.operand())),
+                    _ => Terminator::Ret(None),
+                }
+            }
+            "unreachable" => {
+                self.next();
+                Terminator::Unreachable
+            }
+            _ => return None,
+        };
+        Some(term)
+    }
+
+    /// Reads an instruction
+    fn inst(&mut self, regs: &mut Vec<Option<Ty>>) -> Inst {
+        let dst = match *self.peek() {
+            Tok::Reg(_) => {
+                let location = self.location();
+                let dst = self.reg();
+                self.expect(":");
+                let ty = self.ty();
+                self.define(regs, dst, ty, location);
+                self.expect("=");
+                Some(dst)
+            }
+            _ => None,
+        };
+        let location = self.location();
+        let opcode = self.word();
+        let dst_reg = |parser: &Parser| match dst {
+            Some(dst) => dst,
+            None => parser.error_at(location, &format!("'{}' assigns a register", opcode)),
+        };
+        if let Some(&op) = BinOp::ALL.iter().find(|op| op.name() == opcode) {
+            let dst = dst_reg(self);
+            let ty = self.ty();
+            let lhs = self.operand();
+            self.expect(",");
+            let rhs = self.operand();
+            return Inst::Binary {
+                dst,
+                op,
+                ty,
+                lhs,
+                rhs,
+            };
+        }
+        if let Some(&op) = UnOp::ALL.iter().find(|op| op.name() == opcode) {
+            let dst = dst_reg(self);
+            let ty = self.ty();
+            let operand = self.operand();
+            return Inst::Unary {
+                dst,
+                op,
+                ty,
+                operand,
+            };
+        }
+        if let Some(&op) = ConvOp::ALL.iter().find(|op| op.name() == opcode) {
+            let dst = dst_reg(self);
+            let from = self.ty();
+            let operand = self.operand();
+            return Inst::Conv {
+                dst,
+                op,
+                from,
+                operand,
+            };
+        }
+        let inst = match opcode.as_str() {
+            "slotaddr" => match *self.peek() {
+                Tok::Slot(slot) => {
+                    self.next();
+                    Inst::SlotAddr {
+                        dst: dst_reg(self),
+                        slot: SlotId(slot),
+                    }
+                }
+                ref tok => self.error(&format!("expected a slot, not {}", tok)),
+            },
+            "globaladdr" => Inst::GlobalAddr {
+                dst: dst_reg(self),
+                name: self.global_name(),
+            },
+            "ptradd" => {
+                let base = self.operand();
+                self.expect(",");
+                Inst::PtrAdd {
+                    dst: dst_reg(self),
+                    base,
+                    offset: self.operand(),
+                }
+            }
+            "load" => Inst::Load {
+                dst: dst_reg(self),
+                ty: self.ty(),
+                addr: self.operand(),
+            },
+            "vaarg" => {
+                let dst = dst_reg(self);
+                let ap = self.operand();
+                Inst::VaArg {
+                    dst,
+                    ap,
+                    size: self.attribute_int(),
+                }
+            }
+            "copy" => Inst::Copy {
+                dst: dst_reg(self),
+                ty: self.ty(),
+                src: self.operand(),
+            },
+            "phi" => {
+                let dst = dst_reg(self);
+                let ty = self.ty();
+                let mut incoming = Vec::new();
+                loop {
+                    let block = self.block_id();
+                    self.expect(":");
+                    incoming.push((block, self.operand()));
+                    if !self.consume(",") {
+                        break;
+                    }
+                }
+                Inst::Phi { dst, ty, incoming }
+            }
+            "call" => {
+                let callee = match self.peek() {
+                    Tok::Global(_) => Callee::Direct(self.global_name()),
+                    _ => Callee::Indirect(self.operand()),
+                };
+                self.expect("(");
+                let mut args = Vec::new();
+                while !self.consume(")") {
+                    if !args.is_empty() {
+                        self.expect(",");
+                    }
+                    let ty = self.arg_ty();
+                    args.push((ty, self.operand()));
+                }
+                self.expect("->");
+                Inst::Call {
+                    dst,
+                    callee,
+                    args,
+                    ret: self.ret(),
+                }
+            }
+            "store" | "memcopy" | "memzero" | "vastart" if dst.is_some() => {
+                self.error_at(location, &format!("'{}' doesn't assign a register", opcode))
+            }
+            "store" => {
+                let ty = self.ty();
+                let value = self.operand();
+                self.expect(",");
+                Inst::Store {
+                    ty,
+                    value,
+                    addr: self.operand(),
+                }
+            }
+            "memcopy" => {
+                let dst = self.operand();
+                self.expect(",");
+                let src = self.operand();
+                Inst::MemCopy {
+                    dst,
+                    src,
+                    size: self.attribute_int(),
+                }
+            }
+            "memzero" => {
+                let addr = self.operand();
+                Inst::MemZero {
+                    addr,
+                    size: self.attribute_int(),
+                }
+            }
+            "vastart" => Inst::VaStart { ap: self.operand() },
+            _ => self.error_at(location, &format!("unknown instruction '{}'", opcode)),
+        };
+        inst
+    }
+
+    /// Reads `, int`
+    fn attribute_int(&mut self) -> i64 {
+        self.expect(",");
+        self.int()
+    }
+}
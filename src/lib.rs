@@ -114,14 +114,70 @@ pub mod stream;
 /// It could scan the input and provide parser the tokens
 pub mod scanner;
 
+///. pub mod diagnostics
+/// The warnings and the options and pragmas controlling them
+pub mod diagnostics;
+
+///. pub mod types
+/// The C types the AST is annotated with
+pub mod types;
+
 ///. pub mod ast
 /// It could parse the tokens and provide code generator the AST
 pub mod ast;
 
+///. pub mod symbols
+/// The scoped symbol table the parser resolves identifiers with
+pub mod symbols;
+
+///. pub mod consteval
+/// It evaluates the constant expressions of the AST
+pub mod consteval;
+
 ///. pub mod parser
 /// It could parse the tokens and provide code generator the AST
 pub mod parser;
 
+///. pub mod format
+/// It checks the arguments of the calls to `printf` and `scanf` against their format
+pub mod format;
+
+///. pub mod typeck
+/// It checks the types of the AST before code is generated from it
+pub mod typeck;
+
+///. pub mod flow
+/// It follows the control through the functions to warn about their flaws
+pub mod flow;
+
+///. pub mod uninit
+/// It warns about the local variables read before they are assigned
+pub mod uninit;
+
+///. pub mod ir
+/// The intermediate representation the AST is lowered to
+pub mod ir;
+
+///. pub mod irparse
+/// It reads the text of the IR back into the IR
+pub mod irparse;
+
+///. pub mod cfg
+/// The control-flow graph of a function of the IR and its dominator tree
+pub mod cfg;
+
+///. pub mod verify
+/// It checks that the IR holds together between the passes
+pub mod verify;
+
+///. pub mod ssa
+/// It promotes the local variables to registers in SSA form, and takes the IR out of it
+pub mod ssa;
+
+///. pub mod lower
+/// It translates the AST into the IR
+pub mod lower;
+
 ///.pub mod codegen
-/// It could generate the assembly code from AST
+/// It could generate the assembly code from the IR
 pub mod codegen;
@@ -0,0 +1,923 @@
+// The lowering translates the AST of the program into the IR. Each local
+// variable gets a stack slot the first time it is used, a parameter is
+// stored into its slot on entry; a struct or union passed by value is used
+// in place, through the address of its copy. An expression gives an operand
+// of the type of its value, a struct or union its address, the way an array
+// gives the address of its first element.
+//
+// The statements become basic blocks: a condition branches to the blocks
+// of its outcomes, `&&` and `||` evaluating their right operand in a block
+// of its own, and a loop jumps back to the block testing its condition.
+// A `switch` compares its value with the cases one after the other. The
+// code following a jump, such as the one after a `return` or a `break`,
+// goes in a block which nothing enters, and the blocks which can't be
+// reached from the entry are removed at the end.
+//
+// The value of `&&` and `||` is stored in a slot of its own by the blocks
+// of the outcomes and loaded where they join, so that every register is
+// assigned by a single instruction.
+
+use std::collections::HashMap;
+
+use log::{debug, info};
+
+use crate::ast::{self, BinaryOpType, Node, NodeKind, Program, UnaryOpType};
+use crate::ir::{
+    ArgTy, BinOp, Block, BlockId, Callee, ConvOp, Function, Global, Inst, Module, Operand, Param,
+    Reg, Reloc, Slot, SlotId, Terminator, Ty, UnOp,
+};
+use crate::types::{BitField, Member, Type};
+
+/// Translates the whole program into the IR.
+pub fn lower_program(program: &Program) -> Module {
+    Module {
+        globals: program.globals.iter().map(lower_global).collect(),
+        functions: program.functions.iter().map(lower_function).collect(),
+    }
+}
+
+/// Return the IR of a global variable.
+fn lower_global(global: &ast::Global) -> Global {
+    Global {
+        name: global.name.clone(),
+        // a declaration may be of an incomplete type, it is not allocated here
+        size: match global.is_definition {
+            true => global.ty.size(),
+            false => 0,
+        },
+        align: global.ty.align(),
+        init: global.init.clone(),
+        relocs: global
+            .relocs
+            .iter()
+            .map(|reloc| Reloc {
+                offset: reloc.offset,
+                label: reloc.label.clone(),
+                addend: reloc.addend,
+            })
+            .collect(),
+        is_static: global.is_static,
+        is_definition: global.is_definition,
+    }
+}
+
+/// Return the IR type of a value of the C type `ty`, an address for the
+/// types whose values are used through their address.
+fn ty_of(ty: &Type) -> Ty {
+    match ty {
+        Type::Bool | Type::Char | Type::UChar => Ty::I8,
+        Type::Short | Type::UShort => Ty::I16,
+        Type::Int | Type::UInt => Ty::I32,
+        Type::Long | Type::ULong | Type::LongLong | Type::ULongLong => Ty::I64,
+        _ => Ty::Ptr,
+    }
+}
+
+/// Return how a value of the C type `ty` is passed to a function.
+fn arg_ty(ty: &Type) -> ArgTy {
+    match ty {
+        Type::Record(_) => ArgTy::Byval(ty.size()),
+        _ => ArgTy::Scalar(ty_of(ty)),
+    }
+}
+
+/// Return true if a value of the C type `ty` is used through its address.
+fn is_aggregate(ty: &Type) -> bool {
+    matches!(ty, Type::Array(..) | Type::Record(_) | Type::Func { .. })
+}
+
+/// Translates a function into the IR.
+fn lower_function(function: &ast::Function) -> Function {
+    info!("lower function: {}", function.name);
+    let mut lowering = Lowering {
+        function,
+        locals: vec![None; function.locals.len()],
+        slots: Vec::new(),
+        regs: Vec::new(),
+        blocks: Vec::new(),
+        current: BlockId(0),
+        breaks: Vec::new(),
+        continues: Vec::new(),
+        switches: Vec::new(),
+        labels: HashMap::new(),
+    };
+    lowering.current = lowering.new_block();
+    let params = function
+        .params
+        .iter()
+        .map(|&id| {
+            let ty = arg_ty(&function.locals[id].ty);
+            let reg = match ty {
+                ArgTy::Scalar(ty) => {
+                    let reg = lowering.new_reg(ty);
+                    let addr = lowering.local_addr(id);
+                    lowering.emit(Inst::Store {
+                        ty,
+                        value: Operand::Reg(reg),
+                        addr,
+                    });
+                    reg
+                }
+                // the copy passed by value is the parameter itself
+                ArgTy::Byval(_) => {
+                    let reg = lowering.new_reg(Ty::Ptr);
+                    lowering.locals[id] = Some(Local::Byval(reg));
+                    reg
+                }
+            };
+            Param { reg, ty }
+        })
+        .collect();
+    lowering.stmt(&function.body);
+    // reaching the end of `main` returns 0, the value is unspecified for
+    // the other functions
+    let ret = match function.ret {
+        Type::Void => None,
+        ref ty => Some(arg_ty(ty)),
+    };
+    let value = match ret {
+        None => None,
+        Some(ArgTy::Scalar(_)) => Some(Operand::Const(0)),
+        Some(ArgTy::Byval(size)) => {
+            let slot = lowering.new_slot("(no return value)".to_owned(), size, 8);
+            Some(lowering.slot_addr(slot))
+        }
+    };
+    lowering.terminate(Terminator::Ret(value));
+    let blocks = remove_unreachable(lowering.blocks);
+    Function {
+        name: function.name.clone(),
+        is_static: function.is_static,
+        params,
+        is_variadic: function.va_area.is_some(),
+        ret,
+        slots: lowering.slots,
+        regs: lowering.regs,
+        blocks,
+    }
+}
+
+/// Return the blocks which can be reached from the first one, in the same
+/// order, the blocks they go to renumbered. A block which is not terminated
+/// can't be reached: it is the one following the last terminator.
+fn remove_unreachable(blocks: Vec<(Vec<Inst>, Option<Terminator>)>) -> Vec<Block> {
+    let mut reached = vec![false; blocks.len()];
+    let mut stack = vec![BlockId(0)];
+    reached[0] = true;
+    while let Some(block) = stack.pop() {
+        let successors = blocks[block.0]
+            .1
+            .as_ref()
+            .map(Terminator::successors)
+            .unwrap_or_default();
+        for successor in successors {
+            if !reached[successor.0] {
+                reached[successor.0] = true;
+                stack.push(successor);
+            }
+        }
+    }
+    let mut numbers = Vec::with_capacity(blocks.len());
+    let mut count = 0;
+    for &reached in &reached {
+        numbers.push(BlockId(count));
+        count += reached as usize;
+    }
+    let renumber = |block: &mut BlockId| *block = numbers[block.0];
+    blocks
+        .into_iter()
+        .zip(reached)
+        .filter(|(_, reached)| *reached)
+        .map(|((insts, term), _)| {
+            let mut term = term.expect("remove_unreachable: reached block without terminator");
+            match term {
+                Terminator::Jump(ref mut target) => renumber(target),
+                Terminator::Branch {
+                    ref mut then,
+                    ref mut els,
+                    ..
+                } => {
+                    renumber(then);
+                    renumber(els);
+                }
+                Terminator::Ret(_) | Terminator::Unreachable => {}
+            }
+            Block { insts, term }
+        })
+        .collect()
+}
+
+/// Where a local variable is.
+#[derive(Debug, Clone, Copy)]
+enum Local {
+    Slot(SlotId),
+    /// a struct or union parameter, at the address in the register
+    Byval(Reg),
+}
+
+/// The state of the translation of a function.
+struct Lowering<'a> {
+    function: &'a ast::Function,
+    /// where each local is, None until it is used
+    locals: Vec<Option<Local>>,
+    slots: Vec<Slot>,
+    regs: Vec<Ty>,
+    /// the blocks and their terminators, None until they are terminated
+    blocks: Vec<(Vec<Inst>, Option<Terminator>)>,
+    /// the block the instructions are appended to, never terminated
+    current: BlockId,
+    /// the blocks `break` and `continue` go to, innermost last
+    breaks: Vec<BlockId>,
+    continues: Vec<BlockId>,
+    /// the blocks of the `case` labels and of the `default` label of the
+    /// enclosing `switch` statements, innermost last
+    switches: Vec<(Vec<BlockId>, BlockId)>,
+    /// the blocks of the labels by their names
+    labels: HashMap<String, BlockId>,
+}
+
+impl Lowering<'_> {
+    /// Return a new register of type `ty`
+    fn new_reg(&mut self, ty: Ty) -> Reg {
+        self.regs.push(ty);
+        Reg(self.regs.len() - 1)
+    }
+
+    /// Return a new stack slot
+    fn new_slot(&mut self, name: String, size: i64, align: i64) -> SlotId {
+        self.slots.push(Slot { name, size, align });
+        SlotId(self.slots.len() - 1)
+    }
+
+    /// Return a new empty block
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push((Vec::new(), None));
+        BlockId(self.blocks.len() - 1)
+    }
+
+    /// Appends `inst` to the current block
+    fn emit(&mut self, inst: Inst) {
+        debug!(" emit: {:?}", inst);
+        self.blocks[self.current.0].0.push(inst);
+    }
+
+    /// Ends the current block with `term`, the instructions after it go in
+    /// a new block nothing jumps to yet.
+    fn terminate(&mut self, term: Terminator) {
+        self.blocks[self.current.0].1 = Some(term);
+        self.current = self.new_block();
+    }
+
+    /// Ends the current block with a jump to `block`, which becomes the current one.
+    fn jump_to(&mut self, block: BlockId) {
+        self.blocks[self.current.0].1 = Some(Terminator::Jump(block));
+        self.current = block;
+    }
+
+    /// Return the block of the label `name`
+    fn label(&mut self, name: &str) -> BlockId {
+        match self.labels.get(name) {
+            Some(&block) => block,
+            None => {
+                let block = self.new_block();
+                self.labels.insert(name.to_owned(), block);
+                block
+            }
+        }
+    }
+
+    /// Return the address of the slot
+    fn slot_addr(&mut self, slot: SlotId) -> Operand {
+        let dst = self.new_reg(Ty::Ptr);
+        self.emit(Inst::SlotAddr { dst, slot });
+        Operand::Reg(dst)
+    }
+
+    /// Return the address of the local `id`, giving it a slot if it has none
+    fn local_addr(&mut self, id: usize) -> Operand {
+        match self.locals[id] {
+            Some(Local::Byval(reg)) => Operand::Reg(reg),
+            Some(Local::Slot(slot)) => self.slot_addr(slot),
+            None => {
+                let var = &self.function.locals[id];
+                let slot = self.new_slot(var.name.clone(), var.ty.size(), var.ty.align());
+                self.locals[id] = Some(Local::Slot(slot));
+                self.slot_addr(slot)
+            }
+        }
+    }
+
+    /// Return `lhs op rhs` computed on values of type `ty`
+    fn binary(&mut self, op: BinOp, ty: Ty, lhs: Operand, rhs: Operand) -> Operand {
+        self.compare(op, ty, lhs, rhs, ty)
+    }
+
+    /// Return `lhs op rhs` computed on values of type `ty` into a register
+    /// of type `result`, which differs for a comparison
+    fn compare(&mut self, op: BinOp, ty: Ty, lhs: Operand, rhs: Operand, result: Ty) -> Operand {
+        let dst = self.new_reg(result);
+        self.emit(Inst::Binary {
+            dst,
+            op,
+            ty,
+            lhs,
+            rhs,
+        });
+        Operand::Reg(dst)
+    }
+
+    /// Return `operand` of type `from` converted by `op` to `to`
+    fn conv(&mut self, op: ConvOp, from: Ty, to: Ty, operand: Operand) -> Operand {
+        let dst = self.new_reg(to);
+        self.emit(Inst::Conv {
+            dst,
+            op,
+            from,
+            operand,
+        });
+        Operand::Reg(dst)
+    }
+
+    /// Return the integer `value` of type `from` truncated or extended to `to`
+    fn resize(&mut self, value: Operand, from: Ty, to: Ty, signed: bool) -> Operand {
+        match from.size().cmp(&to.size()) {
+            std::cmp::Ordering::Greater => self.conv(ConvOp::Trunc, from, to, value),
+            std::cmp::Ordering::Less if signed => self.conv(ConvOp::SExt, from, to, value),
+            std::cmp::Ordering::Less => self.conv(ConvOp::ZExt, from, to, value),
+            std::cmp::Ordering::Equal => value,
+        }
+    }
+
+    /// Return the value read at `addr`
+    fn load(&mut self, ty: Ty, addr: Operand) -> Operand {
+        let dst = self.new_reg(ty);
+        self.emit(Inst::Load { dst, ty, addr });
+        Operand::Reg(dst)
+    }
+
+    /// Return `value` of the C type `from` converted to `to`.
+    /// Converting to `_Bool` compares with zero, an integer is truncated,
+    /// or extended according to the signedness of `from`.
+    fn convert(&mut self, value: Operand, from: &Type, to: &Type) -> Operand {
+        let from = from.decay();
+        let (from_ty, to_ty) = (ty_of(&from), ty_of(to));
+        match to {
+            Type::Void => value,
+            Type::Bool if from != Type::Bool => {
+                self.compare(BinOp::Ne, from_ty, value, Operand::Const(0), Ty::I8)
+            }
+            to if to.is_integer() && from.is_integer() => {
+                self.resize(value, from_ty, to_ty, !from.is_unsigned())
+            }
+            to if to.is_integer() && from_ty == Ty::Ptr => {
+                let value = self.conv(ConvOp::PtrToInt, Ty::Ptr, Ty::I64, value);
+                self.resize(value, Ty::I64, to_ty, false)
+            }
+            Type::Ptr(_) if from.is_integer() => {
+                let value = self.resize(value, from_ty, Ty::I64, !from.is_unsigned());
+                self.conv(ConvOp::IntToPtr, Ty::I64, Ty::Ptr, value)
+            }
+            _ => value,
+        }
+    }
+
+    /// Translates a statement.
+    fn stmt(&mut self, node: &Node) {
+        match node.kind {
+            NodeKind::STMT(ref expr) => {
+                self.expr(expr);
+            }
+            NodeKind::MEMZERO(ref var) => {
+                let addr = self.addr(var);
+                let size = var.get_type().size();
+                self.emit(Inst::MemZero { addr, size });
+            }
+            NodeKind::RETURN(ref value) => {
+                let value = value.as_ref().map(|value| self.expr(value));
+                self.terminate(Terminator::Ret(value));
+            }
+            NodeKind::BLOCK(ref stmts) => {
+                for stmt in stmts {
+                    self.stmt(stmt);
+                }
+            }
+            NodeKind::IF {
+                ref cond,
+                ref then,
+                ref els,
+            } => {
+                let (then_block, else_block) = (self.new_block(), self.new_block());
+                let end = match els {
+                    Some(_) => self.new_block(),
+                    None => else_block,
+                };
+                self.cond(cond, then_block, else_block);
+                self.current = then_block;
+                self.stmt(then);
+                self.jump_to(end);
+                if let Some(els) = els {
+                    self.current = else_block;
+                    self.stmt(els);
+                    self.jump_to(end);
+                }
+            }
+            NodeKind::FOR {
+                ref init,
+                ref cond,
+                ref inc,
+                ref body,
+            } => {
+                if let Some(init) = init {
+                    self.stmt(init);
+                }
+                let (test, body_block) = (self.new_block(), self.new_block());
+                let (next, end) = (self.new_block(), self.new_block());
+                self.jump_to(test);
+                match cond {
+                    Some(cond) => self.cond(cond, body_block, end),
+                    None => self.jump_to(body_block),
+                }
+                self.current = body_block;
+                self.breaks.push(end);
+                self.continues.push(next);
+                self.stmt(body);
+                self.breaks.pop();
+                self.continues.pop();
+                self.jump_to(next);
+                if let Some(inc) = inc {
+                    self.expr(inc);
+                }
+                self.jump_to(test);
+                self.current = end;
+            }
+            NodeKind::SWITCH {
+                ref cond,
+                ref body,
+                ref cases,
+                has_default,
+            } => {
+                let ty = ty_of(cond.get_type());
+                let value = self.expr(cond);
+                let blocks = cases.iter().map(|_| self.new_block()).collect::<Vec<_>>();
+                let end = self.new_block();
+                let default = if has_default { self.new_block() } else { end };
+                for (&case, &block) in cases.iter().zip(&blocks) {
+                    let matched =
+                        self.compare(BinOp::Eq, ty, value.clone(), Operand::Const(case), Ty::I32);
+                    let next = self.new_block();
+                    self.blocks[self.current.0].1 = Some(Terminator::Branch {
+                        ty: Ty::I32,
+                        cond: matched,
+                        then: block,
+                        els: next,
+                    });
+                    self.current = next;
+                }
+                // the body is entered at its labels only
+                self.terminate(Terminator::Jump(default));
+                self.breaks.push(end);
+                self.switches.push((blocks, default));
+                self.stmt(body);
+                self.switches.pop();
+                self.breaks.pop();
+                self.jump_to(end);
+            }
+            NodeKind::CASE { index, ref body } => {
+                let (ref blocks, default) = *self
+                    .switches
+                    .last()
+                    .expect("stmt: case outside of a switch");
+                let block = match index {
+                    Some(index) => blocks[index],
+                    None => default,
+                };
+                self.jump_to(block);
+                self.stmt(body);
+            }
+            NodeKind::BREAK => {
+                let target = *self
+                    .breaks
+                    .last()
+                    .expect("stmt: break outside of a loop or switch");
+                self.terminate(Terminator::Jump(target));
+            }
+            NodeKind::CONTINUE => {
+                let target = *self
+                    .continues
+                    .last()
+                    .expect("stmt: continue outside of a loop");
+                self.terminate(Terminator::Jump(target));
+            }
+            NodeKind::GOTO(ref name) => {
+                let target = self.label(name);
+                self.terminate(Terminator::Jump(target));
+            }
+            NodeKind::LABEL { ref name, ref body } => {
+                let block = self.label(name);
+                self.jump_to(block);
+                self.stmt(body);
+            }
+            _ => panic!("stmt: not a statement: {:?}", node),
+        }
+    }
+
+    /// Translates the condition `node`, going to `then` if it holds and
+    /// to `els` otherwise. `&&`, `||` and `!` only branch.
+    fn cond(&mut self, node: &Node, then: BlockId, els: BlockId) {
+        match node.kind {
+            NodeKind::BINOP {
+                op: BinaryOpType::LogAnd,
+                ref lhs,
+                ref rhs,
+            } => {
+                let right = self.new_block();
+                self.cond(lhs, right, els);
+                self.current = right;
+                self.cond(rhs, then, els);
+            }
+            NodeKind::BINOP {
+                op: BinaryOpType::LogOr,
+                ref lhs,
+                ref rhs,
+            } => {
+                let right = self.new_block();
+                self.cond(lhs, then, right);
+                self.current = right;
+                self.cond(rhs, then, els);
+            }
+            NodeKind::UNOP {
+                op: UnaryOpType::Not,
+                ref operand,
+            } => self.cond(operand, els, then),
+            _ => {
+                let ty = ty_of(&node.get_type().decay());
+                let cond = self.expr(node);
+                self.terminate(Terminator::Branch {
+                    ty,
+                    cond,
+                    then,
+                    els,
+                });
+            }
+        }
+    }
+
+    /// Return the address of the lvalue `node`.
+    fn addr(&mut self, node: &Node) -> Operand {
+        match node.kind {
+            NodeKind::VAR(id) => self.local_addr(id),
+            NodeKind::GVAR(ref name) => {
+                let dst = self.new_reg(Ty::Ptr);
+                self.emit(Inst::GlobalAddr {
+                    dst,
+                    name: name.clone(),
+                });
+                Operand::Reg(dst)
+            }
+            NodeKind::UNOP {
+                op: UnaryOpType::Deref,
+                ref operand,
+            } => self.expr(operand),
+            NodeKind::MEMBER {
+                ref base,
+                ref member,
+            } => {
+                let base = self.addr(base);
+                match member.offset {
+                    0 => base,
+                    offset => {
+                        let dst = self.new_reg(Ty::Ptr);
+                        self.emit(Inst::PtrAdd {
+                            dst,
+                            base,
+                            offset: Operand::Const(offset),
+                        });
+                        Operand::Reg(dst)
+                    }
+                }
+            }
+            NodeKind::LITERAL { ref init, ref var } => {
+                self.stmt(init);
+                self.addr(var)
+            }
+            // the members of a returned struct are accessed in its copy
+            NodeKind::CALL { .. } if matches!(node.get_type(), Type::Record(_)) => self.expr(node),
+            _ => panic!("addr: not an lvalue: {:?}", node),
+        }
+    }
+
+    /// Return the value of the expression `node`, its address if it is
+    /// an array, a function, a struct or a union.
+    fn expr(&mut self, node: &Node) -> Operand {
+        match node.kind {
+            NodeKind::NUM(value) => Operand::Const(value),
+            NodeKind::VAR(_)
+            | NodeKind::GVAR(_)
+            | NodeKind::UNOP {
+                op: UnaryOpType::Deref,
+                ..
+            } => {
+                let addr = self.addr(node);
+                match node.get_type() {
+                    ty if is_aggregate(ty) => addr,
+                    ty => self.load(ty_of(ty), addr),
+                }
+            }
+            NodeKind::MEMBER { ref member, .. } => {
+                let addr = self.addr(node);
+                match member.bit_field {
+                    _ if is_aggregate(&member.ty) => addr,
+                    Some(ref bit_field) => {
+                        let ty = ty_of(&member.ty);
+                        let unit = self.load(ty, addr);
+                        let unit = self.resize(unit, ty, Ty::I64, false);
+                        self.extract_bit_field(member, bit_field, unit)
+                    }
+                    None => self.load(ty_of(&member.ty), addr),
+                }
+            }
+            NodeKind::LITERAL { ref init, ref var } => {
+                self.stmt(init);
+                self.expr(var)
+            }
+            NodeKind::UNREACHABLE => {
+                self.terminate(Terminator::Unreachable);
+                Operand::Const(0)
+            }
+            NodeKind::VASTART(ref ap) => {
+                let ap = self.expr(ap);
+                self.emit(Inst::VaStart { ap });
+                Operand::Const(0)
+            }
+            NodeKind::VAARG(ref ap) => {
+                let size = node.get_type().base().map_or(8, Type::size);
+                let ap = self.expr(ap);
+                let dst = self.new_reg(Ty::Ptr);
+                self.emit(Inst::VaArg { dst, ap, size });
+                Operand::Reg(dst)
+            }
+            NodeKind::UNOP {
+                op: UnaryOpType::Addr,
+                ref operand,
+            } => self.addr(operand),
+            NodeKind::UNOP {
+                ref op,
+                ref operand,
+            } => {
+                let ty = ty_of(&operand.get_type().decay());
+                let value = self.expr(operand);
+                let op = match op {
+                    UnaryOpType::Neg => UnOp::Neg,
+                    UnaryOpType::BitNot => UnOp::Not,
+                    _ => {
+                        let result = ty_of(node.get_type());
+                        return self.compare(BinOp::Eq, ty, value, Operand::Const(0), result);
+                    }
+                };
+                let dst = self.new_reg(ty);
+                self.emit(Inst::Unary {
+                    dst,
+                    op,
+                    ty,
+                    operand: value,
+                });
+                Operand::Reg(dst)
+            }
+            NodeKind::CAST(ref operand) | NodeKind::CONV(ref operand) => {
+                let value = self.expr(operand);
+                self.convert(value, operand.get_type(), node.get_type())
+            }
+            NodeKind::ASSIGN { ref lhs, ref rhs } => {
+                let addr = self.addr(lhs);
+                let value = self.expr(rhs);
+                let ty = lhs.get_type();
+                match lhs.kind {
+                    NodeKind::MEMBER {
+                        ref member,
+                        base: _,
+                    } if member.bit_field.is_some() => {
+                        let bit_field = member.bit_field.as_ref().unwrap();
+                        self.store_bit_field(member, bit_field, addr, value)
+                    }
+                    _ if matches!(ty, Type::Record(_)) => {
+                        self.emit(Inst::MemCopy {
+                            dst: addr.clone(),
+                            src: value,
+                            size: ty.size(),
+                        });
+                        addr
+                    }
+                    _ => {
+                        self.emit(Inst::Store {
+                            ty: ty_of(ty),
+                            value: value.clone(),
+                            addr,
+                        });
+                        value
+                    }
+                }
+            }
+            NodeKind::CALL {
+                ref func,
+                ref args,
+                noreturn,
+                ..
+            } => {
+                let callee = match func.kind {
+                    NodeKind::GVAR(ref name) if matches!(func.get_type(), Type::Func { .. }) => {
+                        Callee::Direct(name.clone())
+                    }
+                    _ => Callee::Indirect(self.expr(func)),
+                };
+                let args = args
+                    .iter()
+                    .map(|arg| (arg_ty(arg.get_type()), self.expr(arg)))
+                    .collect();
+                let ret = match node.get_type() {
+                    Type::Void => None,
+                    ty => Some(arg_ty(ty)),
+                };
+                let dst = ret.map(|ret| {
+                    self.new_reg(match ret {
+                        ArgTy::Scalar(ty) => ty,
+                        ArgTy::Byval(_) => Ty::Ptr,
+                    })
+                });
+                self.emit(Inst::Call {
+                    dst,
+                    callee,
+                    args,
+                    ret,
+                });
+                if noreturn {
+                    self.terminate(Terminator::Unreachable);
+                }
+                dst.map_or(Operand::Const(0), Operand::Reg)
+            }
+            NodeKind::BINOP {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } if op.is_logical() => {
+                // the outcomes store the value in a slot read where they join
+                let slot = self.new_slot(format!("({})", op.symbol()), 4, 4);
+                let (then, els, end) = (self.new_block(), self.new_block(), self.new_block());
+                self.cond(node, then, els);
+                for (block, value) in [(then, 1), (els, 0)] {
+                    self.current = block;
+                    let addr = self.slot_addr(slot);
+                    self.emit(Inst::Store {
+                        ty: Ty::I32,
+                        value: Operand::Const(value),
+                        addr,
+                    });
+                    self.jump_to(end);
+                }
+                self.current = end;
+                let addr = self.slot_addr(slot);
+                self.load(Ty::I32, addr)
+            }
+            NodeKind::BINOP {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } => self.binop(node, op, lhs, rhs),
+            _ => panic!("expr: not an expression: {:?}", node),
+        }
+    }
+
+    /// Return the value of the binary operation `node`, other than `&&` and `||`.
+    fn binop(&mut self, node: &Node, op: &BinaryOpType, lhs: &Node, rhs: &Node) -> Operand {
+        let (lhs_ty, rhs_ty) = (lhs.get_type().decay(), rhs.get_type().decay());
+        let result = ty_of(node.get_type());
+        let lhs_value = self.expr(lhs);
+        let mut rhs_value = self.expr(rhs);
+        // pointer arithmetic, the integer operand is already scaled
+        if lhs_ty.base().is_some() || rhs_ty.base().is_some() {
+            match op {
+                BinaryOpType::Add | BinaryOpType::Sub if rhs_ty.is_integer() => {
+                    let offset = self.resize(rhs_value, ty_of(&rhs_ty), Ty::I64, true);
+                    let offset = match op {
+                        BinaryOpType::Sub => {
+                            let dst = self.new_reg(Ty::I64);
+                            self.emit(Inst::Unary {
+                                dst,
+                                op: UnOp::Neg,
+                                ty: Ty::I64,
+                                operand: offset,
+                            });
+                            Operand::Reg(dst)
+                        }
+                        _ => offset,
+                    };
+                    let dst = self.new_reg(Ty::Ptr);
+                    self.emit(Inst::PtrAdd {
+                        dst,
+                        base: lhs_value,
+                        offset,
+                    });
+                    return Operand::Reg(dst);
+                }
+                _ => {
+                    // pointers are compared and subtracted as unsigned numbers
+                    let lhs_value = self.convert(lhs_value, &lhs_ty, &Type::ULong);
+                    let rhs_value = self.convert(rhs_value, &rhs_ty, &Type::ULong);
+                    let op = match op {
+                        BinaryOpType::Sub => BinOp::Sub,
+                        BinaryOpType::Eq => BinOp::Eq,
+                        BinaryOpType::Ne => BinOp::Ne,
+                        BinaryOpType::Lt => BinOp::ULt,
+                        BinaryOpType::Le => BinOp::ULe,
+                        _ => panic!("binop: invalid operands of {:?}", node),
+                    };
+                    return self.compare(op, Ty::I64, lhs_value, rhs_value, result);
+                }
+            }
+        }
+        let ty = ty_of(&lhs_ty);
+        // the operands of a shift are promoted separately
+        if op.is_shift() {
+            rhs_value = self.resize(rhs_value, ty_of(&rhs_ty), ty, !rhs_ty.is_unsigned());
+        }
+        let unsigned = lhs_ty.is_unsigned() || (!op.is_shift() && rhs_ty.is_unsigned());
+        let op = match op {
+            BinaryOpType::Add => BinOp::Add,
+            BinaryOpType::Sub => BinOp::Sub,
+            BinaryOpType::Mul => BinOp::Mul,
+            BinaryOpType::Div if unsigned => BinOp::UDiv,
+            BinaryOpType::Div => BinOp::SDiv,
+            BinaryOpType::Mod if unsigned => BinOp::URem,
+            BinaryOpType::Mod => BinOp::SRem,
+            BinaryOpType::BitAnd => BinOp::And,
+            BinaryOpType::BitOr => BinOp::Or,
+            BinaryOpType::BitXor => BinOp::Xor,
+            BinaryOpType::Shl => BinOp::Shl,
+            BinaryOpType::Shr if unsigned => BinOp::LShr,
+            BinaryOpType::Shr => BinOp::AShr,
+            BinaryOpType::Eq => BinOp::Eq,
+            BinaryOpType::Ne => BinOp::Ne,
+            BinaryOpType::Lt if unsigned => BinOp::ULt,
+            BinaryOpType::Lt => BinOp::SLt,
+            BinaryOpType::Le if unsigned => BinOp::ULe,
+            BinaryOpType::Le => BinOp::SLe,
+            BinaryOpType::LogAnd | BinaryOpType::LogOr => unreachable!(),
+        };
+        self.compare(op, ty, lhs_value, rhs_value, result)
+    }
+
+    /// Return the bit-field extracted from its storage unit `unit`, extended
+    /// to 64 bits, sign or zero extended according to the type of the member.
+    fn extract_bit_field(
+        &mut self,
+        member: &Member,
+        bit_field: &BitField,
+        unit: Operand,
+    ) -> Operand {
+        let shift = Operand::Const(64 - bit_field.width - bit_field.bit_offset);
+        let value = self.binary(BinOp::Shl, Ty::I64, unit, shift);
+        let op = match member.ty.is_unsigned() {
+            true => BinOp::LShr,
+            false => BinOp::AShr,
+        };
+        let value = self.binary(op, Ty::I64, value, Operand::Const(64 - bit_field.width));
+        self.resize(value, Ty::I64, ty_of(&member.ty), false)
+    }
+
+    /// Stores `value` into the bit-field whose storage unit is at `addr`,
+    /// keeping the other bits of the unit.
+    /// Return the new value of the bit-field.
+    fn store_bit_field(
+        &mut self,
+        member: &Member,
+        bit_field: &BitField,
+        addr: Operand,
+        value: Operand,
+    ) -> Operand {
+        let ty = ty_of(&member.ty);
+        let mask = match bit_field.width {
+            64 => -1,
+            width => (1i64 << width) - 1,
+        };
+        let value = self.resize(value, ty, Ty::I64, false);
+        let bits = self.binary(BinOp::And, Ty::I64, value.clone(), Operand::Const(mask));
+        let offset = Operand::Const(bit_field.bit_offset);
+        let bits = self.binary(BinOp::Shl, Ty::I64, bits, offset);
+        // read, modify and write back the storage unit
+        let unit = self.load(ty, addr.clone());
+        let unit = self.resize(unit, ty, Ty::I64, false);
+        let kept = Operand::Const(!(mask << bit_field.bit_offset));
+        let unit = self.binary(BinOp::And, Ty::I64, unit, kept);
+        let unit = self.binary(BinOp::Or, Ty::I64, unit, bits);
+        let unit = self.resize(unit, Ty::I64, ty, false);
+        self.emit(Inst::Store {
+            ty,
+            value: unit,
+            addr,
+        });
+        let field = BitField {
+            bit_offset: 0,
+            width: bit_field.width,
+        };
+        self.extract_bit_field(member, &field, value)
+    }
+}
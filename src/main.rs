@@ -1,38 +1,153 @@
 use std::env;
+use std::fs;
 use std::process;
 
 use log::{debug, error};
 
+use tiny_c_compiler::diagnostics::Diagnostics;
+use tiny_c_compiler::ir::Module;
 use tiny_c_compiler::parser::TinyCParser;
 use tiny_c_compiler::scanner::{TinyCScanner, Token};
 use tiny_c_compiler::stream::BasicStream;
 
+/// What the compiler prints.
+enum Output {
+    /// the assembly code, by default
+    Asm,
+    /// the text of the IR, with `--emit=ir`
+    Ir,
+    /// the control-flow graph of each function in the dot language, with `--dump-cfg=dot`
+    CfgDot,
+}
+
+/// A pass transforming the IR, and its name.
+type Pass = (&'static str, fn(&mut Module));
+
+/// The passes transforming the IR, all run in this order by default.
+const PASSES: [Pass; 1] = [("mem2reg", tiny_c_compiler::ssa::mem2reg)];
+
+/// Compile the C program `source` into the IR.
+fn lower_source(source: String, diagnostics: Diagnostics) -> Module {
+    // init the scanner
+    let scanner = TinyCScanner::new(BasicStream::new_with_string(source));
+
+    debug!("start to parse the program");
+    let mut parser = TinyCParser::new(scanner, diagnostics);
+
+    let program = parser.program();
+
+    debug_assert_eq!(parser.get_current_token(), &Token::Eof);
+
+    debug!("parse the program success: {:?}", program);
+
+    // reject the programs which are not valid C
+    let mut diagnostics = parser.into_diagnostics();
+    tiny_c_compiler::typeck::check_program(&program, &mut diagnostics);
+    tiny_c_compiler::flow::check_program(&program, &mut diagnostics);
+    tiny_c_compiler::uninit::check_program(&program, &mut diagnostics);
+    debug!("{} warnings reported", diagnostics.count());
+
+    tiny_c_compiler::lower::lower_program(&program)
+}
+
 fn main() {
     // enable the log  print debug info
     env::set_var("RUST_LOG", "debug");
     env_logger::init();
     let args = env::args().collect::<Vec<String>>();
 
-    if args.len() != 2 {
+    // the options come before the program:
+    // `susuncc [-W...]... [--emit=ir|--dump-cfg=dot] [--passes=...] [--verify-each] program`,
+    // or the IR is read from a file, `--from-ir file.ir` instead of the program
+    let mut diagnostics = Diagnostics::new();
+    let mut sources = Vec::new();
+    let mut output = Output::Asm;
+    let mut from_ir = None;
+    let mut passes: Vec<Pass> = PASSES.to_vec();
+    // the IR is always verified between the passes in the debug builds
+    let mut verify_each = cfg!(debug_assertions);
+    let mut options = args[1..].iter();
+    while let Some(arg) = options.next() {
+        if arg.starts_with("-W") {
+            if let Err(message) = diagnostics.option(arg) {
+                error!("{}: {}", args[0], message);
+                process::exit(1);
+            }
+        } else if arg == "--emit=ir" {
+            output = Output::Ir;
+        } else if arg == "--emit=asm" {
+            output = Output::Asm;
+        } else if let Some(format) = arg.strip_prefix("--dump-cfg=") {
+            if format != "dot" {
+                error!("{}: unknown format '{}' for --dump-cfg", args[0], format);
+                process::exit(1);
+            }
+            output = Output::CfgDot;
+        } else if let Some(names) = arg.strip_prefix("--passes=") {
+            // the passes run in the order given, none if the list is empty
+            passes.clear();
+            for name in names.split(',').filter(|name| !name.is_empty()) {
+                match PASSES.iter().find(|(pass, _)| *pass == name) {
+                    Some(&pass) => passes.push(pass),
+                    None => {
+                        error!("{}: unknown pass '{}'", args[0], name);
+                        process::exit(1);
+                    }
+                }
+            }
+        } else if arg == "--verify-each" {
+            verify_each = true;
+        } else if arg == "--from-ir" {
+            match options.next() {
+                Some(path) => from_ir = Some(path.clone()),
+                None => {
+                    error!("{}: --from-ir needs a file", args[0]);
+                    process::exit(1);
+                }
+            }
+        } else {
+            sources.push(arg.clone());
+        }
+    }
+    if sources.len() != from_ir.is_none() as usize {
         error!("{} Invalid number of argument", args[0]);
         process::exit(1);
     }
 
-    // init the scanner
-    let scanner = TinyCScanner::new(BasicStream::new_with_string(args[1].clone()));
-
-    debug!("start to parse the expr");
-    let mut parser = TinyCParser::new(scanner);
-
-    let node = parser.expr();
-
-    debug_assert_eq!(parser.get_current_token(), &Token::Eof);
-
-    debug!("parse the expr success: {:?}", node);
+    let (mut module, pass) = match from_ir {
+        Some(path) => {
+            let text = fs::read_to_string(&path).unwrap_or_else(|err| {
+                error!("{}: can't read {}: {}", args[0], path, err);
+                process::exit(1);
+            });
+            (tiny_c_compiler::irparse::parse_module(&text), "irparse")
+        }
+        None => (lower_source(sources.remove(0), diagnostics), "lower"),
+    };
+    let verify = |module: &Module, pass: &str| {
+        if verify_each {
+            tiny_c_compiler::verify::verify_module(module, pass);
+        }
+    };
+    verify(&module, pass);
+    for (pass, run) in passes {
+        debug!("run the pass {}", pass);
+        run(&mut module);
+        verify(&module, pass);
+    }
 
-    println!("  .globl main");
-    println!("main:");
-    // generate the assembly code
-    tiny_c_compiler::codegen::gen_expr(&node);
-    println!("    ret");
+    match output {
+        Output::Ir => print!("{}", module),
+        Output::CfgDot => {
+            for function in &module.functions {
+                print!("{}", tiny_c_compiler::cfg::dot(function));
+            }
+        }
+        Output::Asm => {
+            // the code is generated from copies instead of phis
+            tiny_c_compiler::ssa::out_of_ssa(&mut module);
+            verify(&module, tiny_c_compiler::ssa::OUT_OF_SSA);
+            tiny_c_compiler::codegen::gen_program(&module);
+        }
+    }
 }
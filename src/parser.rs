@@ -2,30 +2,178 @@
 // so it's input is tokens
 // and it's output is AST
 
-// Our goal is to parse a expression
+// Our goal is to parse a program made of function definitions
 // The priority of the operators is:
-// 1. ()
-// 2. * /
-// 3. + -
+// 1. () [] . ->
+// 2. unary + - ! ~ & * sizeof _Alignof (type)
+// 3. * / %
+// 4. + -
+// 5. << >>
+// 6. < <= > >=
+// 7. == !=
+// 8. &
+// 9. ^
+// 10. |
+// 11. &&
+// 12. ||
+// 13. =
 
-use crate::ast::{BinaryOpType, Node};
-use crate::scanner::{KeywordType, Scanner, TinyCScanner, Token};
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{
+    BinaryOpType, Format, FormatKind, Function, Global, Node, NodeKind, Program, Reloc,
+    UnaryOpType, Var,
+};
+use crate::consteval::{convert, eval, eval_reloc};
+use crate::diagnostics::{error, Diagnostics, Warning};
+use crate::scanner::{KeywordType, Location, Scanner, TinyCScanner, Token};
+use crate::symbols::{Ordinary, Symbol, SymbolTable, Tag};
+use crate::types::{ArgClass, MemberDecl, Param, RecordRef, Type};
 
 use log::{debug, info};
 
+/// A step of a declarator deriving a type from the type before it.
+enum Derivation {
+    Pointer,
+    /// an array of the length, negative if unknown
+    Array(i64),
+    /// a function of the parameters returning the type before,
+    /// true if it is variadic
+    Func(Vec<Param>, bool),
+}
+
+/// The storage class specifier of a declaration.
+#[derive(Clone, Copy, PartialEq)]
+enum StorageClass {
+    /// no specifier: external linkage at file scope, automatic in a block
+    None,
+    /// internal linkage at file scope, static storage duration in a block
+    Static,
+    /// a declaration of an object or function defined elsewhere
+    Extern,
+}
+
+/// An initializer matched against the type of the object it initializes.
+#[derive(Clone)]
+enum Initializer {
+    /// an expression initializing a scalar, or a struct by copy
+    Expr(Node),
+    /// the elements of an array or the members of a struct or union by their
+    /// index, the ones which are None or missing at the end are zero
+    List(Vec<Option<Initializer>>),
+}
+
+/// The attributes of a declaration the compiler understands.
+#[derive(Default)]
+struct Attributes {
+    /// `format(kind, string, first)` with the indexes as written, from 1,
+    /// and where it is
+    format: Option<(FormatKind, i64, i64, Location)>,
+}
+
+/// The `switch` statement being parsed.
+struct SwitchContext {
+    /// the promoted type of the controlling expression
+    ty: Type,
+    cases: Vec<i64>,
+    has_default: bool,
+}
+
 /// `TinyCParser` is responsible for parsing tokens and providing the AST.
 pub struct TinyCParser {
     scanner: TinyCScanner,
     current_token: Token,
+    /// the location of the current token
+    location: Location,
+    /// the token after the current one and its location, if it has been looked at
+    peeked: Option<(Token, Location)>,
+    /// locals of the function being parsed
+    locals: Vec<Var>,
+    /// global variables and string literals
+    globals: Vec<Global>,
+    /// the global variables declared in the source and where they are declared first
+    declared_globals: HashMap<usize, Location>,
+    /// the global variables with an initializer and where they are defined
+    defined_globals: HashMap<usize, Location>,
+    /// the identifiers visible in the scopes being parsed
+    symbols: SymbolTable,
+    /// types of the declared and defined functions and where they are declared first
+    functions: HashMap<String, Symbol<Type>>,
+    /// the functions defined with a body and where
+    defined_functions: HashMap<String, Location>,
+    /// names of the functions declared `static`
+    static_functions: HashSet<String>,
+    /// the enclosing `switch` statements, innermost last
+    switches: Vec<SwitchContext>,
+    /// the return type of the function being parsed
+    ret: Type,
+    /// the local holding the register save area if the function being parsed is variadic
+    va_area: Option<usize>,
+    /// the type of `va_list`
+    va_list: Type,
+    /// the warnings reported while parsing
+    diagnostics: Diagnostics,
+    /// how many of the locals of the function being parsed are its parameters
+    param_count: usize,
+    /// the locals of the function being parsed declared in the source,
+    /// and where, for the warnings about the unused ones
+    declared: Vec<(usize, Location)>,
+    /// the locals of the function being parsed referred to by name
+    used: HashSet<usize>,
+    /// the functions which never return: the ones declared `_Noreturn`
+    /// and those of the C library gcc knows of
+    noreturn_functions: HashSet<String>,
+    /// the functions taking a format string: the ones declared with
+    /// the `format` attribute and the formatted I/O of the C library
+    format_functions: HashMap<String, Format>,
+    /// the labels of the function being parsed and where they are
+    labels: HashMap<String, Location>,
+    /// the labels the `goto` statements of the function being parsed jump to
+    gotos: Vec<(String, Location)>,
 }
 
 impl TinyCParser {
-    /// Constructs a new `TinyCParser`
-    pub fn new(mut scanner: TinyCScanner) -> TinyCParser {
-        let current_token = scanner.next_token();
+    /// Constructs a new `TinyCParser` reporting the warnings to `diagnostics`
+    pub fn new(mut scanner: TinyCScanner, mut diagnostics: Diagnostics) -> TinyCParser {
+        let (current_token, location) = Self::scan(&mut scanner, &mut diagnostics);
+        // without a preprocessor <stdarg.h> can't be included, so its
+        // `va_list` is predefined like gcc's `__builtin_va_list`
+        let va_list = Type::va_list();
+        let mut symbols = SymbolTable::new();
+        for name in ["va_list", "__builtin_va_list"] {
+            let typedef = Ordinary::Typedef(va_list.clone());
+            symbols
+                .declare(name, typedef, Location::default(), true)
+                .expect("new: predefined typedef redeclared");
+        }
         TinyCParser {
             scanner,
             current_token,
+            location,
+            peeked: None,
+            locals: Vec::new(),
+            globals: Vec::new(),
+            declared_globals: HashMap::new(),
+            defined_globals: HashMap::new(),
+            symbols,
+            functions: HashMap::new(),
+            defined_functions: HashMap::new(),
+            static_functions: HashSet::new(),
+            switches: Vec::new(),
+            ret: Type::Int,
+            va_area: None,
+            va_list,
+            diagnostics,
+            param_count: 0,
+            declared: Vec::new(),
+            used: HashSet::new(),
+            noreturn_functions: ["exit", "abort", "_Exit"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            format_functions: library_formats(),
+            labels: HashMap::new(),
+            gotos: Vec::new(),
         }
     }
 
@@ -34,27 +182,1863 @@ impl TinyCParser {
         &self.current_token
     }
 
+    /// Return the diagnostics, with the pragmas met while parsing,
+    /// for the checks after parsing
+    pub fn into_diagnostics(self) -> Diagnostics {
+        self.diagnostics
+    }
+
+    /// Return the next token from the scanner and its location.
+    /// The pragmas before it are applied to `diagnostics`.
+    fn scan(scanner: &mut TinyCScanner, diagnostics: &mut Diagnostics) -> (Token, Location) {
+        loop {
+            match scanner.next_token() {
+                Token::Pragma(text) => diagnostics.pragma(scanner.location(), &text),
+                token => return (token, scanner.location()),
+            }
+        }
+    }
+
     /// Updates the current token to the next token from the scanner
     fn next(&mut self) {
-        self.current_token = self.scanner.next_token();
+        (self.current_token, self.location) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => Self::scan(&mut self.scanner, &mut self.diagnostics),
+        };
+    }
+
+    /// Returns the token after the current one without skipping the current one
+    fn peek(&mut self) -> &Token {
+        if self.peeked.is_none() {
+            self.peeked = Some(Self::scan(&mut self.scanner, &mut self.diagnostics));
+        }
+        &self.peeked.as_ref().unwrap().0
+    }
+
+    /// Return true if the current token is '(' and the next one starts a type name,
+    /// e.g. the start of a cast or of `sizeof(int)`.
+    fn is_bracketed_typename(&mut self) -> bool {
+        if !self.is_keyword(KeywordType::Lbracket) {
+            return false;
+        }
+        self.peek();
+        self.is_typename_token(&self.peeked.as_ref().unwrap().0)
+    }
+
+    /// Return true if the current token is the keyword
+    fn is_keyword(&self, keyword: KeywordType) -> bool {
+        self.current_token == Token::Keyword(keyword)
+    }
+
+    /// Skips the current token if it is the keyword, return true if skipped
+    fn consume(&mut self, keyword: KeywordType) -> bool {
+        if self.is_keyword(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skips the current token which must be the keyword
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current token is not the keyword.
+    fn expect(&mut self, keyword: KeywordType) {
+        if !self.is_keyword(keyword.clone()) {
+            error(
+                self.location,
+                &format!("expect a {:?}, but got {:?}", keyword, self.current_token),
+            );
+        }
+        self.next();
+    }
+
+    /// Skips the current token which must be an identifier and returns its name
+    fn expect_ident(&mut self) -> String {
+        match self.current_token {
+            Token::Var(ref name) => {
+                let name = name.clone();
+                self.next();
+                name
+            }
+            _ => error(
+                self.location,
+                &format!("expect an identifier, but got {:?}", self.current_token),
+            ),
+        }
+    }
+
+    /// Declares an ordinary identifier in the innermost scope, or in the
+    /// file scope if `file_scope` is true.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the identifier is declared in the same scope before,
+    /// unless both declarations may refer to the same entity.
+    fn declare(&mut self, name: &str, value: Ordinary, location: Location, file_scope: bool) {
+        if let Err(previous) = self.symbols.declare(name, value, location, file_scope) {
+            error(
+                location,
+                &format!(
+                    "redeclaration of {}, previous declaration at {}",
+                    name, previous.location
+                ),
+            );
+        }
+    }
+
+    /// Declares a struct, union or enum tag in the innermost scope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tag is declared in the same scope before.
+    fn declare_tag(&mut self, name: &str, tag: Tag, location: Location) {
+        if let Err(previous) = self.symbols.declare_tag(name, tag, location) {
+            error(
+                location,
+                &format!(
+                    "redefinition of {}, previous declaration at {}",
+                    name, previous.location
+                ),
+            );
+        }
+    }
+
+    /// Declares a new local variable in the innermost scope
+    fn new_local(&mut self, name: String, ty: Type, location: Location) -> usize {
+        info!(" new local {} of type {:?}", name, ty);
+        let id = self.locals.len();
+        self.warn_shadow(&name, location);
+        self.declare(&name, Ordinary::Local(id), location, false);
+        self.locals.push(Var { name, ty, location });
+        self.declared.push((id, location));
+        id
+    }
+
+    /// Warns if a variable `name` declared in the innermost scope hides
+    /// a variable of an enclosing scope.
+    fn warn_shadow(&mut self, name: &str, location: Location) {
+        let (previous, file_scope) = match self.symbols.shadowed(name) {
+            Some((previous, file_scope)) => (previous.clone(), file_scope),
+            None => return,
+        };
+        let what = match previous.value {
+            Ordinary::Local(id) if id < self.param_count => "a parameter",
+            Ordinary::Global(_) if file_scope => "a global declaration",
+            Ordinary::Local(_) | Ordinary::Global(_) => "a previous local",
+            _ => return,
+        };
+        let message = format!(
+            "declaration of {} shadows {}, previous declaration at {}",
+            name, what, previous.location
+        );
+        self.diagnostics.warn(Warning::Shadow, location, &message);
+    }
+
+    /// Warns about the locals of the function being parsed which are
+    /// never used, the first `param_count` being its parameters.
+    fn warn_unused(&mut self) {
+        for (id, location) in std::mem::take(&mut self.declared) {
+            if self.used.contains(&id) {
+                continue;
+            }
+            let (warning, what) = match id < self.param_count {
+                true => (Warning::UnusedParameter, "parameter"),
+                false => (Warning::UnusedVariable, "variable"),
+            };
+            let message = format!("unused {} {}", what, self.locals[id].name);
+            self.diagnostics.warn(warning, location, &message);
+        }
+        self.used.clear();
+    }
+
+    /// Declares the global variable `name`. A variable declared before with
+    /// the same name is the same object: a tentative definition or an `extern`
+    /// declaration merges with the other declarations, and an array of unknown
+    /// size is completed by a later declaration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the declaration conflicts with the one before.
+    fn declare_global(
+        &mut self,
+        name: String,
+        ty: Type,
+        storage: StorageClass,
+        location: Location,
+    ) -> usize {
+        info!(" declare global {} of type {:?}", name, ty);
+        let is_definition = storage != StorageClass::Extern;
+        if let Some(id) = self.globals.iter().position(|global| global.name == name) {
+            let previous = self.declared_globals[&id];
+            let global = &mut self.globals[id];
+            let complete = match (&global.ty, &ty) {
+                (Type::Array(base, len), Type::Array(new_base, new_len))
+                    if base == new_base && (*len < 0 || *new_len < 0 || len == new_len) =>
+                {
+                    *len < 0
+                }
+                (old, new) if old == new => false,
+                _ => error(
+                    location,
+                    &format!(
+                        "conflicting types for {}, previous declaration at {}",
+                        name, previous
+                    ),
+                ),
+            };
+            if complete {
+                global.ty = ty;
+            }
+            match (global.is_static, storage) {
+                (false, StorageClass::Static) => error(
+                    location,
+                    &format!(
+                        "static declaration of {} follows non-static declaration, \
+                         previous declaration at {}",
+                        name, previous
+                    ),
+                ),
+                (true, StorageClass::None) => error(
+                    location,
+                    &format!(
+                        "non-static declaration of {} follows static declaration, \
+                         previous declaration at {}",
+                        name, previous
+                    ),
+                ),
+                _ => {}
+            }
+            global.is_definition |= is_definition;
+            return id;
+        }
+        let id = self.globals.len();
+        self.declared_globals.insert(id, location);
+        self.globals.push(Global {
+            name,
+            ty,
+            init: None,
+            relocs: Vec::new(),
+            is_static: storage == StorageClass::Static,
+            is_definition,
+        });
+        id
+    }
+
+    /// Parses the initializer of the global variable `id` declared at
+    /// `location` into its initial value. The initializer must be constant.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the variable has been initialized before.
+    fn global_initializer(&mut self, id: usize, location: Location) {
+        if let Some(previous) = self.defined_globals.insert(id, location) {
+            error(
+                location,
+                &format!(
+                    "redefinition of {}, previous definition at {}",
+                    self.globals[id].name, previous
+                ),
+            );
+        }
+        let (ty, init) = self.initializer(self.globals[id].ty.clone());
+        let mut data = vec![0; ty.size() as usize];
+        let mut relocs = Vec::new();
+        write_global_init(&mut data, &mut relocs, &ty, init, 0);
+        let global = &mut self.globals[id];
+        global.ty = ty;
+        global.init = Some(data);
+        global.relocs = relocs;
+        global.is_definition = true;
+    }
+
+    /// Parses an optional storage class specifier according to the rule:
+    /// storage_class = ('static' | 'extern')?
+    fn storage_class(&mut self) -> StorageClass {
+        if self.consume(KeywordType::Static) {
+            StorageClass::Static
+        } else if self.consume(KeywordType::Extern) {
+            StorageClass::Extern
+        } else {
+            StorageClass::None
+        }
+    }
+
+    /// Parses the attributes of a declaration according to the rule:
+    /// attributes = ('__attribute__' '(' '(' (attribute (',' attribute)*)? ')' ')')*
+    /// attribute = ident ('(' (ident | const_expr) (',' const_expr)* ')')?
+    /// Only `format(printf | scanf, string, first)` is understood, the
+    /// other attributes are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the kind of a format attribute is unknown.
+    fn attributes(&mut self) -> Attributes {
+        let mut attributes = Attributes::default();
+        while matches!(self.current_token, Token::Var(ref name) if name == "__attribute__") {
+            self.next();
+            self.expect(KeywordType::Lbracket);
+            self.expect(KeywordType::Lbracket);
+            while !self.consume(KeywordType::Rbracket) {
+                if self.consume(KeywordType::Comma) {
+                    continue;
+                }
+                let location = self.location;
+                let name = self.expect_ident();
+                if !matches!(name.as_str(), "format" | "__format__") {
+                    debug!(" attributes: skip {}", name);
+                    self.skip_brackets();
+                    continue;
+                }
+                self.expect(KeywordType::Lbracket);
+                let kind = match self.expect_ident().trim_matches('_') {
+                    "printf" => FormatKind::Printf,
+                    "scanf" => FormatKind::Scanf,
+                    kind => error(
+                        location,
+                        &format!("unknown format kind {}, expected printf or scanf", kind),
+                    ),
+                };
+                self.expect(KeywordType::Comma);
+                let string = self.const_expr();
+                self.expect(KeywordType::Comma);
+                let first = self.const_expr();
+                self.expect(KeywordType::Rbracket);
+                attributes.format = Some((kind, string, first, location));
+            }
+            self.expect(KeywordType::Rbracket);
+        }
+        attributes
+    }
+
+    /// Skips the tokens between brackets if the current token is '(',
+    /// the brackets nested in them included.
+    fn skip_brackets(&mut self) {
+        if !self.consume(KeywordType::Lbracket) {
+            return;
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            match self.current_token {
+                Token::Keyword(KeywordType::Lbracket) => depth += 1,
+                Token::Keyword(KeywordType::Rbracket) => depth -= 1,
+                Token::Eof => error(self.location, "expected ')'"),
+                _ => {}
+            }
+            self.next();
+        }
+    }
+
+    /// Parses a whole program according to the rule:
+    /// program = (typedef | static_assert | specifiers declspec ';'
+    ///           | specifiers declspec declarator attributes (function | global_variables))*
+    /// specifiers = attributes '_Noreturn'? storage_class '_Noreturn'?
+    /// A declspec alone declares a struct, union or enum.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable defined in the file has an incomplete type,
+    /// or if a variable is declared `_Noreturn` or with the `format` attribute.
+    pub fn program(&mut self) -> Program {
+        let mut functions = Vec::new();
+        while !self.current_token.is_eof() {
+            if self.consume(KeywordType::Typedef) {
+                self.typedef();
+                continue;
+            }
+            if self.is_keyword(KeywordType::StaticAssert) {
+                self.static_assert();
+                continue;
+            }
+            let mut attributes = self.attributes();
+            let mut noreturn = self.consume(KeywordType::Noreturn);
+            let storage = self.storage_class();
+            noreturn |= self.consume(KeywordType::Noreturn);
+            let base = self.declspec();
+            if self.consume(KeywordType::Semicolon) {
+                continue;
+            }
+            let (name, ty, location) = self.declarator(base.clone());
+            let after = self.attributes();
+            attributes.format = after.format.or(attributes.format);
+            if let Type::Func { .. } = ty {
+                if noreturn {
+                    self.noreturn_functions.insert(name.clone());
+                }
+                if let Some(format) = attributes.format {
+                    let format = check_format_attribute(&ty, format);
+                    self.format_functions.insert(name.clone(), format);
+                }
+                if let Some(function) = self.function(name, ty, location, storage) {
+                    functions.push(function);
+                }
+                continue;
+            }
+            if noreturn {
+                error(location, "_Noreturn can only appear on functions");
+            }
+            if let Some((_, _, _, location)) = attributes.format {
+                error(location, "format attribute can only appear on functions");
+            }
+            self.global_variables(base, (name, ty, location), storage);
+        }
+        // an array still of unknown size at the end of the translation unit
+        // has one element, as if declared with an initializer of zero
+        for (id, global) in self.globals.iter_mut().enumerate() {
+            if let Type::Array(ref base, len) = global.ty {
+                if global.is_definition && len < 0 {
+                    global.ty = Type::array_of(base.as_ref().clone(), 1);
+                    let message = format!("array {} assumed to have one element", global.name);
+                    self.diagnostics
+                        .warn_always(self.declared_globals[&id], &message);
+                }
+            }
+            // the type of a tentative definition may be completed after it, but
+            // not after the end of the translation unit
+            match self.declared_globals.get(&id) {
+                Some(&location) if global.is_definition => {
+                    check_object_type(&global.name, &global.ty, location)
+                }
+                _ => {}
+            }
+        }
+        Program {
+            globals: std::mem::take(&mut self.globals),
+            functions,
+        }
+    }
+
+    /// Parses the global variables of a declaration after its first declarator
+    /// according to the rule:
+    /// global_variables = ('=' initializer)? (',' declarator ('=' initializer)?)* ';'
+    /// A variable without an initializer is a tentative definition, it is zero
+    /// initialized unless another declaration initializes it.
+    fn global_variables(
+        &mut self,
+        base: Type,
+        (mut name, mut ty, mut location): (String, Type, Location),
+        storage: StorageClass,
+    ) {
+        loop {
+            let id = self.declare_global(name.clone(), ty, storage, location);
+            self.declare(&name, Ordinary::Global(id), location, true);
+            if self.consume(KeywordType::Assign) {
+                check_object_type(&name, &self.globals[id].ty, location);
+                self.global_initializer(id, location);
+            }
+            if self.consume(KeywordType::Semicolon) {
+                return;
+            }
+            self.expect(KeywordType::Comma);
+            (name, ty, location) = self.declarator(base.clone());
+        }
+    }
+
+    /// Parses a function definition or declaration after its declarator
+    /// according to the rule: function = '{' compound_stmt | ';'
+    /// A function declared `static` once is static in all its declarations.
+    /// Return None for a declaration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a static declaration follows a non-static one, if the
+    /// function is defined twice, or if a label is defined twice or used
+    /// without being defined.
+    fn function(
+        &mut self,
+        name: String,
+        ty: Type,
+        location: Location,
+        storage: StorageClass,
+    ) -> Option<Function> {
+        debug!(" function: {} {:?}", name, ty);
+        let (params, ret, is_variadic) = match ty {
+            Type::Func {
+                ref params,
+                ref ret,
+                is_variadic,
+            } => (params.clone(), ret.as_ref().clone(), is_variadic),
+            _ => panic!("function: {} is not a function", name),
+        };
+        if storage == StorageClass::Static {
+            match self.functions.get(&name) {
+                Some(previous) if !self.static_functions.contains(&name) => error(
+                    location,
+                    &format!(
+                        "static declaration of {} follows non-static declaration, \
+                         previous declaration at {}",
+                        name, previous.location
+                    ),
+                ),
+                _ => {}
+            }
+            self.static_functions.insert(name.clone());
+        }
+        self.declare_function(name.clone(), ty, location);
+        if self.consume(KeywordType::Semicolon) {
+            return None;
+        }
+        self.expect(KeywordType::Lbrace);
+        if let Some(previous) = self.defined_functions.insert(name.clone(), location) {
+            error(
+                location,
+                &format!(
+                    "redefinition of {}, previous definition at {}",
+                    name, previous
+                ),
+            );
+        }
+        self.ret = ret.clone();
+        self.locals = Vec::new();
+        // the parameters are in the scope of the outermost block of the body
+        self.symbols.enter_scope();
+        self.param_count = params.len();
+        let params = params
+            .into_iter()
+            .map(|param| self.new_local(param.name, param.ty, param.location))
+            .collect();
+        // the 6 general purpose and 8 vector argument registers are saved
+        // there for `va_arg`
+        self.va_area = is_variadic.then(|| {
+            self.locals.push(Var {
+                name: "(register save area)".to_owned(),
+                ty: Type::array_of(Type::Long, 22),
+                location: Location::default(),
+            });
+            self.locals.len() - 1
+        });
+        let sret = (ret.classify() == [ArgClass::Memory]).then(|| {
+            self.locals.push(Var {
+                name: "(return address)".to_owned(),
+                ty: Type::pointer_to(ret.clone()),
+                location: Location::default(),
+            });
+            self.locals.len() - 1
+        });
+        let body = self.block_items();
+        self.symbols.leave_scope();
+        self.warn_unused();
+        for (label, location) in std::mem::take(&mut self.gotos) {
+            if !self.labels.contains_key(&label) {
+                error(location, &format!("use of undeclared label {}", label));
+            }
+        }
+        self.labels.clear();
+        Some(Function {
+            is_static: self.static_functions.contains(&name),
+            is_noreturn: self.noreturn_functions.contains(&name),
+            location,
+            name,
+            params,
+            ret,
+            locals: std::mem::take(&mut self.locals),
+            va_area: self.va_area.take(),
+            sret,
+            body,
+        })
+    }
+
+    /// Declares the function `name` in the innermost scope.
+    /// Every declaration of a function must have the same type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type conflicts with the one of a previous declaration.
+    fn declare_function(&mut self, name: String, ty: Type, location: Location) {
+        self.declare(&name, Ordinary::Function, location, false);
+        match self.functions.get(&name) {
+            Some(previous) if previous.value != ty => error(
+                location,
+                &format!(
+                    "conflicting types for {}, previous declaration at {}",
+                    name, previous.location
+                ),
+            ),
+            Some(_) => {}
+            None => {
+                self.functions.insert(
+                    name,
+                    Symbol {
+                        value: ty,
+                        location,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Return the type a typedef name stands for,
+    /// or None if the name is not a typedef name in the current scope
+    fn find_typedef(&self, name: &str) -> Option<Type> {
+        match self.symbols.lookup(name) {
+            Some(Symbol {
+                value: Ordinary::Typedef(ty),
+                ..
+            }) => Some(ty.clone()),
+            _ => None,
+        }
+    }
+
+    /// Return true if the current token starts a declspec.
+    fn is_typename(&self) -> bool {
+        self.is_typename_token(&self.current_token)
+    }
+
+    /// Return true if the token starts a declspec.
+    /// An identifier does if it is a typedef name which is not shadowed
+    /// by a variable or an enumerator of an inner scope.
+    fn is_typename_token(&self, token: &Token) -> bool {
+        match token {
+            Token::Keyword(
+                KeywordType::Void
+                | KeywordType::Bool
+                | KeywordType::Char
+                | KeywordType::Short
+                | KeywordType::Int
+                | KeywordType::Long
+                | KeywordType::Signed
+                | KeywordType::Unsigned
+                | KeywordType::Struct
+                | KeywordType::Union
+                | KeywordType::Enum,
+            ) => true,
+            Token::Var(name) => self.find_typedef(name).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Parses a type name, the type of a declaration without the declared name,
+    /// according to the rule: typename = declspec abstract_declarator
+    fn typename(&mut self) -> Type {
+        let base = self.declspec();
+        self.abstract_declarator(base)
+    }
+
+    /// Parses an abstract declarator, a declarator without a name,
+    /// and return the type it derives from `ty`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the declarator has a name.
+    fn abstract_declarator(&mut self, ty: Type) -> Type {
+        match self.declarator_derivations() {
+            (None, derivations) => derive(ty, derivations),
+            (Some((name, location)), _) => {
+                error(location, &format!("unexpected identifier {}", name))
+            }
+        }
+    }
+
+    /// Parses a typedef after its keyword according to the rule:
+    /// typedef = declspec (declarator (',' declarator)*)? ';'
+    /// Every declarator declares a typedef name in the current scope.
+    fn typedef(&mut self) {
+        let base = self.declspec();
+        let mut first = true;
+        while !self.consume(KeywordType::Semicolon) {
+            if !first {
+                self.expect(KeywordType::Comma);
+            }
+            first = false;
+            let (name, ty, location) = self.declarator(base.clone());
+            debug!(" typedef: {} is {:?}", name, ty);
+            self.declare(&name, Ordinary::Typedef(ty), location, false);
+        }
+    }
+
+    /// Parses the declaration specifiers according to the rule:
+    /// declspec = 'void' | integer_specifier+ | struct_union_decl | enum_specifier | typedef_name
+    /// integer_specifier = '_Bool' | 'char' | 'short' | 'int' | 'long' | 'signed' | 'unsigned'
+    ///
+    /// The integer specifiers may come in any order, e.g. `long unsigned int long`
+    /// is `unsigned long long`.
+    fn declspec(&mut self) -> Type {
+        if let Token::Var(ref name) = self.current_token {
+            if let Some(ty) = self.find_typedef(name) {
+                self.next();
+                return ty;
+            }
+        }
+        if self.consume(KeywordType::Void) {
+            return Type::Void;
+        }
+        if self.consume(KeywordType::Struct) {
+            return self.struct_union_decl(false);
+        }
+        if self.consume(KeywordType::Union) {
+            return self.struct_union_decl(true);
+        }
+        if self.consume(KeywordType::Enum) {
+            return self.enum_specifier();
+        }
+        let location = self.location;
+        let mut specifiers = IntegerSpecifiers::default();
+        while let Token::Keyword(ref keyword) = self.current_token {
+            let count = match keyword {
+                KeywordType::Bool => &mut specifiers.bool_,
+                KeywordType::Char => &mut specifiers.char_,
+                KeywordType::Short => &mut specifiers.short,
+                KeywordType::Int => &mut specifiers.int,
+                KeywordType::Long => &mut specifiers.long,
+                KeywordType::Signed => &mut specifiers.signed,
+                KeywordType::Unsigned => &mut specifiers.unsigned,
+                _ => break,
+            };
+            *count += 1;
+            self.next();
+        }
+        specifiers.to_type(location)
+    }
+
+    /// Parses a struct or union specifier after its keyword according to the rule:
+    /// struct_union_decl = ident? ('{' struct_members)?
+    ///
+    /// A tag with a member list declares a new type in the current scope,
+    /// unless it completes an incomplete type declared there before.
+    /// A tag without a member list refers to the visible type,
+    /// or declares a new incomplete type if there is none.
+    fn struct_union_decl(&mut self, is_union: bool) -> Type {
+        let location = self.location;
+        let tag = match self.current_token {
+            Token::Var(_) => Some(self.expect_ident()),
+            _ => None,
+        };
+        let kind = if is_union { "union" } else { "struct" };
+        if !self.is_keyword(KeywordType::Lbrace) {
+            let tag =
+                tag.unwrap_or_else(|| error(self.location, &format!("{} without a tag", kind)));
+            let record = match self.symbols.lookup_tag(&tag) {
+                Some(Symbol {
+                    value: Tag::Record(record),
+                    ..
+                }) if record.borrow().is_union == is_union => record.clone(),
+                Some(previous) => error(
+                    location,
+                    &format!(
+                        "{} used as a {}, previous declaration at {}",
+                        tag, kind, previous.location
+                    ),
+                ),
+                None => {
+                    let record = RecordRef::new(is_union, Some(tag.clone()));
+                    self.declare_tag(&tag, Tag::Record(record.clone()), location);
+                    record
+                }
+            };
+            return Type::Record(record);
+        }
+        self.next();
+        let record = match tag {
+            Some(tag) => match self.symbols.lookup_tag_in_scope(&tag) {
+                Some(Symbol {
+                    value: Tag::Record(record),
+                    ..
+                }) if !record.borrow().complete && record.borrow().is_union == is_union => {
+                    record.clone()
+                }
+                Some(previous) => error(
+                    location,
+                    &format!(
+                        "redefinition of {}, previous declaration at {}",
+                        tag, previous.location
+                    ),
+                ),
+                None => {
+                    let record = RecordRef::new(is_union, Some(tag.clone()));
+                    self.declare_tag(&tag, Tag::Record(record.clone()), location);
+                    record
+                }
+            },
+            None => RecordRef::new(is_union, None),
+        };
+        let members = self.struct_members();
+        record.layout(members);
+        Type::Record(record)
+    }
+
+    /// Parses an enum specifier after its keyword according to the rule:
+    /// enum_specifier = ident? ('{' enumerator (',' enumerator)* ','? '}')?
+    /// enumerator = ident ('=' const_expr)?
+    ///
+    /// Enumerators are `int` constants declared in the current scope,
+    /// each one is the previous one plus one unless it has a value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value of an enumerator is not representable in `int`.
+    fn enum_specifier(&mut self) -> Type {
+        let location = self.location;
+        let tag = match self.current_token {
+            Token::Var(_) => Some(self.expect_ident()),
+            _ => None,
+        };
+        if !self.consume(KeywordType::Lbrace) {
+            let tag = tag.unwrap_or_else(|| error(self.location, "enum without a tag"));
+            match self.symbols.lookup_tag(&tag) {
+                Some(Symbol {
+                    value: Tag::Enum, ..
+                }) => return Type::Int,
+                _ => error(location, &format!("unknown enum {}", tag)),
+            }
+        }
+        if let Some(tag) = tag {
+            self.declare_tag(&tag, Tag::Enum, location);
+        }
+        let mut value = 0;
+        while !self.consume(KeywordType::Rbrace) {
+            let location = self.location;
+            let name = self.expect_ident();
+            if self.consume(KeywordType::Assign) {
+                let location = self.location;
+                value = self.const_expr();
+                if value != value as i32 as i64 {
+                    error(
+                        location,
+                        &format!("enumerator value {} is not representable in int", value),
+                    );
+                }
+            } else if value != value as i32 as i64 {
+                error(location, "overflow in enumeration values");
+            }
+            debug!(" enum_specifier: enumerator {} = {}", name, value);
+            self.declare(&name, Ordinary::EnumConst(value), location, false);
+            value += 1;
+            if !self.consume(KeywordType::Comma) {
+                self.expect(KeywordType::Rbrace);
+                break;
+            }
+        }
+        Type::Int
+    }
+
+    /// Parses the member declarations of a struct or union according to the rule:
+    /// struct_members = ((declspec (member (',' member)*)? ';') | static_assert)* '}'
+    /// member = declarator (':' const_expr)? | ':' const_expr
+    /// Each struct or union has its own namespace of member names.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two members have the same name.
+    fn struct_members(&mut self) -> Vec<MemberDecl> {
+        let mut members = Vec::new();
+        let mut names: HashMap<String, Location> = HashMap::new();
+        while !self.consume(KeywordType::Rbrace) {
+            if self.is_keyword(KeywordType::StaticAssert) {
+                self.static_assert();
+                continue;
+            }
+            let base = self.declspec();
+            let mut first = true;
+            while !self.consume(KeywordType::Semicolon) {
+                if !first {
+                    self.expect(KeywordType::Comma);
+                }
+                first = false;
+                if self.consume(KeywordType::Colon) {
+                    let width = Some(self.bit_field_width(None, &base));
+                    members.push(MemberDecl {
+                        name: None,
+                        ty: base.clone(),
+                        width,
+                    });
+                    continue;
+                }
+                let (name, ty, location) = self.declarator(base.clone());
+                if let Some(previous) = names.insert(name.clone(), location) {
+                    error(
+                        location,
+                        &format!(
+                            "duplicate member {}, previous declaration at {}",
+                            name, previous
+                        ),
+                    );
+                }
+                let width = if self.consume(KeywordType::Colon) {
+                    Some(self.bit_field_width(Some(&name), &ty))
+                } else {
+                    None
+                };
+                members.push(MemberDecl {
+                    name: Some(name),
+                    ty,
+                    width,
+                });
+            }
+        }
+        members
+    }
+
+    /// Parses the width of a bit-field of type `ty` after its ':'
+    /// according to the rule: bit_field_width = const_expr
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is not an integer type, or if the width is negative,
+    /// wider than the type, or zero for a named bit-field.
+    fn bit_field_width(&mut self, name: Option<&str>, ty: &Type) -> i64 {
+        let location = self.location;
+        let width = self.const_expr();
+        let what = match name {
+            Some(name) => format!("bit-field {}", name),
+            None => "anonymous bit-field".to_owned(),
+        };
+        if !ty.is_integer() {
+            error(location, &format!("{} has non-integral type {}", what, ty));
+        }
+        if width < 0 {
+            error(
+                location,
+                &format!("{} has negative width ({})", what, width),
+            );
+        }
+        if width > ty.size() * 8 {
+            error(
+                location,
+                &format!(
+                    "width of {} ({} bits) exceeds the width of its type ({} bits)",
+                    what,
+                    width,
+                    ty.size() * 8
+                ),
+            );
+        }
+        if width == 0 && name.is_some() {
+            error(location, &format!("named {} has zero width", what));
+        }
+        width
+    }
+
+    /// Parses a static assertion according to the rule:
+    /// static_assert = '_Static_assert' '(' const_expr (',' str)? ')' ';'
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expression is 0.
+    fn static_assert(&mut self) {
+        let location = self.location;
+        self.expect(KeywordType::StaticAssert);
+        self.expect(KeywordType::Lbracket);
+        let value = self.const_expr();
+        let message = if self.consume(KeywordType::Comma) {
+            let mut bytes = self.string_literal_bytes();
+            bytes.pop();
+            format!(": \"{}\"", String::from_utf8_lossy(&bytes))
+        } else {
+            String::new()
+        };
+        self.expect(KeywordType::Rbracket);
+        self.expect(KeywordType::Semicolon);
+        debug!(" static_assert: {} is {}", location, value);
+        if value == 0 {
+            error(location, &format!("static assertion failed{}", message));
+        }
+    }
+
+    /// Parses a declarator and return its name, the type it derives from `ty`
+    /// and the location of the name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the declarator has no name.
+    fn declarator(&mut self, ty: Type) -> (String, Type, Location) {
+        match self.declarator_derivations() {
+            (Some((name, location)), derivations) => (name, derive(ty, derivations), location),
+            (None, _) => error(
+                self.location,
+                &format!("expect an identifier, but got {:?}", self.current_token),
+            ),
+        }
+    }
+
+    /// Parses a declarator, whose name is optional, according to the rule:
+    /// declarator = '*'* ('(' declarator ')' | ident?) type_suffix
+    /// Return the name and its location, and the derivations in the order they apply to the
+    /// type of the declspec. The declarator in parentheses derives its type
+    /// from the type of the rest, e.g. in `int (*f)(int)` f is a pointer to
+    /// `int (int)`, and in `void (*signal(int, void (*)(int)))(int)` signal is
+    /// a function returning a pointer to `void (int)`.
+    fn declarator_derivations(&mut self) -> (Option<(String, Location)>, Vec<Derivation>) {
+        let mut derivations = Vec::new();
+        while self.consume(KeywordType::Mul) {
+            derivations.push(Derivation::Pointer);
+        }
+        let (name, nested) = if self.is_nested_declarator() {
+            self.next();
+            let nested = self.declarator_derivations();
+            self.expect(KeywordType::Rbracket);
+            nested
+        } else if let Token::Var(_) = self.current_token {
+            let location = self.location;
+            (Some((self.expect_ident(), location)), Vec::new())
+        } else {
+            (None, Vec::new())
+        };
+        derivations.extend(self.type_suffix());
+        derivations.extend(nested);
+        (name, derivations)
+    }
+
+    /// Return true if the current token is the '(' of a declarator in parentheses
+    /// rather than the one of a parameter list like in `int (int)` or `int ()`.
+    fn is_nested_declarator(&mut self) -> bool {
+        if !self.is_keyword(KeywordType::Lbracket) {
+            return false;
+        }
+        match self.peek().clone() {
+            Token::Keyword(KeywordType::Mul | KeywordType::Lbracket) => true,
+            Token::Var(name) => self.find_typedef(&name).is_none(),
+            _ => false,
+        }
+    }
+
+    /// Parses the array dimensions or the parameters following a declarator
+    /// according to the rule:
+    /// type_suffix = '[' const_expr? ']' type_suffix | '(' func_params | ε
+    /// The leftmost dimension is the outermost array, so it applies last.
+    /// An array without dimension has an unknown size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of an array is negative.
+    fn type_suffix(&mut self) -> Vec<Derivation> {
+        if self.consume(KeywordType::Lbracket) {
+            let (params, is_variadic) = self.func_params();
+            return vec![Derivation::Func(params, is_variadic)];
+        }
+        if !self.consume(KeywordType::LsquareBracket) {
+            return Vec::new();
+        }
+        let len = if self.consume(KeywordType::RsquareBracket) {
+            -1
+        } else {
+            let location = self.location;
+            let len = self.const_expr();
+            if len < 0 {
+                error(location, "array size is negative");
+            }
+            self.expect(KeywordType::RsquareBracket);
+            len
+        };
+        let mut derivations = self.type_suffix();
+        derivations.push(Derivation::Array(len));
+        derivations
+    }
+
+    /// Parses the parameter list of a function according to the rule:
+    /// func_params = ('void' | param (',' param)* (',' '...')?)? ')'
+    /// param = declspec declarator
+    /// The name of a parameter is optional. A parameter declared as an array
+    /// or as a function is a pointer.
+    /// Return the parameters and true if the function is variadic.
+    fn func_params(&mut self) -> (Vec<Param>, bool) {
+        if self.is_keyword(KeywordType::Void)
+            && *self.peek() == Token::Keyword(KeywordType::Rbracket)
+        {
+            self.next();
+            self.next();
+            return (Vec::new(), false);
+        }
+        let mut params = Vec::new();
+        while !self.consume(KeywordType::Rbracket) {
+            if !params.is_empty() {
+                self.expect(KeywordType::Comma);
+                if self.consume(KeywordType::Ellipsis) {
+                    self.expect(KeywordType::Rbracket);
+                    return (params, true);
+                }
+            }
+            let base = self.declspec();
+            let location = self.location;
+            let (name, derivations) = self.declarator_derivations();
+            let (name, location) = name.unwrap_or((String::new(), location));
+            params.push(Param {
+                name,
+                ty: derive(base, derivations).decay(),
+                location,
+            });
+        }
+        (params, false)
+    }
+
+    /// Parses a declaration according to the rule:
+    /// declaration = storage_class declspec
+    ///               (declarator ('=' initializer)? (',' declarator ('=' initializer)?)*)? ';'
+    /// Initializers of automatic variables become assignment statements,
+    /// an aggregate is zeroed before its elements are assigned.
+    /// A static variable is a global one visible only in the block, and an
+    /// `extern` declaration refers to the global variable of the name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an `extern` declaration has an initializer.
+    fn declaration(&mut self) -> Vec<Node> {
+        let storage = self.storage_class();
+        let base = self.declspec();
+        let mut nodes = Vec::new();
+        let mut first = true;
+        while !self.consume(KeywordType::Semicolon) {
+            if !first {
+                self.expect(KeywordType::Comma);
+            }
+            first = false;
+            let (name, ty, location) = self.declarator(base.clone());
+            if let Type::Func { .. } = ty {
+                self.declare_function(name, ty, location);
+                continue;
+            }
+            match storage {
+                StorageClass::Static => {
+                    self.static_local(name, ty, location);
+                    continue;
+                }
+                StorageClass::Extern => {
+                    let id = self.declare_global(name.clone(), ty, storage, location);
+                    if self.is_keyword(KeywordType::Assign) {
+                        error(
+                            self.location,
+                            &format!("extern variable {} has an initializer", name),
+                        );
+                    }
+                    self.declare(&name, Ordinary::Global(id), location, false);
+                    continue;
+                }
+                StorageClass::None => {}
+            }
+            check_object_type(&name, &ty, location);
+            let id = self.new_local(name, ty.clone(), location);
+            if self.consume(KeywordType::Assign) {
+                self.local_initializer(id, &mut nodes);
+            } else if let Type::Array(_, len) = ty {
+                if len < 0 {
+                    error(
+                        location,
+                        &format!("array size missing in {}", self.locals[id].name),
+                    );
+                }
+            }
+        }
+        nodes
+    }
+
+    /// Declares a function-local static variable and parses its initializer.
+    /// It is a global variable whose symbol is made unique by a suffix,
+    /// as other functions may have static variables of the same name.
+    fn static_local(&mut self, name: String, ty: Type, location: Location) {
+        check_object_type(&name, &ty, location);
+        let id = self.globals.len();
+        info!(" new static local {} of type {:?}", name, ty);
+        self.globals.push(Global {
+            name: format!("{}.{}", name, id),
+            ty,
+            init: None,
+            relocs: Vec::new(),
+            is_static: true,
+            is_definition: true,
+        });
+        self.warn_shadow(&name, location);
+        self.declare(&name, Ordinary::Global(id), location, false);
+        if self.consume(KeywordType::Assign) {
+            self.global_initializer(id, location);
+        } else if let Type::Array(_, len) = self.globals[id].ty {
+            if len < 0 {
+                error(location, &format!("array size missing in {}", name));
+            }
+        }
+    }
+
+    /// Parses the initializer of the local variable `id` and appends to `nodes`
+    /// the statements initializing it: an aggregate is zeroed before its
+    /// elements are assigned.
+    fn local_initializer(&mut self, id: usize, nodes: &mut Vec<Node>) {
+        let (ty, init) = self.initializer(self.locals[id].ty.clone());
+        self.locals[id].ty = ty.clone();
+        let var = Node::from_var(id, ty);
+        if let Initializer::List(_) = init {
+            nodes.push(Node::new(NodeKind::MEMZERO(Box::new(var.clone())), None));
+        }
+        local_init_assigns(var, init, nodes);
+    }
+
+    /// Parses an initializer of an object of type `ty`.
+    /// Return the type of the object, which is completed by the initializer
+    /// if it is an array of unknown size.
+    fn initializer(&mut self, ty: Type) -> (Type, Initializer) {
+        let init = self.sub_initializer(&ty, true);
+        match (ty, &init) {
+            (Type::Array(base, len), Initializer::List(elements)) if len < 0 => {
+                (Type::array_of(*base, elements.len() as i64), init)
+            }
+            (ty, _) => (ty, init),
+        }
+    }
+
+    /// Parses an initializer of a (sub)object of type `ty` according to the rule:
+    /// initializer = string_literal
+    ///             | '{' (initializer_element (',' initializer_element)*)? ','? '}'
+    ///             | initializer_element (',' initializer_element)*
+    ///             | assign
+    /// initializer_element = designation | initializer
+    ///
+    /// The braces of a nested aggregate may be elided, then it takes as many
+    /// initializers of the enclosing list as it has elements, up to the next
+    /// designation. At the top level an aggregate without braces is a struct
+    /// initialized by an expression.
+    fn sub_initializer(&mut self, ty: &Type, top: bool) -> Initializer {
+        match ty {
+            Type::Array(base, len) => {
+                let is_char = base.is_integer() && base.size() == 1;
+                if is_char && matches!(self.current_token, Token::Str(_)) {
+                    return self.string_initializer(*len);
+                }
+                if is_char
+                    && self.is_keyword(KeywordType::Lbrace)
+                    && matches!(self.peek(), Token::Str(_))
+                {
+                    self.next();
+                    let init = self.string_initializer(*len);
+                    self.end_braced_initializer();
+                    return init;
+                }
+                self.list_initializer(ty)
+            }
+            Type::Record(_) => {
+                if top && !self.is_keyword(KeywordType::Lbrace) {
+                    return Initializer::Expr(self.assign());
+                }
+                self.list_initializer(ty)
+            }
+            _ => {
+                let braced = self.consume(KeywordType::Lbrace);
+                let node = self.assign();
+                if braced {
+                    self.end_braced_initializer();
+                }
+                Initializer::Expr(node)
+            }
+        }
+    }
+
+    /// Parses the initializers of the elements of an array or the members of
+    /// a struct or union `ty`, in braces or with the braces elided.
+    /// The elements are initialized in order, a designation initializes the
+    /// designated element and the next ones follow it.
+    fn list_initializer(&mut self, ty: &Type) -> Initializer {
+        let braced = self.consume(KeywordType::Lbrace);
+        let mut elements = Vec::new();
+        let mut index = 0;
+        let mut first = true;
+        loop {
+            let designated = self.designation_follows(first);
+            // a designation in an elided list belongs to the enclosing list
+            if (designated && !braced) || (!designated && !has_element(ty, index)) {
+                break;
+            }
+            if !self.next_initializer(first) {
+                break;
+            }
+            first = false;
+            if designated {
+                let (paths, init) = self.designation(ty);
+                for path in &paths {
+                    set_initializer(ty, &mut elements, path, init.clone());
+                }
+                index = paths.last().expect("list_initializer: empty designation")[0];
+            } else {
+                let init = self.sub_initializer(&element_type(ty, index), false);
+                set_initializer(ty, &mut elements, &[index], init);
+            }
+            index += 1;
+        }
+        if braced {
+            self.end_braced_initializer();
+        }
+        Initializer::List(elements)
+    }
+
+    /// Return true if a designation follows in an initializer list,
+    /// at the current token if it is the `first` initializer, or after the comma.
+    fn designation_follows(&mut self, first: bool) -> bool {
+        let is_designator = |token: &Token| {
+            matches!(
+                token,
+                Token::Keyword(KeywordType::Dot | KeywordType::LsquareBracket)
+            )
+        };
+        if first {
+            is_designator(&self.current_token)
+        } else {
+            self.is_keyword(KeywordType::Comma) && is_designator(self.peek())
+        }
+    }
+
+    /// Parses a designation and the initializer after it according to the rule:
+    /// designation = designator+ '=' initializer
+    /// designator = '[' const_expr ('...' const_expr)? ']' | '.' ident
+    ///
+    /// A range `[first ... last]` is a GNU extension designating several
+    /// elements. Return the paths of element indexes from `ty` to each
+    /// designated subobject, with the initializer to store there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a designator doesn't match the type.
+    fn designation(&mut self, ty: &Type) -> (Vec<Vec<usize>>, Initializer) {
+        let (first, last) = if self.consume(KeywordType::LsquareBracket) {
+            let len = match ty {
+                Type::Array(_, len) => *len,
+                _ => error(
+                    self.location,
+                    &format!("array index in initializer of {:?}", ty),
+                ),
+            };
+            let first = self.const_expr();
+            let last = if self.consume(KeywordType::Ellipsis) {
+                self.const_expr()
+            } else {
+                first
+            };
+            self.expect(KeywordType::RsquareBracket);
+            if first < 0 || last < first || (len >= 0 && last >= len) {
+                error(
+                    self.location,
+                    &format!(
+                        "array index [{}, {}] out of the bounds of {:?}",
+                        first, last, ty
+                    ),
+                );
+            }
+            (first as usize, last as usize)
+        } else {
+            self.expect(KeywordType::Dot);
+            let name = self.expect_ident();
+            let record = match ty {
+                Type::Record(record) => record.borrow(),
+                _ => error(
+                    self.location,
+                    &format!("field name {} in initializer of {:?}", name, ty),
+                ),
+            };
+            let index = record
+                .members
+                .iter()
+                .position(|member| member.name == name)
+                .unwrap_or_else(|| error(self.location, &format!("no member named {}", name)));
+            (index, index)
+        };
+        let element = element_type(ty, first);
+        let (sub_paths, init) =
+            if self.is_keyword(KeywordType::Dot) || self.is_keyword(KeywordType::LsquareBracket) {
+                self.designation(&element)
+            } else {
+                self.expect(KeywordType::Assign);
+                (vec![Vec::new()], self.sub_initializer(&element, false))
+            };
+        let mut paths = Vec::new();
+        for index in first..=last {
+            for sub_path in &sub_paths {
+                let mut path = vec![index];
+                path.extend_from_slice(sub_path);
+                paths.push(path);
+            }
+        }
+        (paths, init)
+    }
+
+    /// Parses a string literal initializing a char array of `len` elements,
+    /// or of unknown size if `len` is negative.
+    /// The string is truncated or padded with zeros to the size of the array.
+    fn string_initializer(&mut self, len: i64) -> Initializer {
+        let mut bytes = self.string_literal_bytes();
+        let len = if len < 0 { bytes.len() } else { len as usize };
+        bytes.resize(len, 0);
+        Initializer::List(
+            bytes
+                .into_iter()
+                .map(|byte| Some(Initializer::Expr(Node::from_num(byte as i8 as i64))))
+                .collect(),
+        )
+    }
+
+    /// Return true if another initializer follows in an initializer list,
+    /// skipping the comma separating it from the previous one.
+    /// A list ends at its '}', or at an optional ',' before it.
+    fn next_initializer(&mut self, first: bool) -> bool {
+        if self.is_keyword(KeywordType::Rbrace)
+            || (self.is_keyword(KeywordType::Comma)
+                && *self.peek() == Token::Keyword(KeywordType::Rbrace))
+        {
+            return false;
+        }
+        if !first {
+            self.expect(KeywordType::Comma);
+        }
+        true
+    }
+
+    /// Skips the end of a braced initializer list, an optional ',' and the '}'
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more initializers than elements.
+    fn end_braced_initializer(&mut self) {
+        self.consume(KeywordType::Comma);
+        if !self.consume(KeywordType::Rbrace) {
+            error(
+                self.location,
+                &format!("excess elements in initializer at {:?}", self.current_token),
+            );
+        }
+    }
+
+    /// Parses adjacent string literals, which are concatenated,
+    /// and returns their bytes with the terminating NUL
+    fn string_literal_bytes(&mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        while let Token::Str(ref string) = self.current_token {
+            bytes.extend_from_slice(string);
+            self.next();
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    /// Parses a string literal into an anonymous global char array
+    fn string_literal(&mut self) -> Node {
+        let location = self.location;
+        let bytes = self.string_literal_bytes();
+        let name = format!(".L..str.{}", self.globals.len());
+        let ty = Type::array_of(Type::Char, bytes.len() as i64);
+        info!(" string literal {} of {} bytes", name, bytes.len());
+        self.globals.push(Global {
+            name: name.clone(),
+            ty: ty.clone(),
+            init: Some(bytes),
+            relocs: Vec::new(),
+            is_static: true,
+            is_definition: true,
+        });
+        Node::from_gvar(name, ty).at(location)
+    }
+
+    /// Parses a compound statement, a block with its own scope,
+    /// after its '{' according to the rule: compound_stmt = block_items
+    fn compound_stmt(&mut self) -> Node {
+        self.symbols.enter_scope();
+        let node = self.block_items();
+        self.symbols.leave_scope();
+        node
+    }
+
+    /// Parses the items of a block in the current scope according to the rule:
+    /// block_items = ('typedef' typedef | static_assert | declaration | stmt)* '}'
+    fn block_items(&mut self) -> Node {
+        let mut nodes = Vec::new();
+        while !self.consume(KeywordType::Rbrace) {
+            if self.consume(KeywordType::Typedef) {
+                self.typedef();
+            } else if self.is_keyword(KeywordType::StaticAssert) {
+                self.static_assert();
+            } else if self.is_typename()
+                || self.is_keyword(KeywordType::Static)
+                || self.is_keyword(KeywordType::Extern)
+            {
+                nodes.extend(self.declaration());
+            } else {
+                nodes.push(self.stmt());
+            }
+        }
+        Node::new(NodeKind::BLOCK(nodes), None)
+    }
+
+    /// Parses a statement according to the rule:
+    /// stmt = 'return' expr? ';'
+    ///      | 'if' '(' expr ')' stmt ('else' stmt)?
+    ///      | 'for' '(' expr? ';' expr? ';' expr? ')' stmt
+    ///      | 'while' '(' expr ')' stmt
+    ///      | 'switch' '(' expr ')' stmt
+    ///      | 'case' const_expr ':' stmt
+    ///      | 'default' ':' stmt
+    ///      | 'break' ';'
+    ///      | 'continue' ';'
+    ///      | 'goto' ident ';'
+    ///      | ident ':' stmt
+    ///      | '{' compound_stmt
+    ///      | attributes ';'
+    ///      | expr? ';'
+    /// `__attribute__((fallthrough));` is a null statement, which tells the
+    /// flow analysis falling through to the `case` label after it is intended.
+    fn stmt(&mut self) -> Node {
+        let location = self.location;
+        if self.consume(KeywordType::Return) {
+            if self.consume(KeywordType::Semicolon) {
+                return Node::new(NodeKind::RETURN(None), None).at(location);
+            }
+            let mut node = self.expr();
+            if node.get_type().decay().is_scalar() && self.ret.is_scalar() {
+                node = Node::from_cast(node, self.ret.clone());
+            }
+            self.expect(KeywordType::Semicolon);
+            return Node::new(NodeKind::RETURN(Some(Box::new(node))), None).at(location);
+        }
+        if self.consume(KeywordType::If) {
+            self.expect(KeywordType::Lbracket);
+            let cond = Box::new(self.expr());
+            self.expect(KeywordType::Rbracket);
+            self.warn_assignment_condition(&cond);
+            let then = Box::new(self.stmt());
+            let els = if self.consume(KeywordType::Else) {
+                Some(Box::new(self.stmt()))
+            } else {
+                None
+            };
+            return Node::new(NodeKind::IF { cond, then, els }, None).at(location);
+        }
+        if self.consume(KeywordType::For) {
+            self.expect(KeywordType::Lbracket);
+            let init = self.opt_expr_stmt(KeywordType::Semicolon);
+            let cond = self.opt_expr(KeywordType::Semicolon);
+            let inc = self.opt_expr(KeywordType::Rbracket);
+            if let Some(ref cond) = cond {
+                self.warn_assignment_condition(cond);
+            }
+            let body = Box::new(self.stmt());
+            return Node::new(
+                NodeKind::FOR {
+                    init,
+                    cond,
+                    inc,
+                    body,
+                },
+                None,
+            )
+            .at(location);
+        }
+        if self.consume(KeywordType::While) {
+            self.expect(KeywordType::Lbracket);
+            let cond = self.expr();
+            self.expect(KeywordType::Rbracket);
+            self.warn_assignment_condition(&cond);
+            let body = Box::new(self.stmt());
+            return Node::new(
+                NodeKind::FOR {
+                    init: None,
+                    cond: Some(Box::new(cond)),
+                    inc: None,
+                    body,
+                },
+                None,
+            )
+            .at(location);
+        }
+        if self.consume(KeywordType::Switch) {
+            self.expect(KeywordType::Lbracket);
+            let cond = self.expr();
+            if !cond.get_type().is_integer() {
+                error(
+                    cond.location,
+                    &format!(
+                        "statement requires expression of integer type ({} invalid)",
+                        cond.get_type()
+                    ),
+                );
+            }
+            let ty = cond.promoted_type();
+            let cond = Box::new(Node::from_cast(cond, ty.clone()));
+            self.expect(KeywordType::Rbracket);
+            self.switches.push(SwitchContext {
+                ty,
+                cases: Vec::new(),
+                has_default: false,
+            });
+            let body = Box::new(self.stmt());
+            let context = self.switches.pop().unwrap();
+            return Node::new(
+                NodeKind::SWITCH {
+                    cond,
+                    body,
+                    cases: context.cases,
+                    has_default: context.has_default,
+                },
+                None,
+            )
+            .at(location);
+        }
+        if self.consume(KeywordType::Case) {
+            let value = self.const_expr();
+            self.expect(KeywordType::Colon);
+            let context = self
+                .switches
+                .last_mut()
+                .unwrap_or_else(|| error(location, "case label not within a switch statement"));
+            // the case value is converted to the type of the controlling expression
+            let value = convert(value, &context.ty);
+            if context.cases.contains(&value) {
+                error(location, &format!("duplicate case value {}", value));
+            }
+            let index = Some(context.cases.len());
+            context.cases.push(value);
+            let body = Box::new(self.stmt());
+            return Node::new(NodeKind::CASE { index, body }, None).at(location);
+        }
+        if self.consume(KeywordType::Default) {
+            self.expect(KeywordType::Colon);
+            let context = self
+                .switches
+                .last_mut()
+                .unwrap_or_else(|| error(location, "default label not within a switch statement"));
+            if context.has_default {
+                error(location, "multiple default labels in one switch");
+            }
+            context.has_default = true;
+            let body = Box::new(self.stmt());
+            return Node::new(NodeKind::CASE { index: None, body }, None).at(location);
+        }
+        if self.consume(KeywordType::Break) {
+            self.expect(KeywordType::Semicolon);
+            return Node::new(NodeKind::BREAK, None).at(location);
+        }
+        if self.consume(KeywordType::Continue) {
+            self.expect(KeywordType::Semicolon);
+            return Node::new(NodeKind::CONTINUE, None).at(location);
+        }
+        if self.consume(KeywordType::Goto) {
+            let name = self.expect_ident();
+            self.expect(KeywordType::Semicolon);
+            self.gotos.push((name.clone(), location));
+            return Node::new(NodeKind::GOTO(name), None).at(location);
+        }
+        if let Token::Var(ref name) = self.current_token {
+            let name = name.clone();
+            if self.peek() == &Token::Keyword(KeywordType::Colon) {
+                self.next();
+                self.next();
+                if let Some(previous) = self.labels.insert(name.clone(), location) {
+                    error(
+                        location,
+                        &format!(
+                            "redefinition of label {}, previous definition at {}",
+                            name, previous
+                        ),
+                    );
+                }
+                let body = Box::new(self.stmt());
+                return Node::new(NodeKind::LABEL { name, body }, None).at(location);
+            }
+        }
+        if self.consume(KeywordType::Lbrace) {
+            return self.compound_stmt().at(location);
+        }
+        if matches!(self.current_token, Token::Var(ref name) if name == "__attribute__") {
+            self.attributes();
+            self.expect(KeywordType::Semicolon);
+            return Node::new(NodeKind::BLOCK(Vec::new()), None).at(location);
+        }
+        if self.consume(KeywordType::Semicolon) {
+            return Node::new(NodeKind::BLOCK(Vec::new()), None).at(location);
+        }
+        let node = self.expr();
+        self.expect(KeywordType::Semicolon);
+        Node::new(NodeKind::STMT(Box::new(node)), None).at(location)
     }
 
-    /// Parses an expression according to the rule: expr = mul ('+' mul | '-' mul)*
+    /// Parses an optional expression terminated by `end`
+    fn opt_expr(&mut self, end: KeywordType) -> Option<Box<Node>> {
+        if self.consume(end.clone()) {
+            return None;
+        }
+        let node = self.expr();
+        self.expect(end);
+        Some(Box::new(node))
+    }
+
+    /// Parses an optional expression terminated by `end` as an expression statement
+    fn opt_expr_stmt(&mut self, end: KeywordType) -> Option<Box<Node>> {
+        self.opt_expr(end)
+            .map(|node| Box::new(Node::new(NodeKind::STMT(node), None)))
+    }
+
+    /// Parses a constant expression and evaluates it
+    fn const_expr(&mut self) -> i64 {
+        let node = self.assign();
+        eval(&node)
+    }
+
+    /// Parses an expression according to the rule: expr = assign
     pub fn expr(&mut self) -> Node {
+        self.assign()
+    }
+
+    /// Parses an assignment according to the rule: assign = logor ('=' assign)?
+    fn assign(&mut self) -> Node {
+        let node = self.logor();
+        let location = self.location;
+        if self.consume(KeywordType::Assign) {
+            info!(" assign construct an assignment left node: {:?}", node);
+            return Node::from_assign(node, self.assign()).at(location);
+        }
+        node
+    }
+
+    /// Parses a binary expression of the left associative operators `ops`,
+    /// whose operands are parsed by `operand`, according to the rule:
+    /// binary = operand (op operand)*
+    fn binary(&mut self, ops: &[KeywordType], operand: fn(&mut Self) -> Node) -> Node {
+        let mut node = operand(self);
+        loop {
+            let op = match self.current_token {
+                Token::Keyword(ref keyword) if ops.contains(keyword) => {
+                    BinaryOpType::from_keyword(keyword).unwrap()
+                }
+                _ => return node,
+            };
+            let location = self.location;
+            self.next();
+            let rhs = operand(self);
+            self.warn_precedence(&op, &node);
+            self.warn_precedence(&op, &rhs);
+            node = Node::from_binop(op, node, rhs).at(location);
+        }
+    }
+
+    /// Warns if `operand` of `op` is an operation written without brackets
+    /// whose precedence relative to `op` is often mistaken.
+    fn warn_precedence(&mut self, op: &BinaryOpType, operand: &Node) {
+        if operand.parenthesized {
+            return;
+        }
+        let inner = match operand.kind {
+            NodeKind::BINOP { op: ref inner, .. } => inner,
+            _ => return,
+        };
+        let message = match (op, inner) {
+            (BinaryOpType::LogOr, BinaryOpType::LogAnd) => {
+                "suggest parentheses around '&&' within '||'".to_owned()
+            }
+            (BinaryOpType::BitAnd | BinaryOpType::BitOr | BinaryOpType::BitXor, _)
+                if inner.is_comparison() =>
+            {
+                format!(
+                    "suggest parentheses around comparison in operand of '{}'",
+                    op.symbol()
+                )
+            }
+            (BinaryOpType::Shl | BinaryOpType::Shr, BinaryOpType::Add | BinaryOpType::Sub) => {
+                format!(
+                    "suggest parentheses around '{}' inside '{}'",
+                    inner.symbol(),
+                    op.symbol()
+                )
+            }
+            _ => return,
+        };
+        self.diagnostics
+            .warn(Warning::Parentheses, operand.location, &message);
+    }
+
+    /// Warns if the controlling expression `cond` of a statement is
+    /// an assignment written without brackets, likely meant to be `==`.
+    fn warn_assignment_condition(&mut self, cond: &Node) {
+        if let NodeKind::ASSIGN { .. } = cond.kind {
+            if !cond.parenthesized {
+                self.diagnostics.warn(
+                    Warning::Parentheses,
+                    cond.location,
+                    "suggest parentheses around assignment used as truth value",
+                );
+            }
+        }
+    }
+
+    /// Parses a logical or according to the rule: logor = logand ('||' logand)*
+    fn logor(&mut self) -> Node {
+        self.binary(&[KeywordType::LogOr], Self::logand)
+    }
+
+    /// Parses a logical and according to the rule: logand = bitor ('&&' bitor)*
+    fn logand(&mut self) -> Node {
+        self.binary(&[KeywordType::LogAnd], Self::bitor)
+    }
+
+    /// Parses a bitwise or according to the rule: bitor = bitxor ('|' bitxor)*
+    fn bitor(&mut self) -> Node {
+        self.binary(&[KeywordType::Or], Self::bitxor)
+    }
+
+    /// Parses a bitwise xor according to the rule: bitxor = bitand ('^' bitand)*
+    fn bitxor(&mut self) -> Node {
+        self.binary(&[KeywordType::Xor], Self::bitand)
+    }
+
+    /// Parses a bitwise and according to the rule: bitand = equality ('&' equality)*
+    fn bitand(&mut self) -> Node {
+        self.binary(&[KeywordType::And], Self::equality)
+    }
+
+    /// Parses an equality according to the rule:
+    /// equality = relational ('==' relational | '!=' relational)*
+    fn equality(&mut self) -> Node {
+        let mut node = self.relational();
+        loop {
+            let location = self.location;
+            if self.consume(KeywordType::Eq) {
+                node = Node::from_binop(BinaryOpType::Eq, node, self.relational()).at(location);
+            } else if self.consume(KeywordType::Ne) {
+                node = Node::from_binop(BinaryOpType::Ne, node, self.relational()).at(location);
+            } else {
+                return node;
+            }
+        }
+    }
+
+    /// Parses a relational expression according to the rule:
+    /// relational = shift ('<' shift | '<=' shift | '>' shift | '>=' shift)*
+    /// `a > b` is parsed as `b < a` and `a >= b` as `b <= a`.
+    fn relational(&mut self) -> Node {
+        let mut node = self.shift();
+        loop {
+            let location = self.location;
+            if self.consume(KeywordType::Lt) {
+                node = Node::from_binop(BinaryOpType::Lt, node, self.shift()).at(location);
+            } else if self.consume(KeywordType::Le) {
+                node = Node::from_binop(BinaryOpType::Le, node, self.shift()).at(location);
+            } else if self.consume(KeywordType::Gt) {
+                let rhs = self.shift();
+                node = Node::from_binop(BinaryOpType::Lt, rhs, node).at(location);
+            } else if self.consume(KeywordType::Ge) {
+                let rhs = self.shift();
+                node = Node::from_binop(BinaryOpType::Le, rhs, node).at(location);
+            } else {
+                return node;
+            }
+        }
+    }
+
+    /// Parses a shift according to the rule: shift = add ('<<' add | '>>' add)*
+    fn shift(&mut self) -> Node {
+        self.binary(&[KeywordType::Shl, KeywordType::Shr], Self::add)
+    }
+
+    /// Parses an add expression according to the rule: add = mul ('+' mul | '-' mul)*
+    fn add(&mut self) -> Node {
         let mut node = self.mul();
-        debug!(" expr: {:?}", node);
+        debug!(" add: {:?}", node);
         loop {
+            let location = self.location;
             match self.current_token {
                 Token::Keyword(ref keyword) => match keyword {
                     KeywordType::Add => {
-                        info!(" expr construct a add operation left node: {:?}", node);
+                        info!(" add construct a add operation left node: {:?}", node);
                         self.next();
-                        node = Node::from_binop(BinaryOpType::Add, node, self.mul());
+                        let rhs = self.mul();
+                        node = new_add(node, rhs, location);
                     }
                     KeywordType::Sub => {
-                        info!(" expr construct a sub operation left node: {:?}", node);
+                        info!(" add construct a sub operation left node: {:?}", node);
                         self.next();
-                        node = Node::from_binop(BinaryOpType::Sub, node, self.mul());
+                        let rhs = self.mul();
+                        node = new_sub(node, rhs, location);
                     }
                     _ => {
                         return node;
@@ -67,21 +2051,28 @@ impl TinyCParser {
         }
     }
 
-    /// Parses a mul expression according to the rule: mul = primary ('*' primary | '/' primary)*
+    /// Parses a mul expression according to the rule:
+    /// mul = cast ('*' cast | '/' cast | '%' cast)*
     fn mul(&mut self) -> Node {
-        let mut node = self.primary();
+        let mut node = self.cast();
         loop {
+            let location = self.location;
             match self.current_token {
                 Token::Keyword(ref keyword) => match keyword {
                     KeywordType::Mul => {
                         info!(" mul construct a mul operation left node: {:?}", node);
                         self.next();
-                        node = Node::from_binop(BinaryOpType::Mul, node, self.primary());
+                        node = Node::from_binop(BinaryOpType::Mul, node, self.cast()).at(location);
                     }
                     KeywordType::Div => {
                         info!(" mul construct a div operation left node: {:?}", node);
                         self.next();
-                        node = Node::from_binop(BinaryOpType::Div, node, self.primary());
+                        node = Node::from_binop(BinaryOpType::Div, node, self.cast()).at(location);
+                    }
+                    KeywordType::Mod => {
+                        info!(" mul construct a mod operation left node: {:?}", node);
+                        self.next();
+                        node = Node::from_binop(BinaryOpType::Mod, node, self.cast()).at(location);
                     }
                     _ => {
                         return node;
@@ -94,18 +2085,376 @@ impl TinyCParser {
         }
     }
 
-    /// Parses a primary expression, which is a number or a bracketed expression.
+    /// Parses a cast expression according to the rule:
+    /// cast = '(' typename ')' cast | '(' typename ')' compound_literal postfix_operators | unary
+    fn cast(&mut self) -> Node {
+        let location = self.location;
+        if self.is_bracketed_typename() {
+            self.next();
+            let ty = self.typename();
+            self.expect(KeywordType::Rbracket);
+            if self.is_keyword(KeywordType::Lbrace) {
+                let node = self.compound_literal(ty);
+                return self.postfix_operators(node);
+            }
+            let operand = self.cast();
+            info!(" cast construct a cast of {:?} to {:?}", operand, ty);
+            return Node::from_explicit_cast(operand, ty).at(location);
+        }
+        self.unary()
+    }
+
+    /// Parses a unary expression according to the rule:
+    /// unary = ('+' | '-' | '!' | '~' | '&' | '*') cast
+    ///       | 'sizeof' '(' typename ')' | 'sizeof' unary
+    ///       | 'sizeof' '(' typename ')' compound_literal postfix_operators
+    ///       | '_Alignof' '(' typename ')'
+    ///       | postfix
+    fn unary(&mut self) -> Node {
+        if self.consume(KeywordType::Add) {
+            let operand = self.cast();
+            let ty = operand.promoted_type();
+            return Node::from_cast(operand, ty);
+        }
+        if self.is_keyword(KeywordType::Sizeof) {
+            let location = self.location;
+            self.next();
+            let ty = if self.is_bracketed_typename() {
+                self.next();
+                let ty = self.typename();
+                self.expect(KeywordType::Rbracket);
+                if self.is_keyword(KeywordType::Lbrace) {
+                    let node = self.compound_literal(ty);
+                    self.postfix_operators(node).get_type().clone()
+                } else {
+                    ty
+                }
+            } else {
+                // the operand is never evaluated, only its type matters
+                self.unary().get_type().clone()
+            };
+            info!(" unary construct a sizeof of {:?}", ty);
+            if !ty.is_complete() {
+                error(
+                    location,
+                    "invalid application of sizeof to an incomplete type",
+                );
+            }
+            return Node::from_typed_num(ty.size(), Type::size_t());
+        }
+        if self.consume(KeywordType::Alignof) {
+            self.expect(KeywordType::Lbracket);
+            let ty = self.typename();
+            self.expect(KeywordType::Rbracket);
+            info!(" unary construct an _Alignof of {:?}", ty);
+            return Node::from_typed_num(ty.align(), Type::size_t());
+        }
+        if let Token::Keyword(ref keyword) = self.current_token {
+            if let Some(op) = UnaryOpType::from_keyword(keyword) {
+                info!(" unary construct a {:?} operation", op);
+                let location = self.location;
+                self.next();
+                let operand = self.cast();
+                return match op {
+                    UnaryOpType::Deref => deref(operand, location),
+                    _ => Node::from_unop(op, operand).at(location),
+                };
+            }
+        }
+        self.postfix()
+    }
+
+    /// Parses a postfix expression according to the rule:
+    /// postfix = primary postfix_operators
+    fn postfix(&mut self) -> Node {
+        let node = self.primary();
+        self.postfix_operators(node)
+    }
+
+    /// Parses the postfix operators applied to `node` according to the rule:
+    /// postfix_operators = ('[' expr ']' | '.' ident | '->' ident | funcall)*
+    /// `a[i]` is a shorthand for `*(a + i)` and `p->m` for `(*p).m`.
+    fn postfix_operators(&mut self, mut node: Node) -> Node {
+        loop {
+            let location = self.location;
+            if self.is_keyword(KeywordType::Lbracket) {
+                node = self.funcall(node);
+            } else if self.consume(KeywordType::LsquareBracket) {
+                let index = self.expr();
+                self.expect(KeywordType::RsquareBracket);
+                info!(" postfix construct a subscript of {:?}", node);
+                node = deref(new_add(node, index, location), location);
+            } else if self.consume(KeywordType::Dot) {
+                let name = self.expect_ident();
+                node = member(node, &name, location);
+            } else if self.consume(KeywordType::Arrow) {
+                let name = self.expect_ident();
+                node = member(deref(node, location), &name, location);
+            } else {
+                return node;
+            }
+        }
+    }
+
+    /// Parses a compound literal of type `ty` after its type name according to the rule:
+    /// compound_literal = initializer
+    ///
+    /// At file scope the literal is an anonymous static object initialized
+    /// before the program starts. In a block it is an anonymous local
+    /// initialized each time the expression is evaluated.
+    fn compound_literal(&mut self, ty: Type) -> Node {
+        if self.symbols.is_file_scope() {
+            let id = self.globals.len();
+            let name = format!(".L..literal.{}", id);
+            info!(" compound literal {} of type {:?}", name, ty);
+            self.globals.push(Global {
+                name: name.clone(),
+                ty,
+                init: None,
+                relocs: Vec::new(),
+                is_static: true,
+                is_definition: true,
+            });
+            self.global_initializer(id, self.location);
+            return Node::from_gvar(name, self.globals[id].ty.clone());
+        }
+        let id = self.locals.len();
+        info!(" compound literal local {} of type {:?}", id, ty);
+        self.locals.push(Var {
+            name: format!("(compound literal {})", id),
+            ty,
+            location: Location::default(),
+        });
+        let mut nodes = Vec::new();
+        self.local_initializer(id, &mut nodes);
+        let ty = self.locals[id].ty.clone();
+        Node::new(
+            NodeKind::LITERAL {
+                init: Box::new(Node::new(NodeKind::BLOCK(nodes), None)),
+                var: Box::new(Node::from_var(id, ty.clone())),
+            },
+            Some(ty),
+        )
+    }
+
+    /// Parses a function call after the function designator `func`
+    /// according to the rule: funcall = '(' (assign (',' assign)*)? ')'
+    /// `func` is a function, called directly, or a pointer to a function,
+    /// called indirectly. Arguments are converted to the types of the
+    /// parameters, the ones without a parameter are promoted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `func` is not a function nor a pointer to a function.
+    fn funcall(&mut self, func: Node) -> Node {
+        let func_ty = func.get_type().decay();
+        let (ty, params) = match func_ty.base() {
+            Some(Type::Func { ret, params, .. }) => (*ret.clone(), params.clone()),
+            _ => error(
+                func.location,
+                &format!(
+                    "called object type {} is not a function or function pointer",
+                    func_ty
+                ),
+            ),
+        };
+        self.expect(KeywordType::Lbracket);
+        let mut args = Vec::new();
+        while !self.consume(KeywordType::Rbracket) {
+            if !args.is_empty() {
+                self.expect(KeywordType::Comma);
+            }
+            args.push(self.assign());
+        }
+        let args = args
+            .into_iter()
+            .enumerate()
+            .map(|(i, arg)| match params.get(i) {
+                Some(param) if param.ty.is_scalar() && arg.get_type().decay().is_scalar() => {
+                    Node::from_cast(arg, param.ty.clone())
+                }
+                None if arg.get_type().is_integer() => {
+                    let ty = arg.promoted_type();
+                    Node::from_cast(arg, ty)
+                }
+                // an array is passed by its address, like a string literal to `printf`
+                None if matches!(arg.get_type(), Type::Array(..) | Type::Func { .. }) => {
+                    let ty = arg.get_type().decay();
+                    Node::from_cast(arg, ty)
+                }
+                _ => arg,
+            })
+            .collect();
+        info!(" funcall: {:?} returning {:?}", func, ty);
+        let location = func.location;
+        // a returned struct is stored in a temporary the value refers to
+        let ret_buffer = matches!(ty, Type::Record(_)).then(|| {
+            self.locals.push(Var {
+                name: format!("(return value {})", self.locals.len()),
+                ty: ty.clone(),
+                location: Location::default(),
+            });
+            self.locals.len() - 1
+        });
+        let noreturn =
+            matches!(func.kind, NodeKind::GVAR(ref name) if self.noreturn_functions.contains(name));
+        let format = match func.kind {
+            NodeKind::GVAR(ref name) => self.format_functions.get(name).copied(),
+            _ => None,
+        };
+        Node::new(
+            NodeKind::CALL {
+                func: Box::new(func),
+                args,
+                ret_buffer,
+                noreturn,
+                format,
+            },
+            Some(ty),
+        )
+        .at(location)
+    }
+
+    /// Parses a call to a builtin after its name according to the rule:
+    /// builtin = 'va_start' '(' assign ',' assign ')'
+    ///         | 'va_arg' '(' assign ',' typename ')'
+    ///         | 'va_copy' '(' assign ',' assign ')'
+    ///         | 'va_end' '(' assign ')'
+    /// The names may also be prefixed by `__builtin_`.
+    /// `__builtin_unreachable` '(' ')' tells that the call is never reached.
+    /// Return None if `name` is not a builtin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an operand is not a `va_list`, if `va_start` is used outside
+    /// of a variadic function or `va_arg` with a type other than a scalar.
+    fn builtin(&mut self, name: &str) -> Option<Node> {
+        if name == "__builtin_unreachable" {
+            self.expect(KeywordType::Lbracket);
+            self.expect(KeywordType::Rbracket);
+            return Some(Node::new(NodeKind::UNREACHABLE, Some(Type::Void)));
+        }
+        let name = name.strip_prefix("__builtin_").unwrap_or(name);
+        if !matches!(name, "va_start" | "va_arg" | "va_copy" | "va_end") {
+            return None;
+        }
+        self.expect(KeywordType::Lbracket);
+        let ap = self.va_list_operand(name);
+        let node = match name {
+            "va_start" => {
+                if self.va_area.is_none() {
+                    error(
+                        ap.location,
+                        "va_start used in a function with fixed arguments",
+                    );
+                }
+                // the last named parameter is not needed to find the others
+                self.expect(KeywordType::Comma);
+                self.assign();
+                Node::new(NodeKind::VASTART(Box::new(ap)), Some(Type::Void))
+            }
+            "va_arg" => {
+                self.expect(KeywordType::Comma);
+                let ty = self.typename();
+                if !ty.is_scalar() && !matches!(ty, Type::Record(_)) {
+                    error(ap.location, &format!("va_arg of {} is not supported", ty));
+                }
+                let location = ap.location;
+                let addr = Node::new(NodeKind::VAARG(Box::new(ap)), Some(Type::pointer_to(ty)));
+                Node::from_unop(UnaryOpType::Deref, addr).at(location)
+            }
+            "va_copy" => {
+                self.expect(KeywordType::Comma);
+                let src = self.va_list_operand(name);
+                let copy = Node::from_assign(
+                    Node::from_unop(UnaryOpType::Deref, ap),
+                    Node::from_unop(UnaryOpType::Deref, src),
+                );
+                Node::from_explicit_cast(copy, Type::Void)
+            }
+            _ => Node::from_explicit_cast(ap, Type::Void),
+        };
+        self.expect(KeywordType::Rbracket);
+        info!(" builtin: {} {:?}", name, node);
+        Some(node)
+    }
+
+    /// Parses an operand of the builtin `name` which must be a `va_list`,
+    /// or a parameter declared as one.
+    fn va_list_operand(&mut self, name: &str) -> Node {
+        let ap = self.assign();
+        if ap.get_type().decay() != self.va_list.decay() {
+            error(ap.location, &format!("{}: {:?} is not a va_list", name, ap));
+        }
+        ap
+    }
+
+    /// Parses a primary expression, which is a number, a string literal,
+    /// a variable, a function designator or a bracketed expression.
     fn primary(&mut self) -> Node {
         match self.current_token {
-            Token::Number(n) => {
+            Token::Var(ref name) => {
+                let name = name.clone();
+                let location = self.location;
                 self.next();
-                info!(" primary: get a number: {}", n);
-                Node::from_num(n)
+                info!(" primary: get a variable: {}", name);
+                let symbol = self
+                    .symbols
+                    .lookup(&name)
+                    .map(|symbol| symbol.value.clone());
+                let node = match symbol {
+                    Some(Ordinary::Local(id)) => {
+                        self.used.insert(id);
+                        Node::from_var(id, self.locals[id].ty.clone())
+                    }
+                    Some(Ordinary::Global(id)) => {
+                        let global = &self.globals[id];
+                        Node::from_gvar(global.name.clone(), global.ty.clone())
+                    }
+                    Some(Ordinary::Function) => {
+                        Node::from_gvar(name.clone(), self.functions[&name].value.clone())
+                    }
+                    Some(Ordinary::EnumConst(value)) => Node::from_num(value),
+                    Some(Ordinary::Typedef(_)) => {
+                        error(location, &format!("unexpected typedef name {}", name))
+                    }
+                    // a function declared in a block is still called with its type
+                    None => match self.functions.get(&name) {
+                        Some(function) if self.is_keyword(KeywordType::Lbracket) => {
+                            Node::from_gvar(name, function.value.clone())
+                        }
+                        // a function which is not declared returns `int`
+                        None if self.is_keyword(KeywordType::Lbracket) => {
+                            if let Some(node) = self.builtin(&name) {
+                                return node.at(location);
+                            }
+                            Node::from_gvar(
+                                name,
+                                Type::Func {
+                                    ret: Box::new(Type::Int),
+                                    params: Vec::new(),
+                                    is_variadic: false,
+                                },
+                            )
+                        }
+                        _ => error(location, &format!("use of undeclared identifier {}", name)),
+                    },
+                };
+                node.at(location)
             }
+            Token::Number(n, ref ty) => {
+                let ty = ty.clone();
+                let location = self.location;
+                self.next();
+                info!(" primary: get a number: {} of type {:?}", n, ty);
+                Node::from_typed_num(n, ty).at(location)
+            }
+            Token::Str(_) => self.string_literal(),
             Token::Keyword(KeywordType::Lbracket) => {
                 debug!("primary: get a left bracket try to get an expr");
                 self.next();
-                let node = self.expr();
+                let mut node = self.expr();
+                node.parenthesized = true;
                 match self.current_token {
                     Token::Keyword(ref keyword) => {
                         if *keyword == KeywordType::Rbracket {
@@ -113,13 +2462,455 @@ impl TinyCParser {
                             self.next();
                             node
                         } else {
-                            panic!("primary: expect a right bracket");
+                            error(self.location, "expect a right bracket");
                         }
                     }
-                    _ => panic!("primary: expect a right bracket"),
+                    _ => error(self.location, "expect a right bracket"),
+                }
+            }
+            _ => error(
+                self.location,
+                "expect a number, a variable or a left bracket",
+            ),
+        }
+    }
+}
+
+/// The integer specifiers of a declspec, how many times each one appears.
+#[derive(Default)]
+struct IntegerSpecifiers {
+    bool_: usize,
+    char_: usize,
+    short: usize,
+    int: usize,
+    long: usize,
+    signed: usize,
+    unsigned: usize,
+}
+
+impl IntegerSpecifiers {
+    /// Return the integer type the specifiers stand for, `int` if there is none.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the specifiers at `location` are not a valid combination.
+    fn to_type(&self, location: Location) -> Type {
+        let unsigned = match (self.signed, self.unsigned) {
+            (0, 0) => false,
+            (1, 0) => false,
+            (0, 1) => true,
+            _ => error(location, "invalid signedness specifiers"),
+        };
+        let sign = self.signed + self.unsigned;
+        match (self.bool_, self.char_, self.short, self.int, self.long) {
+            (1, 0, 0, 0, 0) if sign == 0 => Type::Bool,
+            (0, 1, 0, 0, 0) if unsigned => Type::UChar,
+            (0, 1, 0, 0, 0) => Type::Char,
+            (0, 0, 1, 0 | 1, 0) if unsigned => Type::UShort,
+            (0, 0, 1, 0 | 1, 0) => Type::Short,
+            (0, 0, 0, 0 | 1, 0) if unsigned => Type::UInt,
+            (0, 0, 0, 0 | 1, 0) => Type::Int,
+            (0, 0, 0, 0 | 1, 1) if unsigned => Type::ULong,
+            (0, 0, 0, 0 | 1, 1) => Type::Long,
+            (0, 0, 0, 0 | 1, 2) if unsigned => Type::ULongLong,
+            (0, 0, 0, 0 | 1, 2) => Type::LongLong,
+            _ => error(location, "invalid type specifiers"),
+        }
+    }
+}
+
+/// Return the format string parameter described by the attribute
+/// `format(kind, string, first)` of a function of type `ty`.
+///
+/// # Panics
+///
+/// Panics if the format string is not a `char *` parameter, or if the
+/// first argument converted is neither 0 nor the position of `...`.
+fn check_format_attribute(
+    ty: &Type,
+    (kind, string, first, location): (FormatKind, i64, i64, Location),
+) -> Format {
+    let Type::Func {
+        params,
+        is_variadic,
+        ..
+    } = ty
+    else {
+        unreachable!("check_format_attribute: {} is not a function type", ty);
+    };
+    if string < 1 || string as usize > params.len() {
+        error(
+            location,
+            &format!("format string argument {} is out of range", string),
+        );
+    }
+    let param = &params[string as usize - 1];
+    if !matches!(param.ty, Type::Ptr(ref base) if matches!(**base, Type::Char | Type::UChar)) {
+        error(
+            location,
+            &format!(
+                "format string argument {} has type {}, not a string type",
+                string, param.ty
+            ),
+        );
+    }
+    let first = match first {
+        0 => None,
+        _ if *is_variadic && first as usize == params.len() + 1 => Some(first as usize - 1),
+        _ => error(
+            location,
+            &format!(
+                "format arguments {} should be 0 or the position of '...'",
+                first
+            ),
+        ),
+    };
+    Format {
+        kind,
+        string: string as usize - 1,
+        first,
+    }
+}
+
+/// Return the formatted I/O functions of the C library gcc knows of,
+/// by name.
+fn library_formats() -> HashMap<String, Format> {
+    let functions = [
+        ("printf", FormatKind::Printf, 0, Some(1)),
+        ("fprintf", FormatKind::Printf, 1, Some(2)),
+        ("dprintf", FormatKind::Printf, 1, Some(2)),
+        ("sprintf", FormatKind::Printf, 1, Some(2)),
+        ("snprintf", FormatKind::Printf, 2, Some(3)),
+        ("vprintf", FormatKind::Printf, 0, None),
+        ("vfprintf", FormatKind::Printf, 1, None),
+        ("vdprintf", FormatKind::Printf, 1, None),
+        ("vsprintf", FormatKind::Printf, 1, None),
+        ("vsnprintf", FormatKind::Printf, 2, None),
+        ("scanf", FormatKind::Scanf, 0, Some(1)),
+        ("fscanf", FormatKind::Scanf, 1, Some(2)),
+        ("sscanf", FormatKind::Scanf, 1, Some(2)),
+        ("vscanf", FormatKind::Scanf, 0, None),
+        ("vfscanf", FormatKind::Scanf, 1, None),
+        ("vsscanf", FormatKind::Scanf, 1, None),
+    ];
+    functions
+        .into_iter()
+        .map(|(name, kind, string, first)| {
+            (
+                name.to_owned(),
+                Format {
+                    kind,
+                    string,
+                    first,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Applies the `derivations` of a declarator to `ty` in order.
+fn derive(ty: Type, derivations: Vec<Derivation>) -> Type {
+    derivations
+        .into_iter()
+        .fold(ty, |ty, derivation| match derivation {
+            Derivation::Pointer => Type::pointer_to(ty),
+            Derivation::Array(len) => Type::array_of(ty, len),
+            Derivation::Func(params, is_variadic) => Type::Func {
+                ret: Box::new(ty),
+                params,
+                is_variadic,
+            },
+        })
+}
+
+/// Return true if the aggregate `ty` has an element at `index` to be
+/// initialized in order. Only the first member of a union is.
+fn has_element(ty: &Type, index: usize) -> bool {
+    match ty {
+        Type::Array(_, len) => *len < 0 || (index as i64) < *len,
+        Type::Record(record) => {
+            let record = record.borrow();
+            let count = if record.is_union {
+                record.members.len().min(1)
+            } else {
+                record.members.len()
+            };
+            index < count
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Return the type of the element at `index` of the aggregate `ty`.
+fn element_type(ty: &Type, index: usize) -> Type {
+    match ty {
+        Type::Array(base, _) => base.as_ref().clone(),
+        Type::Record(record) => record.borrow().members[index].ty.clone(),
+        _ => unreachable!(),
+    }
+}
+
+/// Checks the type of the variable `name` defined at `location`, which may
+/// only be an array of unknown size if its initializer gives the size.
+///
+/// # Panics
+///
+/// Panics if the type is `void` or an incomplete struct or union.
+fn check_object_type(name: &str, ty: &Type, location: Location) {
+    let object = match ty {
+        Type::Array(base, len) if *len < 0 => base,
+        ty => ty,
+    };
+    if *object == Type::Void || !object.is_complete() {
+        error(
+            location,
+            &format!("variable {} has incomplete type {}", name, ty),
+        );
+    }
+}
+
+/// Stores `init` as the initializer of the subobject of the aggregate `ty`
+/// at the end of `path`, a list of element indexes, replacing the initializer
+/// stored there before. Initializing a member of a union discards the
+/// initializers of the other members.
+fn set_initializer(
+    ty: &Type,
+    elements: &mut Vec<Option<Initializer>>,
+    path: &[usize],
+    init: Initializer,
+) {
+    let index = path[0];
+    if elements.len() <= index {
+        elements.resize(index + 1, None);
+    }
+    if let Type::Record(record) = ty {
+        if record.borrow().is_union {
+            elements.iter_mut().for_each(|element| *element = None);
+        }
+    }
+    if path.len() == 1 {
+        elements[index] = Some(init);
+        return;
+    }
+    let mut sub_elements = match elements[index].take() {
+        Some(Initializer::List(sub_elements)) => sub_elements,
+        _ => Vec::new(),
+    };
+    set_initializer(
+        &element_type(ty, index),
+        &mut sub_elements,
+        &path[1..],
+        init,
+    );
+    elements[index] = Some(Initializer::List(sub_elements));
+}
+
+/// Appends to `nodes` the assignments initializing the local object
+/// designated by `target`, which has been zeroed if it is an aggregate.
+fn local_init_assigns(target: Node, init: Initializer, nodes: &mut Vec<Node>) {
+    match init {
+        Initializer::Expr(expr) => {
+            let location = expr.location;
+            let node = Node::from_assign(target, expr).at(location);
+            nodes.push(Node::new(NodeKind::STMT(Box::new(node)), None));
+        }
+        Initializer::List(elements) => {
+            let members = match target.get_type() {
+                Type::Record(record) => record.borrow().members.clone(),
+                _ => Vec::new(),
+            };
+            for (i, element) in elements.into_iter().enumerate() {
+                let Some(element) = element else {
+                    continue;
+                };
+                let target = match members.get(i) {
+                    Some(member) => Node::from_member(target.clone(), &member.name),
+                    None => {
+                        let location = target.location;
+                        let index = Node::from_num(i as i64);
+                        deref(new_add(target.clone(), index, location), location)
+                    }
+                };
+                local_init_assigns(target, element, nodes);
+            }
+        }
+    }
+}
+
+/// Writes the initial value of a global object of type `ty` at `offset`
+/// of `data`, the address constants are recorded in `relocs`.
+///
+/// # Panics
+///
+/// Panics if an initializer is not constant.
+fn write_global_init(
+    data: &mut [u8],
+    relocs: &mut Vec<Reloc>,
+    ty: &Type,
+    init: Initializer,
+    offset: i64,
+) {
+    let elements = match init {
+        Initializer::Expr(expr) => {
+            if !ty.is_scalar() || !expr.get_type().decay().is_scalar() {
+                error(
+                    expr.location,
+                    "initializer element is not a compile-time constant",
+                );
+            }
+            match eval_reloc(&Node::from_cast(expr, ty.clone())) {
+                (addend, Some(label)) => relocs.push(Reloc {
+                    offset,
+                    label,
+                    addend,
+                }),
+                (value, None) => write_bytes(data, offset, ty.size(), value),
+            }
+            return;
+        }
+        Initializer::List(elements) => elements,
+    };
+    match ty {
+        Type::Array(base, _) => {
+            for (i, element) in elements.into_iter().enumerate() {
+                if let Some(element) = element {
+                    let offset = offset + i as i64 * base.size();
+                    write_global_init(data, relocs, base, element, offset);
                 }
             }
-            _ => panic!("primary: expect a number or a left bracket"),
         }
+        Type::Record(record) => {
+            let members = record.borrow().members.clone();
+            for (member, element) in members.iter().zip(elements) {
+                let Some(element) = element else {
+                    continue;
+                };
+                let offset = offset + member.offset;
+                let bit_field = match member.bit_field {
+                    Some(bit_field) => bit_field,
+                    None => {
+                        write_global_init(data, relocs, &member.ty, element, offset);
+                        continue;
+                    }
+                };
+                let value = match element {
+                    Initializer::Expr(expr) => eval(&expr),
+                    Initializer::List(_) => panic!("write_global_init: braces around bit-field"),
+                };
+                // merge the bit-field into its storage unit
+                let size = member.ty.size();
+                let mask = if bit_field.width == 64 {
+                    -1
+                } else {
+                    (1i64 << bit_field.width) - 1
+                };
+                let unit = read_bytes(data, offset, size) & !(mask << bit_field.bit_offset);
+                let unit = unit | ((value & mask) << bit_field.bit_offset);
+                write_bytes(data, offset, size, unit);
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Writes the `size` lower bytes of `value` at `offset` of `data`, little endian.
+fn write_bytes(data: &mut [u8], offset: i64, size: i64, value: i64) {
+    let offset = offset as usize;
+    data[offset..offset + size as usize].copy_from_slice(&value.to_le_bytes()[..size as usize]);
+}
+
+/// Reads `size` bytes at `offset` of `data`, little endian.
+fn read_bytes(data: &[u8], offset: i64, size: i64) -> i64 {
+    let mut bytes = [0; 8];
+    let offset = offset as usize;
+    bytes[..size as usize].copy_from_slice(&data[offset..offset + size as usize]);
+    i64::from_le_bytes(bytes)
+}
+
+/// Builds `lhs + rhs` at `location`, scaling the integer operand by the
+/// pointee size when the other operand is a pointer.
+/// The integer operand is converted to `ptrdiff_t` before it is scaled.
+/// Other operands are left to the type checker.
+fn new_add(lhs: Node, rhs: Node, location: Location) -> Node {
+    match (lhs.get_type().base(), rhs.get_type().base()) {
+        (Some(base), None) if rhs.get_type().is_integer() => {
+            let size = Node::from_typed_num(base.size(), Type::ptrdiff_t());
+            let rhs = Node::from_cast(rhs, Type::ptrdiff_t());
+            let offset = Node::from_binop(BinaryOpType::Mul, rhs, size).at(location);
+            Node::from_binop(BinaryOpType::Add, lhs, offset).at(location)
+        }
+        // canonicalize `num + ptr` to `ptr + num`
+        (None, Some(_)) if lhs.get_type().is_integer() => new_add(rhs, lhs, location),
+        _ => Node::from_binop(BinaryOpType::Add, lhs, rhs).at(location),
+    }
+}
+
+/// Builds `lhs - rhs` at `location`. `ptr - num` is scaled like `new_add`,
+/// `ptr - ptr` gives the number of elements between them as `ptrdiff_t`.
+fn new_sub(lhs: Node, rhs: Node, location: Location) -> Node {
+    match (lhs.get_type().base(), rhs.get_type().base()) {
+        (Some(base), None) if rhs.get_type().is_integer() => {
+            let size = Node::from_typed_num(base.size(), Type::ptrdiff_t());
+            let rhs = Node::from_cast(rhs, Type::ptrdiff_t());
+            let offset = Node::from_binop(BinaryOpType::Mul, rhs, size).at(location);
+            Node::from_binop(BinaryOpType::Sub, lhs, offset).at(location)
+        }
+        (Some(base), Some(_)) => {
+            let size = Node::from_typed_num(base.size(), Type::ptrdiff_t());
+            let diff = Node::new(
+                NodeKind::BINOP {
+                    op: BinaryOpType::Sub,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                Some(Type::ptrdiff_t()),
+            );
+            Node::from_binop(BinaryOpType::Div, diff.at(location), size).at(location)
+        }
+        _ => Node::from_binop(BinaryOpType::Sub, lhs, rhs).at(location),
+    }
+}
+
+/// Builds `*node` at `location`.
+///
+/// # Panics
+///
+/// Panics if `node` is not a pointer, as the result would have no type.
+fn deref(node: Node, location: Location) -> Node {
+    if node.get_type().base().is_none() && !matches!(node.get_type(), Type::Func { .. }) {
+        error(
+            location,
+            &format!(
+                "indirection requires pointer operand ({} invalid)",
+                node.get_type()
+            ),
+        );
+    }
+    Node::from_unop(UnaryOpType::Deref, node).at(location)
+}
+
+/// Builds the access to the member `name` of `base` at `location`.
+///
+/// # Panics
+///
+/// Panics if `base` is not a complete struct or union with such a member.
+fn member(base: Node, name: &str, location: Location) -> Node {
+    match base.get_type() {
+        Type::Record(record) if !record.borrow().complete => error(
+            location,
+            &format!("incomplete definition of type {}", base.get_type()),
+        ),
+        Type::Record(record) if record.borrow().find_member(name).is_none() => error(
+            location,
+            &format!("no member named {} in {}", name, base.get_type()),
+        ),
+        Type::Record(_) => Node::from_member(base, name).at(location),
+        ty => error(
+            location,
+            &format!(
+                "member reference base type {} is not a structure or union",
+                ty
+            ),
+        ),
     }
 }
@@ -1,16 +1,76 @@
+use std::fmt;
+
+use crate::diagnostics;
 use crate::stream::{BasicStream, Stream};
+use crate::types::Type;
 
 // use simplel logger to print log
 use log::{debug, error, info};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum KeywordType {
     Mul,
     Div,
+    Mod,
     Add,
     Sub,
     Lbracket,
     Rbracket,
+    Lbrace,
+    Rbrace,
+    LsquareBracket,
+    RsquareBracket,
+    Semicolon,
+    Colon,
+    Comma,
+    Dot,
+    Ellipsis,
+    Arrow,
+    Assign,
+    And,
+    Or,
+    Xor,
+    Tilde,
+    Not,
+    LogAnd,
+    LogOr,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Void,
+    Bool,
+    Char,
+    Short,
+    Int,
+    Long,
+    Signed,
+    Unsigned,
+    Struct,
+    Union,
+    Enum,
+    Typedef,
+    Static,
+    Extern,
+    Return,
+    If,
+    Else,
+    For,
+    While,
+    Switch,
+    Case,
+    Default,
+    Break,
+    Continue,
+    Goto,
+    Noreturn,
+    Sizeof,
+    Alignof,
+    StaticAssert,
 }
 
 impl KeywordType {
@@ -19,30 +79,105 @@ impl KeywordType {
         match c {
             '*' => KeywordType::Mul,
             '/' => KeywordType::Div,
+            '%' => KeywordType::Mod,
             '+' => KeywordType::Add,
             '-' => KeywordType::Sub,
             '(' => KeywordType::Lbracket,
             ')' => KeywordType::Rbracket,
+            '{' => KeywordType::Lbrace,
+            '}' => KeywordType::Rbrace,
+            '[' => KeywordType::LsquareBracket,
+            ']' => KeywordType::RsquareBracket,
+            ';' => KeywordType::Semicolon,
+            ':' => KeywordType::Colon,
+            ',' => KeywordType::Comma,
+            '.' => KeywordType::Dot,
+            '=' => KeywordType::Assign,
+            '&' => KeywordType::And,
+            '|' => KeywordType::Or,
+            '^' => KeywordType::Xor,
+            '~' => KeywordType::Tilde,
+            '!' => KeywordType::Not,
+            '<' => KeywordType::Lt,
+            '>' => KeywordType::Gt,
             _ => {
                 error!("from_char: invalid char: {}", c);
                 panic!("from_char: invalid char: {}", c);
             }
         }
     }
+
+    // construct a keyword type from a punctuator made of two chars
+    // return None if the chars are not a punctuator
+    pub fn from_two_chars(first: char, second: char) -> Option<KeywordType> {
+        match (first, second) {
+            ('=', '=') => Some(KeywordType::Eq),
+            ('!', '=') => Some(KeywordType::Ne),
+            ('<', '=') => Some(KeywordType::Le),
+            ('>', '=') => Some(KeywordType::Ge),
+            ('-', '>') => Some(KeywordType::Arrow),
+            ('&', '&') => Some(KeywordType::LogAnd),
+            ('|', '|') => Some(KeywordType::LogOr),
+            ('<', '<') => Some(KeywordType::Shl),
+            ('>', '>') => Some(KeywordType::Shr),
+            _ => None,
+        }
+    }
+
+    // construct a keyword type from a reserved word
+    // return None if the word is an ordinary identifier
+    pub fn from_word(word: &str) -> Option<KeywordType> {
+        match word {
+            "void" => Some(KeywordType::Void),
+            "_Bool" => Some(KeywordType::Bool),
+            "char" => Some(KeywordType::Char),
+            "short" => Some(KeywordType::Short),
+            "int" => Some(KeywordType::Int),
+            "long" => Some(KeywordType::Long),
+            "signed" => Some(KeywordType::Signed),
+            "unsigned" => Some(KeywordType::Unsigned),
+            "struct" => Some(KeywordType::Struct),
+            "union" => Some(KeywordType::Union),
+            "enum" => Some(KeywordType::Enum),
+            "typedef" => Some(KeywordType::Typedef),
+            "static" => Some(KeywordType::Static),
+            "extern" => Some(KeywordType::Extern),
+            "return" => Some(KeywordType::Return),
+            "if" => Some(KeywordType::If),
+            "else" => Some(KeywordType::Else),
+            "for" => Some(KeywordType::For),
+            "while" => Some(KeywordType::While),
+            "switch" => Some(KeywordType::Switch),
+            "case" => Some(KeywordType::Case),
+            "default" => Some(KeywordType::Default),
+            "break" => Some(KeywordType::Break),
+            "continue" => Some(KeywordType::Continue),
+            "goto" => Some(KeywordType::Goto),
+            "_Noreturn" => Some(KeywordType::Noreturn),
+            "sizeof" => Some(KeywordType::Sizeof),
+            "_Alignof" => Some(KeywordType::Alignof),
+            "_Static_assert" => Some(KeywordType::StaticAssert),
+            _ => None,
+        }
+    }
 }
 
 /// Token has three types
 /// 1. keyword
 /// 2. number
 /// 3. eof (end of file)
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     /// the token is a keyword in C
     Keyword(KeywordType),
-    /// the token is a number
-    Number(i64),
+    /// the token is a number or a character constant and its type
+    Number(i64, Type),
+    /// the token is a string literal, without its terminating NUL
+    Str(Vec<u8>),
     /// the token is a variable's name
     Var(String),
+    /// a `#pragma` line, the text after `pragma`
+    Pragma(String),
     /// end of file
     Eof,
     /// the token's init state ,undefined
@@ -54,7 +189,7 @@ impl Token {
     /// otherwise ,panic
     pub fn get_number(&self) -> i64 {
         match self {
-            Token::Number(number) => *number,
+            Token::Number(number, _) => *number,
             _ => {
                 error!("get_number: token is not a number");
                 panic!("get_number: token is not a number");
@@ -86,6 +221,24 @@ impl Token {
     }
 }
 
+/// The position of a token in the source, line and column counted from 1.
+/// The default location, line 0, is the one of the predefined names.
+/// Locations are ordered as they are in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "<built-in>"),
+            _ => write!(f, "{}:{}", self.line, self.column),
+        }
+    }
+}
+
 /// basic scanner trait
 pub trait Scanner {
     /// get the next token from the Scanner
@@ -98,27 +251,244 @@ pub struct TinyCScanner {
     string_stream: BasicStream,
     current_buffer: Option<String>,
     cursor: usize,
+    /// the current line and the index its first char is at in the buffer
+    line: usize,
+    line_start: usize,
+    /// the location of the last token
+    location: Location,
+}
+
+/// Return the type of an integer constant: the first type of its candidates
+/// able to represent the value. A decimal constant without `u` suffix is
+/// never unsigned, an octal or hexadecimal one may be.
+fn number_type(value: u64, decimal: bool, long: usize, unsigned: bool) -> Type {
+    let candidates: &[Type] = match (long, unsigned, decimal) {
+        (0, false, true) => &[Type::Int, Type::Long, Type::LongLong],
+        (0, false, false) => &[
+            Type::Int,
+            Type::UInt,
+            Type::Long,
+            Type::ULong,
+            Type::LongLong,
+            Type::ULongLong,
+        ],
+        (0, true, _) => &[Type::UInt, Type::ULong, Type::ULongLong],
+        (1, false, true) => &[Type::Long, Type::LongLong],
+        (1, false, false) => &[Type::Long, Type::ULong, Type::LongLong, Type::ULongLong],
+        (1, true, _) => &[Type::ULong, Type::ULongLong],
+        (_, false, true) => &[Type::LongLong],
+        (_, false, false) => &[Type::LongLong, Type::ULongLong],
+        (_, true, _) => &[Type::ULongLong],
+    };
+    let fits = |ty: &Type| match ty {
+        Type::Int => value <= i32::MAX as u64,
+        Type::UInt => value <= u32::MAX as u64,
+        Type::Long | Type::LongLong => value <= i64::MAX as u64,
+        _ => true,
+    };
+    // a decimal constant too large for long long is unsigned, like gcc does
+    candidates
+        .iter()
+        .find(|ty| fits(ty))
+        .cloned()
+        .unwrap_or(Type::ULongLong)
 }
 
 /// This helper method parses a number token from the buffer.
-fn parse_number_token(buffer: &str, index: &mut usize) -> Token {
+/// Decimal, octal (`0` prefix), hexadecimal (`0x`) and binary (`0b`) constants
+/// with `u`, `l` and `ll` suffixes are recognized.
+fn parse_number_token(buffer: &str, index: &mut usize, location: Location) -> Token {
+    let rest = &buffer[*index..];
+    let lower = rest.to_ascii_lowercase();
+    let (radix, prefix) = if lower.starts_with("0x") {
+        (16, 2)
+    } else if lower.starts_with("0b") {
+        (2, 2)
+    } else if rest.starts_with('0') {
+        (8, 1)
+    } else {
+        (10, 0)
+    };
+    let digits = rest[prefix..]
+        .chars()
+        .take_while(|c| c.is_digit(radix))
+        .count();
+    let value = if digits == 0 {
+        if radix != 8 {
+            diagnostics::error(location, &format!("invalid number {}", rest));
+        }
+        0
+    } else {
+        let text = &rest[prefix..prefix + digits];
+        u64::from_str_radix(text, radix)
+            .unwrap_or_else(|_| diagnostics::error(location, &format!("number too large {}", text)))
+    };
+    let end = prefix + digits;
+    let suffix_len = rest[end..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+    let suffix = &lower[end..end + suffix_len];
+    let (long, unsigned) = match suffix {
+        "" => (0, false),
+        "u" => (0, true),
+        "l" => (1, false),
+        "ul" | "lu" => (1, true),
+        "ll" => (2, false),
+        "ull" | "llu" => (2, true),
+        _ => diagnostics::error(location, &format!("invalid suffix {}", suffix)),
+    };
+    *index += end + suffix_len;
+    let ty = number_type(value, radix == 10, long, unsigned);
+    Token::Number(value as i64, ty)
+}
+
+/// This helper method parses a character constant like `'a'` or `'\n'`
+/// from the buffer. Its type is `int` and its value is the `char` value.
+fn parse_char_token(buffer: &str, index: &mut usize, location: Location) -> Token {
+    let bytes = buffer.as_bytes();
+    let mut i = *index + 1;
+    let value = read_char(bytes, &mut i, b'\'', location);
+    if bytes.get(i) != Some(&b'\'') {
+        diagnostics::error(location, "unclosed char literal");
+    }
+    *index = i + 1;
+    // `char` is signed
+    Token::Number(value as i8 as i64, Type::Int)
+}
+
+/// This helper method parses a string literal like `"a\tb"` from the buffer.
+/// The token holds the bytes of the string without the terminating NUL.
+fn parse_str_token(buffer: &str, index: &mut usize, location: Location) -> Token {
+    let bytes = buffer.as_bytes();
+    let mut i = *index + 1;
+    let mut string = Vec::new();
+    while bytes.get(i) != Some(&b'"') {
+        string.push(read_char(bytes, &mut i, b'"', location));
+    }
+    *index = i + 1;
+    Token::Str(string)
+}
+
+/// Reads one possibly escaped char of a char or string literal at `bytes[*i]`
+/// and moves `i` past it.
+///
+/// # Panics
+///
+/// Panics if the literal ends before its closing `quote`.
+fn read_char(bytes: &[u8], i: &mut usize, quote: u8, location: Location) -> u8 {
+    let byte_at = |i: usize| match bytes.get(i) {
+        Some(b'\n') | None => {
+            diagnostics::error(location, &format!("unclosed {} literal", quote as char))
+        }
+        Some(&byte) => byte,
+    };
+    let c = byte_at(*i);
+    *i += 1;
+    if c == quote && quote == b'\'' {
+        diagnostics::error(location, "empty char literal");
+    }
+    if c != b'\\' {
+        return c;
+    }
+    let escape = byte_at(*i);
+    *i += 1;
+    match escape {
+        b'0'..=b'7' => {
+            let mut value = (escape - b'0') as u32;
+            for _ in 0..2 {
+                match byte_at(*i) {
+                    digit @ b'0'..=b'7' => value = value * 8 + (digit - b'0') as u32,
+                    _ => break,
+                }
+                *i += 1;
+            }
+            value as u8
+        }
+        b'x' => {
+            let mut value = 0u32;
+            while (byte_at(*i) as char).is_ascii_hexdigit() {
+                value = value * 16 + (byte_at(*i) as char).to_digit(16).unwrap();
+                *i += 1;
+            }
+            value as u8
+        }
+        b'a' => 7,
+        b'b' => 8,
+        b't' => b'\t',
+        b'n' => b'\n',
+        b'v' => 11,
+        b'f' => 12,
+        b'r' => b'\r',
+        // a GNU extension for the escape character
+        b'e' => 27,
+        _ => escape,
+    }
+}
+
+/// This helper method parses a preprocessing directive, the rest of the line
+/// after `#`. There is no preprocessor, only `#pragma` is passed to the parser.
+///
+/// # Panics
+///
+/// Panics if the directive is not a pragma.
+fn parse_directive_token(buffer: &str, index: &mut usize, location: Location) -> Token {
+    let rest = &buffer[*index + 1..];
+    let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+    *index += 1 + line.len();
+    let directive = line.trim_start();
+    let name_len = directive
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(directive.len());
+    match &directive[..name_len] {
+        "pragma" => Token::Pragma(directive[name_len..].trim().to_owned()),
+        name => diagnostics::error(
+            location,
+            &format!("unsupported preprocessing directive #{}", name),
+        ),
+    }
+}
+
+/// This helper method parses an identifier or a reserved word from the buffer.
+fn parse_word_token(buffer: &str, index: &mut usize) -> Token {
     let start = *index;
     let mut end = start;
-    // Range the str get the number end position
     for c in buffer[*index..].chars() {
-        if c.is_ascii_digit() {
+        if c.is_ascii_alphanumeric() || c == '_' {
             end += 1;
         } else {
             break;
         }
     }
-    let number: i64 = buffer[start..end].parse().unwrap();
     *index = end;
-    Token::Number(number)
+    let word = &buffer[start..end];
+    match KeywordType::from_word(word) {
+        Some(keyword) => {
+            info!("Recognized a reserved word: {}", word);
+            Token::Keyword(keyword)
+        }
+        None => Token::Var(word.to_string()),
+    }
 }
 
 /// This helper method parses a keyword token from the buffer.
-fn parse_keyword_token(c: char, index: &mut usize) -> Token {
+/// Punctuators made of two chars take precedence over single chars.
+fn parse_keyword_token(buffer: &str, index: &mut usize) -> Token {
+    if buffer[*index..].starts_with("...") {
+        info!("Recognized a keyword: {:?}", KeywordType::Ellipsis);
+        *index += 3;
+        return Token::Keyword(KeywordType::Ellipsis);
+    }
+    let mut chars = buffer[*index..].chars();
+    let c = chars.next().unwrap();
+    if let Some(keyword) = chars
+        .next()
+        .and_then(|next| KeywordType::from_two_chars(c, next))
+    {
+        info!("Recognized a keyword: {:?}", keyword);
+        *index += 2;
+        return Token::Keyword(keyword);
+    }
     info!("Recognized a keyword char: {}", c);
     *index += 1;
     Token::Keyword(KeywordType::from_char(c))
@@ -126,7 +496,7 @@ fn parse_keyword_token(c: char, index: &mut usize) -> Token {
 
 impl TinyCScanner {
     /// This method generates the next token from the current buffer.
-    /// It recognizes ASCII digits, ASCII whitespaces, and special keyword characters.
+    /// It recognizes ASCII digits, identifiers, ASCII whitespaces, and special keyword characters.
     ///
     /// # Panics
     ///
@@ -138,28 +508,57 @@ impl TinyCScanner {
         let mut token = Token::Eof;
         let mut index = self.cursor;
 
-        if index >= buffer.len() {
-            return Token::Eof;
-        }
-
         for c in buffer[index..].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.line_start = index + 1;
+            }
+            if !c.is_ascii_whitespace() {
+                self.location = Location {
+                    line: self.line,
+                    column: index - self.line_start + 1,
+                };
+            }
             match c {
                 _ if c.is_ascii_digit() => {
-                    token = parse_number_token(buffer, &mut index);
+                    token = parse_number_token(buffer, &mut index, self.location);
+                    break;
+                }
+                _ if c.is_ascii_alphabetic() || c == '_' => {
+                    token = parse_word_token(buffer, &mut index);
+                    break;
+                }
+                '\'' => {
+                    token = parse_char_token(buffer, &mut index, self.location);
+                    break;
+                }
+                '"' => {
+                    token = parse_str_token(buffer, &mut index, self.location);
+                    break;
+                }
+                '#' => {
+                    token = parse_directive_token(buffer, &mut index, self.location);
                     break;
                 }
                 _ if c.is_ascii_whitespace() => {
                     info!("Skipping whitespace");
                     index += 1;
                 }
-                '+' | '-' | '*' | '/' | '(' | ')' => {
-                    token = parse_keyword_token(c, &mut index);
+                '+' | '-' | '*' | '/' | '%' | '(' | ')' | '{' | '}' | '[' | ']' | ';' | ':'
+                | ',' | '.' | '=' | '&' | '|' | '^' | '~' | '<' | '>' | '!' => {
+                    token = parse_keyword_token(buffer, &mut index);
                     break;
                 }
-                _ => panic!("TinyCScanner: Invalid char: {}", c),
+                _ => diagnostics::error(self.location, &format!("invalid char {}", c)),
             }
         }
 
+        if token == Token::Eof {
+            self.location = Location {
+                line: self.line,
+                column: index - self.line_start + 1,
+            };
+        }
         self.cursor = index;
         token
     }
@@ -170,8 +569,16 @@ impl TinyCScanner {
             string_stream,
             current_buffer: None,
             cursor: 0,
+            line: 1,
+            line_start: 0,
+            location: Location::default(),
         }
     }
+
+    /// Return the location of the token returned last by `next_token`.
+    pub fn location(&self) -> Location {
+        self.location
+    }
 }
 
 // impl Scanner
@@ -203,7 +610,7 @@ mod tests {
 
     fn assert_next_number(scanner: &mut TinyCScanner, number: i64) {
         let token = scanner.next_token();
-        assert_eq!(token, Token::Number(number));
+        assert_eq!(token, Token::Number(number, Type::Int));
     }
 
     fn assert_next_keyword(scanner: &mut TinyCScanner, keyword: KeywordType) {
@@ -247,4 +654,162 @@ mod tests {
         assert_next_number(&mut scanner, 0);
         assert_eq!(scanner.next_token(), Token::Eof);
     }
+
+    #[test]
+    fn test_scanner_parse_identifier_and_reserved_word() {
+        let mut scanner = create_scanner("int *p_1 = &x; return p_1;".to_owned());
+        assert_next_keyword(&mut scanner, KeywordType::Int);
+        assert_next_keyword(&mut scanner, KeywordType::Mul);
+        assert_eq!(scanner.next_token(), Token::Var("p_1".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Assign);
+        assert_next_keyword(&mut scanner, KeywordType::And);
+        assert_eq!(scanner.next_token(), Token::Var("x".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Semicolon);
+        assert_next_keyword(&mut scanner, KeywordType::Return);
+        assert_eq!(scanner.next_token(), Token::Var("p_1".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Semicolon);
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_member_access() {
+        let mut scanner = create_scanner("s.a->b-c".to_owned());
+        assert_eq!(scanner.next_token(), Token::Var("s".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Dot);
+        assert_eq!(scanner.next_token(), Token::Var("a".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Arrow);
+        assert_eq!(scanner.next_token(), Token::Var("b".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Sub);
+        assert_eq!(scanner.next_token(), Token::Var("c".to_owned()));
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_ellipsis() {
+        let mut scanner = create_scanner("[1 ... 3].x".to_owned());
+        assert_next_keyword(&mut scanner, KeywordType::LsquareBracket);
+        assert_next_number(&mut scanner, 1);
+        assert_next_keyword(&mut scanner, KeywordType::Ellipsis);
+        assert_next_number(&mut scanner, 3);
+        assert_next_keyword(&mut scanner, KeywordType::RsquareBracket);
+        assert_next_keyword(&mut scanner, KeywordType::Dot);
+        assert_eq!(scanner.next_token(), Token::Var("x".to_owned()));
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_locations() {
+        let mut scanner = create_scanner("int x;\n  x =\n\n1;".to_owned());
+        let mut locations = Vec::new();
+        while scanner.next_token() != Token::Eof {
+            let location = scanner.location();
+            locations.push((location.line, location.column));
+        }
+        assert_eq!(
+            locations,
+            vec![(1, 1), (1, 5), (1, 6), (2, 3), (2, 5), (4, 1), (4, 2)]
+        );
+        assert_eq!(scanner.location().to_string(), "4:3");
+        assert_eq!(Location::default().to_string(), "<built-in>");
+    }
+
+    #[test]
+    fn test_scanner_parse_two_char_keyword() {
+        let mut scanner = create_scanner("a[0]<=b==c!=d>=e<f>g=h".to_owned());
+        assert_eq!(scanner.next_token(), Token::Var("a".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::LsquareBracket);
+        assert_next_number(&mut scanner, 0);
+        assert_next_keyword(&mut scanner, KeywordType::RsquareBracket);
+        for keyword in [
+            KeywordType::Le,
+            KeywordType::Eq,
+            KeywordType::Ne,
+            KeywordType::Ge,
+            KeywordType::Lt,
+            KeywordType::Gt,
+            KeywordType::Assign,
+        ] {
+            assert_next_keyword(&mut scanner, keyword);
+            assert!(matches!(scanner.next_token(), Token::Var(_)));
+        }
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_integer_constants() {
+        let mut scanner =
+            create_scanner("0x1F 017 0b101 0 42u 42L 42ul 42LL 2147483648 0xFFFFFFFF".to_owned());
+        for (number, ty) in [
+            (31, Type::Int),
+            (15, Type::Int),
+            (5, Type::Int),
+            (0, Type::Int),
+            (42, Type::UInt),
+            (42, Type::Long),
+            (42, Type::ULong),
+            (42, Type::LongLong),
+            (2147483648, Type::Long),
+            (4294967295, Type::UInt),
+        ] {
+            assert_eq!(scanner.next_token(), Token::Number(number, ty));
+        }
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_char_constants() {
+        let mut scanner = create_scanner(r"'a' '\n' '\0' '\x41' '\101' '\'' '\377'".to_owned());
+        for number in [97, 10, 0, 65, 65, 39, -1] {
+            assert_next_number(&mut scanner, number);
+        }
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_operators() {
+        let mut scanner = create_scanner("a<<b>>c&&d||e%f|g^~!h".to_owned());
+        for keyword in [
+            KeywordType::Shl,
+            KeywordType::Shr,
+            KeywordType::LogAnd,
+            KeywordType::LogOr,
+            KeywordType::Mod,
+            KeywordType::Or,
+        ] {
+            assert!(matches!(scanner.next_token(), Token::Var(_)));
+            assert_next_keyword(&mut scanner, keyword);
+        }
+        assert!(matches!(scanner.next_token(), Token::Var(_)));
+        assert_next_keyword(&mut scanner, KeywordType::Xor);
+        assert_next_keyword(&mut scanner, KeywordType::Tilde);
+        assert_next_keyword(&mut scanner, KeywordType::Not);
+        assert!(matches!(scanner.next_token(), Token::Var(_)));
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_string_literal() {
+        let mut scanner = create_scanner(r#"s = "a\tb\"c\0" "";"#.to_owned());
+        assert_eq!(scanner.next_token(), Token::Var("s".to_owned()));
+        assert_next_keyword(&mut scanner, KeywordType::Assign);
+        assert_eq!(scanner.next_token(), Token::Str(b"a\tb\"c\0".to_vec()));
+        assert_eq!(scanner.next_token(), Token::Str(Vec::new()));
+        assert_next_keyword(&mut scanner, KeywordType::Semicolon);
+        assert_eq!(scanner.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_scanner_parse_pragma() {
+        let mut scanner =
+            create_scanner("x\n  # pragma GCC diagnostic push \n#pragma once\ny".to_owned());
+        assert_eq!(scanner.next_token(), Token::Var("x".to_owned()));
+        assert_eq!(
+            scanner.next_token(),
+            Token::Pragma("GCC diagnostic push".to_owned())
+        );
+        assert_eq!(scanner.location(), Location { line: 2, column: 3 });
+        assert_eq!(scanner.next_token(), Token::Pragma("once".to_owned()));
+        assert_eq!(scanner.next_token(), Token::Var("y".to_owned()));
+        assert_eq!(scanner.location(), Location { line: 4, column: 1 });
+    }
 }
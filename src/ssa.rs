@@ -0,0 +1,439 @@
+// The SSA form of the functions of the IR, where the local variables are
+// registers assigned once instead of stack slots.
+//
+// mem2reg promotes the slots whose address is only loaded from and stored to,
+// all with the same type, to registers. A store becomes the value of the
+// variable until the next one, a load is replaced by the value reaching it,
+// and where the values of several paths meet a phi chooses one. The phis go
+// in the iterated dominance frontier of the blocks storing to the slot, and
+// only where the variable is live, whence "pruned" SSA. The values are then
+// renamed in a walk of the dominator tree. A variable read before it is
+// assigned is 0.
+//
+// out_of_ssa replaces a phi by copies at the end of its predecessors. The
+// copies of an edge go on a new block if the predecessor goes elsewhere as
+// well, or they would change the value of the phi on the other paths, the
+// "lost copy" problem. The phis of a block read their operands at once, and
+// the copies of an edge are ordered so that none overwrites a register
+// another still reads, a cycle such as a swap going through a temporary.
+
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::cfg::{Cfg, DomTree};
+use crate::ir::{Block, BlockId, Function, Inst, Module, Operand, Reg, SlotId, Terminator, Ty};
+
+/// How the address of a slot is used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    /// never
+    Unused,
+    /// only to load and store values of this type, of the size of the slot
+    Value(Ty),
+    /// otherwise, the slot stays in memory
+    Escapes,
+}
+
+/// The name of the pass taking the functions out of SSA form, after which
+/// a register may be assigned more than once.
+pub const OUT_OF_SSA: &str = "out-of-ssa";
+
+/// Promotes the slots of the functions of the module to registers.
+pub fn mem2reg(module: &mut Module) {
+    for function in &mut module.functions {
+        promote_function(function);
+    }
+}
+
+/// Replaces the phis of the functions of the module by copies.
+pub fn out_of_ssa(module: &mut Module) {
+    for function in &mut module.functions {
+        remove_phis(function);
+    }
+}
+
+/// Return the slot each register holding the address of a slot points to
+fn slot_addresses(function: &Function) -> HashMap<Reg, SlotId> {
+    let mut addresses = HashMap::new();
+    for block in &function.blocks {
+        for inst in &block.insts {
+            if let Inst::SlotAddr { dst, slot } = *inst {
+                addresses.insert(dst, slot);
+            }
+        }
+    }
+    addresses
+}
+
+/// Return how the address of each slot is used
+fn slot_accesses(function: &Function, addresses: &HashMap<Reg, SlotId>) -> Vec<Access> {
+    let mut accesses = vec![Access::Unused; function.slots.len()];
+    let mut access = |slot: SlotId, ty: Ty| {
+        let size = function.slots[slot.0].size;
+        accesses[slot.0] = match accesses[slot.0] {
+            Access::Unused if ty.size() == size => Access::Value(ty),
+            Access::Value(other) if other == ty => Access::Value(ty),
+            _ => Access::Escapes,
+        };
+    };
+    let mut escapes = Vec::new();
+    for block in &function.blocks {
+        for inst in &block.insts {
+            match *inst {
+                Inst::Load {
+                    ty,
+                    addr: Operand::Reg(addr),
+                    ..
+                } if addresses.contains_key(&addr) => access(addresses[&addr], ty),
+                Inst::Store {
+                    ty,
+                    ref value,
+                    addr: Operand::Reg(addr),
+                } if addresses.contains_key(&addr) => {
+                    access(addresses[&addr], ty);
+                    escapes.push(value);
+                }
+                _ => escapes.extend(inst.operands()),
+            }
+        }
+        if let Terminator::Branch { ref cond, .. } | Terminator::Ret(Some(ref cond)) = block.term {
+            escapes.push(cond);
+        }
+    }
+    for operand in escapes {
+        if let Operand::Reg(reg) = operand {
+            if let Some(&slot) = addresses.get(reg) {
+                accesses[slot.0] = Access::Escapes;
+            }
+        }
+    }
+    accesses
+}
+
+/// Return true if every register of the function is assigned once at most
+fn is_ssa(function: &Function) -> bool {
+    let mut assigned = vec![false; function.regs.len()];
+    let dsts = function.params.iter().map(|param| param.reg).chain(
+        function
+            .blocks
+            .iter()
+            .flat_map(|block| block.insts.iter().filter_map(Inst::dst)),
+    );
+    for dst in dsts {
+        if assigned[dst.0] {
+            return false;
+        }
+        assigned[dst.0] = true;
+    }
+    true
+}
+
+/// Return the slot the instruction loads from or stores to if it is promoted
+fn promoted_access(
+    inst: &Inst,
+    addresses: &HashMap<Reg, SlotId>,
+    accesses: &[Access],
+) -> Option<SlotId> {
+    let addr = match *inst {
+        Inst::Load {
+            addr: Operand::Reg(addr),
+            ..
+        }
+        | Inst::Store {
+            addr: Operand::Reg(addr),
+            ..
+        } => addr,
+        _ => return None,
+    };
+    let slot = *addresses.get(&addr)?;
+    match accesses[slot.0] {
+        Access::Value(_) => Some(slot),
+        Access::Unused | Access::Escapes => None,
+    }
+}
+
+/// Promotes the slots of the function to registers
+fn promote_function(function: &mut Function) {
+    // the loads are replaced by the registers they read from, which must
+    // be assigned once
+    assert!(
+        is_ssa(function),
+        "mem2reg: @{} is not in SSA form",
+        function.name
+    );
+    // the phis are placed on the dominance frontiers, which don't take the
+    // entry of the function for an edge into bb0
+    let cfg = Cfg::new(function);
+    assert!(
+        cfg.preds[0].is_empty(),
+        "mem2reg: @{} branches to its entry block",
+        function.name
+    );
+    let addresses = slot_addresses(function);
+    let accesses = slot_accesses(function, &addresses);
+    if accesses.iter().all(|&access| access == Access::Escapes) {
+        return;
+    }
+    let dom = DomTree::new(&cfg);
+    let frontiers = dom.frontiers(&cfg);
+    let len = function.blocks.len();
+
+    // place the phis, the slot of each one
+    let mut phi_slots: HashMap<Reg, SlotId> = HashMap::new();
+    let mut new_phis: Vec<Vec<Inst>> = vec![Vec::new(); len];
+    for (s, &access) in accesses.iter().enumerate() {
+        let ty = match access {
+            Access::Value(ty) => ty,
+            Access::Unused | Access::Escapes => continue,
+        };
+        let slot = SlotId(s);
+        let is_slot = |inst: &Inst| promoted_access(inst, &addresses, &accesses) == Some(slot);
+        let mut stores = vec![false; len];
+        let mut live_in = vec![false; len];
+        let mut work = Vec::new();
+        for (b, block) in function.blocks.iter().enumerate() {
+            for inst in block.insts.iter().filter(|inst| is_slot(inst)) {
+                match inst {
+                    // the value loaded before any store comes from the predecessors
+                    Inst::Load { .. } if !stores[b] && !live_in[b] => {
+                        live_in[b] = true;
+                        work.push(BlockId(b));
+                    }
+                    Inst::Store { .. } => stores[b] = true,
+                    _ => {}
+                }
+            }
+        }
+        while let Some(block) = work.pop() {
+            for &pred in &cfg.preds[block.0] {
+                if !live_in[pred.0] && !stores[pred.0] {
+                    live_in[pred.0] = true;
+                    work.push(pred);
+                }
+            }
+        }
+        // the iterated dominance frontier of the stores, where a phi is
+        // itself a store
+        let mut has_phi = vec![false; len];
+        let mut work: Vec<BlockId> = (0..len).filter(|&b| stores[b]).map(BlockId).collect();
+        while let Some(block) = work.pop() {
+            for &frontier in &frontiers[block.0] {
+                if has_phi[frontier.0] || !live_in[frontier.0] {
+                    continue;
+                }
+                has_phi[frontier.0] = true;
+                let dst = Reg(function.regs.len());
+                function.regs.push(ty);
+                let incoming = cfg.preds[frontier.0]
+                    .iter()
+                    .map(|&pred| (pred, Operand::Const(0)))
+                    .collect();
+                new_phis[frontier.0].push(Inst::Phi { dst, ty, incoming });
+                phi_slots.insert(dst, slot);
+                if !stores[frontier.0] {
+                    work.push(frontier);
+                }
+            }
+        }
+    }
+    for (block, phis) in function.blocks.iter_mut().zip(new_phis) {
+        block.insts.splice(0..0, phis);
+    }
+
+    // rename the values in the dominator tree, with the values of the
+    // variables on a stack restored when leaving a block
+    let mut values: Vec<Vec<Operand>> = vec![Vec::new(); function.slots.len()];
+    let mut replaced: HashMap<Reg, Operand> = HashMap::new();
+    let mut stack = vec![(BlockId(0), None)];
+    while let Some((block, saved)) = stack.pop() {
+        if let Some(saved) = saved {
+            // leaving the block
+            for (values, len) in values.iter_mut().zip(saved) {
+                values.truncate(len);
+            }
+            continue;
+        }
+        let saved: Vec<usize> = values.iter().map(Vec::len).collect();
+        let current = |values: &[Vec<Operand>], slot: SlotId| {
+            values[slot.0].last().cloned().unwrap_or(Operand::Const(0))
+        };
+        for inst in &function.blocks[block.0].insts {
+            if let Inst::Phi { dst, .. } = *inst {
+                if let Some(&slot) = phi_slots.get(&dst) {
+                    values[slot.0].push(Operand::Reg(dst));
+                }
+                continue;
+            }
+            let slot = match promoted_access(inst, &addresses, &accesses) {
+                Some(slot) => slot,
+                None => continue,
+            };
+            match *inst {
+                Inst::Load { dst, .. } => {
+                    replaced.insert(dst, current(&values, slot));
+                }
+                Inst::Store { ref value, .. } => values[slot.0].push(value.clone()),
+                _ => unreachable!(),
+            }
+        }
+        for &succ in &cfg.succs[block.0] {
+            for inst in &mut function.blocks[succ.0].insts {
+                match inst {
+                    Inst::Phi { dst, incoming, .. } if phi_slots.contains_key(dst) => {
+                        let value = current(&values, phi_slots[dst]);
+                        for (pred, operand) in incoming.iter_mut() {
+                            if *pred == block {
+                                *operand = value.clone();
+                            }
+                        }
+                    }
+                    Inst::Phi { .. } => {}
+                    _ => break,
+                }
+            }
+        }
+        stack.push((block, Some(saved)));
+        for &child in dom.children(block).iter().rev() {
+            stack.push((child, None));
+        }
+    }
+
+    // remove the loads and the stores, and read the values instead of the
+    // loads, 0 in the blocks which can't be reached
+    for block in &mut function.blocks {
+        block.insts.retain(|inst| match *inst {
+            Inst::SlotAddr { slot, .. } => accesses[slot.0] == Access::Escapes,
+            _ => match promoted_access(inst, &addresses, &accesses) {
+                Some(_) => {
+                    if let Inst::Load { dst, .. } = *inst {
+                        replaced.entry(dst).or_insert(Operand::Const(0));
+                    }
+                    false
+                }
+                None => true,
+            },
+        });
+    }
+    let resolve = |operand: &mut Operand| {
+        // a loaded value may itself be the value of a load
+        while let Operand::Reg(reg) = *operand {
+            match replaced.get(&reg) {
+                Some(value) => *operand = value.clone(),
+                None => break,
+            }
+        }
+    };
+    for block in &mut function.blocks {
+        for inst in &mut block.insts {
+            inst.operands_mut().into_iter().for_each(&resolve);
+        }
+        if let Some(operand) = block.term.operand_mut() {
+            resolve(operand);
+        }
+    }
+
+    // the promoted slots are gone, the others are renumbered
+    let mut numbers = Vec::with_capacity(function.slots.len());
+    let mut slots = Vec::new();
+    for (slot, access) in function.slots.drain(..).zip(&accesses) {
+        numbers.push(SlotId(slots.len()));
+        if *access == Access::Escapes {
+            slots.push(slot);
+        }
+    }
+    function.slots = slots;
+    for block in &mut function.blocks {
+        for inst in &mut block.insts {
+            if let Inst::SlotAddr { slot, .. } = inst {
+                *slot = numbers[slot.0];
+            }
+        }
+    }
+    debug!(
+        "@{}: {} slots promoted, {} phis placed",
+        function.name,
+        numbers.len() - function.slots.len(),
+        phi_slots.len()
+    );
+}
+
+/// Return the instructions making the copies, which happen at once: a copy
+/// goes once the registers it assigns are no longer read by the others, and
+/// if all are, they form cycles, one of which is broken by saving a register
+/// in a new one
+fn sequentialize(function: &mut Function, mut copies: Vec<(Reg, Ty, Operand)>) -> Vec<Inst> {
+    copies.retain(|(dst, _, src)| *src != Operand::Reg(*dst));
+    let mut insts = Vec::with_capacity(copies.len());
+    while !copies.is_empty() {
+        let is_read = |dst: Reg| copies.iter().any(|(_, _, src)| *src == Operand::Reg(dst));
+        match copies.iter().position(|&(dst, _, _)| !is_read(dst)) {
+            Some(i) => {
+                let (dst, ty, src) = copies.remove(i);
+                insts.push(Inst::Copy { dst, ty, src });
+            }
+            None => {
+                let (dst, ty, _) = copies[0];
+                let temp = Reg(function.regs.len());
+                function.regs.push(ty);
+                insts.push(Inst::Copy {
+                    dst: temp,
+                    ty,
+                    src: Operand::Reg(dst),
+                });
+                for (_, _, src) in &mut copies {
+                    if *src == Operand::Reg(dst) {
+                        *src = Operand::Reg(temp);
+                    }
+                }
+            }
+        }
+    }
+    insts
+}
+
+/// Replaces the phis of the function by copies
+fn remove_phis(function: &mut Function) {
+    let cfg = Cfg::new(function);
+    for b in 0..cfg.succs.len() {
+        let count = function.blocks[b]
+            .insts
+            .iter()
+            .take_while(|inst| matches!(inst, Inst::Phi { .. }))
+            .count();
+        if count == 0 {
+            continue;
+        }
+        let phis: Vec<Inst> = function.blocks[b].insts.drain(..count).collect();
+        for &pred in &cfg.preds[b] {
+            let copies = phis
+                .iter()
+                .map(|phi| match phi {
+                    Inst::Phi { dst, ty, incoming } => {
+                        let (_, value) = incoming.iter().find(|(block, _)| *block == pred).unwrap();
+                        (*dst, *ty, value.clone())
+                    }
+                    _ => unreachable!(),
+                })
+                .collect();
+            let insts = sequentialize(function, copies);
+            if cfg.succs[pred.0].len() == 1 {
+                function.blocks[pred.0].insts.extend(insts);
+                continue;
+            }
+            // split the edge
+            let edge = BlockId(function.blocks.len());
+            function.blocks.push(Block {
+                insts,
+                term: Terminator::Jump(BlockId(b)),
+            });
+            if let Terminator::Branch { then, els, .. } = &mut function.blocks[pred.0].term {
+                for target in [then, els] {
+                    if target.0 == b {
+                        *target = edge;
+                    }
+                }
+            }
+        }
+    }
+}
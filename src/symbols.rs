@@ -0,0 +1,290 @@
+// The symbol table maps the identifiers visible at a point of the program to
+// what they were declared as. C has separate namespaces: ordinary identifiers
+// (objects, functions, enumerators and typedef names), tags of structs, unions
+// and enums, members of each struct or union, and labels. The first two have
+// block scopes and are kept here, members belong to their record and are
+// checked when it is parsed. Labels have the whole function as their scope,
+// the parser keeps those of the function it parses.
+//
+// Names are resolved while parsing, not in a later pass, since whether an
+// identifier is a typedef name changes how the tokens after it are parsed.
+
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::scanner::Location;
+use crate::types::{RecordRef, Type};
+
+/// What an ordinary identifier refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ordinary {
+    /// a local variable, the index into the locals of the function
+    Local(usize),
+    /// a global variable, the index into the globals of the program
+    Global(usize),
+    /// a function, whose type is the one of its declarations
+    Function,
+    /// an enumerator and its value
+    EnumConst(i64),
+    /// a typedef name and the type it stands for
+    Typedef(Type),
+}
+
+impl Ordinary {
+    /// Return true if a declaration of `other` may follow `self` in the
+    /// same scope: an object or a function with linkage may be declared more
+    /// than once, and a typedef name may be redefined as the same type.
+    /// Whether the types of the declarations match is checked by the parser.
+    fn may_redeclare_as(&self, other: &Ordinary) -> bool {
+        match (self, other) {
+            // a static local is a distinct global variable without linkage
+            (Ordinary::Global(id), Ordinary::Global(other)) => id == other,
+            (Ordinary::Function, Ordinary::Function) => true,
+            (Ordinary::Typedef(ty), Ordinary::Typedef(other)) => ty == other,
+            _ => false,
+        }
+    }
+}
+
+/// What a struct, union or enum tag refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Record(RecordRef),
+    Enum,
+}
+
+/// A declared identifier: what it refers to and where it is declared first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol<T> {
+    pub value: T,
+    pub location: Location,
+}
+
+/// A block scope, or the file scope.
+#[derive(Debug, Default)]
+struct Scope {
+    ordinary: HashMap<String, Symbol<Ordinary>>,
+    tags: HashMap<String, Symbol<Tag>>,
+}
+
+/// The scopes enclosing the point being parsed, the file scope first and
+/// the innermost last. An identifier declared in an inner scope hides the
+/// one of an outer scope in the same namespace.
+#[derive(Debug)]
+pub struct SymbolTable {
+    scopes: Vec<Scope>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SymbolTable {
+    /// Constructs a table with an empty file scope
+    pub fn new() -> SymbolTable {
+        SymbolTable {
+            scopes: vec![Scope::default()],
+        }
+    }
+
+    /// Opens a new innermost scope
+    pub fn enter_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    /// Closes the innermost scope, its identifiers are no longer visible
+    ///
+    /// # Panics
+    ///
+    /// Panics if the innermost scope is the file scope.
+    pub fn leave_scope(&mut self) {
+        if self.scopes.len() == 1 {
+            panic!("leave_scope: can't leave the file scope");
+        }
+        self.scopes.pop();
+    }
+
+    /// Return true if the innermost scope is the file scope
+    pub fn is_file_scope(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    /// Declares an ordinary identifier in the innermost scope, or in the file
+    /// scope if `file_scope` is true. A redeclaration allowed in the same scope
+    /// keeps the location of the first declaration.
+    /// Return the previous declaration if it conflicts.
+    pub fn declare(
+        &mut self,
+        name: &str,
+        value: Ordinary,
+        location: Location,
+        file_scope: bool,
+    ) -> Result<(), Symbol<Ordinary>> {
+        debug!(" declare {} as {:?} at {}", name, value, location);
+        let scope = match file_scope {
+            true => self.scopes.first_mut(),
+            false => self.scopes.last_mut(),
+        }
+        .expect("declare: no scope");
+        match scope.ordinary.get_mut(name) {
+            Some(previous) if previous.value.may_redeclare_as(&value) => {
+                previous.value = value;
+                Ok(())
+            }
+            Some(previous) => Err(previous.clone()),
+            None => {
+                scope
+                    .ordinary
+                    .insert(name.to_owned(), Symbol { value, location });
+                Ok(())
+            }
+        }
+    }
+
+    /// Looks an ordinary identifier up from the innermost scope to the outermost one
+    pub fn lookup(&self, name: &str) -> Option<&Symbol<Ordinary>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.ordinary.get(name))
+    }
+
+    /// Return the declaration of `name` a declaration in the innermost scope
+    /// would hide, and true if it is in the file scope. None if there is none,
+    /// or if `name` is declared in the innermost scope already.
+    pub fn shadowed(&self, name: &str) -> Option<(&Symbol<Ordinary>, bool)> {
+        let (innermost, enclosing) = self.scopes.split_last()?;
+        if innermost.ordinary.contains_key(name) {
+            return None;
+        }
+        enclosing
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(depth, scope)| scope.ordinary.get(name).map(|symbol| (symbol, depth == 0)))
+    }
+
+    /// Declares a tag in the innermost scope.
+    /// Return the previous declaration if the tag is declared there already.
+    pub fn declare_tag(
+        &mut self,
+        name: &str,
+        value: Tag,
+        location: Location,
+    ) -> Result<(), Symbol<Tag>> {
+        debug!(" declare tag {} as {:?} at {}", name, value, location);
+        let scope = self.scopes.last_mut().expect("declare_tag: no scope");
+        match scope.tags.get(name) {
+            Some(previous) => Err(previous.clone()),
+            None => {
+                scope
+                    .tags
+                    .insert(name.to_owned(), Symbol { value, location });
+                Ok(())
+            }
+        }
+    }
+
+    /// Looks a tag up from the innermost scope to the outermost one
+    pub fn lookup_tag(&self, name: &str) -> Option<&Symbol<Tag>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.tags.get(name))
+    }
+
+    /// Looks a tag up in the innermost scope only
+    pub fn lookup_tag_in_scope(&self, name: &str) -> Option<&Symbol<Tag>> {
+        self.scopes.last().and_then(|scope| scope.tags.get(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> Location {
+        Location { line, column }
+    }
+
+    #[test]
+    fn test_shadowing_and_scopes() {
+        let mut symbols = SymbolTable::new();
+        assert!(symbols.is_file_scope());
+        symbols
+            .declare("x", Ordinary::Global(0), at(1, 5), false)
+            .unwrap();
+        symbols.enter_scope();
+        symbols
+            .declare("x", Ordinary::Local(0), at(2, 9), false)
+            .unwrap();
+        assert_eq!(symbols.lookup("x").unwrap().value, Ordinary::Local(0));
+        assert!(symbols.shadowed("x").is_none());
+        symbols.enter_scope();
+        let (previous, file_scope) = symbols.shadowed("x").unwrap();
+        assert_eq!((previous.location, file_scope), (at(2, 9), false));
+        symbols.leave_scope();
+        symbols.leave_scope();
+        assert!(symbols.shadowed("x").is_none());
+        symbols.enter_scope();
+        let (previous, file_scope) = symbols.shadowed("x").unwrap();
+        assert_eq!((previous.location, file_scope), (at(1, 5), true));
+        symbols.leave_scope();
+        assert_eq!(symbols.lookup("x").unwrap().value, Ordinary::Global(0));
+        assert!(symbols.lookup("y").is_none());
+    }
+
+    #[test]
+    fn test_redeclarations() {
+        let mut symbols = SymbolTable::new();
+        symbols
+            .declare("f", Ordinary::Function, at(1, 5), false)
+            .unwrap();
+        symbols
+            .declare("f", Ordinary::Function, at(2, 5), false)
+            .unwrap();
+        assert_eq!(symbols.lookup("f").unwrap().location, at(1, 5));
+        let previous = symbols
+            .declare("f", Ordinary::Global(0), at(3, 5), false)
+            .unwrap_err();
+        assert_eq!(previous.location, at(1, 5));
+        symbols
+            .declare("T", Ordinary::Typedef(Type::Int), at(4, 13), false)
+            .unwrap();
+        symbols
+            .declare("T", Ordinary::Typedef(Type::Int), at(5, 13), false)
+            .unwrap();
+        assert!(symbols
+            .declare("T", Ordinary::Typedef(Type::Long), at(6, 14), false)
+            .is_err());
+        symbols.enter_scope();
+        symbols
+            .declare("a", Ordinary::Local(0), at(7, 9), false)
+            .unwrap();
+        assert!(symbols
+            .declare("a", Ordinary::Local(1), at(8, 9), false)
+            .is_err());
+        // a file scope declaration from a block doesn't conflict with it
+        symbols
+            .declare("a", Ordinary::Global(1), at(9, 9), true)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_tags_are_a_separate_namespace() {
+        let mut symbols = SymbolTable::new();
+        symbols
+            .declare("S", Ordinary::Global(0), at(1, 5), false)
+            .unwrap();
+        symbols.declare_tag("S", Tag::Enum, at(2, 6)).unwrap();
+        assert_eq!(symbols.lookup_tag("S").unwrap().value, Tag::Enum);
+        assert!(symbols.declare_tag("S", Tag::Enum, at(3, 6)).is_err());
+        symbols.enter_scope();
+        assert!(symbols.lookup_tag_in_scope("S").is_none());
+        symbols.declare_tag("S", Tag::Enum, at(4, 6)).unwrap();
+        assert_eq!(symbols.lookup_tag_in_scope("S").unwrap().location, at(4, 6));
+    }
+}
@@ -0,0 +1,473 @@
+// The type checker validates the AST the parser built before code is
+// generated from it. Every expression is typed when its node is constructed,
+// since the parser needs the types to go on (the size of a pointee to scale
+// `p + 1`, the members of a struct to access them), and the implicit
+// conversions C performs are inserted then as CONV nodes. What is left to the
+// checker is deciding whether the program is valid C: operands of the right
+// kinds, assignments, arguments and returned values of compatible types,
+// conditions of scalar types. Each error is reported at the location of the
+// node it is found at, the first one stops the compilation.
+//
+// Pointers to incompatible types are accepted where C requires them to be
+// compatible, as gcc does with the incompatible-pointer-types warning on by
+// default: `int *p = a` for an `int a[2][3]` converts the pointer to the
+// first row. So is comparing a pointer with an
+// integer. Subtracting pointers to different types is an error, as there is
+// no sensible count of elements between them.
+//
+// The checker also reports the warnings about the types of the operands,
+// such as a comparison converting a signed integer to unsigned, and about
+// the arguments of the calls to `printf` and `scanf` not matching their
+// format.
+
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::ast::{BinaryOpType, Format, Node, NodeKind, Program, UnaryOpType};
+use crate::diagnostics::{error, Diagnostics, Warning};
+use crate::format;
+use crate::types::Type;
+
+/// Where a value is converted as if by assignment, for the messages.
+#[derive(Debug, Clone, Copy)]
+enum Conversion {
+    Assignment,
+    Argument,
+    Return,
+}
+
+impl Conversion {
+    /// Return the message for a value of type `from` which can't be converted to `to`.
+    fn message(&self, from: &Type, to: &Type) -> String {
+        match self {
+            Conversion::Assignment => {
+                format!("assigning to {} from incompatible type {}", to, from)
+            }
+            Conversion::Argument => {
+                format!("passing {} to parameter of incompatible type {}", from, to)
+            }
+            Conversion::Return => format!(
+                "returning {} from a function with incompatible result type {}",
+                from, to
+            ),
+        }
+    }
+
+    /// Return the message for a pointer of type `from` converted to the
+    /// pointer type `to` whose pointee is incompatible.
+    fn pointer_message(&self, from: &Type, to: &Type) -> String {
+        match self {
+            Conversion::Assignment => {
+                format!(
+                    "incompatible pointer types assigning to {} from {}",
+                    to, from
+                )
+            }
+            Conversion::Argument => format!(
+                "incompatible pointer types passing {} to parameter of type {}",
+                from, to
+            ),
+            Conversion::Return => format!(
+                "incompatible pointer types returning {} from a function with result type {}",
+                from, to
+            ),
+        }
+    }
+}
+
+/// Checks every function of the program, reporting the warnings to `diagnostics`.
+///
+/// # Panics
+///
+/// Panics at the first invalid statement or expression.
+pub fn check_program(program: &Program, diagnostics: &mut Diagnostics) {
+    // the string literals, for the format checks
+    let strings = program
+        .globals
+        .iter()
+        .filter(|global| global.name.starts_with(".L..str."))
+        .filter_map(|global| Some((global.name.as_str(), global.init.as_deref()?)))
+        .collect::<HashMap<_, _>>();
+    for function in &program.functions {
+        debug!("check function: {}", function.name);
+        let mut checker = Checker {
+            diagnostics,
+            strings: &strings,
+            ret: function.ret.clone(),
+        };
+        checker.check_stmt(&function.body);
+    }
+}
+
+/// The checker of the body of a function.
+struct Checker<'a> {
+    diagnostics: &'a mut Diagnostics,
+    /// the bytes of the string literals by the names of their arrays
+    strings: &'a HashMap<&'a str, &'a [u8]>,
+    /// the return type of the function
+    ret: Type,
+}
+
+impl Checker<'_> {
+    /// Checks a statement.
+    fn check_stmt(&mut self, node: &Node) {
+        match node.kind {
+            NodeKind::STMT(ref expr) | NodeKind::MEMZERO(ref expr) => self.check_expr(expr),
+            NodeKind::RETURN(None) if self.ret != Type::Void => {
+                error(node.location, "non-void function should return a value")
+            }
+            NodeKind::RETURN(None) => {}
+            NodeKind::RETURN(Some(ref value)) => {
+                self.check_expr(value);
+                if self.ret == Type::Void {
+                    error(node.location, "void function should not return a value");
+                }
+                let ret = self.ret.clone();
+                self.check_conversion(value, &ret, Conversion::Return);
+            }
+            NodeKind::BLOCK(ref stmts) => {
+                for stmt in stmts {
+                    self.check_stmt(stmt);
+                }
+            }
+            NodeKind::IF {
+                ref cond,
+                ref then,
+                ref els,
+            } => {
+                self.check_condition(cond);
+                self.check_stmt(then);
+                if let Some(ref els) = els {
+                    self.check_stmt(els);
+                }
+            }
+            NodeKind::FOR {
+                ref init,
+                ref cond,
+                ref inc,
+                ref body,
+            } => {
+                if let Some(ref init) = init {
+                    self.check_stmt(init);
+                }
+                if let Some(ref cond) = cond {
+                    self.check_condition(cond);
+                }
+                if let Some(ref inc) = inc {
+                    self.check_expr(inc);
+                }
+                self.check_stmt(body);
+            }
+            // the parser has checked the controlling expression is an integer
+            NodeKind::SWITCH {
+                ref cond, ref body, ..
+            } => {
+                self.check_expr(cond);
+                self.check_stmt(body);
+            }
+            NodeKind::CASE { ref body, .. } | NodeKind::LABEL { ref body, .. } => {
+                self.check_stmt(body)
+            }
+            NodeKind::BREAK | NodeKind::CONTINUE | NodeKind::GOTO(_) => {}
+            _ => error(node.location, &format!("not a statement {:?}", node)),
+        }
+    }
+
+    /// Checks the controlling expression of a statement or the operand of
+    /// a logical operator, which is compared with 0.
+    fn check_condition(&mut self, cond: &Node) {
+        self.check_expr(cond);
+        if !cond.get_type().decay().is_scalar() {
+            error(
+                cond.location,
+                &format!(
+                    "statement requires expression of scalar type ({} invalid)",
+                    cond.get_type()
+                ),
+            );
+        }
+    }
+
+    /// Checks an expression and its operands.
+    fn check_expr(&mut self, node: &Node) {
+        match node.kind {
+            NodeKind::NUM(_) | NodeKind::VAR(_) | NodeKind::GVAR(_) | NodeKind::UNREACHABLE => {}
+            NodeKind::BINOP {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } => {
+                if op.is_logical() {
+                    self.check_condition(lhs);
+                    self.check_condition(rhs);
+                } else {
+                    self.check_expr(lhs);
+                    self.check_expr(rhs);
+                    check_binop(node, op, lhs, rhs);
+                    if op.is_comparison() {
+                        self.warn_sign_compare(node, lhs, rhs);
+                    }
+                }
+            }
+            NodeKind::UNOP {
+                ref op,
+                ref operand,
+            } => {
+                if let UnaryOpType::Not = op {
+                    self.check_condition(operand);
+                } else {
+                    self.check_expr(operand);
+                    check_unop(node, op, operand);
+                }
+            }
+            NodeKind::ASSIGN { ref lhs, ref rhs } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+                self.check_assign(node, lhs, rhs);
+            }
+            NodeKind::CAST(ref operand) => {
+                self.check_expr(operand);
+                check_cast(node, operand);
+            }
+            NodeKind::CONV(ref operand) => self.check_expr(operand),
+            NodeKind::MEMBER { ref base, .. } => self.check_expr(base),
+            NodeKind::CALL {
+                ref func,
+                ref args,
+                ref format,
+                ..
+            } => {
+                self.check_expr(func);
+                for arg in args {
+                    self.check_expr(arg);
+                }
+                self.check_call(node, func, args);
+                if let Some(format) = format {
+                    self.check_format(format, args);
+                }
+            }
+            NodeKind::LITERAL { ref init, ref var } => {
+                self.check_stmt(init);
+                self.check_expr(var);
+            }
+            NodeKind::VASTART(ref operand) | NodeKind::VAARG(ref operand) => {
+                self.check_expr(operand)
+            }
+            _ => error(node.location, &format!("not an expression {:?}", node)),
+        }
+    }
+
+    /// Checks the arguments `args` of a call against the format string
+    /// `format` if it is a string literal.
+    fn check_format(&mut self, format: &Format, args: &[Node]) {
+        let literal = args
+            .get(format.string)
+            .and_then(|string| match unconverted(string).kind {
+                NodeKind::GVAR(ref name) => self.strings.get(name.as_str()).copied(),
+                _ => None,
+            });
+        // without the terminating null character
+        let literal = literal.map(|literal| &literal[..literal.len() - 1]);
+        format::check_call(self.diagnostics, format, literal, args);
+    }
+
+    /// Warns if the comparison `node` converts a signed operand to unsigned,
+    /// unless it is a constant which is not negative.
+    fn warn_sign_compare(&mut self, node: &Node, lhs: &Node, rhs: &Node) {
+        if !lhs.get_type().is_unsigned() {
+            return;
+        }
+        let (lhs, rhs) = (unconverted(lhs), unconverted(rhs));
+        let converted = |operand: &Node| {
+            !operand.get_type().is_unsigned() && !matches!(operand.kind, NodeKind::NUM(n) if n >= 0)
+        };
+        if converted(lhs) || converted(rhs) {
+            let message = format!(
+                "comparison of integers of different signs: {} and {}",
+                lhs.get_type(),
+                rhs.get_type()
+            );
+            self.diagnostics
+                .warn(Warning::SignCompare, node.location, &message);
+        }
+    }
+
+    /// Checks that `lhs` is a modifiable lvalue which can be assigned `rhs`.
+    fn check_assign(&mut self, node: &Node, lhs: &Node, rhs: &Node) {
+        if !lhs.is_lvalue() {
+            error(node.location, "expression is not assignable");
+        }
+        if let Type::Array(..) = lhs.get_type() {
+            error(
+                node.location,
+                &format!("array type {} is not assignable", lhs.get_type()),
+            );
+        }
+        self.check_conversion(rhs, lhs.get_type(), Conversion::Assignment);
+    }
+
+    /// Checks the number and the types of the arguments of a call.
+    /// A function declared without parameters may be called with any arguments.
+    fn check_call(&mut self, node: &Node, func: &Node, args: &[Node]) {
+        let (params, is_variadic) = match func.get_type().decay().base() {
+            Some(Type::Func {
+                params,
+                is_variadic,
+                ..
+            }) => (params.clone(), *is_variadic),
+            _ => unreachable!(),
+        };
+        if !params.is_empty() && args.len() < params.len() {
+            error(
+                node.location,
+                &format!(
+                    "too few arguments to function call, expected {}, have {}",
+                    params.len(),
+                    args.len()
+                ),
+            );
+        }
+        if !params.is_empty() && !is_variadic && args.len() > params.len() {
+            error(
+                node.location,
+                &format!(
+                    "too many arguments to function call, expected {}, have {}",
+                    params.len(),
+                    args.len()
+                ),
+            );
+        }
+        for (i, arg) in args.iter().enumerate() {
+            match params.get(i) {
+                Some(param) => self.check_conversion(arg, &param.ty, Conversion::Argument),
+                None if *arg.get_type() == Type::Void => {
+                    error(arg.location, "argument type void is incomplete")
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Checks that `value` can be converted to `ty` as if by assignment.
+    /// `value` may already be converted, its type before is the one checked.
+    /// Warns if it converts a pointer to a pointer to an incompatible type,
+    /// unless one of them is `void *`.
+    fn check_conversion(&mut self, value: &Node, ty: &Type, conversion: Conversion) {
+        let original = unconverted(value);
+        let from = original.get_type().decay();
+        let valid = match (ty, &from) {
+            (Type::Bool, _) => from.is_scalar(),
+            (_, Type::Ptr(_)) if ty.is_integer() => false,
+            (_, _) if ty.is_integer() => from.is_integer(),
+            (Type::Ptr(_), Type::Ptr(_)) => true,
+            // only a null pointer constant converts to a pointer without a cast
+            (Type::Ptr(_), _) => from.is_integer() && matches!(original.kind, NodeKind::NUM(0)),
+            _ => *ty == from,
+        };
+        if !valid {
+            error(value.location, &conversion.message(original.get_type(), ty));
+        }
+        if let (Type::Ptr(base), Type::Ptr(from_base)) = (ty, &from) {
+            let is_void = **base == Type::Void || **from_base == Type::Void;
+            if !is_void && !compatible(base, from_base) {
+                let message = conversion.pointer_message(&from, ty);
+                self.diagnostics
+                    .warn(Warning::IncompatiblePointerTypes, value.location, &message);
+            }
+        }
+    }
+}
+
+/// Return the operand of an implicit conversion, or `node` if it is not one.
+fn unconverted(node: &Node) -> &Node {
+    match node.kind {
+        NodeKind::CONV(ref operand) => operand,
+        _ => node,
+    }
+}
+
+/// Checks the operands of a binary operation other than `&&` and `||`.
+/// Pointer arithmetic has been built by the parser with the integer operand
+/// scaled, so `p + i` is checked as a pointer plus a `ptrdiff_t`.
+fn check_binop(node: &Node, op: &BinaryOpType, lhs: &Node, rhs: &Node) {
+    let (lhs_ty, rhs_ty) = (lhs.get_type().decay(), rhs.get_type().decay());
+    let valid = match (lhs_ty.base(), rhs_ty.base()) {
+        (None, None) => lhs_ty.is_integer() && rhs_ty.is_integer(),
+        (Some(_), None) => {
+            (rhs_ty.is_integer() && matches!(op, BinaryOpType::Add | BinaryOpType::Sub))
+                || is_pointer_comparison(op, &lhs_ty, &rhs_ty)
+        }
+        (None, Some(_)) => is_pointer_comparison(op, &lhs_ty, &rhs_ty),
+        (Some(lhs_base), Some(rhs_base)) => match op {
+            BinaryOpType::Sub => compatible(lhs_base, rhs_base),
+            _ => is_pointer_comparison(op, &lhs_ty, &rhs_ty),
+        },
+    };
+    if !valid {
+        error(
+            node.location,
+            &format!(
+                "invalid operands to binary {} ({} and {})",
+                op.symbol(),
+                lhs.get_type(),
+                rhs.get_type()
+            ),
+        );
+    }
+}
+
+/// Return true if `op` compares a pointer of type `lhs` with `rhs`, which is
+/// another pointer or an integer, gcc accepting the latter with a warning.
+fn is_pointer_comparison(op: &BinaryOpType, lhs: &Type, rhs: &Type) -> bool {
+    op.is_comparison() && lhs.is_scalar() && rhs.is_scalar()
+}
+
+/// Checks the operand of a unary operation other than `!`.
+fn check_unop(node: &Node, op: &UnaryOpType, operand: &Node) {
+    match op {
+        UnaryOpType::Neg | UnaryOpType::BitNot if !operand.get_type().is_integer() => error(
+            node.location,
+            &format!(
+                "invalid argument type {} to unary {}",
+                operand.get_type(),
+                op.symbol()
+            ),
+        ),
+        UnaryOpType::Addr if operand.is_bit_field() => {
+            error(node.location, "address of bit-field requested")
+        }
+        UnaryOpType::Addr if !operand.is_lvalue() => error(
+            node.location,
+            &format!(
+                "cannot take the address of an rvalue of type {}",
+                operand.get_type()
+            ),
+        ),
+        _ => {}
+    }
+}
+
+/// Checks an explicit cast of `operand` to the type of `node`:
+/// a scalar to a scalar, or anything to `void` to discard it.
+fn check_cast(node: &Node, operand: &Node) {
+    let ty = node.get_type();
+    if *ty != Type::Void && (!ty.is_scalar() || !operand.get_type().decay().is_scalar()) {
+        error(
+            node.location,
+            &format!("cannot cast {} to {}", operand.get_type(), ty),
+        );
+    }
+}
+
+/// Return true if the types are compatible: the same type, except that
+/// an array of unknown size is compatible with an array of any size.
+fn compatible(lhs: &Type, rhs: &Type) -> bool {
+    match (lhs, rhs) {
+        (Type::Ptr(lhs), Type::Ptr(rhs)) => compatible(lhs, rhs),
+        (Type::Array(lhs, lhs_len), Type::Array(rhs, rhs_len)) => {
+            compatible(lhs, rhs) && (*lhs_len < 0 || *rhs_len < 0 || lhs_len == rhs_len)
+        }
+        _ => lhs == rhs,
+    }
+}
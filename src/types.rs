@@ -0,0 +1,717 @@
+use std::cell::{Ref, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use log::debug;
+
+use crate::scanner::Location;
+
+/// Enum to represent the C types known to the compiler.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// `void`, an incomplete type without values
+    Void,
+    /// `_Bool`
+    Bool,
+    /// `char` and `signed char`, `char` is signed on x86-64
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    /// `long`, the type of `ptrdiff_t`
+    Long,
+    /// `unsigned long`, the type of `size_t`
+    ULong,
+    LongLong,
+    ULongLong,
+    /// pointer to the inner type
+    Ptr(Box<Type>),
+    /// array of `len` elements of the inner type,
+    /// `len` is negative for an array of unknown size like `int a[]`
+    Array(Box<Type>, i64),
+    /// a struct or a union
+    Record(RecordRef),
+    /// a function returning `ret`, taking more arguments after `params`
+    /// if it is variadic
+    Func {
+        ret: Box<Type>,
+        params: Vec<Param>,
+        is_variadic: bool,
+    },
+}
+
+/// A named parameter of a function type.
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub ty: Type,
+    /// where the parameter is declared
+    pub location: Location,
+}
+
+/// The names of the parameters don't make function types different.
+impl PartialEq for Param {
+    fn eq(&self, other: &Param) -> bool {
+        self.ty == other.ty
+    }
+}
+
+/// The class of an eightbyte of an argument or a return value in the
+/// System V x86-64 calling convention. Floating point eightbytes would be
+/// SSE and use the vector registers, but there are no floating types,
+/// so every eightbyte of a value of at most 16 bytes is INTEGER.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgClass {
+    /// passed in the next general purpose register
+    Integer,
+    /// passed on the stack, or returned through a hidden pointer to memory
+    /// provided by the caller
+    Memory,
+}
+
+/// Round `n` up to the nearest multiple of `align`.
+pub fn align_to(n: i64, align: i64) -> i64 {
+    (n + align - 1) / align * align
+}
+
+impl Type {
+    /// Function to create a pointer type pointing to `base`.
+    pub fn pointer_to(base: Type) -> Type {
+        debug!("Creating Type::Ptr to {:?}", base);
+        Type::Ptr(Box::new(base))
+    }
+
+    /// Function to create an array type of `len` elements.
+    pub fn array_of(base: Type, len: i64) -> Type {
+        debug!("Creating Type::Array of {} {:?}", len, base);
+        Type::Array(Box::new(base), len)
+    }
+
+    /// The type used for the difference of two pointers.
+    pub fn ptrdiff_t() -> Type {
+        Type::Long
+    }
+
+    /// The type of the result of `sizeof`.
+    pub fn size_t() -> Type {
+        Type::ULong
+    }
+
+    /// Return the size of the type in bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is incomplete.
+    /// The size of `void` and of a function is 1 like in gcc,
+    /// for the arithmetic on pointers to them.
+    pub fn size(&self) -> i64 {
+        match self {
+            Type::Void | Type::Bool | Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt => 4,
+            Type::Long | Type::ULong | Type::LongLong | Type::ULongLong | Type::Ptr(_) => 8,
+            Type::Array(_, len) if *len < 0 => panic!("size: array of unknown size"),
+            Type::Array(base, len) => base.size() * len,
+            Type::Record(record) => {
+                let record = record.borrow();
+                if !record.complete {
+                    panic!("size: incomplete type {:?}", self);
+                }
+                record.size
+            }
+            Type::Func { .. } => 1,
+        }
+    }
+
+    /// Return true if the size of the type is known: it is not an array of
+    /// unknown size nor a struct or union whose members are not declared.
+    pub fn is_complete(&self) -> bool {
+        match self {
+            Type::Array(_, len) if *len < 0 => false,
+            Type::Array(base, _) => base.is_complete(),
+            Type::Record(record) => record.borrow().complete,
+            _ => true,
+        }
+    }
+
+    /// Return the alignment of the type in bytes.
+    pub fn align(&self) -> i64 {
+        match self {
+            Type::Array(base, _) => base.align(),
+            Type::Record(record) => record.borrow().align,
+            Type::Func { .. } => 1,
+            _ => self.size(),
+        }
+    }
+
+    /// Return true if the type is an integer type.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Type::Bool
+                | Type::Char
+                | Type::UChar
+                | Type::Short
+                | Type::UShort
+                | Type::Int
+                | Type::UInt
+                | Type::Long
+                | Type::ULong
+                | Type::LongLong
+                | Type::ULongLong
+        )
+    }
+
+    /// Return true if the type is an unsigned integer type.
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Type::Bool | Type::UChar | Type::UShort | Type::UInt | Type::ULong | Type::ULongLong
+        )
+    }
+
+    /// Return true if the type is an integer or a pointer.
+    pub fn is_scalar(&self) -> bool {
+        self.is_integer() || matches!(self, Type::Ptr(_))
+    }
+
+    /// Return the integer conversion rank of an integer type.
+    fn rank(&self) -> u8 {
+        match self {
+            Type::Bool => 0,
+            Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt => 3,
+            Type::Long | Type::ULong => 4,
+            Type::LongLong | Type::ULongLong => 5,
+            _ => panic!("rank: {:?} is not an integer type", self),
+        }
+    }
+
+    /// Return the unsigned type corresponding to an integer type.
+    fn to_unsigned(&self) -> Type {
+        match self {
+            Type::Char => Type::UChar,
+            Type::Short => Type::UShort,
+            Type::Int => Type::UInt,
+            Type::Long => Type::ULong,
+            Type::LongLong => Type::ULongLong,
+            _ => self.clone(),
+        }
+    }
+
+    /// Return the type after the integer promotions:
+    /// integer types ranking below `int` become `int`.
+    pub fn promote(&self) -> Type {
+        if self.is_integer() && self.rank() < Type::Int.rank() {
+            Type::Int
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Return the common type of the usual arithmetic conversions
+    /// of two integer types.
+    pub fn usual_arith(lhs: &Type, rhs: &Type) -> Type {
+        let (lhs, rhs) = (lhs.promote(), rhs.promote());
+        if lhs == rhs {
+            return lhs;
+        }
+        if lhs.is_unsigned() == rhs.is_unsigned() {
+            return if lhs.rank() > rhs.rank() { lhs } else { rhs };
+        }
+        let (unsigned, signed) = if lhs.is_unsigned() {
+            (lhs, rhs)
+        } else {
+            (rhs, lhs)
+        };
+        if unsigned.rank() >= signed.rank() {
+            unsigned
+        } else if signed.size() > unsigned.size() {
+            // the signed type can represent all values of the unsigned one
+            signed
+        } else {
+            signed.to_unsigned()
+        }
+    }
+
+    /// Function to create the `va_list` type of the System V ABI, an array of
+    /// one struct holding the state of the traversal of variadic arguments:
+    /// the offsets of the next general purpose and floating point registers in
+    /// the register save area, the next argument passed on the stack and
+    /// the register save area itself.
+    pub fn va_list() -> Type {
+        let record = RecordRef::new(false, Some("__va_list_tag".to_owned()));
+        let member = |name: &str, ty: Type| MemberDecl {
+            name: Some(name.to_owned()),
+            ty,
+            width: None,
+        };
+        record.layout(vec![
+            member("gp_offset", Type::UInt),
+            member("fp_offset", Type::UInt),
+            member("overflow_arg_area", Type::pointer_to(Type::Void)),
+            member("reg_save_area", Type::pointer_to(Type::Void)),
+        ]);
+        Type::array_of(Type::Record(record), 1)
+    }
+
+    /// Classifies a value of this type for a call: the class of each of
+    /// its eightbytes, or a single Memory for a struct or union of more
+    /// than 16 bytes, which is passed in memory as a whole.
+    pub fn classify(&self) -> Vec<ArgClass> {
+        match self.size() {
+            size if size > 16 => vec![ArgClass::Memory],
+            size => vec![ArgClass::Integer; ((size + 7) / 8) as usize],
+        }
+    }
+
+    /// Return the pointee type if the type is a pointer,
+    /// or the element type if it is an array, otherwise None.
+    pub fn base(&self) -> Option<&Type> {
+        match self {
+            Type::Ptr(base) | Type::Array(base, _) => Some(base),
+            _ => None,
+        }
+    }
+
+    /// Return the type a value of this type has in expression context:
+    /// arrays decay to a pointer to their first element
+    /// and functions to a pointer to the function.
+    pub fn decay(&self) -> Type {
+        match self {
+            Type::Array(base, _) => Type::Ptr(base.clone()),
+            Type::Func { .. } => Type::pointer_to(self.clone()),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Prints the type the way it is spelled in C, e.g. `unsigned long`,
+/// `char *`, `int [3]` or `int (*)(int, ...)`.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.spell(String::new()))
+    }
+}
+
+impl Type {
+    /// Return the spelling of a declaration of `declarator` with this type,
+    /// the declarator being empty for an abstract one.
+    fn spell(&self, declarator: String) -> String {
+        let name = match self {
+            Type::Void => "void",
+            Type::Bool => "_Bool",
+            Type::Char => "char",
+            Type::UChar => "unsigned char",
+            Type::Short => "short",
+            Type::UShort => "unsigned short",
+            Type::Int => "int",
+            Type::UInt => "unsigned int",
+            Type::Long => "long",
+            Type::ULong => "unsigned long",
+            Type::LongLong => "long long",
+            Type::ULongLong => "unsigned long long",
+            Type::Ptr(base) => {
+                return match **base {
+                    // the pointer binds tighter than the suffix of the base
+                    Type::Array(..) | Type::Func { .. } => base.spell(format!("(*{})", declarator)),
+                    _ => base.spell(format!("*{}", declarator)),
+                };
+            }
+            Type::Array(base, len) if *len < 0 => return base.spell(format!("{}[]", declarator)),
+            Type::Array(base, len) => return base.spell(format!("{}[{}]", declarator, len)),
+            Type::Func {
+                ret,
+                params,
+                is_variadic,
+            } => {
+                let mut params = params
+                    .iter()
+                    .map(|param| param.ty.to_string())
+                    .collect::<Vec<_>>();
+                if *is_variadic {
+                    params.push("...".to_owned());
+                }
+                return ret.spell(format!("{}({})", declarator, params.join(", ")));
+            }
+            Type::Record(record) => {
+                return format!("{:?} {}", record, declarator).trim_end().to_owned()
+            }
+        };
+        match declarator.is_empty() {
+            true => name.to_owned(),
+            false => format!("{} {}", name, declarator),
+        }
+    }
+}
+
+/// A member of a struct or union after layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    /// byte offset of the member, or of the storage unit holding a bit-field
+    pub offset: i64,
+    pub bit_field: Option<BitField>,
+}
+
+/// The position of a bit-field inside its storage unit,
+/// which has the size of the declared type of the bit-field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitField {
+    pub bit_offset: i64,
+    pub width: i64,
+}
+
+/// A member as written in the declaration, the input of the layout.
+#[derive(Debug)]
+pub struct MemberDecl {
+    pub name: Option<String>,
+    pub ty: Type,
+    /// the width of a bit-field
+    pub width: Option<i64>,
+}
+
+/// A struct or union type.
+#[derive(Debug)]
+pub struct Record {
+    pub is_union: bool,
+    pub tag: Option<String>,
+    pub members: Vec<Member>,
+    pub size: i64,
+    pub align: i64,
+    /// false until the member list has been seen
+    pub complete: bool,
+}
+
+impl Record {
+    /// Lays out the members the way gcc does on x86-64 (System V ABI):
+    /// every member is aligned to its type, a bit-field is packed right after
+    /// the previous one unless it would straddle a storage unit of its type,
+    /// a zero width bit-field aligns the next member to its type,
+    /// and unnamed bit-fields do not contribute to the alignment of the record.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bit-field is not an integer or is wider than its type.
+    pub fn layout(&mut self, decls: Vec<MemberDecl>) {
+        // in a struct the next free bit, in a union the largest member in bits
+        let mut bits = 0;
+        let mut align = 1;
+        let mut members = Vec::new();
+        for decl in decls {
+            let ty_bits = decl.ty.size() * 8;
+            let ty_align = decl.ty.align();
+            let (offset, bit_field, end) = match decl.width {
+                None => {
+                    let offset = if self.is_union {
+                        0
+                    } else {
+                        align_to(align_to(bits, 8) / 8, ty_align)
+                    };
+                    (offset, None, offset * 8 + ty_bits)
+                }
+                Some(width) => {
+                    if !decl.ty.is_integer() || width < 0 || width > ty_bits {
+                        panic!("layout: invalid bit-field {:?}", decl);
+                    }
+                    if width == 0 {
+                        if decl.name.is_some() {
+                            panic!("layout: named zero width bit-field {:?}", decl);
+                        }
+                        if !self.is_union {
+                            bits = align_to(bits, ty_align * 8);
+                        }
+                        continue;
+                    }
+                    let mut start = if self.is_union { 0 } else { bits };
+                    if start / ty_bits != (start + width - 1) / ty_bits {
+                        start = align_to(start, ty_bits);
+                    }
+                    let unit = start / ty_bits * ty_bits;
+                    let bit_field = BitField {
+                        bit_offset: start - unit,
+                        width,
+                    };
+                    (unit / 8, Some(bit_field), start + width)
+                }
+            };
+            bits = if self.is_union { bits.max(end) } else { end };
+            if let Some(name) = decl.name {
+                align = align.max(ty_align);
+                members.push(Member {
+                    name,
+                    ty: decl.ty,
+                    offset,
+                    bit_field,
+                });
+            }
+        }
+        self.size = align_to(align_to(bits, 8) / 8, align);
+        self.align = align;
+        self.members = members;
+        self.complete = true;
+    }
+
+    /// Return the member called `name`.
+    pub fn find_member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+/// A shared handle to a struct or union, so that an incomplete type
+/// becomes complete everywhere once its members are declared.
+/// Two handles are the same type only if they refer to the same record.
+#[derive(Clone)]
+pub struct RecordRef(Rc<RefCell<Record>>);
+
+impl RecordRef {
+    /// Function to create an incomplete struct or union.
+    pub fn new(is_union: bool, tag: Option<String>) -> RecordRef {
+        RecordRef(Rc::new(RefCell::new(Record {
+            is_union,
+            tag,
+            members: Vec::new(),
+            size: 0,
+            align: 1,
+            complete: false,
+        })))
+    }
+
+    /// Borrow the record.
+    pub fn borrow(&self) -> Ref<'_, Record> {
+        self.0.borrow()
+    }
+
+    /// Complete the record with its members.
+    pub fn layout(&self, decls: Vec<MemberDecl>) {
+        self.0.borrow_mut().layout(decls);
+        // the members may refer to the record, so it must not be borrowed mutably here
+        let record = self.borrow();
+        debug!(
+            "layout of {:?}: size {} align {} members {:?}",
+            self, record.size, record.align, record.members
+        );
+    }
+}
+
+impl PartialEq for RecordRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+// A record may point to itself through its members, so only its name is printed.
+impl fmt::Debug for RecordRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = self.0.borrow();
+        let kind = if record.is_union { "union" } else { "struct" };
+        match record.tag {
+            Some(ref tag) => write!(f, "{} {}", kind, tag),
+            None => write!(f, "{} <anonymous>", kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, ty: Type, width: Option<i64>) -> MemberDecl {
+        MemberDecl {
+            name: Some(name.to_owned()),
+            ty,
+            width,
+        }
+    }
+
+    fn unnamed(ty: Type, width: i64) -> MemberDecl {
+        MemberDecl {
+            name: None,
+            ty,
+            width: Some(width),
+        }
+    }
+
+    fn layout(is_union: bool, decls: Vec<MemberDecl>) -> RecordRef {
+        let record = RecordRef::new(is_union, None);
+        record.layout(decls);
+        record
+    }
+
+    #[test]
+    fn test_va_list_layout() {
+        let va_list = Type::va_list();
+        assert_eq!((va_list.size(), va_list.align()), (24, 8));
+        let record = match va_list.base() {
+            Some(Type::Record(record)) => record.clone(),
+            ty => panic!("va_list element is {:?}", ty),
+        };
+        let offsets: Vec<i64> = record.borrow().members.iter().map(|m| m.offset).collect();
+        assert_eq!(offsets, vec![0, 4, 8, 16]);
+    }
+
+    #[test]
+    fn test_display() {
+        let params = vec![Param {
+            name: "x".to_owned(),
+            ty: Type::Int,
+            location: Location::default(),
+        }];
+        let func = Type::Func {
+            ret: Box::new(Type::pointer_to(Type::Char)),
+            params,
+            is_variadic: true,
+        };
+        assert_eq!(Type::ULong.to_string(), "unsigned long");
+        assert_eq!(Type::pointer_to(Type::Int).to_string(), "int *");
+        assert_eq!(Type::array_of(Type::Int, 3).to_string(), "int [3]");
+        assert_eq!(
+            Type::pointer_to(Type::array_of(Type::Char, -1)).to_string(),
+            "char (*)[]"
+        );
+        assert_eq!(func.to_string(), "char *(int, ...)");
+        assert_eq!(Type::pointer_to(func).to_string(), "char *(*)(int, ...)");
+        let record = RecordRef::new(false, Some("S".to_owned()));
+        assert_eq!(Type::Record(record).to_string(), "struct S");
+    }
+
+    #[test]
+    fn test_classify() {
+        let small = layout(false, vec![member("c", Type::Char, None)]);
+        assert_eq!(Type::Record(small).classify(), vec![ArgClass::Integer]);
+        let pair = layout(
+            false,
+            vec![member("a", Type::Long, None), member("b", Type::Int, None)],
+        );
+        assert_eq!(
+            Type::Record(pair).classify(),
+            vec![ArgClass::Integer, ArgClass::Integer]
+        );
+        let large = layout(
+            false,
+            vec![member("a", Type::array_of(Type::Char, 17), None)],
+        );
+        assert_eq!(Type::Record(large).classify(), vec![ArgClass::Memory]);
+        let union = layout(
+            true,
+            vec![
+                member("a", Type::array_of(Type::Int, 3), None),
+                member("b", Type::Long, None),
+            ],
+        );
+        assert_eq!(
+            Type::Record(union).classify(),
+            vec![ArgClass::Integer, ArgClass::Integer]
+        );
+    }
+
+    #[test]
+    fn test_usual_arithmetic_conversions() {
+        assert_eq!(Type::usual_arith(&Type::Char, &Type::Short), Type::Int);
+        assert_eq!(Type::usual_arith(&Type::Bool, &Type::UChar), Type::Int);
+        assert_eq!(Type::usual_arith(&Type::Int, &Type::UInt), Type::UInt);
+        assert_eq!(Type::usual_arith(&Type::UInt, &Type::Long), Type::Long);
+        assert_eq!(Type::usual_arith(&Type::Long, &Type::ULong), Type::ULong);
+        assert_eq!(
+            Type::usual_arith(&Type::LongLong, &Type::ULong),
+            Type::ULongLong
+        );
+        assert_eq!(
+            Type::usual_arith(&Type::ULongLong, &Type::Long),
+            Type::ULongLong
+        );
+        assert_eq!(
+            Type::usual_arith(&Type::Int, &Type::LongLong),
+            Type::LongLong
+        );
+    }
+
+    // The expected values are what gcc reports on x86-64.
+
+    #[test]
+    fn test_layout_padding() {
+        let record = layout(
+            false,
+            vec![
+                member("a", Type::Int, None),
+                member("b", Type::Long, None),
+                member("c", Type::Int, None),
+            ],
+        );
+        let record = record.borrow();
+        assert_eq!((record.size, record.align), (24, 8));
+        assert_eq!(record.find_member("b").unwrap().offset, 8);
+        assert_eq!(record.find_member("c").unwrap().offset, 16);
+    }
+
+    #[test]
+    fn test_layout_bit_fields() {
+        // struct { int a:3; long b:40; int c; }
+        let record = layout(
+            false,
+            vec![
+                member("a", Type::Int, Some(3)),
+                member("b", Type::Long, Some(40)),
+                member("c", Type::Int, None),
+            ],
+        );
+        let record = record.borrow();
+        assert_eq!((record.size, record.align), (16, 8));
+        let b = record.find_member("b").unwrap();
+        assert_eq!((b.offset, b.bit_field.unwrap().bit_offset), (0, 3));
+        assert_eq!(record.find_member("c").unwrap().offset, 8);
+
+        // struct { long a:60; int b:5; }, b would straddle an int
+        let record = layout(
+            false,
+            vec![
+                member("a", Type::Long, Some(60)),
+                member("b", Type::Int, Some(5)),
+            ],
+        );
+        let record = record.borrow();
+        assert_eq!((record.size, record.align), (16, 8));
+        let b = record.find_member("b").unwrap();
+        assert_eq!((b.offset, b.bit_field.unwrap().bit_offset), (8, 0));
+    }
+
+    #[test]
+    fn test_layout_unnamed_bit_fields() {
+        // struct { int a:1; long :0; int b:1; }
+        let record = layout(
+            false,
+            vec![
+                member("a", Type::Int, Some(1)),
+                unnamed(Type::Long, 0),
+                member("b", Type::Int, Some(1)),
+            ],
+        );
+        let record = record.borrow();
+        assert_eq!((record.size, record.align), (12, 4));
+        assert_eq!(record.find_member("b").unwrap().offset, 8);
+
+        // struct { int :7; }
+        let record = layout(false, vec![unnamed(Type::Int, 7)]);
+        assert_eq!((record.borrow().size, record.borrow().align), (1, 1));
+    }
+
+    #[test]
+    fn test_layout_union() {
+        // union { int a:3; long b:40; int c; }
+        let record = layout(
+            true,
+            vec![
+                member("a", Type::Int, Some(3)),
+                member("b", Type::Long, Some(40)),
+                member("c", Type::Int, None),
+            ],
+        );
+        let record = record.borrow();
+        assert_eq!((record.size, record.align), (8, 8));
+        assert!(record.members.iter().all(|member| member.offset == 0));
+    }
+}
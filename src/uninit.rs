@@ -0,0 +1,374 @@
+// The definite assignment analysis finds the local variables which may be
+// read before they are assigned a value. It follows the statements of each
+// function like the flow analysis, carrying which variables are assigned:
+// on every path to the point, or on some path only. Where paths join, at the
+// end of an `if`, at a label or at the start of a loop, the first set is the
+// intersection of the ones of the paths and the second one their union.
+// A loop is followed until the sets at its start settle, and the whole body
+// until the sets at the labels settle, so the last pass has seen every path.
+//
+// Reading a variable assigned on no path is an error in the program, it is
+// uninitialized; reading one assigned on some paths only may be an error,
+// depending on the paths the conditions take. Only the scalar variables
+// declared in the function are followed. Taking the address of one is taken
+// as assigning it, as what is done through the pointer is not followed.
+// Each variable is reported once, at the first use found.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use log::debug;
+
+use crate::ast::{BinaryOpType, Function, Node, NodeKind, Program, UnaryOpType};
+use crate::diagnostics::{Diagnostics, Warning};
+use crate::scanner::Location;
+
+/// Analyzes every function of the program, reporting the warnings to `diagnostics`.
+pub fn check_program(program: &Program, diagnostics: &mut Diagnostics) {
+    for function in &program.functions {
+        check_function(function, diagnostics);
+    }
+}
+
+/// Analyzes the body of a function.
+fn check_function(function: &Function, diagnostics: &mut Diagnostics) {
+    debug!("definite assignment of function: {}", function.name);
+    let tracked = function
+        .locals
+        .iter()
+        .enumerate()
+        .map(|(id, var)| {
+            var.ty.is_scalar() && !var.name.starts_with('(') && !function.params.contains(&id)
+        })
+        .collect::<Vec<_>>();
+    let start = Assigned {
+        all: tracked.iter().map(|tracked| !tracked).collect(),
+        some: tracked.iter().map(|tracked| !tracked).collect(),
+    };
+    let mut analysis = Analysis {
+        labels: HashMap::new(),
+        breaks: Vec::new(),
+        continues: Vec::new(),
+        switches: Vec::new(),
+        uses: BTreeMap::new(),
+    };
+    loop {
+        let labels = analysis.labels.clone();
+        analysis.uses.clear();
+        analysis.stmt(&function.body, Some(start.clone()));
+        if analysis.labels == labels {
+            break;
+        }
+    }
+    let mut reported = HashSet::new();
+    for (location, (id, assigned)) in analysis.uses {
+        if !reported.insert(id) {
+            continue;
+        }
+        let var = &function.locals[id];
+        let (warning, what) = match assigned {
+            false => (Warning::Uninitialized, "is"),
+            true => (Warning::MaybeUninitialized, "may be"),
+        };
+        let message = format!(
+            "variable {} {} uninitialized when used here, declared at {}",
+            var.name, what, var.location
+        );
+        diagnostics.warn(warning, location, &message);
+    }
+}
+
+/// The variables assigned at a point of a function, by their index
+/// into its locals.
+#[derive(Debug, Clone, PartialEq)]
+struct Assigned {
+    /// assigned on every path to the point
+    all: Vec<bool>,
+    /// assigned on some path to the point
+    some: Vec<bool>,
+}
+
+impl Assigned {
+    /// Return the variables assigned where the paths to `self` and `other` join
+    fn join(&self, other: &Assigned) -> Assigned {
+        let zip = |lhs: &[bool], rhs: &[bool], f: fn(bool, bool) -> bool| {
+            lhs.iter().zip(rhs).map(|(&l, &r)| f(l, r)).collect()
+        };
+        Assigned {
+            all: zip(&self.all, &other.all, |l, r| l && r),
+            some: zip(&self.some, &other.some, |l, r| l || r),
+        }
+    }
+
+    /// Records that the variable `id` is assigned
+    fn assign(&mut self, id: usize) {
+        self.all[id] = true;
+        self.some[id] = true;
+    }
+}
+
+/// Return the variables assigned where the paths to `lhs` and `rhs` join,
+/// None for a point no path reaches.
+fn join(lhs: Option<Assigned>, rhs: Option<Assigned>) -> Option<Assigned> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(lhs.join(&rhs)),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+/// The state of the analysis of a function.
+struct Analysis {
+    /// the variables assigned at each label jumped to by a `goto`
+    labels: HashMap<String, Assigned>,
+    /// for each enclosing loop or `switch`, innermost last,
+    /// the variables assigned where a `break` leaves it
+    breaks: Vec<Option<Assigned>>,
+    /// for each enclosing loop, the variables assigned where a `continue` goes on
+    continues: Vec<Option<Assigned>>,
+    /// for each enclosing `switch`, the variables assigned at its labels
+    switches: Vec<Option<Assigned>>,
+    /// the reads of variables not assigned on every path to them,
+    /// and true if they are assigned on some path
+    uses: BTreeMap<Location, (usize, bool)>,
+}
+
+impl Analysis {
+    /// Follows the statement `node` with the variables `state` assigned
+    /// before it, None if it is not reached from the statement before.
+    /// Return the variables assigned after it.
+    fn stmt(&mut self, node: &Node, state: Option<Assigned>) -> Option<Assigned> {
+        match node.kind {
+            NodeKind::STMT(ref expr) => state.map(|mut state| {
+                self.expr(expr, &mut state);
+                state
+            }),
+            NodeKind::MEMZERO(_) => state.map(|mut state| {
+                self.expr(node, &mut state);
+                state
+            }),
+            NodeKind::RETURN(ref value) => {
+                if let (Some(value), Some(mut state)) = (value, state) {
+                    self.expr(value, &mut state);
+                }
+                None
+            }
+            NodeKind::BLOCK(ref stmts) => stmts
+                .iter()
+                .fold(state, |state, stmt| self.stmt(stmt, state)),
+            NodeKind::IF {
+                ref cond,
+                ref then,
+                ref els,
+            } => {
+                let (true_state, false_state) = self.cond(cond, state);
+                let then = self.stmt(then, true_state);
+                let els = match els {
+                    Some(els) => self.stmt(els, false_state),
+                    None => false_state,
+                };
+                join(then, els)
+            }
+            NodeKind::FOR {
+                ref init,
+                ref cond,
+                ref inc,
+                ref body,
+            } => {
+                let entry = match init {
+                    Some(init) => self.stmt(init, state),
+                    None => state,
+                };
+                let mut start = entry.clone();
+                loop {
+                    // a loop without condition is only left by `break`
+                    let (true_state, false_state) = match cond {
+                        Some(cond) => self.cond(cond, start.clone()),
+                        None => (start.clone(), None),
+                    };
+                    self.breaks.push(None);
+                    self.continues.push(None);
+                    let end = self.stmt(body, true_state);
+                    let end = join(end, self.continues.pop().unwrap());
+                    let broken = self.breaks.pop().unwrap();
+                    let end = end.map(|mut state| {
+                        if let Some(inc) = inc {
+                            self.expr(inc, &mut state);
+                        }
+                        state
+                    });
+                    let next = join(entry.clone(), end);
+                    if next == start {
+                        return join(false_state, broken);
+                    }
+                    start = next;
+                }
+            }
+            // the body is entered at its labels only
+            NodeKind::SWITCH {
+                ref cond,
+                ref body,
+                has_default,
+                ..
+            } => {
+                let state = state.map(|mut state| {
+                    self.expr(cond, &mut state);
+                    state
+                });
+                self.breaks.push(None);
+                self.switches.push(state.clone());
+                let end = self.stmt(body, None);
+                self.switches.pop();
+                let broken = self.breaks.pop().unwrap();
+                let skipped = if has_default { None } else { state };
+                join(join(end, broken), skipped)
+            }
+            NodeKind::CASE { ref body, .. } => {
+                let entry = join(state, self.switches.last().cloned().flatten());
+                self.stmt(body, entry)
+            }
+            NodeKind::LABEL { ref name, ref body } => {
+                let entry = join(state, self.labels.get(name).cloned());
+                self.stmt(body, entry)
+            }
+            NodeKind::BREAK => {
+                if let Some(broken) = self.breaks.last_mut() {
+                    *broken = join(broken.take(), state);
+                }
+                None
+            }
+            NodeKind::CONTINUE => {
+                if let Some(continued) = self.continues.last_mut() {
+                    *continued = join(continued.take(), state);
+                }
+                None
+            }
+            NodeKind::GOTO(ref name) => {
+                if let Some(state) = state {
+                    let label = join(self.labels.remove(name), Some(state)).unwrap();
+                    self.labels.insert(name.clone(), label);
+                }
+                None
+            }
+            _ => state,
+        }
+    }
+
+    /// Follows the condition `node` evaluated with the variables `state`
+    /// assigned, None if it is not reached.
+    /// Return the variables assigned when it is true and when it is false,
+    /// None for an outcome which cannot happen: the right operand of `&&`
+    /// is always evaluated when it is true, a constant is never false.
+    fn cond(
+        &mut self,
+        node: &Node,
+        state: Option<Assigned>,
+    ) -> (Option<Assigned>, Option<Assigned>) {
+        let Some(mut state) = state else {
+            return (None, None);
+        };
+        match node.kind {
+            NodeKind::NUM(value) => match value {
+                0 => (None, Some(state)),
+                _ => (Some(state), None),
+            },
+            NodeKind::CAST(ref operand) | NodeKind::CONV(ref operand) => {
+                self.cond(operand, Some(state))
+            }
+            NodeKind::UNOP {
+                op: UnaryOpType::Not,
+                ref operand,
+            } => {
+                let (true_state, false_state) = self.cond(operand, Some(state));
+                (false_state, true_state)
+            }
+            NodeKind::BINOP {
+                op: BinaryOpType::LogAnd,
+                ref lhs,
+                ref rhs,
+            } => {
+                let (lhs_true, lhs_false) = self.cond(lhs, Some(state));
+                let (rhs_true, rhs_false) = self.cond(rhs, lhs_true);
+                (rhs_true, join(lhs_false, rhs_false))
+            }
+            NodeKind::BINOP {
+                op: BinaryOpType::LogOr,
+                ref lhs,
+                ref rhs,
+            } => {
+                let (lhs_true, lhs_false) = self.cond(lhs, Some(state));
+                let (rhs_true, rhs_false) = self.cond(rhs, lhs_false);
+                (join(lhs_true, rhs_true), rhs_false)
+            }
+            _ => {
+                self.expr(node, &mut state);
+                (Some(state.clone()), Some(state))
+            }
+        }
+    }
+
+    /// Follows the expression `node` evaluated with the variables `state`
+    /// assigned, recording the reads of the unassigned ones.
+    fn expr(&mut self, node: &Node, state: &mut Assigned) {
+        match node.kind {
+            NodeKind::VAR(id) if !state.all[id] => {
+                self.uses.insert(node.location, (id, state.some[id]));
+            }
+            NodeKind::ASSIGN { ref lhs, ref rhs } => {
+                self.expr(rhs, state);
+                self.lvalue(lhs, state);
+            }
+            NodeKind::MEMZERO(ref var) => self.lvalue(var, state),
+            NodeKind::UNOP {
+                op: UnaryOpType::Addr,
+                ref operand,
+            } => self.lvalue(operand, state),
+            NodeKind::UNOP { ref operand, .. }
+            | NodeKind::CAST(ref operand)
+            | NodeKind::CONV(ref operand)
+            | NodeKind::VASTART(ref operand)
+            | NodeKind::VAARG(ref operand) => self.expr(operand, state),
+            // the right operand is evaluated on some paths only
+            NodeKind::BINOP {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } if op.is_logical() => {
+                self.expr(lhs, state);
+                let mut evaluated = state.clone();
+                self.expr(rhs, &mut evaluated);
+                *state = state.join(&evaluated);
+            }
+            NodeKind::BINOP {
+                ref lhs, ref rhs, ..
+            } => {
+                self.expr(lhs, state);
+                self.expr(rhs, state);
+            }
+            NodeKind::MEMBER { ref base, .. } => self.expr(base, state),
+            NodeKind::CALL {
+                ref func, ref args, ..
+            } => {
+                self.expr(func, state);
+                for arg in args {
+                    self.expr(arg, state);
+                }
+            }
+            NodeKind::LITERAL { ref init, ref var } => {
+                if let Some(assigned) = self.stmt(init, Some(state.clone())) {
+                    *state = assigned;
+                }
+                self.expr(var, state);
+            }
+            _ => {}
+        }
+    }
+
+    /// Follows the lvalue `node` designating an object which is assigned
+    /// or whose address is taken: a variable is not read, so it is assigned.
+    fn lvalue(&mut self, node: &Node, state: &mut Assigned) {
+        match node.kind {
+            NodeKind::VAR(id) => state.assign(id),
+            NodeKind::MEMBER { ref base, .. } => self.lvalue(base, state),
+            _ => self.expr(node, state),
+        }
+    }
+}
@@ -0,0 +1,503 @@
+// The verifier checks that the IR holds together before a pass reads it:
+//
+//  - the terminators and the phis refer to the blocks of the function and
+//    the instructions to its registers and slots;
+//  - no terminator branches to the entry block bb0, which the function
+//    starts with only once;
+//  - the operands have the types the instructions expect, and the registers
+//    the types of the values assigned to them;
+//  - a register is assigned once and before it is read: an assignment
+//    dominates the instructions reading the register, and the end of the
+//    predecessor a phi takes it from. Out of SSA form, after out-of-ssa, a
+//    register may be assigned by several instructions, the copies replacing
+//    the phis, and only the registers assigned once are checked;
+//  - the phis come first in their block and take one operand from each
+//    predecessor;
+//  - what is returned matches what the function returns.
+//
+// A block always ends in a single terminator, which the IR can't express
+// otherwise. The verifier panics on the first error with the instruction at
+// fault, a broken IR is a bug in the pass which made it.
+
+use std::collections::HashMap;
+
+use log::debug;
+
+use crate::cfg::{Cfg, DomTree};
+use crate::ir::{
+    ArgTy, BinOp, BlockId, Callee, ConvOp, Function, Inst, Module, Operand, Reg, Terminator, Ty,
+};
+use crate::ssa::OUT_OF_SSA;
+
+/// Checks every function of the module, which `after` made.
+/// Panics with the instruction at fault if the IR is broken.
+pub fn verify_module(module: &Module, after: &str) {
+    debug!("verify the IR after {}", after);
+    for function in &module.functions {
+        verify_function(function, after);
+    }
+}
+
+/// Where a register is assigned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Def {
+    /// a parameter, assigned before the entry
+    Param,
+    /// the instruction at this index in the block
+    Inst(BlockId, usize),
+}
+
+/// The state of the verification of a function.
+struct Verifier<'a> {
+    function: &'a Function,
+    /// the pass which made the IR
+    after: &'a str,
+    /// where each register is assigned
+    defs: HashMap<Reg, Vec<Def>>,
+    /// true before out-of-ssa, every register must then be assigned once
+    is_ssa: bool,
+}
+
+/// Checks the function, which `after` made.
+/// Panics with the instruction at fault if the IR is broken.
+pub fn verify_function(function: &Function, after: &str) {
+    let mut verifier = Verifier {
+        function,
+        after,
+        defs: HashMap::new(),
+        is_ssa: after != OUT_OF_SSA,
+    };
+    if function.blocks.is_empty() {
+        verifier.fail(None, "", "the function has no blocks");
+    }
+    verifier.check_references();
+    let cfg = Cfg::new(function);
+    if let Some(&pred) = cfg.preds[0].first() {
+        let len = function.blocks[pred.0].insts.len();
+        verifier.fail_inst(pred, len, "the entry block bb0 can't be branched to");
+    }
+    let dom = DomTree::new(&cfg);
+    for (i, param) in function.params.iter().enumerate() {
+        let ty = match param.ty {
+            ArgTy::Scalar(ty) => ty,
+            ArgTy::Byval(_) => Ty::Ptr,
+        };
+        if function.regs[param.reg.0] != ty {
+            let message = format!(
+                "parameter {} {} has type {}, not {}",
+                i, param.reg, function.regs[param.reg.0], ty
+            );
+            verifier.fail(None, "", &message);
+        }
+        verifier.defs.entry(param.reg).or_default().push(Def::Param);
+    }
+    for (b, block) in function.blocks.iter().enumerate() {
+        for (i, inst) in block.insts.iter().enumerate() {
+            if let Some(dst) = inst.dst() {
+                verifier
+                    .defs
+                    .entry(dst)
+                    .or_default()
+                    .push(Def::Inst(BlockId(b), i));
+            }
+        }
+    }
+    if verifier.is_ssa {
+        for (reg, defs) in &verifier.defs {
+            if let [_, Def::Inst(block, i), ..] = defs[..] {
+                let message = format!("{} is assigned more than once in SSA form", reg);
+                verifier.fail_inst(block, i, &message);
+            }
+        }
+    }
+    for (b, block) in function.blocks.iter().enumerate() {
+        let block_id = BlockId(b);
+        let mut phis = true;
+        for (i, inst) in block.insts.iter().enumerate() {
+            match inst {
+                Inst::Phi { .. } if !phis => verifier.fail_inst(
+                    block_id,
+                    i,
+                    "a phi comes after the other instructions of its block",
+                ),
+                Inst::Phi { ref incoming, .. } => {
+                    let mut blocks: Vec<BlockId> =
+                        incoming.iter().map(|&(block, _)| block).collect();
+                    blocks.sort();
+                    if blocks != cfg.preds[b] {
+                        let preds: Vec<String> =
+                            cfg.preds[b].iter().map(BlockId::to_string).collect();
+                        let message = format!(
+                            "the phi doesn't take one operand from each of the predecessors [{}]",
+                            preds.join(", ")
+                        );
+                        verifier.fail_inst(block_id, i, &message);
+                    }
+                    for &(pred, ref operand) in incoming {
+                        // the operand is read at the end of the predecessor
+                        let len = function.blocks[pred.0].insts.len();
+                        verifier.check_defined(&cfg, &dom, operand, pred, len, (block_id, i));
+                    }
+                }
+                _ => {
+                    phis = false;
+                    for operand in inst.operands() {
+                        verifier.check_defined(&cfg, &dom, operand, block_id, i, (block_id, i));
+                    }
+                }
+            }
+            verifier.check_types(block_id, i, inst);
+        }
+        if let Terminator::Branch { ref cond, .. } | Terminator::Ret(Some(ref cond)) = block.term {
+            let len = block.insts.len();
+            verifier.check_defined(&cfg, &dom, cond, block_id, len, (block_id, len));
+        }
+        verifier.check_terminator(block_id);
+    }
+}
+
+impl Verifier<'_> {
+    /// Panics with the instruction at fault, written `what`, in `block`
+    fn fail(&self, block: Option<BlockId>, what: &str, message: &str) -> ! {
+        let place = match block {
+            Some(block) => format!("@{}, {}", self.function.name, block),
+            None => format!("@{}", self.function.name),
+        };
+        match what {
+            "" => panic!("verify: after {}: {}: {}", self.after, place, message),
+            _ => panic!(
+                "verify: after {}: {}: {} in '{}'",
+                self.after, place, message, what
+            ),
+        }
+    }
+
+    /// Panics with the instruction `i` of the block at fault, or its
+    /// terminator if `i` is past its instructions
+    fn fail_inst(&self, block: BlockId, i: usize, message: &str) -> ! {
+        let what = match self.function.blocks[block.0].insts.get(i) {
+            Some(inst) => self.function.display(inst).to_string(),
+            None => self.function.blocks[block.0].term.to_string(),
+        };
+        self.fail(Some(block), &what, message)
+    }
+
+    /// Checks that the blocks, the registers and the slots the function
+    /// refers to exist
+    fn check_references(&self) {
+        let function = self.function;
+        let check_reg = |reg: Reg, block: BlockId, i: usize| {
+            if reg.0 >= function.regs.len() {
+                self.fail_inst(
+                    block,
+                    i,
+                    &format!("{} is not a register of the function", reg),
+                );
+            }
+        };
+        let check_block = |target: BlockId, block: BlockId, i: usize| {
+            if target.0 >= function.blocks.len() {
+                self.fail_inst(
+                    block,
+                    i,
+                    &format!("{} is not a block of the function", target),
+                );
+            }
+        };
+        for param in &function.params {
+            if param.reg.0 >= function.regs.len() {
+                self.fail(
+                    None,
+                    "",
+                    &format!("parameter {} is not a register of the function", param.reg),
+                );
+            }
+        }
+        for (b, block) in function.blocks.iter().enumerate() {
+            let block_id = BlockId(b);
+            for (i, inst) in block.insts.iter().enumerate() {
+                if let Some(dst) = inst.dst() {
+                    check_reg(dst, block_id, i);
+                }
+                for operand in inst.operands() {
+                    if let Operand::Reg(reg) = *operand {
+                        check_reg(reg, block_id, i);
+                    }
+                }
+                match *inst {
+                    Inst::Phi { ref incoming, .. } => {
+                        for &(pred, _) in incoming {
+                            check_block(pred, block_id, i);
+                        }
+                    }
+                    Inst::SlotAddr { slot, .. } if slot.0 >= function.slots.len() => self
+                        .fail_inst(
+                            block_id,
+                            i,
+                            &format!("{} is not a slot of the function", slot),
+                        ),
+                    _ => {}
+                }
+            }
+            let len = block.insts.len();
+            for target in block.term.successors() {
+                check_block(target, block_id, len);
+            }
+            if let Terminator::Branch {
+                cond: Operand::Reg(reg),
+                ..
+            }
+            | Terminator::Ret(Some(Operand::Reg(reg))) = block.term
+            {
+                check_reg(reg, block_id, len);
+            }
+        }
+    }
+
+    /// Checks that the register `operand` is, if it is one, is assigned
+    /// before the instruction `i` of `block`, which may be past its
+    /// instructions for the end of the block. `at` is the instruction reading it.
+    fn check_defined(
+        &self,
+        cfg: &Cfg,
+        dom: &DomTree,
+        operand: &Operand,
+        block: BlockId,
+        i: usize,
+        at: (BlockId, usize),
+    ) {
+        let reg = match *operand {
+            Operand::Reg(reg) => reg,
+            Operand::Const(_) => return,
+        };
+        let defs = match self.defs.get(&reg) {
+            Some(defs) => defs,
+            None => self.fail_inst(at.0, at.1, &format!("{} is never assigned", reg)),
+        };
+        // the registers assigned by several copies are not in SSA form, and
+        // nothing is checked in the blocks which can't be reached
+        if defs.len() > 1 || !cfg.is_reachable(block) {
+            return;
+        }
+        let dominates = match defs[0] {
+            Def::Param => true,
+            Def::Inst(def_block, j) if def_block == block => j < i,
+            Def::Inst(def_block, _) => dom.dominates(def_block, block),
+        };
+        if !dominates {
+            self.fail_inst(
+                at.0,
+                at.1,
+                &format!("{} is not assigned on every path to its use", reg),
+            );
+        }
+    }
+
+    /// Return the type of the operand, None for a constant which is of any type
+    fn operand_ty(&self, operand: &Operand) -> Option<Ty> {
+        match *operand {
+            Operand::Reg(reg) => Some(self.function.regs[reg.0]),
+            Operand::Const(_) => None,
+        }
+    }
+
+    /// Checks that the operand of the instruction `i` of the block is of type `ty`
+    fn expect(&self, block: BlockId, i: usize, operand: &Operand, ty: Ty) {
+        if let Some(actual) = self.operand_ty(operand) {
+            if actual != ty {
+                self.fail_inst(
+                    block,
+                    i,
+                    &format!("operand {} has type {}, not {}", operand, actual, ty),
+                );
+            }
+        }
+    }
+
+    /// Checks that the register the instruction `i` of the block assigns is of type `ty`
+    fn expect_dst(&self, block: BlockId, i: usize, dst: Reg, ty: Ty) {
+        let actual = self.function.regs[dst.0];
+        if actual != ty {
+            self.fail_inst(
+                block,
+                i,
+                &format!("{} has type {}, not {}", dst, actual, ty),
+            );
+        }
+    }
+
+    /// Checks the types of the operands of the instruction `i` of the block
+    /// and of the register it assigns
+    fn check_types(&self, block: BlockId, i: usize, inst: &Inst) {
+        let fail = |message: &str| self.fail_inst(block, i, message);
+        match *inst {
+            Inst::Binary {
+                dst,
+                op,
+                ty,
+                ref lhs,
+                ref rhs,
+            } => {
+                self.expect(block, i, lhs, ty);
+                self.expect(block, i, rhs, ty);
+                if op.is_comparison() {
+                    if self.function.regs[dst.0] == Ty::Ptr {
+                        fail(&format!("{} can't hold the result of a comparison", dst));
+                    }
+                } else {
+                    if ty == Ty::Ptr {
+                        fail(&format!("{} operates on integers", op.name()));
+                    }
+                    self.expect_dst(block, i, dst, ty);
+                }
+                if op == BinOp::Shl || op == BinOp::LShr || op == BinOp::AShr {
+                    if let Operand::Const(shift) = *rhs {
+                        if shift < 0 || shift >= ty.size() * 8 {
+                            fail(&format!("shift by {} out of range for {}", shift, ty));
+                        }
+                    }
+                }
+            }
+            Inst::Unary {
+                dst,
+                ty,
+                ref operand,
+                ..
+            } => {
+                if ty == Ty::Ptr {
+                    fail("the operand of a unary operation is an integer");
+                }
+                self.expect(block, i, operand, ty);
+                self.expect_dst(block, i, dst, ty);
+            }
+            Inst::Conv {
+                dst,
+                op,
+                from,
+                ref operand,
+            } => {
+                self.expect(block, i, operand, from);
+                let to = self.function.regs[dst.0];
+                let valid = match op {
+                    ConvOp::Trunc => from != Ty::Ptr && to != Ty::Ptr && to.size() < from.size(),
+                    ConvOp::SExt | ConvOp::ZExt => {
+                        from != Ty::Ptr && to != Ty::Ptr && to.size() > from.size()
+                    }
+                    ConvOp::PtrToInt => from == Ty::Ptr && to == Ty::I64,
+                    ConvOp::IntToPtr => from == Ty::I64 && to == Ty::Ptr,
+                };
+                if !valid {
+                    fail(&format!("{} can't convert {} to {}", op.name(), from, to));
+                }
+            }
+            Inst::SlotAddr { dst, .. } | Inst::GlobalAddr { dst, .. } => {
+                self.expect_dst(block, i, dst, Ty::Ptr)
+            }
+            Inst::PtrAdd {
+                dst,
+                ref base,
+                ref offset,
+            } => {
+                self.expect(block, i, base, Ty::Ptr);
+                self.expect(block, i, offset, Ty::I64);
+                self.expect_dst(block, i, dst, Ty::Ptr);
+            }
+            Inst::Load { dst, ty, ref addr } => {
+                self.expect(block, i, addr, Ty::Ptr);
+                self.expect_dst(block, i, dst, ty);
+            }
+            Inst::Store {
+                ty,
+                ref value,
+                ref addr,
+            } => {
+                self.expect(block, i, value, ty);
+                self.expect(block, i, addr, Ty::Ptr);
+            }
+            Inst::MemCopy {
+                ref dst,
+                ref src,
+                size,
+            } => {
+                self.expect(block, i, dst, Ty::Ptr);
+                self.expect(block, i, src, Ty::Ptr);
+                if size < 0 {
+                    fail("the size is negative");
+                }
+            }
+            Inst::MemZero { ref addr, size } => {
+                self.expect(block, i, addr, Ty::Ptr);
+                if size < 0 {
+                    fail("the size is negative");
+                }
+            }
+            Inst::Call {
+                dst,
+                ref callee,
+                ref args,
+                ret,
+            } => {
+                if let Callee::Indirect(ref operand) = *callee {
+                    self.expect(block, i, operand, Ty::Ptr);
+                }
+                for (ty, arg) in args {
+                    self.expect(block, i, arg, value_ty(*ty));
+                }
+                match (dst, ret) {
+                    (Some(dst), Some(ret)) => self.expect_dst(block, i, dst, value_ty(ret)),
+                    (Some(_), None) => fail("a call returning void assigns a register"),
+                    (None, _) => {}
+                }
+            }
+            Inst::VaStart { ref ap } => {
+                if !self.function.is_variadic {
+                    fail("va_start in a function which is not variadic");
+                }
+                self.expect(block, i, ap, Ty::Ptr);
+            }
+            Inst::VaArg { dst, ref ap, .. } => {
+                self.expect(block, i, ap, Ty::Ptr);
+                self.expect_dst(block, i, dst, Ty::Ptr);
+            }
+            Inst::Copy { dst, ty, ref src } => {
+                self.expect(block, i, src, ty);
+                self.expect_dst(block, i, dst, ty);
+            }
+            Inst::Phi {
+                dst,
+                ty,
+                ref incoming,
+            } => {
+                for (_, operand) in incoming {
+                    self.expect(block, i, operand, ty);
+                }
+                self.expect_dst(block, i, dst, ty);
+            }
+        }
+    }
+
+    /// Checks the terminator of the block
+    fn check_terminator(&self, block: BlockId) {
+        let i = self.function.blocks[block.0].insts.len();
+        match self.function.blocks[block.0].term {
+            Terminator::Branch { ty, ref cond, .. } => self.expect(block, i, cond, ty),
+            Terminator::Ret(ref value) => match (value, self.function.ret) {
+                (Some(value), Some(ret)) => self.expect(block, i, value, value_ty(ret)),
+                (None, None) => {}
+                (Some(_), None) => self.fail_inst(block, i, "the function returns void"),
+                (None, Some(ret)) => {
+                    self.fail_inst(block, i, &format!("the function returns {}", ret))
+                }
+            },
+            Terminator::Jump(_) | Terminator::Unreachable => {}
+        }
+    }
+}
+
+/// Return the type of the register holding a value passed as `ty`
+fn value_ty(ty: ArgTy) -> Ty {
+    match ty {
+        ArgTy::Scalar(ty) => ty,
+        ArgTy::Byval(_) => Ty::Ptr,
+    }
+}
@@ -5,16 +5,12 @@ if [ $? -ne 0 ]; then
 	exit 1
 fi
 
-# otherwise the tests will concurrently
-# causing errors by writing data to 'tmp.s'
-export RUST_TEST_THREADS=1
-
+# every test program is compiled into its own
+# file under target/tmp, so the tests can run concurrently
 cargo test
 
 # test failed
 if [ $? -ne 0 ]; then
-	# print the tmp.s for debug
-	cat tmp.s
 	echo "cargo test failed"
 	exit 1
 fi
@@ -0,0 +1,110 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::program_test_func;
+
+    #[test]
+    fn test_comparison() {
+        program_test_func("int main() { return 0==1; }", 0);
+        program_test_func("int main() { return 42==42; }", 1);
+        program_test_func("int main() { return 0!=1; }", 1);
+        program_test_func("int main() { return 0<1; }", 1);
+        program_test_func("int main() { return 1<=1; }", 1);
+        program_test_func("int main() { return 2<=1; }", 0);
+        program_test_func("int main() { return 1>0; }", 1);
+        program_test_func("int main() { return 1>=2; }", 0);
+        program_test_func("int main() { return -1<0==1; }", 1);
+    }
+
+    #[test]
+    fn test_control_flow() {
+        program_test_func("int main() { if (0) return 2; return 3; }", 3);
+        program_test_func("int main() { if (2-1) return 2; return 3; }", 2);
+        program_test_func("int main() { if (0) { return 2; } else { return 4; } }", 4);
+        program_test_func(
+            "int main() { int i=0; int j=0; for (i=0; i<=10; i=i+1) j=i+j; return j; }",
+            55,
+        );
+        program_test_func("int main() { for (;;) { return 3; } return 5; }", 3);
+        program_test_func("int main() { int i=0; while (i<10) i=i+1; return i; }", 10);
+    }
+
+    #[test]
+    fn test_array() {
+        program_test_func("int main() { int x[2]; int *y=&x; *y=3; return *x; }", 3);
+        program_test_func(
+            "int main() { int x[3]; *x=3; *(x+1)=4; *(x+2)=5; return *(x+2); }",
+            5,
+        );
+        program_test_func(
+            "int main() { int x[3]; x[0]=3; x[1]=4; x[2]=5; return x[0]+x[1]; }",
+            7,
+        );
+        program_test_func("int main() { int x[3]; x[1]=4; return 1[x]; }", 4);
+        program_test_func(
+            "int main() { int x[3]; int *p=x+1; p[1]=6; return x[2]; }",
+            6,
+        );
+        program_test_func("int main() { int x[4]; return &x[3]-x; }", 3);
+    }
+
+    #[test]
+    fn test_multi_dimensional_array() {
+        program_test_func("int main() { int x[2][3]; int *y=x; *y=0; return **x; }", 0);
+        program_test_func(
+            "int main() { int x[2][3]; int *y=x; *(y+4)=4; return *(*(x+1)+1); }",
+            4,
+        );
+        program_test_func(
+            "int main() { int x[2][3]; int *y=x; *(y+5)=5; return x[1][2]; }",
+            5,
+        );
+        program_test_func(
+            "int main() { int x[2][3]; x[1][0]=7; int *y=x; return y[3]; }",
+            7,
+        );
+        program_test_func(
+            "int main() { int x[2][3][4]; x[1][2][3]=9; return x[1][2][3]; }",
+            9,
+        );
+    }
+
+    #[test]
+    fn test_sizeof() {
+        program_test_func("int main() { int x; return sizeof(x); }", 4);
+        program_test_func("int main() { int x; return sizeof x; }", 4);
+        program_test_func("int main() { long x; return sizeof x; }", 8);
+        program_test_func("int main() { int *x; return sizeof(x); }", 8);
+        program_test_func("int main() { int x[4]; return sizeof(x); }", 16);
+        program_test_func("int main() { int x[3][4]; return sizeof(x); }", 48);
+        program_test_func("int main() { int x[3][4]; return sizeof(*x); }", 16);
+        program_test_func("int main() { int x[3][4]; return sizeof(**x); }", 4);
+        program_test_func("int main() { int x[3][4]; return sizeof(x[0]+1); }", 8);
+        program_test_func("int main() { int x[3]; return sizeof(x+1); }", 8);
+    }
+
+    #[test]
+    fn test_array_algorithm() {
+        // bubble sort, then check the order
+        program_test_func(
+            "int main() { int a[6]; a[0]=5; a[1]=2; a[2]=9; a[3]=1; a[4]=7; a[5]=3; \
+             int i; int j; \
+             for (i=0; i<6; i=i+1) for (j=0; j+1<6-i; j=j+1) \
+               if (a[j]>a[j+1]) { int t=a[j]; a[j]=a[j+1]; a[j+1]=t; } \
+             for (i=0; i+1<6; i=i+1) if (a[i]>a[i+1]) return 0; \
+             return a[0]*100+a[5]-a[2]; }",
+            106,
+        );
+        // matrix product of a 2x2 matrix with itself
+        program_test_func(
+            "int main() { int m[2][2]; int r[2][2]; m[0][0]=1; m[0][1]=2; m[1][0]=3; m[1][1]=4; \
+             int i; int j; int k; \
+             for (i=0; i<2; i=i+1) for (j=0; j<2; j=j+1) { \
+               r[i][j]=0; for (k=0; k<2; k=k+1) r[i][j]=r[i][j]+m[i][k]*m[k][j]; } \
+             return r[1][1]; }",
+            22,
+        );
+    }
+}
@@ -0,0 +1,134 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{error_test_func, program_test_func};
+
+    #[test]
+    fn test_integer_casts() {
+        program_test_func("int main() { return (char)300; }", 44);
+        program_test_func("int main() { return (char)255 == -1; }", 1);
+        program_test_func("int main() { return (unsigned char)-1; }", 255);
+        program_test_func("int main() { return (short)65537; }", 1);
+        program_test_func("int main() { return (unsigned short)-1 == 65535; }", 1);
+        program_test_func("int main() { return (int)4294967297; }", 1);
+        program_test_func("int main() { return (long)-1 < 0; }", 1);
+        program_test_func("int main() { return (unsigned)-1 > 0; }", 1);
+        program_test_func("int main() { return (long)(unsigned)-1 == 4294967295; }", 1);
+        program_test_func("int main() { return (long)(int)4294967295 == -1; }", 1);
+        program_test_func("int main() { return (_Bool)256; }", 1);
+        program_test_func("int main() { return (_Bool)0; }", 0);
+        program_test_func("int main() { return -(char)-3; }", 3);
+        program_test_func("int main() { int x=7; return (long)x * 3; }", 21);
+    }
+
+    #[test]
+    fn test_pointer_casts() {
+        program_test_func("int main() { int x=258; return *(unsigned char *)&x; }", 2);
+        program_test_func(
+            "int main() { long x=-1; int *p=(int *)&x; return p[0] == -1 && p[1] == -1; }",
+            1,
+        );
+        program_test_func(
+            "int main() { int a[2]; long d=(long)(a+1) - (long)a; return d; }",
+            4,
+        );
+        program_test_func("int main() { int *p=(int *)0; return (long)(p+2); }", 8);
+        program_test_func(
+            "int main() { struct { int a; int b; } s; s.b=9; return *((int *)&s + 1); }",
+            9,
+        );
+        program_test_func(
+            "typedef unsigned char byte; int main() { int x=-1; return ((byte *)&x)[3]; }",
+            255,
+        );
+    }
+
+    #[test]
+    fn test_cast_versus_bracketed_expression() {
+        program_test_func("int main() { int x=3; return (x)*2; }", 6);
+        program_test_func("int main() { return (2+3)*(char)2; }", 10);
+        program_test_func(
+            "typedef int T; int main() { int x=3; { int T=4; return (T)-x; } }",
+            1,
+        );
+        program_test_func("typedef int T; int main() { return (T)-1 < 0; }", 1);
+    }
+
+    #[test]
+    fn test_sizeof_type() {
+        for (ty, size) in [
+            ("char", 1),
+            ("_Bool", 1),
+            ("short", 2),
+            ("unsigned int", 4),
+            ("long", 8),
+            ("long long", 8),
+            ("char *", 8),
+            ("int [4]", 16),
+            ("int *[3]", 24),
+            ("char [2][3]", 6),
+            ("struct { char c; long l; }", 16),
+            ("union { char c[5]; int i; }", 8),
+        ] {
+            program_test_func(&format!("int main() {{ return sizeof({}); }}", ty), size);
+        }
+        program_test_func("typedef short S; int main() { return sizeof(S); }", 2);
+        program_test_func("int main() { return sizeof(int) - 5 > 0; }", 1);
+        program_test_func("int main() { int x; return sizeof x; }", 4);
+        program_test_func("int main() { char x; return sizeof (x); }", 1);
+        program_test_func("int main() { return sizeof((char)1) + 1; }", 2);
+        program_test_func("int main() { int x=1; sizeof(x=2); return x; }", 1);
+        // the size of an incomplete type is not known
+        error_test_func(
+            "struct S; int main() { return sizeof(struct S); }",
+            "1:31: invalid application of sizeof to an incomplete type",
+        );
+        error_test_func(
+            "struct S; int main() { struct S *p=0; return sizeof(*p); }",
+            "1:46: invalid application of sizeof to an incomplete type",
+        );
+        error_test_func(
+            "extern int a[]; int main() { return sizeof(a); }",
+            "1:37: invalid application of sizeof to an incomplete type",
+        );
+        error_test_func(
+            "struct S; int main() { return sizeof(struct S [2]); }",
+            "1:31: invalid application of sizeof to an incomplete type",
+        );
+    }
+
+    #[test]
+    fn test_alignof() {
+        for (ty, align) in [
+            ("char", 1),
+            ("short", 2),
+            ("int", 4),
+            ("long", 8),
+            ("int *", 8),
+            ("char [7]", 1),
+            ("long [2]", 8),
+            ("struct { char c; short s; }", 2),
+            ("struct { char c; long l; }", 8),
+        ] {
+            program_test_func(&format!("int main() {{ return _Alignof({}); }}", ty), align);
+        }
+    }
+
+    #[test]
+    fn test_casts_in_constant_expressions() {
+        program_test_func(
+            "int main() { enum { a=(char)300, b=(unsigned char)-1 }; return a+b == 299; }",
+            1,
+        );
+        program_test_func(
+            "int main() { int a[sizeof(long)*2]; return sizeof(a); }",
+            64,
+        );
+        program_test_func(
+            "int main() { enum { a=_Alignof(long)+sizeof(short) }; return a; }",
+            10,
+        );
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{compile_ir, compile_ir_error, emit_ir, program_test_func};
+
+    #[test]
+    fn test_dump_cfg_loops() {
+        let program =
+            "int main() { int n = 0; while (1) { n = n + 1; if (n == 4) break; } return n; }";
+        program_test_func(program, 4);
+        // the graph of the IR as lowered, the variable in memory
+        let text = emit_ir(&["--passes="], program);
+        let dot = compile_ir(&["--passes=", "--dump-cfg=dot"], &text);
+        assert!(
+            dot.starts_with("digraph \"main\" {\n  node [shape=box, fontname=\"monospace\"];\n")
+        );
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("\n  bb0 [label=\"bb0:\\l  %0: ptr = slotaddr $0\\l  store i32 0, %0\\l  jump bb1\\l\"];\n"));
+        assert!(dot
+            .contains("\n  bb1 [label=\"bb1: ; loop depth 1\\l  branch i32 1, bb2, bb4\\l\"];\n"));
+        assert!(dot.contains("\n  bb1 -> bb2 [label=\"T\"];\n  bb1 -> bb4 [label=\"F\"];\n"));
+        // the edge closing the loop
+        assert!(dot.contains("\n  bb3 -> bb1 [style=dashed];\n"));
+        assert!(dot.contains("\n  bb5 -> bb4;\n"));
+    }
+
+    #[test]
+    fn test_dump_cfg_functions() {
+        // a graph for each function, the blocks which can't be reached in grey
+        let text = "function @f() -> void {\nbb0:\n  ret\nbb1:\n  jump bb1\n}\n\
+                    function @g(%0: i32) -> i32 {\nbb0:\n  branch i32 %0, bb1, bb1\nbb1:\n  ret %0\n}\n";
+        let dot = compile_ir(&["--dump-cfg=dot"], text);
+        assert_eq!(
+            dot,
+            "digraph \"f\" {\n\
+             \x20 node [shape=box, fontname=\"monospace\"];\n\
+             \x20 bb0 [label=\"bb0:\\l  ret\\l\"];\n\
+             \x20 bb1 [label=\"bb1:\\l  jump bb1\\l\", color=gray, fontcolor=gray];\n\
+             \x20 bb1 -> bb1;\n\
+             }\n\
+             digraph \"g\" {\n\
+             \x20 node [shape=box, fontname=\"monospace\"];\n\
+             \x20 bb0 [label=\"bb0:\\l  branch i32 %0, bb1, bb1\\l\"];\n\
+             \x20 bb1 [label=\"bb1:\\l  ret %0\\l\"];\n\
+             \x20 bb0 -> bb1;\n\
+             }\n"
+        );
+        // the only format is dot
+        compile_ir_error(&["--dump-cfg=png"], text);
+    }
+}
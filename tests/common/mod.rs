@@ -0,0 +1,257 @@
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Once;
+
+static INIT: Once = Once::new();
+
+/// Every compiled program gets its own files, so the tests can run concurrently.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Setup function that is only run once, even if called multiple times.
+fn setup() {
+    INIT.call_once(|| {
+        env_logger::init();
+    });
+}
+
+/// Compile `program` with susuncc, link it with gcc and run it.
+/// Return the exit code of the program.
+pub fn compile_and_run(program: &str) -> i32 {
+    compile_and_run_with_gcc(program, "")
+}
+
+/// Compile `program` with susuncc and `gcc_source` with gcc,
+/// link them together and run the result.
+/// Return the exit code of the program.
+pub fn compile_and_run_with_gcc(program: &str, gcc_source: &str) -> i32 {
+    compile_and_run_files(&[program], gcc_source)
+}
+
+/// Compile each of `programs` with susuncc and `gcc_source` with gcc,
+/// link them together and run the result.
+/// Return the exit code of the program.
+pub fn compile_and_run_files(programs: &[&str], gcc_source: &str) -> i32 {
+    let args: Vec<Vec<String>> = programs.iter().map(|p| vec![p.to_string()]).collect();
+    compile_and_run_with(&args, gcc_source)
+}
+
+/// Compile `text`, the IR of a program, with susuncc, link it with gcc and run it.
+/// Return the exit code of the program.
+#[allow(dead_code)]
+pub fn compile_and_run_ir(text: &str) -> i32 {
+    let path = write_ir(text);
+    compile_and_run_with(&[vec!["--from-ir".to_owned(), path]], "")
+}
+
+/// Write `text`, the IR of a program, to a file of its own.
+/// Return the path of the file.
+fn write_ir(text: &str) -> String {
+    let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let path = format!("{}/tmp{}.ir", env!("CARGO_TARGET_TMPDIR"), id);
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+/// Run susuncc with each of `args` to compile a file, compile `gcc_source`
+/// with gcc, link them together and run the result.
+/// Return the exit code of the program.
+fn compile_and_run_with(args: &[Vec<String>], gcc_source: &str) -> i32 {
+    setup();
+    let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let dir = env!("CARGO_TARGET_TMPDIR");
+    let gcc_c = format!("{}/tmp{}_gcc.c", dir, id);
+    let exe = format!("{}/tmp{}", dir, id);
+    std::fs::write(&gcc_c, gcc_source).unwrap();
+
+    let mut link = Command::new("gcc");
+    link.arg("-o").arg(&exe);
+    for (i, args) in args.iter().enumerate() {
+        let asm = format!("{}/tmp{}_{}.s", dir, id, i);
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_susuncc"));
+        cmd.args(args);
+        // redirect the child's stdout to the assembly file
+        cmd.stdout(std::fs::File::create(&asm).unwrap());
+        assert!(
+            cmd.status().unwrap().success(),
+            "failed to compile {:?}",
+            args
+        );
+        link.arg(asm);
+    }
+    // use gcc to assemble the files and link them with the gcc compiled one
+    assert!(link.arg(&gcc_c).status().unwrap().success());
+    // execute the program, its exit code is the result
+    Command::new(&exe).status().unwrap().code().unwrap()
+}
+
+/// Compile `program` with susuncc given `options` into the text of its IR.
+#[allow(dead_code)]
+pub fn emit_ir(options: &[&str], program: &str) -> String {
+    setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_susuncc"))
+        .args(options)
+        .arg("--emit=ir")
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "failed to compile {}", program);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Read the IR `text` with susuncc given `options`, which must accept it.
+/// Return what it prints.
+#[allow(dead_code)]
+pub fn compile_ir(options: &[&str], text: &str) -> String {
+    setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_susuncc"))
+        .args(options)
+        .arg("--from-ir")
+        .arg(write_ir(text))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "failed to compile {}", text);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Read the IR `text` with susuncc given `options`, which must reject it.
+/// Return the error message of the compiler.
+#[allow(dead_code)]
+pub fn compile_ir_error(options: &[&str], text: &str) -> String {
+    setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_susuncc"))
+        .args(options)
+        .arg("--from-ir")
+        .arg(write_ir(text))
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "compiled {}", text);
+    // the message is on the line after the one locating the panic
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .skip_while(|line| !line.contains("panicked at"))
+        .nth(1)
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Assert that `program` exits with `expected_value`.
+pub fn program_test_func(program: &str, expected_value: i32) {
+    println!("program: {}, expected_value: {}", program, expected_value);
+    assert_eq!(compile_and_run(program), expected_value, "{}", program);
+}
+
+/// Assert that `program` linked with the gcc compiled `gcc_source`
+/// exits with `expected_value`.
+#[allow(dead_code)]
+pub fn gcc_test_func(program: &str, gcc_source: &str, expected_value: i32) {
+    println!("program: {}, expected_value: {}", program, expected_value);
+    assert_eq!(
+        compile_and_run_with_gcc(program, gcc_source),
+        expected_value,
+        "{}",
+        program
+    );
+}
+
+/// Assert that `programs`, each compiled as its own file, linked with the
+/// gcc compiled `gcc_source` exit with `expected_value`.
+#[allow(dead_code)]
+pub fn files_test_func(programs: &[&str], gcc_source: &str, expected_value: i32) {
+    println!(
+        "programs: {:?}, expected_value: {}",
+        programs, expected_value
+    );
+    assert_eq!(
+        compile_and_run_files(programs, gcc_source),
+        expected_value,
+        "{:?}",
+        programs
+    );
+}
+
+/// Compile `program` with susuncc, which must reject it.
+/// Return the error message of the compiler.
+#[allow(dead_code)]
+pub fn compile_error(program: &str) -> String {
+    compile_error_with(&[], program)
+}
+
+/// Compile `program` with susuncc given `options`, which must reject it.
+/// Return the error message of the compiler.
+#[allow(dead_code)]
+pub fn compile_error_with(options: &[&str], program: &str) -> String {
+    setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_susuncc"))
+        .args(options)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "compiled {}", program);
+    // the message is on the line after the one locating the panic
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .skip_while(|line| !line.contains("panicked at"))
+        .nth(1)
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Assert that susuncc rejects `program` with a message containing `expected`.
+#[allow(dead_code)]
+pub fn error_test_func(program: &str, expected: &str) {
+    println!("program: {}, expected error: {}", program, expected);
+    let message = compile_error(program);
+    assert!(
+        message.contains(expected),
+        "{:?} doesn't contain {:?} for {}",
+        message,
+        expected,
+        program
+    );
+}
+
+/// Compile `program` with susuncc given `options`, which must accept it.
+/// Return the warnings of the compiler.
+#[allow(dead_code)]
+pub fn compile_warnings(options: &[&str], program: &str) -> Vec<String> {
+    setup();
+    let output = Command::new(env!("CARGO_BIN_EXE_susuncc"))
+        .args(options)
+        .arg(program)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "failed to compile {}", program);
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|line| line.starts_with("warning: "))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Assert that susuncc given `options` warns about `program` once for each
+/// of `expected`, with a message containing it.
+#[allow(dead_code)]
+pub fn warning_test_func(options: &[&str], program: &str, expected: &[&str]) {
+    println!(
+        "program: {}, options: {:?}, expected warnings: {:?}",
+        program, options, expected
+    );
+    let warnings = compile_warnings(options, program);
+    assert_eq!(
+        warnings.len(),
+        expected.len(),
+        "{:?} don't match {:?} for {}",
+        warnings,
+        expected,
+        program
+    );
+    for (warning, expected) in warnings.iter().zip(expected) {
+        assert!(
+            warning.contains(expected),
+            "{:?} doesn't contain {:?} for {}",
+            warning,
+            expected,
+            program
+        );
+    }
+}
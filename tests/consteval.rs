@@ -0,0 +1,132 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{error_test_func, program_test_func};
+
+    #[test]
+    fn test_constant_arithmetic() {
+        program_test_func(
+            "int x = 1 << 31; long y = -9223372036854775807L-1; \
+             int main() { return (x < 0) + (y < 0); }",
+            2,
+        );
+        program_test_func(
+            "unsigned x = 0u - 1; unsigned long y = 18446744073709551615UL * 3; \
+             int main() { return (x == 4294967295u) + (y == 18446744073709551613UL); }",
+            2,
+        );
+        program_test_func(
+            "int a[-7 / 2 + 5]; int b[-7 % 2 + 2]; unsigned c = -7u / 2; \
+             int main() { return sizeof(a) + sizeof(b) + (c == 2147483644u); }",
+            13,
+        );
+        program_test_func(
+            "int a[0 && 1/0]; int b[1 || 1/0]; char c = 300; \
+             int main() { return sizeof(a) + sizeof(b) + c; }",
+            48,
+        );
+        program_test_func(
+            "int main() { switch (-1) { case 4294967295u: return 1; case 2: return 2; } return 0; }",
+            1,
+        );
+        program_test_func(
+            "struct S { int a : 2+1; int : 8-3; int b : sizeof(int); }; \
+             int main() { struct S s; s.a=-1; s.b=7; return s.a+s.b+sizeof(struct S); }",
+            10,
+        );
+    }
+
+    #[test]
+    fn test_static_assert() {
+        program_test_func(
+            "_Static_assert(sizeof(int) == 4, \"int is 4 bytes\"); \
+             struct T { char c; _Static_assert(sizeof(struct T *) == 8, \"pointer\"); }; \
+             int main() { _Static_assert(sizeof(long) == 8, \"long\"); _Static_assert(1); return 3; }",
+            3,
+        );
+        error_test_func(
+            "_Static_assert(sizeof(int) == 8, \"int is \" \"8 bytes\");",
+            "1:1: static assertion failed: \"int is 8 bytes\"",
+        );
+        error_test_func(
+            "int main() {\n  _Static_assert(0);\n  return 0;\n}",
+            "2:3: static assertion failed",
+        );
+    }
+
+    #[test]
+    fn test_constant_errors() {
+        error_test_func(
+            "int a[2147483647+1];",
+            "1:17: overflow in constant expression",
+        );
+        error_test_func(
+            "long x = (-9223372036854775807L-1) / -1;",
+            "1:36: overflow in constant expression",
+        );
+        error_test_func(
+            "int x = (-2147483647-1) % -1;",
+            "1:25: overflow in constant expression",
+        );
+        error_test_func(
+            "int x = -(-2147483647-1);",
+            "1:9: overflow in constant expression",
+        );
+        error_test_func("int x = 4 << 30;", "1:11: overflow in constant expression");
+        error_test_func(
+            "int x = 1 << 32;",
+            "1:11: shift count 32 is out of range for int",
+        );
+        error_test_func(
+            "int main() { switch (1) { case 1/0: return 1; } return 0; }",
+            "1:33: division by zero in constant expression",
+        );
+        error_test_func("int a[-2];", "1:7: array size is negative");
+        error_test_func(
+            "int x; int a[(long)&x];",
+            "1:14: address of x is not an integer constant",
+        );
+        error_test_func(
+            "int x; int y = x;",
+            "1:16: expression is not a compile-time constant",
+        );
+    }
+
+    #[test]
+    fn test_invalid_constants() {
+        error_test_func(
+            "int main() { switch (1) { case 1: case 2-1: return 1; } return 0; }",
+            "1:35: duplicate case value 1",
+        );
+        error_test_func(
+            "int main() { case 1: return 0; }",
+            "1:14: case label not within a switch statement",
+        );
+        error_test_func(
+            "enum { A = 2147483647, B };",
+            "1:24: overflow in enumeration values",
+        );
+        error_test_func(
+            "enum { A = 2147483648 };",
+            "1:12: enumerator value 2147483648 is not representable in int",
+        );
+        error_test_func(
+            "struct S { int a : 33; };",
+            "1:20: width of bit-field a (33 bits) exceeds the width of its type (32 bits)",
+        );
+        error_test_func(
+            "struct S { char : -1; };",
+            "1:19: anonymous bit-field has negative width (-1)",
+        );
+        error_test_func(
+            "struct S { int a : 0; };",
+            "1:20: named bit-field a has zero width",
+        );
+        error_test_func(
+            "struct S { int *a : 3; };",
+            "1:21: bit-field a has non-integral type int *",
+        );
+    }
+}
@@ -0,0 +1,144 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::program_test_func;
+
+    #[test]
+    fn test_enum_values() {
+        program_test_func("int main() { enum { zero, one, two }; return zero; }", 0);
+        program_test_func("int main() { enum { zero, one, two }; return two; }", 2);
+        program_test_func(
+            "int main() { enum { five=5, six, seven }; return seven; }",
+            7,
+        );
+        program_test_func(
+            "int main() { enum { zero, five=5, three=3, four }; return four; }",
+            4,
+        );
+        program_test_func("int main() { enum { neg=-2, next }; return next+2; }", 1);
+        program_test_func("int main() { enum { a=3, b=a*2, c }; return c; }", 7);
+        program_test_func("int main() { enum { zero, one, }; return one; }", 1);
+    }
+
+    #[test]
+    fn test_enum_type() {
+        program_test_func(
+            "int main() { enum t { zero, one, two } x; return sizeof(x); }",
+            4,
+        );
+        program_test_func(
+            "int main() { enum t { zero, one, two }; enum t y; y=two; return y; }",
+            2,
+        );
+        program_test_func(
+            "enum color { red, green, blue }; int f(enum color c) { return c*10; } \
+             int main() { return f(blue); }",
+            20,
+        );
+        program_test_func(
+            "int main() { struct { enum { small, large } size; int n; } x; x.size=large; return x.size; }",
+            1,
+        );
+    }
+
+    #[test]
+    fn test_enum_scope() {
+        // enumerators are ordinary identifiers, shadowed by inner variables
+        program_test_func("int main() { enum { a=3 }; { int a=5; return a; } }", 5);
+        program_test_func("int main() { enum { a=3 }; { int a=5; } return a; }", 3);
+        program_test_func("int main() { int a=5; { enum { a=3 }; return a; } }", 3);
+        program_test_func(
+            "int main() { enum { a=1 }; { enum { a=2 }; } return a; }",
+            1,
+        );
+    }
+
+    #[test]
+    fn test_enum_constant_expression() {
+        program_test_func(
+            "int main() { enum { n=4 }; int x[n]; return sizeof(x); }",
+            16,
+        );
+        program_test_func(
+            "int main() { enum { n=2 }; int x[n*3][n]; return sizeof(x); }",
+            48,
+        );
+        program_test_func(
+            "int main() { long l; enum { a=sizeof(l), b }; return b; }",
+            9,
+        );
+    }
+
+    #[test]
+    fn test_switch() {
+        program_test_func(
+            "int main() { int i=0; switch (0) { case 0: i=5; break; case 1: i=6; break; case 2: i=7; break; } return i; }",
+            5,
+        );
+        program_test_func(
+            "int main() { int i=0; switch (1) { case 0: i=5; break; case 1: i=6; break; case 2: i=7; break; } return i; }",
+            6,
+        );
+        program_test_func(
+            "int main() { int i=0; switch (3) { case 0: i=5; break; case 1: i=6; break; } return i; }",
+            0,
+        );
+        program_test_func(
+            "int main() { int i=0; switch (3) { case 0: i=5; break; default: i=7; } return i; }",
+            7,
+        );
+        // without a break the execution falls through
+        program_test_func(
+            "int main() { int i=0; switch (1) { case 1: i=i+1; case 2: i=i+2; case 3: i=i+4; } return i; }",
+            7,
+        );
+        program_test_func(
+            "int main() { int i=0; switch (2) { default: i=1; case 1: case 2: i=i+2; } return i; }",
+            2,
+        );
+        program_test_func(
+            "int main() { int i=0; switch (-1) { case -1: i=3; } return i; }",
+            3,
+        );
+    }
+
+    #[test]
+    fn test_switch_with_enum() {
+        program_test_func(
+            "enum op { add, sub, mul }; \
+             int apply(enum op o, int x, int y) { switch (o) { case add: return x+y; case sub: return x-y; case mul: return x*y; } return 0; } \
+             int main() { return apply(add, 3, 4)*100 + apply(sub, 9, 4)*10 + apply(mul, 1, 1); }",
+            751 % 256,
+        );
+        program_test_func(
+            "int main() { enum { a=10, b=a+5 }; switch (15) { case a: return 1; case b: return 2; } return 3; }",
+            2,
+        );
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        program_test_func(
+            "int main() { int i=0; for (;;) { i=i+1; if (i==5) break; } return i; }",
+            5,
+        );
+        program_test_func(
+            "int main() { int i=0; int j=0; for (i=0; i<10; i=i+1) { if (i<5) continue; j=j+1; } return j; }",
+            5,
+        );
+        program_test_func(
+            "int main() { int i=0; int j=0; while (i<10) { i=i+1; if (i>3) continue; j=j+1; } return j; }",
+            3,
+        );
+        program_test_func(
+            "int main() { int i=0; for (;;) { switch (i) { case 3: break; } if (i==3) break; i=i+1; } return i; }",
+            3,
+        );
+        program_test_func(
+            "int main() { int i=0; int j=0; for (i=0; i<4; i=i+1) { switch (i) { case 1: continue; } j=j+i; } return j; }",
+            5,
+        );
+    }
+}
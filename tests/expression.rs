@@ -1,45 +1,16 @@
-use std::sync::Once;
-
-static INIT: Once = Once::new();
-
-/// Setup function that is only run once, even if called multiple times.
-fn setup() {
-    INIT.call_once(|| {
-        env_logger::init();
-    });
-}
+mod common;
 
 // test the easy compiler
 #[cfg(test)]
 mod tests {
 
-    use std::process::Command;
-
-    use crate::setup;
+    use crate::common::program_test_func;
 
+    /// The expression is the return value of `main`.
     fn expr_test_func(expr: &str, expected_value: i32) {
-        setup();
-        let mut cmd = Command::new("./target/debug/susuncc");
-        cmd.arg(expr);
-        // open the "tmp.s" file
-        let tmp_file = std::fs::File::create("tmp.s").unwrap();
-        // redirect the child's stdout to the file
-        cmd.stdout(tmp_file);
-        assert!(cmd.status().unwrap().success());
-        // use gcc to compile the "tmp.s" file
-        assert!(Command::new("gcc")
-            .arg("-o")
-            .arg("tmp")
-            .arg("tmp.s")
-            .status()
-            .unwrap()
-            .success());
-        // execute "./tmp" file use std::process
-        // the exit code should be the same as the expr
-        println!("expr: {}, expected_value: {}", expr, expected_value);
-        assert_eq!(
-            Command::new("./tmp").status().unwrap().code().unwrap(),
-            expected_value
+        program_test_func(
+            &format!("int main() {{ return {}; }}", expr),
+            expected_value,
         );
     }
 
@@ -94,4 +65,12 @@ mod tests {
         expr_test_func("5*(9-6)", 15);
         expr_test_func("(3+5)/2", 4);
     }
+
+    #[test]
+    fn test_unary() {
+        expr_test_func("-10+20", 10);
+        expr_test_func("- -10", 10);
+        expr_test_func("- - +10", 10);
+        expr_test_func("-(3-5)*2", 4);
+    }
 }
@@ -0,0 +1,128 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{error_test_func, program_test_func, warning_test_func};
+
+    #[test]
+    fn test_goto() {
+        program_test_func(
+            "int main() { int i = 0; int s = 0; loop: if (i == 5) goto end; s = s + i; i = i + 1; goto loop; end: return s; }",
+            10,
+        );
+        program_test_func(
+            "int f(int x) { if (x) goto one; return 2; one: return 1; } int main() { return f(1) * 10 + f(0); }",
+            12,
+        );
+        program_test_func(
+            "int main() { int x = 0; { inner: x = x + 1; } if (x < 3) goto inner; return x; } \
+             int g() { int x = 7; goto inner; inner: return x; }",
+            3,
+        );
+        error_test_func(
+            "int main() { goto out; return 0; }",
+            "1:14: use of undeclared label out",
+        );
+        error_test_func(
+            "int main() {\n  a: ;\n  a: return 0;\n}",
+            "3:3: redefinition of label a, previous definition at 2:3",
+        );
+        error_test_func(
+            "_Noreturn int x;",
+            "1:15: _Noreturn can only appear on functions",
+        );
+    }
+
+    #[test]
+    fn test_return_type() {
+        let program = "int f(int x) {\n  if (x) return 1;\n}\nint g(int x) {\n  x = 1;\n}\n\
+                       int h(int x) {\n  for (;;) {\n    if (x) return 2;\n  }\n}\n\
+                       int k(int x) {\n  while (1) {\n    if (x) break;\n  }\n}\nint main() { }";
+        warning_test_func(&[], program, &[]);
+        warning_test_func(
+            &["-Wall"],
+            program,
+            &[
+                "warning: 1:5: non-void function f does not return a value in all control paths [-Wreturn-type]",
+                "warning: 4:5: non-void function g does not return a value [-Wreturn-type]",
+                "warning: 12:5: non-void function k does not return a value",
+            ],
+        );
+        warning_test_func(
+            &["-Wreturn-type"],
+            "int f(int x) { switch (x) { case 1: return 1; default: return 2; } } \
+             int g(int x) { switch (x) { case 1: return 1; } } \
+             int h(int x) { if (x) return 1; else { return 2; } } \
+             void v() { } int main() { return 0; }",
+            &["1:74: non-void function g does not return a value in all control paths"],
+        );
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        warning_test_func(
+            &["-Wunreachable-code"],
+            "int f(int x) {\n  return x;\n  x = 1;\n  x = 2;\n}\nint g(int x) {\n  switch (x) {\n  case 1:\n    return 1;\n    break;\n  default:\n    goto end;\n    x = 3;\n  }\n  x = 4;\nend:\n  return x;\n}\n\
+             void exit(int);\nint h(int x) {\n  exit(x);\n  return 0;\n}\nint main() { return 0; }",
+            &[
+                "warning: 3:3: code will never be executed [-Wunreachable-code]",
+                "warning: 13:5: code will never be executed",
+                "warning: 15:3: code will never be executed",
+                "warning: 22:3: code will never be executed",
+            ],
+        );
+        // not enabled by the groups, like gcc
+        warning_test_func(
+            &["-Wall", "-Wextra"],
+            "int main() { return 0; return 1; }",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_fallthrough_attribute() {
+        // marks the fall through to the next label as intended
+        warning_test_func(
+            &["-Wextra"],
+            "int f(int x) {\n  int y = 0;\n  switch (x) {\n  case 0:\n    y = 1;\n    __attribute__((fallthrough));\n  \
+             case 1:\n    y = y + 2;\n    {\n      y = 5;\n      __attribute__((__fallthrough__));\n    }\n  \
+             case 2:\n    y = y + 3;\n  default:\n    return y;\n  }\n}\nint main() { return f(0); }",
+            &["warning: 14:5: this statement may fall through [-Wimplicit-fallthrough]"],
+        );
+        program_test_func(
+            "int main() { int y = 0; switch (1) { case 1: y = 1; __attribute__((fallthrough)); \
+             case 2: y = y + 2; } return y; }",
+            3,
+        );
+    }
+
+    #[test]
+    fn test_noreturn() {
+        warning_test_func(
+            &["-Wall", "-Wextra"],
+            "_Noreturn void die(int c) {\n  if (c) __builtin_unreachable();\n}\nvoid fail(void);\n\
+             _Noreturn void stop() { return; }\nint f(int x) {\n  switch (x) {\n  case 0:\n    die(1);\n  \
+             case 1:\n    x = 2;\n  default:\n    return x;\n  }\n}\n\
+             int g(int x) {\n  if (x) die(0); else fail();\n}\nint main() { return 0; }",
+            &[
+                "warning: 1:16: function declared _Noreturn should not return [-Winvalid-noreturn]",
+                "warning: 5:25: function declared _Noreturn should not return",
+                "warning: 11:5: this statement may fall through [-Wimplicit-fallthrough]",
+                "warning: 16:5: non-void function g does not return a value",
+            ],
+        );
+        warning_test_func(
+            &["-Wall"],
+            "void abort(void); static _Noreturn void die() { for (;;) ; } \
+             int f(int x) { if (x) return 1; die(); } int g() { abort(); } \
+             int h(int x) { if (x) return 1; __builtin_unreachable(); } int main() { return f(1); }",
+            &[],
+        );
+        program_test_func(
+            "void exit(int); _Noreturn void quit(int c) { exit(c); } \
+             int main() { quit(42); }",
+            42,
+        );
+    }
+}
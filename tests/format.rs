@@ -0,0 +1,113 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{error_test_func, program_test_func, warning_test_func};
+
+    #[test]
+    fn test_printf_format() {
+        let program = "int main() {\n  long l = 1;\n  printf(\"%d %s\\n\", l, 3);\n  return 0;\n}";
+        warning_test_func(&[], program, &[]);
+        warning_test_func(
+            &["-Wall"],
+            program,
+            &[
+                "warning: 3:21: format '%d' expects an argument of type int, but argument 2 has type long; did you mean '%ld'? [-Wformat]",
+                "warning: 3:24: format '%s' expects an argument of type char *, but argument 3 has type int; did you mean '%d'? [-Wformat]",
+            ],
+        );
+        warning_test_func(
+            &["-Wformat"],
+            "int main() {\n  char *s = \"a\";\n  char c = 'x';\n  unsigned long n = sizeof(s);\n  \
+             printf(\"%5.2d|%x|%c|%p|%s|%-*.*s|%zu|%%\\n\", 1, 2u, c, s, s, 3, 4, s, n);\n  \
+             snprintf(s, 1, \"%s\", s);\n  printf(\"%d %d\\n\", 1);\n  printf(\"%d\\n\", 1, 2);\n  printf(s);\n  \
+             printf(\"%y\");\n  printf(\"100%\");\n  printf(\"%*d\", 1L, 2);\n  printf(\"%8p\", 1);\n  return 0;\n}",
+            &[
+                "7:10: format '%d' expects a matching int argument",
+                "8:21: too many arguments for format",
+                "9:10: format string is not a string literal and there are no format arguments; did you mean to print it with \"%s\"?",
+                "10:10: unknown conversion type character 'y' in format",
+                "11:10: spurious trailing '%' in format",
+                "12:17: format '%*d' expects an argument of type int, but argument 2 has type long [-Wformat]",
+                "13:17: format '%8p' expects an argument of type void *, but argument 2 has type int; did you mean '%8d'?",
+            ],
+        );
+        // a long string literal is passed by its address
+        program_test_func(
+            "int main() { char buf[64]; sprintf(buf, \"%s-%d\", \"a string literal longer than 16 bytes\", 42); \
+             return buf[1] + buf[38] + buf[39]; }",
+            32 + 52 + 50,
+        );
+    }
+
+    #[test]
+    fn test_scanf_format() {
+        warning_test_func(
+            &["-Wall"],
+            "int main() {\n  int x; char buf[8]; long l; char c;\n  \
+             scanf(\"%d %7s %ld %hhd %*d %[^]x] %c\", &x, buf, &l, &c, buf, buf);\n  \
+             scanf(\"%d\", x);\n  scanf(\"%d\", &l);\n  scanf(\"%d\", buf);\n  scanf(\"%s\", &x);\n  \
+             scanf(\"%[a-z\", buf);\n  return 0;\n}",
+            &[
+                "4:15: format '%d' expects an argument of type int *, but argument 2 has type int; did you mean to pass its address with '&'?",
+                "5:15: format '%d' expects an argument of type int *, but argument 2 has type long *; did you mean '%ld'?",
+                "6:15: format '%d' expects an argument of type int *, but argument 2 has type char *; did you mean '%s'?",
+                "7:15: format '%s' expects an argument of type char *, but argument 2 has type int *; did you mean '%d'?",
+                "8:9: no closing ']' for '%[' format",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_wide_and_numbered_formats() {
+        // `l` makes the characters and the strings wide
+        warning_test_func(
+            &["-Wall"],
+            "int main() {\n  int ws[2] = {65, 0}; int wc = 66;\n  printf(\"%ls %lc %5ls\\n\", ws, wc, ws);\n  \
+             scanf(\"%ls %lc %l[a-z]\", ws, ws, ws);\n  printf(\"%ls\\n\", \"abc\");\n  return 0;\n}",
+            &["5:19: format '%ls' expects an argument of type int *, but argument 2 has type char *; did you mean '%s'?"],
+        );
+        // the arguments are not checked when they are numbered, the specifications are
+        warning_test_func(
+            &["-Wall"],
+            "int main() {\n  int x;\n  printf(\"%2$s %1$d %1$*3$d\\n\", 1, \"a\", 4);\n  \
+             scanf(\"%1$d\", &x);\n  printf(\"%1$y\\n\", 1);\n  return 0;\n}",
+            &["5:10: unknown conversion type character 'y' in format"],
+        );
+    }
+
+    #[test]
+    fn test_format_attribute() {
+        warning_test_func(
+            &["-Wall"],
+            "__attribute__((format(printf, 1, 2))) int logf(char *fmt, ...);\n\
+             int warnf(int level, char *fmt, ...) __attribute__((unused, __format__(__printf__, 2, 3)));\n\
+             int vlogf(char *fmt, va_list ap) __attribute__((format(printf, 1, 0)));\n\
+             int readf(char *fmt, ...) __attribute__((format(scanf, 1, 2)));\n\
+             int main() {\n  logf(\"%d\", \"x\");\n  warnf(1, \"%s\", 2);\n  vlogf(\"%q\", 0);\n  readf(\"%d\", 1);\n  return 0;\n}",
+            &[
+                "6:14: format '%d' expects an argument of type int, but argument 2 has type char *; did you mean '%s'?",
+                "7:18: format '%s' expects an argument of type char *, but argument 3 has type int; did you mean '%d'?",
+                "8:9: unknown conversion type character 'q' in format",
+                "9:15: format '%d' expects an argument of type int *, but argument 2 has type int",
+            ],
+        );
+        error_test_func(
+            "int f(char *fmt, int x) __attribute__((format(printf, 1, 2)));",
+            "1:40: format arguments 2 should be 0 or the position of '...'",
+        );
+        error_test_func(
+            "int f(int x, ...) __attribute__((format(printf, 1, 2)));",
+            "1:34: format string argument 1 has type int, not a string type",
+        );
+        error_test_func(
+            "int f(char *fmt, ...) __attribute__((format(printf, 3, 0)));",
+            "1:38: format string argument 3 is out of range",
+        );
+        error_test_func(
+            "int x __attribute__((format(printf, 1, 2)));",
+            "1:22: format attribute can only appear on functions",
+        );
+    }
+}
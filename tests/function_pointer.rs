@@ -0,0 +1,146 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    #[test]
+    fn test_function_pointer_declarators() {
+        program_test_func(
+            "int add(int a, int b) { return a+b; } int main() { int (*f)(int, int)=add; return f(2, 3); }",
+            5,
+        );
+        program_test_func(
+            "int neg(int x) { return -x; } int main() { int (*f)(int); f=&neg; return (*f)(-4); }",
+            4,
+        );
+        program_test_func(
+            "int one() { return 1; } int main() { int (*f)(void)=one; return (**f)()+(&one)()+one(); }",
+            3,
+        );
+        program_test_func(
+            "int main() { int (*a)[3]; return sizeof(*a)+sizeof(a); }",
+            20,
+        );
+        program_test_func("int main() { int *a[3]; return sizeof(a)+sizeof(*a); }", 32);
+        program_test_func(
+            "int main() { int x[2][3]; int (*p)[3]=x; p[1][2]=7; return x[1][2]+sizeof(p[0]); }",
+            19,
+        );
+        program_test_func(
+            "int main() { int (*f)(int); int (**pf)(int)=&f; return sizeof(f)+sizeof(*pf); }",
+            16,
+        );
+        program_test_func(
+            "int main() { return sizeof(int (*)(int)) + sizeof(int *[2]); }",
+            24,
+        );
+    }
+
+    #[test]
+    fn test_signal_shape() {
+        program_test_func(
+            "int hits; void on(int n) { hits=hits+n; } void (*handler)(int); \
+             void (*signal(int sig, void (*f)(int)))(int) { void (*old)(int)=handler; handler=f; return old; } \
+             int main() { signal(1, on); void (*old)(int)=signal(2, 0); old(5); return hits+(handler == 0); }",
+            6,
+        );
+        program_test_func(
+            "typedef void (*sighandler)(int); int got; void h(int n) { got=n; } \
+             sighandler install(sighandler f) { return f; } \
+             int main() { install(h)(9); return got; }",
+            9,
+        );
+        program_test_func(
+            "int three() { return 3; } int (*get(void))() { return three; } int main() { return get()(); }",
+            3,
+        );
+    }
+
+    #[test]
+    fn test_dispatch_tables() {
+        program_test_func(
+            "int add(int a, int b) { return a+b; } int sub(int a, int b) { return a-b; } \
+             int mul(int a, int b) { return a*b; } \
+             int (*ops[])(int, int)={add, sub, mul}; \
+             int main() { return ops[0](1, 2)*50+ops[1](5, 3)*10+ops[2](2, 2); }",
+            174,
+        );
+        program_test_func(
+            "int inc(int x) { return x+1; } int dbl(int x) { return x*2; } \
+             int main() { int (*f[2])(int)={inc, dbl}; int x=1; int i; \
+             for (i=0; i<4; i=i+1) x=f[i%2](x); return x; }",
+            10,
+        );
+        program_test_func(
+            "struct Op { char c; int (*apply)(int); }; int sq(int x) { return x*x; } \
+             struct Op op={'s', sq}; int main() { struct Op *p=&op; return p->apply(5)+op.apply(2); }",
+            29,
+        );
+        program_test_func(
+            "static int hidden(int x) { return x+40; } int (*exported)(int)=&hidden; \
+             int main() { return exported(2); }",
+            42,
+        );
+    }
+
+    #[test]
+    fn test_function_pointer_comparison_and_casts() {
+        program_test_func(
+            "int f() { return 0; } int g() { return 0; } int main() { int (*p)()=f; \
+             return (p == f)*4+(p != g)*2+(p == 0); }",
+            6,
+        );
+        program_test_func(
+            "int f() { return 7; } int main() { long addr=(long)f; int (*p)()=(int (*)())addr; return p(); }",
+            7,
+        );
+        program_test_func("int main() { void (*p)(void)=0; return !p; }", 1);
+    }
+
+    #[test]
+    fn test_void() {
+        program_test_func(
+            "int x; void set(int v) { x=v; return; } int main() { set(3); return x; }",
+            3,
+        );
+        program_test_func(
+            "void f(void) {} int main() { f(); return sizeof(void *); }",
+            8,
+        );
+        program_test_func(
+            "int main() { int x=1; (void)x; void *p=&x; return *(int *)p; }",
+            1,
+        );
+        program_test_func(
+            "int main() { char a[4]; void *p=a; void *q=p+3; return (char *)q - a; }",
+            3,
+        );
+    }
+
+    #[test]
+    fn test_callbacks_with_gcc() {
+        gcc_test_func(
+            "void qsort(void *base, long n, long size, int (*cmp)(void *, void *)); \
+             int cmp(void *a, void *b) { return *(int *)a - *(int *)b; } \
+             int main() { int a[5]={5, 3, 9, 1, 7}; qsort(a, 5, sizeof(int), cmp); \
+             return a[0]*10000+a[1]*1000+a[2]*100+a[3]*10+a[4] == 13579; }",
+            "",
+            1,
+        );
+        gcc_test_func(
+            "int twice(int (*f)(int), int x); int inc(int x) { return x+1; } \
+             int main() { return twice(inc, 40); }",
+            "int twice(int (*f)(int), int x) { return f(f(x)); }",
+            42,
+        );
+        gcc_test_func(
+            "int (*pick(int which))(int); int main() { return pick(0)(6)*10+pick(1)(6); }",
+            "static int half(int x) { return x / 2; }\n\
+             static int dbl(int x) { return x * 2; }\n\
+             int (*pick(int which))(int) { return which ? dbl : half; }",
+            42,
+        );
+    }
+}
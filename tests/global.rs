@@ -0,0 +1,177 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    #[test]
+    fn test_global_variables() {
+        program_test_func("int x; int main() { return x; }", 0);
+        program_test_func("int x; int main() { x=3; return x; }", 3);
+        program_test_func("int x=5; int main() { return x; }", 5);
+        program_test_func("int x, y=2; int main() { x=3; return x*y; }", 6);
+        program_test_func(
+            "int x; int f() { x=x+1; return x; } int main() { f(); f(); return f(); }",
+            3,
+        );
+        program_test_func(
+            "long a[4]; int main() { a[3]=7; return a[3]+sizeof(a); }",
+            39,
+        );
+        program_test_func("int x=2*3+sizeof(long); int main() { return x; }", 14);
+        program_test_func("char c=-1; short s=65537; int main() { return c+s; }", 0);
+        program_test_func("int x=1; int main() { int x=2; return x; }", 2);
+    }
+
+    #[test]
+    fn test_array_initializers() {
+        program_test_func("int a[3]={1,2,3}; int main() { return a[0]+a[1]+a[2]; }", 6);
+        program_test_func("int a[5]={1,2,}; int main() { return a[1]+a[4]; }", 2);
+        program_test_func(
+            "int a[2][3]={{1,2,3},{4,5,6}}; int main() { return a[1][2]; }",
+            6,
+        );
+        program_test_func(
+            "int a[2][3]={1,2,3,4,5,6}; int main() { return a[1][0]; }",
+            4,
+        );
+        program_test_func(
+            "int a[2][3]={{1},{4,5}}; int main() { return a[0][1]+a[1][1]; }",
+            5,
+        );
+        program_test_func("int a[]={1,2,3,4}; int main() { return sizeof(a); }", 16);
+        program_test_func(
+            "int a[][2]={{1,2},{3,4},{5}}; int main() { return sizeof(a)+a[2][0]; }",
+            29,
+        );
+        program_test_func("int x={3}; int main() { return x; }", 3);
+    }
+
+    #[test]
+    fn test_string_literals() {
+        program_test_func(r#"int main() { return "abc"[1]; }"#, 98);
+        program_test_func(r#"int main() { return sizeof("abc"); }"#, 4);
+        program_test_func(r#"int main() { return sizeof("ab" "cd"); }"#, 5);
+        program_test_func(r#"int main() { char *p="a\nb"; return p[1]; }"#, 10);
+        program_test_func(r#"int main() { return "\x41\101"[1]; }"#, 65);
+        program_test_func(r#"char *p="hi"; int main() { return p[1]; }"#, 105);
+        program_test_func(
+            r#"char s[]="hello"; int main() { return sizeof(s)+s[1]; }"#,
+            107,
+        );
+        program_test_func(r#"char s[8]="ab"; int main() { return s[7]+s[1]; }"#, 98);
+        program_test_func(r#"char s[2]="ab"; int main() { return sizeof(s); }"#, 2);
+        program_test_func(
+            r#"char s[2][3]={"ab", {"c"}}; int main() { return s[1][0]; }"#,
+            99,
+        );
+    }
+
+    #[test]
+    fn test_address_constants() {
+        program_test_func("int g=7; int *p=&g; int main() { return *p; }", 7);
+        program_test_func(
+            "int a[4]={1,2,3,4}; int *p=a+2; int main() { return *p; }",
+            3,
+        );
+        program_test_func(
+            "int a[4]={1,2,3,4}; int *p=&a[3]; int main() { return *p; }",
+            4,
+        );
+        program_test_func("long x; long *p=&x+1; int main() { return p-&x; }", 1);
+        program_test_func(
+            "int a[4]={1,2,3,4}; int *q=a+3-2; int main() { return *q; }",
+            2,
+        );
+        program_test_func(
+            "struct { int a; int b; } s={1,2}; int *p=&s.b; int main() { return *p; }",
+            2,
+        );
+        program_test_func(
+            "int x=3; int *p=&x; int **pp=&p; int main() { return **pp; }",
+            3,
+        );
+        program_test_func("long n=(long)((int *)16+1); int main() { return n; }", 20);
+    }
+
+    #[test]
+    fn test_struct_initializers() {
+        program_test_func(
+            "struct { int a; char b; long c; } s={1,2,3}; int main() { return s.a+s.b+s.c; }",
+            6,
+        );
+        program_test_func(
+            "struct { int a[2]; int b; } s={{1,2},3}; int main() { return s.a[1]+s.b; }",
+            5,
+        );
+        program_test_func(
+            "struct { int a[2]; int b; } s={1,2,3}; int main() { return s.a[1]+s.b; }",
+            5,
+        );
+        program_test_func(
+            "struct { int a; int b; } s[2]={1,2,3}; int main() { return s[1].a+s[1].b; }",
+            3,
+        );
+        program_test_func(
+            "union { int i; char c[4]; } u={0x01020304}; int main() { return u.c[0]; }",
+            4,
+        );
+        program_test_func(
+            "struct { int a:3; int b:5; unsigned c:2; } s={-1, 9, 3}; \
+             int main() { return s.a == -1 && s.b == 9 && s.c == 3; }",
+            1,
+        );
+        program_test_func(
+            r#"struct { char *name; int v; } t[]={{"ab", 1}, {"cd", 2}};
+               int main() { return t[1].name[1]+t[1].v+sizeof(t); }"#,
+            134,
+        );
+    }
+
+    #[test]
+    fn test_local_initializers() {
+        program_test_func("int main() { int a[3]={1,2}; return a[0]+a[1]+a[2]; }", 3);
+        program_test_func("int main() { int a[]={1,2,3}; return sizeof(a); }", 12);
+        program_test_func(
+            r#"int main() { char s[]="abc"; return s[3] == 0 && sizeof(s) == 4; }"#,
+            1,
+        );
+        program_test_func(
+            "int main() { struct { int x; int y; } p={3}; return p.x*10+p.y; }",
+            30,
+        );
+        program_test_func(
+            "int main() { int a[2][2]={{1,2},{3}}; return a[1][0]*10+a[1][1]; }",
+            30,
+        );
+        program_test_func(
+            "int f() { int a[64]; int i; for (i=0; i<64; i=i+1) a[i]=i; return a[63]; } \
+             int g() { int b[64]={1}; return b[63]+b[0]; } int main() { f(); return g(); }",
+            1,
+        );
+        program_test_func(
+            "int main() { struct S { int a; int b; } s={1,2}; struct S t=s; return t.b; }",
+            2,
+        );
+    }
+
+    #[test]
+    fn test_globals_shared_with_gcc() {
+        gcc_test_func(
+            "int counter=41; int bump(); int main() { return bump(); }",
+            "extern int counter; int bump() { return counter + 1; }",
+            42,
+        );
+        gcc_test_func(
+            "int a[3]={1,2,3}; int *p=a+1; int get(); int main() { return get(); }",
+            "extern int *p; int get() { return *p * 10 + p[1]; }",
+            23,
+        );
+        gcc_test_func(
+            "int values[4]; int fill(); int main() { fill(); return values[0]+values[3]; }",
+            "extern int values[4]; int fill() { values[0] = 4; values[3] = 5; return 0; }",
+            9,
+        );
+    }
+}
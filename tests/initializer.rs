@@ -0,0 +1,165 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    #[test]
+    fn test_designated_array_elements() {
+        program_test_func(
+            "int a[5]={[2]=3, [4]=5}; int main() { return a[2]*10+a[4]+a[3]; }",
+            35,
+        );
+        program_test_func("int a[]={[3]=1}; int main() { return sizeof(a); }", 16);
+        program_test_func(
+            "int a[5]={1, [3]=4, 5}; int main() { return a[0]+a[3]*10+a[4]*20+a[1]+a[2]; }",
+            141,
+        );
+        program_test_func(
+            "int a[3]={1, 2, 3, [0]=7}; int main() { return a[0]+a[1]; }",
+            9,
+        );
+        program_test_func(
+            "int main() { int a[4]={[1]=2, [3]=4}; return a[0]+a[1]*10+a[2]+a[3]*50; }",
+            220,
+        );
+        program_test_func(
+            "enum { RED, GREEN, BLUE }; char *names[]={[BLUE]=\"blue\", [RED]=\"red\"}; \
+             int main() { return names[0][0]+names[2][0]+(names[1] == 0); }",
+            213,
+        );
+    }
+
+    #[test]
+    fn test_designated_members() {
+        program_test_func(
+            "struct { int a; int b; int c; } s={.c=3, .a=1}; int main() { return s.a*100+s.b*10+s.c; }",
+            103,
+        );
+        program_test_func(
+            "struct { int a; int b; int c; } s={.b=2, 3}; int main() { return s.a*100+s.b*10+s.c; }",
+            23,
+        );
+        program_test_func(
+            "int main() { struct { char x; long y; } s={.y=5, .x=1}; return s.x+s.y; }",
+            6,
+        );
+        program_test_func(
+            "union { char c; int i; } u={.i=0x0102}; int main() { return u.i == 258; }",
+            1,
+        );
+        program_test_func(
+            "int main() { union { char c; int i; } u={.c=1, .i=0x0300}; return u.i == 768; }",
+            1,
+        );
+        program_test_func(
+            "struct { int a:4; int b:4; } s={.b=-2, .a=3}; int main() { return s.a*10+s.b; }",
+            28,
+        );
+    }
+
+    #[test]
+    fn test_nested_designators() {
+        program_test_func(
+            "struct P { int x; int y; }; struct { struct P p[2]; int n; } s={.p[1].y=4, .n=5}; \
+             int main() { return s.p[1].y*10+s.n+s.p[0].x; }",
+            45,
+        );
+        program_test_func(
+            "int main() { struct P { int x; int y; } a[3]={[1].y=2, [2]={3, 4}, [1].x=1}; \
+             return a[1].x*64+a[1].y*16+a[2].x*4+a[2].y; }",
+            112,
+        );
+        program_test_func(
+            "struct { int a[3]; int b; } s={.a={1, 2}, .a[2]=3}; int main() { return s.a[0]+s.a[1]+s.a[2]+s.b; }",
+            6,
+        );
+        program_test_func(
+            "struct { int a[3]; int b; } s={.a={1, 2, 3}, .a={4}}; int main() { return s.a[0]+s.a[1]+s.a[2]; }",
+            4,
+        );
+        program_test_func(
+            "int a[2][3]={[1]={[2]=6}, [0][1]=2}; int main() { return a[0][1]*10+a[1][2]; }",
+            26,
+        );
+        program_test_func(
+            "struct { int a[2]; int b; } s={.a[1]=5, 6}; int main() { return s.a[1]*10+s.b; }",
+            56,
+        );
+    }
+
+    #[test]
+    fn test_designated_ranges() {
+        program_test_func(
+            "int a[6]={[1 ... 3]=7, 8}; int main() { return a[0]+a[1]+a[3]+a[4]*10+a[5]; }",
+            94,
+        );
+        program_test_func(
+            "int main() { int a[10]={[0 ... 9]=1, [5]=3}; int s=0; int i; \
+             for (i=0; i<10; i=i+1) s=s+a[i]; return s; }",
+            12,
+        );
+        program_test_func(
+            "struct P { int x; int y; } a[3]={[0 ... 2].y=2, [1].x=1}; \
+             int main() { return a[0].y+a[1].y+a[2].y+a[1].x*10; }",
+            16,
+        );
+        program_test_func(
+            "char s[]={[0 ... 4]='a'}; int main() { return sizeof(s)+s[4]; }",
+            102,
+        );
+    }
+
+    #[test]
+    fn test_compound_literals() {
+        program_test_func("int main() { return (int){3}; }", 3);
+        program_test_func(
+            "struct P { int x; int y; }; int main() { return (struct P){1, 2}.y; }",
+            2,
+        );
+        program_test_func(
+            "struct P { int x; int y; }; int main() { struct P p; p=(struct P){.y=4}; return p.x+p.y; }",
+            4,
+        );
+        program_test_func("int main() { int *p=(int[]){1, 2, 3}; return p[2]; }", 3);
+        program_test_func("int main() { return sizeof((int[]){1, 2, 3}); }", 12);
+        program_test_func("int main() { return sizeof (int[]){1, 2, 3, 4}; }", 16);
+        program_test_func(
+            "struct P { int x; int y; }; int main() { struct P *p=&(struct P){5, 6}; p->x=7; return p->x+p->y; }",
+            13,
+        );
+        program_test_func(
+            "int main() { int s=0; int i; for (i=0; i<3; i=i+1) { int *p=(int[2]){i}; s=s+p[0]+p[1]; p[1]=9; } return s; }",
+            3,
+        );
+        program_test_func("int main() { return (char){300}; }", 44);
+    }
+
+    #[test]
+    fn test_file_scope_compound_literals() {
+        program_test_func("int *p=(int[]){4, 5, 6}; int main() { return p[1]; }", 5);
+        program_test_func(
+            "struct P { int x; int y; }; struct P *p=&(struct P){.y=8}; int main() { return p->y+p->x; }",
+            8,
+        );
+        program_test_func(
+            "int *p=(int[]){1, 2}; int main() { p[0]=3; return p[0]+((int[]){1, 2})[0]; }",
+            4,
+        );
+    }
+
+    #[test]
+    fn test_designated_tables_with_gcc() {
+        gcc_test_func(
+            "struct Entry { char *name; int value; }; \
+             struct Entry table[]={[2]={.value=30, .name=\"c\"}, [0].name=\"a\", [0].value=10}; \
+             int check(struct Entry *t, int n); int main() { return check(table, sizeof(table)/sizeof(table[0])); }",
+            "#include <string.h>\n\
+             struct Entry { char *name; int value; };\n\
+             int check(struct Entry *t, int n) { return n == 3 && !strcmp(t[0].name, \"a\") && t[0].value == 10 \
+             && t[1].name == 0 && t[1].value == 0 && !strcmp(t[2].name, \"c\") && t[2].value == 30; }",
+            1,
+        );
+    }
+}
@@ -0,0 +1,187 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    #[test]
+    fn test_integer_sizes() {
+        for (decl, size) in [
+            ("_Bool x;", 1),
+            ("char x;", 1),
+            ("signed char x;", 1),
+            ("unsigned char x;", 1),
+            ("short x;", 2),
+            ("unsigned short int x;", 2),
+            ("int x;", 4),
+            ("unsigned x;", 4),
+            ("signed int x;", 4),
+            ("long x;", 8),
+            ("unsigned long x;", 8),
+            ("long long x;", 8),
+            ("long unsigned int long x;", 8),
+        ] {
+            program_test_func(
+                &format!("int main() {{ {} return sizeof(x); }}", decl),
+                size,
+            );
+        }
+        program_test_func(
+            "int main() { struct { char a; short b; char c; int d; } x; return sizeof(x); }",
+            12,
+        );
+    }
+
+    #[test]
+    fn test_integer_constants() {
+        program_test_func("int main() { return 0x10+010+0b10; }", 26);
+        program_test_func("int main() { return 'a'; }", 97);
+        program_test_func("int main() { return '\\n'; }", 10);
+        program_test_func("int main() { return '\\377' == -1; }", 1);
+        program_test_func("int main() { return sizeof(2147483647); }", 4);
+        program_test_func("int main() { return sizeof(2147483648); }", 8);
+        program_test_func("int main() { return sizeof(0xFFFFFFFF); }", 4);
+        program_test_func("int main() { return sizeof(1L); }", 8);
+        program_test_func("int main() { return 0xFFFFFFFF > 0; }", 1);
+        program_test_func("int main() { return 4294967296 > 0; }", 1);
+    }
+
+    #[test]
+    fn test_overflow_wraps() {
+        program_test_func("int main() { return 2147483647+1 < 0; }", 1);
+        program_test_func(
+            "int main() { int x=2147483647; x=x+1; return x == -2147483647-1; }",
+            1,
+        );
+        program_test_func("int main() { long x=2147483647; return x+1 > 0; }", 1);
+        program_test_func("int main() { char c=127; c=c+1; return c == -128; }", 1);
+        program_test_func("int main() { unsigned char c=255; c=c+1; return c; }", 0);
+        program_test_func("int main() { short s=65537; return s; }", 1);
+        program_test_func("int main() { unsigned short s=-1; return s == 65535; }", 1);
+        program_test_func("int main() { unsigned x=-1; return x == 4294967295; }", 1);
+    }
+
+    #[test]
+    fn test_integer_promotions() {
+        program_test_func(
+            "int main() { char a=100; char b=100; int c=a+b; return c == 200; }",
+            1,
+        );
+        program_test_func("int main() { unsigned char a=200; return a+a == 400; }", 1);
+        program_test_func("int main() { unsigned char a=1; return -a < 0; }", 1);
+        program_test_func("int main() { unsigned short a=0; return ~a == -1; }", 1);
+        program_test_func("int main() { char c=-1; return c == 255; }", 0);
+        program_test_func("int main() { unsigned char c=-1; return c == 255; }", 1);
+    }
+
+    #[test]
+    fn test_usual_arithmetic_conversions() {
+        program_test_func("int main() { return -1 < 0; }", 1);
+        program_test_func("int main() { return -1 < 0u; }", 0);
+        program_test_func("int main() { unsigned x=0; return x-1 > 0; }", 1);
+        program_test_func("int main() { unsigned x=1; long y=-2; return x+y < 0; }", 1);
+        program_test_func("int main() { unsigned long x=1; return x-2 > 0; }", 1);
+        program_test_func("int main() { int i=-1; unsigned u=1; return i > u; }", 1);
+        program_test_func("int main() { int i=-1; long l=1; return i > l; }", 0);
+    }
+
+    #[test]
+    fn test_unsigned_division_and_shift() {
+        program_test_func("int main() { int x=-7; return x/2 == -3 && x%2 == -1; }", 1);
+        program_test_func("int main() { unsigned x=-1; return x/2 == 2147483647; }", 1);
+        program_test_func("int main() { unsigned x=-1; return x%10; }", 5);
+        program_test_func("int main() { unsigned long x=-1; return x%10; }", 5);
+        program_test_func("int main() { int x=-16; return x>>2 == -4; }", 1);
+        program_test_func("int main() { unsigned x=-16; return x>>28; }", 15);
+        program_test_func("int main() { return 1<<31 < 0; }", 1);
+        program_test_func("int main() { return 1u<<31 > 0; }", 1);
+        program_test_func("int main() { long x=1; return (x<<40)>>38; }", 4);
+        program_test_func("int main() { char c=1; return sizeof(c<<1L); }", 4);
+    }
+
+    #[test]
+    fn test_bitwise_and_logical_operators() {
+        program_test_func("int main() { return (6&3)|(8^1); }", 11);
+        program_test_func("int main() { return ~0 == -1; }", 1);
+        program_test_func("int main() { return !0 + !5; }", 1);
+        program_test_func("int main() { return 2 && 3; }", 1);
+        program_test_func("int main() { return 0 || 0; }", 0);
+        program_test_func("int main() { return 1 + 2 == 3 && 4 | 1 == 5; }", 1);
+        program_test_func(
+            "int main() { int x=0; 0 && (x=1); 1 || (x=2); return x; }",
+            0,
+        );
+        program_test_func("int main() { int *p=0; return !p; }", 1);
+    }
+
+    #[test]
+    fn test_bool() {
+        program_test_func("int main() { _Bool b=256; return b; }", 1);
+        program_test_func("int main() { _Bool b=0; b=b+1; b=b+1; return b; }", 1);
+        program_test_func("int main() { int x; _Bool b=&x; return b; }", 1);
+        program_test_func("int main() { _Bool b=1; return b+b; }", 2);
+    }
+
+    #[test]
+    fn test_integer_parameters_and_returns() {
+        program_test_func(
+            "int f(char c) { return c; } int main() { return f(257); }",
+            1,
+        );
+        program_test_func(
+            "unsigned char f(int x) { return x; } int main() { return f(-1) == 255; }",
+            1,
+        );
+        program_test_func(
+            "long f(long a, unsigned short b) { return a+b; } int main() { return f(-1, -1) == 65534; }",
+            1,
+        );
+        gcc_test_func(
+            "char g(); int main() { return g() == -1; }",
+            "char g() { return -1; }",
+            1,
+        );
+        gcc_test_func(
+            "int h(unsigned short x, signed char y, long z); int main() { return h(-1, 255, -1); }",
+            "int h(unsigned short x, signed char y, long z) { return x == 65535 && y == -1 && z == -1; }",
+            1,
+        );
+    }
+
+    #[test]
+    fn test_narrow_members_and_switch() {
+        program_test_func(
+            "int main() { struct { unsigned a:3; int b:3; } x; x.a=7; x.b=7; return x.a*10 + (x.b == -1); }",
+            71,
+        );
+        program_test_func(
+            "int main() { struct { unsigned char a; short b; } x; x.a=-1; x.b=-1; return x.a + x.b; }",
+            254,
+        );
+        program_test_func(
+            "int main() { char c=-1; switch (c) { case 255: return 1; case -1: return 2; } return 0; }",
+            2,
+        );
+        program_test_func(
+            "int main() { unsigned char c=-1; switch (c) { case 255: return 1; case -1: return 2; } return 0; }",
+            1,
+        );
+    }
+
+    #[test]
+    fn test_pointer_arithmetic_with_narrow_index() {
+        program_test_func(
+            "int main() { int a[4]; a[3]=7; unsigned char i=3; return *(a+i); }",
+            7,
+        );
+        program_test_func(
+            "int main() { int a[4]; a[0]=5; int *p=a+3; char i=-3; return p[i]; }",
+            5,
+        );
+        program_test_func(
+            "int main() { int a[4]; a[0]=5; int *p=a+3; unsigned i=3; return *(p-i); }",
+            5,
+        );
+    }
+}
@@ -0,0 +1,197 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{
+        compile_and_run, compile_and_run_ir, compile_ir, compile_ir_error, emit_ir, gcc_test_func,
+        program_test_func,
+    };
+
+    #[test]
+    fn test_narrow_operations() {
+        // the upper bits of a narrow value are not defined in its register
+        program_test_func(
+            "int main() { unsigned char a = 200; unsigned char b = 100; unsigned char c = a + b; return c / 4; }",
+            11,
+        );
+        program_test_func(
+            "int main() { char c = -8; short s = -100; return (c >> 1) + 10 + s / 7 + 14 + s % 7 + 2; }",
+            6,
+        );
+        program_test_func(
+            "int main() { unsigned u = 4294967295; int i = -1; long l = u; return (l == 4294967295) + (i < 0) + (u > 0); }",
+            3,
+        );
+        program_test_func(
+            "int main() { unsigned short s = 65535; s = s + 1; long l = -1; unsigned x = l; \
+             return s + (x >> 28); }",
+            15,
+        );
+        program_test_func(
+            "int main() { long big = 1099511627776; int lo = big; return (big >> 40) + lo + (big > 2147483647); }",
+            2,
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        // the blocks after a jump are never reached
+        program_test_func(
+            "int f(int x) { switch (x) { case 1: return 10; x = 5; case 2: x = x + 1; break; default: goto end; } \
+             return x; end: return 0; } \
+             int main() { return f(1) + f(2) + f(3); }",
+            13,
+        );
+        program_test_func(
+            "int main() { int i; int s = 0; for (i = 0; i < 10; i = i + 1) { if (i == 3) continue; \
+             if (i == 7) break; s = s + i; } return s; }",
+            18,
+        );
+        program_test_func(
+            "int c; int t() { c = c + 1; return 1; } int z() { c = c + 10; return 0; } \
+             int main() { int a = z() && t(); int b = t() || z(); int n = !(z() || !t()); \
+             return a + b * 2 + n * 4 + c; }",
+            28,
+        );
+        program_test_func(
+            "int main() { int *p = 0; int x = 3; if (p) return 1; if (!&x) return 2; return p == 0; }",
+            1,
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        program_test_func(
+            "struct P { char a; long b; }; struct P mk(int a) { struct P p; p.a = a; p.b = a * 2; return p; } \
+             int main() { return mk(3).b + mk(4).a; }",
+            10,
+        );
+        program_test_func(
+            "struct L { long a[3]; }; struct L id(struct L l) { return l; } \
+             int main() { struct L l; l.a[0] = 1; l.a[2] = 5; return id(id(l)).a[2] + id(l).a[0]; }",
+            6,
+        );
+        gcc_test_func(
+            "int add8(char a, short b, int c, long d, char e, short f, int g, long h); \
+             int main() { char c = -1; return add8(c, -2, 3, 4, 5, 6, 7, 8) + 10; }",
+            "int add8(char a, short b, int c, long d, char e, short f, int g, long h) \
+             { return a + b + c + d + e + f + g + h; }",
+            40,
+        );
+        gcc_test_func(
+            "unsigned char get(); int main() { unsigned char c = get(); return c / 2; }",
+            "unsigned char get() { return 250; }",
+            125,
+        );
+    }
+
+    #[test]
+    fn test_bit_fields() {
+        program_test_func(
+            "struct B { int a : 3; unsigned b : 5; long c : 40; }; \
+             int main() { struct B s; s.a = 5; s.b = 33; s.c = -2; int x = (s.a = 3); \
+             return (s.a + 10) * 10 + s.b + (s.c == -2) + x; }",
+            135,
+        );
+    }
+
+    /// Programs whose IR uses every kind of global, instruction and terminator.
+    const PROGRAMS: [(&str, i32); 4] = [
+        (
+            "char *s = \"a\\\"b\\n\"; int g = 3; int *pg = &g + 1; extern int e; static int z; \
+             static int sq(int x) { return x * x; } int (*fp)(int) = sq; \
+             int main() { unsigned char c = 255; long l = -9223372036854775807 - 1; \
+             return fp(2) + s[1] + (pg - &g) + z + (c >> 4) + (l < 0) + ~0 + -1; }",
+            4 + 34 + 1 + 15 + 1 - 2,
+        ),
+        (
+            "struct P { char a; long b; }; struct L { long a[3]; }; \
+             struct P mk(int a) { struct P p; p.a = a; p.b = a * 2; return p; } \
+             struct L id(struct L l) { return l; } \
+             int main() { struct L l = {{1, 2, 3}}; struct P p = mk(3); p = mk(4); \
+             return p.b + id(l).a[2] + mk(1).a; }",
+            12,
+        ),
+        (
+            "int sum(int n, ...) { va_list ap; va_start(ap, n); int s = 0; int i; \
+             for (i = 0; i < n; i = i + 1) s = s + va_arg(ap, int); return s; } \
+             int f(int x) { switch (x) { case 1: return 10; case 2: x = x + 1; break; default: goto end; } \
+             return x; end: return x > 5 && x < 9 || !x; } \
+             int main() { return sum(3, 1, 2, 3) + f(1) + f(2) + f(7) + f(0); }",
+            6 + 10 + 3 + 1 + 1,
+        ),
+        (
+            "struct B { int a : 3; unsigned b : 5; }; _Noreturn void exit(int); \
+             int main() { struct B s; s.b = 33; s.a = -1; int a[4] = {}; if (s.a != -1) exit(1); \
+             return s.b + a[3] + sizeof(a); }",
+            17,
+        ),
+    ];
+
+    #[test]
+    fn test_ir_round_trip() {
+        for (program, expected) in PROGRAMS {
+            let text = emit_ir(&[], program);
+            // printing the IR read gives the same text
+            assert_eq!(compile_ir(&["--emit=ir"], &text), text, "{}", program);
+            assert_eq!(compile_and_run(program), expected, "{}", program);
+            assert_eq!(compile_and_run_ir(&text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_ir_from_file() {
+        // written by hand, with comments and registers in any order
+        let text = "; the sum of the numbers from 1 to 10\n\
+                    function @main() -> i32 {\n\
+                    \x20 $0 = slot \"s\", size 4, align 4\n\
+                    \x20 $1 = slot \"i\", size 8, align 8\n\
+                    bb0:\n\
+                    \x20 %10: ptr = slotaddr $0 ; s\n\
+                    \x20 %11: ptr = slotaddr $1\n\
+                    \x20 store i32 0, %10\n\
+                    \x20 store i64 1, %11\n\
+                    \x20 jump bb1\n\
+                    bb1:\n\
+                    \x20 %1: i64 = load i64 %11\n\
+                    \x20 %2: i32 = sle i64 %1, 10\n\
+                    \x20 branch i32 %2, bb2, bb3\n\
+                    bb2:\n\
+                    \x20 %3: i32 = load i32 %10\n\
+                    \x20 %4: i32 = trunc i64 %1\n\
+                    \x20 %5: i32 = add i32 %3, %4\n\
+                    \x20 store i32 %5, %10\n\
+                    \x20 %6: i64 = add i64 %1, 1\n\
+                    \x20 store i64 %6, %11\n\
+                    \x20 jump bb1\n\
+                    bb3:\n\
+                    \x20 %7: i32 = load i32 %10\n\
+                    \x20 ret %7\n\
+                    }\n";
+        assert_eq!(compile_and_run_ir(text), 55);
+        // printed as written, without promoting the slots
+        let printed = compile_ir(&["--passes=", "--emit=ir"], text);
+        assert!(printed
+            .starts_with("\nfunction @main() -> i32 {\n  $0 = slot \"s\", size 4, align 4\n"));
+        assert!(printed.contains("\n  %10: ptr = slotaddr $0\n"));
+        assert_eq!(compile_ir(&["--passes=", "--emit=ir"], &printed), printed);
+    }
+
+    #[test]
+    fn test_ir_errors() {
+        for (text, expected) in [
+            ("function @f() -> i32 {\nbb0:\n  ret 0\n", "4:1: expected a block, not the end of the input"),
+            ("function @f() -> i32 {\nbb1:\n  ret 0\n}", "2:1: expected bb0"),
+            ("function @f() -> i32 {\nbb0:\n  %0: i32 = frob i32 1\n  ret %0\n}", "3:13: unknown instruction 'frob'"),
+            ("function @f() -> i32 {\nbb0:\n  %0: i32 = add i32 1, 2\n  %0: i64 = add i64 1, 2\n  ret 0\n}", "4:3: register %0 is assigned both i32 and i64"),
+            ("function @f() -> i32 {\nbb0:\n  %0: i32 = store i32 1, 2\n  ret 0\n}", "3:13: 'store' doesn't assign a register"),
+            ("function @f() -> i32 {\nbb0:\n  add i32 1, 2\n  ret 0\n}", "3:3: 'add' assigns a register"),
+            ("global @g, size 4, align 4, init \"\\0\"", "1:34: expected two hexadecimal digits after '\\'"),
+            ("global @g, size 4, align 4 #", "1:28: unexpected character '#'"),
+        ] {
+            let message = compile_ir_error(&[], text);
+            assert!(message.contains(expected), "{:?} doesn't contain {:?}", message, expected);
+        }
+    }
+}
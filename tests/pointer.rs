@@ -0,0 +1,67 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::program_test_func;
+
+    #[test]
+    fn test_local_variables() {
+        program_test_func("int main() { int a; a=3; return a; }", 3);
+        program_test_func("int main() { int a=3; int z=5; return a+z; }", 8);
+        program_test_func("int main() { int foo=3, bar=5; return foo*bar; }", 15);
+        program_test_func("int main() { int a; int b; a=b=3; return a+b; }", 6);
+        program_test_func("int main() { int x=1; { int x=2; } return x; }", 1);
+        program_test_func("int main() { ;; { } return 5; }", 5);
+    }
+
+    #[test]
+    fn test_address_and_dereference() {
+        program_test_func("int main() { int x=3; return *&x; }", 3);
+        program_test_func(
+            "int main() { int x=3; int *y=&x; int **z=&y; return **z; }",
+            3,
+        );
+        program_test_func("int main() { int x=3; int *y=&x; *y=5; return x; }", 5);
+        program_test_func(
+            "int main() { int x=3; int *y=&x; int **z=&y; **z=7; return x; }",
+            7,
+        );
+        program_test_func("int main() { int x=3; return *&*&x; }", 3);
+    }
+
+    #[test]
+    fn test_pointer_arithmetic() {
+        program_test_func(
+            "int main() { int a[2]; a[0]=3; a[1]=5; return *(&a[0]+1); }",
+            5,
+        );
+        program_test_func(
+            "int main() { int a[2]; a[0]=3; a[1]=5; return *(1+&a[0]); }",
+            5,
+        );
+        program_test_func(
+            "int main() { int a[2]; a[0]=3; a[1]=5; return *(&a[1]-1); }",
+            3,
+        );
+        program_test_func(
+            "int main() { int a[2]; a[0]=3; a[1]=5; *(&a[0]+1)=7; return a[1]; }",
+            7,
+        );
+        program_test_func(
+            "int main() { int a[2]; a[0]=3; a[1]=5; *(&a[1]-1)=7; return a[0]; }",
+            7,
+        );
+        program_test_func("int main() { long a[3]; a[2]=9; return *(&a[0]+2); }", 9);
+    }
+
+    #[test]
+    fn test_pointer_difference() {
+        program_test_func("int main() { int x; int y; return &x-&y; }", 1);
+        program_test_func("int main() { int x; int y; return &y+1-&y; }", 1);
+        program_test_func(
+            "int main() { int x; int *p=&x; int *q=p-3; return p-q; }",
+            3,
+        );
+    }
+}
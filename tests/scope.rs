@@ -0,0 +1,182 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{error_test_func, program_test_func};
+
+    #[test]
+    fn test_shadowing() {
+        program_test_func(
+            "int x=1; int main() { int r=x; int x=2; r=r*10+x; { int x=3; r=r*10+x; } return r-x*100; }",
+            -77 & 0xff,
+        );
+        program_test_func(
+            "int main() { int x=5; { x=x+1; int x=10; x=x+1; } return x; }",
+            6,
+        );
+        program_test_func(
+            "int f() { return 7; } int main() { int r=f(); { int f=3; r=r+f; } return r+f(); }",
+            17,
+        );
+        program_test_func(
+            "typedef int T; int main() { T a=1; { int T=2; a=a+T; } T b=4; return a+b; }",
+            7,
+        );
+        program_test_func(
+            "enum { A=1 }; int main() { int r=A; { enum { A=5 }; r=r+A; } return r+A; }",
+            7,
+        );
+        program_test_func(
+            "int f(int x) { { int x=2; } return x; } int main() { return f(9); }",
+            9,
+        );
+    }
+
+    #[test]
+    fn test_separate_namespaces() {
+        program_test_func(
+            "struct S { int S; }; int main() { struct S S; S.S=3; return S.S; }",
+            3,
+        );
+        program_test_func(
+            "struct x { int a; }; int x; int main() { struct x y; y.a=2; x=3; return x+y.a; }",
+            5,
+        );
+        program_test_func(
+            "struct T { int a; }; int main() { struct T t; t.a=1; { struct T { char b; } u; u.b=2; \
+             t.a=t.a+sizeof(u); } return t.a+sizeof(struct T); }",
+            6,
+        );
+        program_test_func(
+            "struct P { int x; }; struct Q { int x; }; int main() { struct P p; struct Q q; \
+             p.x=1; q.x=2; return p.x+q.x; }",
+            3,
+        );
+    }
+
+    #[test]
+    fn test_allowed_redeclarations() {
+        program_test_func(
+            "typedef int T; typedef int T; int main() { T x=3; return x; }",
+            3,
+        );
+        program_test_func(
+            "int f(int a); int f(int b); int f(int c) { return c; } int main() { return f(4); }",
+            4,
+        );
+        program_test_func(
+            "int x; extern int x; int x=5; int main() { extern int x; extern int x; return x; }",
+            5,
+        );
+        program_test_func(
+            "typedef __builtin_va_list va_list; int main() { va_list ap; return sizeof(ap); }",
+            24,
+        );
+    }
+
+    #[test]
+    fn test_undeclared_identifiers() {
+        error_test_func(
+            "int main() {\n  return y;\n}",
+            "2:10: use of undeclared identifier y",
+        );
+        error_test_func(
+            "int main() { { int x=1; } return x; }",
+            "1:34: use of undeclared identifier x",
+        );
+        error_test_func(
+            "int main() { int *p=&p2; return 0; }",
+            "1:22: use of undeclared identifier p2",
+        );
+        error_test_func(
+            "int main() { struct S *p; return sizeof(enum E); }",
+            "1:46: unknown enum E",
+        );
+    }
+
+    #[test]
+    fn test_redeclared_identifiers() {
+        error_test_func(
+            "int main() {\n  int x;\n  long x;\n  return 0;\n}",
+            "3:8: redeclaration of x, previous declaration at 2:7",
+        );
+        error_test_func(
+            "int f(int a, int a) { return a; }",
+            "1:18: redeclaration of a, previous declaration at 1:11",
+        );
+        error_test_func(
+            "int f(int a) { int a; return a; }",
+            "1:20: redeclaration of a, previous declaration at 1:11",
+        );
+        error_test_func(
+            "int x;\nint x() { return 0; }",
+            "2:5: redeclaration of x, previous declaration at 1:5",
+        );
+        error_test_func(
+            "typedef int T;\ntypedef long T;",
+            "2:14: redeclaration of T, previous declaration at 1:13",
+        );
+        error_test_func(
+            "enum { A, B };\nint B;",
+            "2:5: redeclaration of B, previous declaration at 1:11",
+        );
+        error_test_func(
+            "int main() { static int s; static int s; return 0; }",
+            "1:39: redeclaration of s, previous declaration at 1:25",
+        );
+        error_test_func(
+            "int main() { int v; typedef int v; return 0; }",
+            "1:33: redeclaration of v, previous declaration at 1:18",
+        );
+    }
+
+    #[test]
+    fn test_redefined_and_conflicting_functions() {
+        error_test_func(
+            "int f() { return 1; }\nint f() { return 2; }",
+            "2:5: redefinition of f, previous definition at 1:5",
+        );
+        error_test_func(
+            "int f(int a);\nint f(int a) { return a; }\nint f(int b) { return b; }",
+            "3:5: redefinition of f, previous definition at 2:5",
+        );
+        error_test_func(
+            "int f(int a, int b);\nint f(int a) { return a; }",
+            "2:5: conflicting types for f, previous declaration at 1:5",
+        );
+        error_test_func(
+            "int f(int);\nlong f(int);",
+            "2:6: conflicting types for f, previous declaration at 1:5",
+        );
+        error_test_func(
+            "int f(int, ...);\nint f(int);",
+            "2:5: conflicting types for f, previous declaration at 1:5",
+        );
+        // a declaration in a block must agree with the one at file scope
+        error_test_func(
+            "int f(int);\nint main() { char f(int); return 0; }",
+            "2:19: conflicting types for f, previous declaration at 1:5",
+        );
+    }
+
+    #[test]
+    fn test_redefined_tags_and_members() {
+        error_test_func(
+            "struct S { int a; };\nstruct S { int b; };",
+            "2:8: redefinition of S, previous declaration at 1:8",
+        );
+        error_test_func(
+            "struct S;\nunion S *p;",
+            "2:7: S used as a union, previous declaration at 1:8",
+        );
+        error_test_func(
+            "enum E { A };\nenum E { B };",
+            "2:6: redefinition of E, previous declaration at 1:6",
+        );
+        error_test_func(
+            "struct S {\n  int a;\n  char b;\n  long a;\n};",
+            "4:8: duplicate member a, previous declaration at 2:7",
+        );
+    }
+}
@@ -0,0 +1,160 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{compile_and_run_ir, compile_ir_error, emit_ir, program_test_func};
+
+    #[test]
+    fn test_mem2reg() {
+        // the variable is a phi where the paths meet
+        let text = emit_ir(&[], "int f(int c) { int x = 1; if (c) x = 2; return x; }");
+        assert!(!text.contains("slot"), "{}", text);
+        assert!(
+            !text.contains("load") && !text.contains("store"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains(": i32 = phi i32 bb0: 1, bb1: 2\n"),
+            "{}",
+            text
+        );
+        // no phi where the variable is dead
+        let text = emit_ir(
+            &[],
+            "int f(int c) { int x = 1; if (c) x = 2; x = 3; return x; }",
+        );
+        assert!(!text.contains("phi"), "{}", text);
+        assert!(text.contains("  ret 3\n"), "{}", text);
+        // a variable whose address is taken stays in memory, as do the
+        // arrays and the structs
+        let text = emit_ir(
+            &[],
+            "struct P { int a; int b; }; int main() { int x = 1; int *p = &x; int a[2]; struct P s; \
+             a[0] = 2; s.a = 3; return *p + a[0] + s.a; }",
+        );
+        assert!(text.contains("slot \"x\""), "{}", text);
+        assert!(text.contains("slot \"a\""), "{}", text);
+        assert!(text.contains("slot \"s\""), "{}", text);
+        assert!(!text.contains("slot \"p\""), "{}", text);
+        // the passes can be left out
+        let text = emit_ir(&["--passes="], "int main() { int x = 1; return x; }");
+        assert!(text.contains("slot \"x\""), "{}", text);
+        // a loop back to the entry block would need a phi there, bb0 can't be
+        // branched to
+        let text = "function @main() -> i32 {\n\
+                    \x20 $0 = slot \"x\", size 4, align 4\n\
+                    bb0:\n\
+                    \x20 %0: ptr = slotaddr $0\n\
+                    \x20 %1: i32 = load i32 %0\n\
+                    \x20 %2: i32 = add i32 %1, 1\n\
+                    \x20 store i32 %2, %0\n\
+                    \x20 %3: i32 = slt i32 %2, 5\n\
+                    \x20 branch i32 %3, bb0, bb1\n\
+                    bb1:\n\
+                    \x20 ret %2\n\
+                    }\n";
+        let message = compile_ir_error(&[], text);
+        assert!(
+            message.contains("the entry block bb0 can't be branched to"),
+            "{}",
+            message
+        );
+        compile_ir_error(
+            &["--passes=frob"],
+            "function @f() -> void {\nbb0:\n  ret\n}\n",
+        );
+    }
+
+    #[test]
+    fn test_promoted_programs() {
+        // the values swapped in a loop
+        program_test_func(
+            "int gcd(int a, int b) { while (b) { int t = a % b; a = b; b = t; } return a; } \
+             int main() { return gcd(84, 36); }",
+            12,
+        );
+        program_test_func(
+            "int main() { int a[5] = {1, 2, 3, 4, 5}; int *p = a; int s = 0; int i; \
+             for (i = 0; i < 5; i = i + 1) { if (i == 1) continue; s = s + *p; p = p + 1; } return s; }",
+            10,
+        );
+        program_test_func(
+            "int f(int n) { int r = 0; again: switch (n) { case 0: return r; case 1: r = r + 1; \
+             default: r = r + 10; } n = n - 1; goto again; } int main() { return f(3); }",
+            31,
+        );
+        program_test_func(
+            "int main() { int a = 0; int b = 3; int c = a || b; int d = a && b; long l = 5; \
+             char ch = 200; unsigned char u = ch; return c + d * 2 + l + (u > 100); }",
+            7,
+        );
+        // a variable read before it is assigned
+        program_test_func(
+            "int main() { int i; int s; for (i = 0; i < 3; i = i + 1) { if (i > 0) s = s + i; else s = 1; } \
+             return s; }",
+            4,
+        );
+    }
+
+    #[test]
+    fn test_out_of_ssa() {
+        // the phi is read after the loop, where the copy closing the loop
+        // must not go: the lost copy problem
+        let text = "function @main() -> i32 {\n\
+                    bb0:\n\
+                    \x20 jump bb1\n\
+                    bb1:\n\
+                    \x20 %0: i32 = phi i32 bb0: 1, bb1: %1\n\
+                    \x20 %1: i32 = add i32 %0, 1\n\
+                    \x20 %2: i32 = slt i32 %1, 5\n\
+                    \x20 branch i32 %2, bb1, bb2\n\
+                    bb2:\n\
+                    \x20 ret %0\n\
+                    }\n";
+        assert_eq!(compile_and_run_ir(text), 4);
+        // the phis read each other at once: the swap problem
+        let text = "function @main() -> i32 {\n\
+                    bb0:\n\
+                    \x20 jump bb1\n\
+                    bb1:\n\
+                    \x20 %0: i32 = phi i32 bb0: 1, bb1: %1\n\
+                    \x20 %1: i32 = phi i32 bb0: 2, bb1: %0\n\
+                    \x20 %2: i32 = phi i32 bb0: 0, bb1: %3\n\
+                    \x20 %3: i32 = add i32 %2, 1\n\
+                    \x20 %4: i32 = slt i32 %3, 3\n\
+                    \x20 branch i32 %4, bb1, bb2\n\
+                    bb2:\n\
+                    \x20 %5: i32 = mul i32 %0, 10\n\
+                    \x20 %6: i32 = add i32 %5, %1\n\
+                    \x20 ret %6\n\
+                    }\n";
+        assert_eq!(compile_and_run_ir(text), 12);
+        // three values rotated, and a copy of a constant
+        let text = "function @main() -> i32 {\n\
+                    bb0:\n\
+                    \x20 jump bb1\n\
+                    bb1:\n\
+                    \x20 %0: i32 = phi i32 bb0: 1, bb1: %1\n\
+                    \x20 %1: i32 = phi i32 bb0: 2, bb1: %2\n\
+                    \x20 %2: i32 = phi i32 bb0: 3, bb1: %0\n\
+                    \x20 %3: i32 = phi i32 bb0: 0, bb1: %4\n\
+                    \x20 %5: i64 = phi i64 bb0: 7, bb1: 0\n\
+                    \x20 %4: i32 = add i32 %3, 1\n\
+                    \x20 %6: i32 = slt i32 %4, 2\n\
+                    \x20 branch i32 %6, bb1, bb2\n\
+                    bb2:\n\
+                    \x20 %7: i32 = mul i32 %0, 100\n\
+                    \x20 %8: i32 = mul i32 %1, 10\n\
+                    \x20 %9: i32 = add i32 %7, %8\n\
+                    \x20 %10: i32 = add i32 %9, %2\n\
+                    \x20 %11: i32 = trunc i64 %5\n\
+                    \x20 %12: i32 = add i32 %10, %11\n\
+                    \x20 %13: i32 = srem i32 %12, 256\n\
+                    \x20 ret %13\n\
+                    }\n";
+        // (2, 3, 1) after one turn of the loop, 231 % 256
+        assert_eq!(compile_and_run_ir(text), 231);
+    }
+}
@@ -0,0 +1,208 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{
+        compile_error_with, error_test_func, files_test_func, gcc_test_func, program_test_func,
+        warning_test_func,
+    };
+
+    #[test]
+    fn test_tentative_definitions() {
+        program_test_func("int x; int x; int main() { return x; }", 0);
+        program_test_func("int x; int x=3; int x; int main() { return x; }", 3);
+        program_test_func("int x=4; int x; int main() { return x; }", 4);
+        program_test_func(
+            "int a[]; int a[4]; int main() { a[3]=2; return sizeof(a)+a[3]; }",
+            18,
+        );
+        program_test_func(
+            "extern int a[]; int f() { return a[1]; } int a[]={5,6}; int main() { return f(); }",
+            6,
+        );
+        program_test_func("extern int x; int x=7; int main() { return x; }", 7);
+        // an array never completed has one element
+        program_test_func(
+            "int a[]; int f() { a[0]=3; return a[0]; } int b=4; int main() { return f()+b; }",
+            7,
+        );
+        warning_test_func(
+            &[],
+            "int x;\nint a[];\nextern int b[];\nint main() { return 0; }",
+            &["warning: 2:5: array a assumed to have one element"],
+        );
+        let message = compile_error_with(&["-Werror"], "int a[];\nint main() { return 0; }");
+        assert!(
+            message.contains("error: 1:5: array a assumed to have one element [-Werror]"),
+            "{}",
+            message
+        );
+        error_test_func(
+            "int main() { static int a[]; return 0; }",
+            "1:25: array size missing in a",
+        );
+    }
+
+    #[test]
+    fn test_incomplete_objects() {
+        // a tentative definition is completed by the end of the file
+        program_test_func(
+            "struct S g; struct S { int a; }; int main() { g.a=5; return g.a; }",
+            5,
+        );
+        error_test_func(
+            "struct S;\nstruct S g;\nint main() { return 0; }",
+            "2:10: variable g has incomplete type struct S",
+        );
+        error_test_func(
+            "struct S;\nstruct S g={1};",
+            "2:10: variable g has incomplete type struct S",
+        );
+        error_test_func(
+            "struct S;\nint main() { struct S x; return 0; }",
+            "2:23: variable x has incomplete type struct S",
+        );
+        error_test_func(
+            "struct S;\nint main() { static struct S x; return 0; }",
+            "2:30: variable x has incomplete type struct S",
+        );
+        error_test_func(
+            "struct S;\nint main() { struct S a[]={1}; return 0; }",
+            "2:23: variable a has incomplete type struct S []",
+        );
+        error_test_func(
+            "int main() { void v; return 0; }",
+            "1:19: variable v has incomplete type void",
+        );
+    }
+
+    #[test]
+    fn test_static_variables() {
+        program_test_func("static int x=3; int main() { return x; }", 3);
+        program_test_func("static int x; static int x; int main() { return x; }", 0);
+        program_test_func("static int x=2; extern int x; int main() { return x; }", 2);
+        program_test_func(
+            "int f() { static int n; n=n+1; return n; } int main() { f(); f(); return f(); }",
+            3,
+        );
+        program_test_func(
+            "int f() { static int n=10; return n=n+1; } \
+             int g() { static int n=20; return n=n+1; } \
+             int main() { f(); g(); return f()+g(); }",
+            34,
+        );
+        program_test_func(
+            "int *f() { static int a[]={1,2,3}; return a; } int main() { f()[1]=9; return f()[1]; }",
+            9,
+        );
+        program_test_func(
+            r#"char *f() { static char *s="hello"; return s; } int main() { return f()[4]; }"#,
+            111,
+        );
+        program_test_func(
+            "int x=1; int main() { static int *p=&x; { static int x=5; return *p+x; } }",
+            6,
+        );
+    }
+
+    #[test]
+    fn test_extern_declarations() {
+        program_test_func("int main() { extern int x; return x; } int x=8;", 8);
+        program_test_func(
+            "int x=1; int main() { int x=2; { extern int x; return x; } }",
+            1,
+        );
+        program_test_func(
+            "static int f() { return 5; } int g(); int main() { return f()+g(); } \
+             static int f(); int g() { return f(); }",
+            10,
+        );
+        // an object of incomplete type may be declared and its address taken
+        program_test_func("extern struct S s; int main() { return 0; }", 0);
+        gcc_test_func(
+            "extern struct S s; int get(struct S *p); int main() { struct S *p=&s; return get(p); }",
+            "struct S { int a; }; struct S s={7}; int get(struct S *p) { return p->a; }",
+            7,
+        );
+    }
+
+    #[test]
+    fn test_conflicting_declarations() {
+        error_test_func(
+            "int x;\nlong x;",
+            "2:6: conflicting types for x, previous declaration at 1:5",
+        );
+        error_test_func(
+            "int a[2];\nint a[3];",
+            "2:5: conflicting types for a, previous declaration at 1:5",
+        );
+        error_test_func(
+            "int x;\nstatic int x;",
+            "2:12: static declaration of x follows non-static declaration, previous declaration at 1:5",
+        );
+        error_test_func(
+            "static int x;\nint x;",
+            "2:5: non-static declaration of x follows static declaration, previous declaration at 1:12",
+        );
+        error_test_func(
+            "int x=1;\nint x;\nint x=2;",
+            "3:5: redefinition of x, previous definition at 1:5",
+        );
+        error_test_func(
+            "int f();\nstatic int f() { return 0; }",
+            "2:12: static declaration of f follows non-static declaration, previous declaration at 1:5",
+        );
+    }
+
+    #[test]
+    fn test_linkage_with_gcc() {
+        gcc_test_func(
+            "extern int shared; int main() { return shared; }",
+            "int shared = 12;",
+            12,
+        );
+        gcc_test_func(
+            "int shared; int set(); int main() { set(); return shared; }",
+            "extern int shared; int set() { shared = 13; return 0; }",
+            13,
+        );
+        // the static definitions are not visible to gcc's file
+        gcc_test_func(
+            "static int value=1; static int get() { return value; } int other(); \
+             int main() { return get()*10+other(); }",
+            "static int value = 2; static int get() { return value; } int other() { return get(); }",
+            12,
+        );
+    }
+
+    #[test]
+    fn test_multiple_files() {
+        files_test_func(
+            &[
+                "int counter; int bump() { counter=counter+1; return counter; }",
+                "extern int counter; int bump(); int main() { bump(); bump(); return counter; }",
+            ],
+            "",
+            2,
+        );
+        files_test_func(
+            &[
+                "static int n=3; static int f() { return n; } int a() { return f(); }",
+                "static int n=4; static int f() { return n; } int b() { return f(); }",
+                "int a(); int b(); int main() { return a()*10+b(); }",
+            ],
+            "",
+            34,
+        );
+        files_test_func(
+            &[
+                "int count() { static int n; n=n+1; return n; }",
+                "int next() { static int n=100; n=n+1; return n; }",
+                "int count(); int next(); int main() { count(); next(); return count()+next(); }",
+            ],
+            "",
+            104,
+        );
+    }
+}
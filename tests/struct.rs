@@ -0,0 +1,248 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    #[test]
+    fn test_function_call() {
+        program_test_func("int ret3() { return 3; } int main() { return ret3(); }", 3);
+        program_test_func(
+            "int add(int x, int y) { return x+y; } int main() { return add(3, 5); }",
+            8,
+        );
+        program_test_func(
+            "int sub6(int a, int b, int c, int d, int e, int f) { return a-b-c-d-e-f; } \
+             int main() { return sub6(100, 1, 2, 3, 4, 5); }",
+            85,
+        );
+        program_test_func(
+            "int fib(int x) { if (x<=1) return 1; return fib(x-1)+fib(x-2); } \
+             int main() { return fib(9); }",
+            55,
+        );
+        program_test_func(
+            "int sum(int *a, int n) { int s=0; int i; for (i=0; i<n; i=i+1) s=s+a[i]; return s; } \
+             int main() { int a[3]; a[0]=1; a[1]=2; a[2]=3; return sum(a, 3); }",
+            6,
+        );
+        program_test_func(
+            "long twice(long x); int main() { return twice(21); } long twice(long x) { return x*2; }",
+            42,
+        );
+    }
+
+    #[test]
+    fn test_struct_member() {
+        program_test_func(
+            "int main() { struct {int a; int b;} x; x.a=1; x.b=2; return x.a; }",
+            1,
+        );
+        program_test_func(
+            "int main() { struct {int a; int b;} x; x.a=1; x.b=2; return x.b; }",
+            2,
+        );
+        program_test_func(
+            "int main() { struct {int a; long b; int c;} x; x.a=1; x.b=2; x.c=3; return x.a+x.b+x.c; }",
+            6,
+        );
+        program_test_func(
+            "int main() { struct {int a[3];} x; x.a[0]=5; x.a[2]=7; return x.a[0]+x.a[2]; }",
+            12,
+        );
+        program_test_func(
+            "int main() { struct {struct {int b;} a;} x; x.a.b=6; return x.a.b; }",
+            6,
+        );
+        program_test_func(
+            "int main() { struct {int a; int b;} x[3]; int *p=x; p[1]=7; return x[0].b; }",
+            7,
+        );
+        program_test_func(
+            "int main() { struct t {int a; int b;} x; struct t *y=&x; y->a=3; y->b=4; return x.a*x.b; }",
+            12,
+        );
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        program_test_func(
+            "int main() { struct {int a; int b;} x; return sizeof(x); }",
+            8,
+        );
+        program_test_func(
+            "int main() { struct {int a; long b;} x; return sizeof(x); }",
+            16,
+        );
+        program_test_func(
+            "int main() { struct {long a; int b;} x; return sizeof(x); }",
+            16,
+        );
+        program_test_func("int main() { struct {int a[3];} x; return sizeof(x); }", 12);
+        program_test_func("int main() { struct {int a;} x[4]; return sizeof(x); }", 16);
+        program_test_func(
+            "int main() { struct {long a; int b; long c;} x; return (&x.c)-(&x.a); }",
+            2,
+        );
+        program_test_func("int main() { struct {} x; return sizeof(x); }", 0);
+    }
+
+    #[test]
+    fn test_struct_tag_and_scope() {
+        program_test_func(
+            "int main() { struct t {int a; int b;} x; struct t y; return sizeof(y); }",
+            8,
+        );
+        program_test_func(
+            "int main() { struct t {int a; int b;}; struct t y; return sizeof(y); }",
+            8,
+        );
+        program_test_func(
+            "int main() { struct t {int a[2];}; { struct t {int a[4];}; } struct t y; return sizeof(y); }",
+            8,
+        );
+        program_test_func(
+            "int main() { struct t {int x;}; int t=1; struct t y; y.x=2; return t+y.x; }",
+            3,
+        );
+        program_test_func(
+            "struct t {int a; long b;}; int f(struct t *p) { return p->a+p->b; } \
+             int main() { struct t x; x.a=3; x.b=4; return f(&x); }",
+            7,
+        );
+        // a self referential struct through an incomplete type
+        program_test_func(
+            "int main() { struct node {int v; struct node *next;} a; struct node b; \
+             a.v=1; b.v=2; a.next=&b; return a.next->v; }",
+            2,
+        );
+        program_test_func(
+            "int main() { struct t *p; struct t {int a; int b;} x; p=&x; p->b=9; return x.b; }",
+            9,
+        );
+    }
+
+    #[test]
+    fn test_struct_assign() {
+        program_test_func(
+            "int main() { struct t {int a; long b;} x; struct t y; x.a=3; x.b=5; y=x; return y.a+y.b; }",
+            8,
+        );
+        program_test_func(
+            "int main() { struct t {int a[3];} x; struct t y; x.a[2]=7; y=x; x.a[2]=1; return y.a[2]; }",
+            7,
+        );
+    }
+
+    #[test]
+    fn test_union() {
+        program_test_func(
+            "int main() { union {int a; long b;} x; return sizeof(x); }",
+            8,
+        );
+        program_test_func(
+            "int main() { union {int a[3]; long b;} x; return sizeof(x); }",
+            16,
+        );
+        program_test_func(
+            "int main() { union {int a[2]; long b;} x; x.b=4294967298; return x.a[0]+x.a[1]; }",
+            3,
+        );
+        program_test_func(
+            "int main() { union {int a; long b;} x; x.b=0; x.a=5; return x.b; }",
+            5,
+        );
+    }
+
+    #[test]
+    fn test_bit_field() {
+        program_test_func(
+            "int main() { struct {int a:3; int b:5;} x; x.a=1; x.b=3; return x.a+x.b; }",
+            4,
+        );
+        program_test_func(
+            "int main() { struct {int a:3; int b:5;} x; return sizeof(x); }",
+            4,
+        );
+        // the bit-fields are signed: 7 in 3 bits is -1
+        program_test_func(
+            "int main() { struct {int a:3;} x; x.a=7; return x.a==-1; }",
+            1,
+        );
+        program_test_func("int main() { struct {int a:3;} x; return (x.a=9)==1; }", 1);
+        program_test_func(
+            "int main() { struct {int a:3; int b:29; int c;} x; x.c=0; x.a=-1; x.b=100; return x.b+x.c; }",
+            100,
+        );
+        program_test_func(
+            "int main() { struct {long a:60; int b:5;} x; x.a=1; x.b=2; return sizeof(x)+x.a+x.b; }",
+            19,
+        );
+    }
+
+    /// The layout of every struct must be the one gcc computes.
+    #[test]
+    fn test_layout_matches_gcc() {
+        let decls = [
+            "struct t { int a; long b; int c; };",
+            "struct t { int a[3]; long b; int c[1]; };",
+            "struct t { int a; struct { long b; int c; } s; int d; };",
+            "struct t { int a:3; long b:40; int c; };",
+            "struct t { long a:60; int b:5; };",
+            "struct t { int a; int :5; int b:28; };",
+            "struct t { int a:1; long :0; int b:1; };",
+            "struct t { long a:3; int :0; int b; };",
+            "struct t { int a:31; int b:2; long c:33; long d:33; };",
+            "struct t { int x; union { long y:5; int z; } u; int w:4; };",
+            "union t { int a:3; long b:40; int c; };",
+        ];
+        for decl in decls {
+            let kind = if decl.starts_with("union") {
+                "union"
+            } else {
+                "struct"
+            };
+            // the alignment shows in the padding before `x` in the wrapper
+            let gcc_source = format!(
+                "{} struct w {{ int c; {} t x; }}; \
+                 long gcc_size(void) {{ return sizeof({} t); }} \
+                 long gcc_wrapped_size(void) {{ return sizeof(struct w); }}",
+                decl, kind, kind
+            );
+            let program = format!(
+                "{} struct w {{ int c; {} t x; }}; long gcc_size(); long gcc_wrapped_size(); \
+                 int main() {{ {} t x; struct w w; \
+                   if (gcc_size()!=sizeof(x)) return 1; \
+                   if (gcc_wrapped_size()!=sizeof(w)) return 2; \
+                   return 0; }}",
+                decl, kind, kind
+            );
+            gcc_test_func(&program, &gcc_source, 0);
+        }
+    }
+
+    /// Structs written by gcc compiled code are read back by susuncc compiled code
+    /// and the other way around, including bit-fields.
+    #[test]
+    fn test_struct_crosses_gcc_boundary() {
+        let decl =
+            "struct t { int a; long b; int c:5; int d:20; long e:33; int f[2]; struct t *next; };";
+        let gcc_source = format!(
+            "{} void gcc_fill(struct t *p) {{ p->a=1; p->b=2; p->c=-3; p->d=4; p->e=5; p->f[1]=6; p->next=p; }} \
+             int gcc_check(struct t *p) {{ return p->a==7 && p->b==8 && p->c==9 && p->d==-10 && p->e==11 && p->f[1]==12 && p->next==0; }}",
+            decl
+        );
+        let program = format!(
+            "{} int gcc_check(struct t *p); \
+             int main() {{ struct t x; gcc_fill(&x); \
+               if (x.next!=&x) return 1; \
+               if (x.a+x.b+x.c+x.d+x.e+x.f[1]!=15) return 2; \
+               if (x.c!=-3) return 3; \
+               x.a=7; x.b=8; x.c=9; x.d=-10; x.e=11; x.f[1]=12; x.next=0; \
+               return gcc_check(&x)==1; }}",
+            decl
+        );
+        gcc_test_func(&program, &gcc_source, 1);
+    }
+}
@@ -0,0 +1,232 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    /// The declaration of `struct S`, an array of `n` chars.
+    fn bytes_struct(n: usize) -> String {
+        format!("struct S {{ char a[{}]; }};", n)
+    }
+
+    #[test]
+    fn test_struct_by_value() {
+        program_test_func(
+            "struct P { int x; int y; }; int f(struct P p) { return p.x*10+p.y; } \
+             int main() { struct P p; p.x=4; p.y=2; return f(p); }",
+            42,
+        );
+        program_test_func(
+            "struct P { int x; int y; }; int f(struct P p) { p.x=0; return p.y; } \
+             int main() { struct P p; p.x=4; p.y=2; f(p); return p.x; }",
+            4,
+        );
+        program_test_func(
+            "struct L { long a; long b; long c; }; long f(int x, struct L l, int y) \
+             { return l.a+l.b+l.c+x*y; } \
+             int main() { struct L l; l.a=1; l.b=2; l.c=3; return f(5, l, 6); }",
+            36,
+        );
+        program_test_func(
+            "struct P { int x; int y; }; struct P mk(int x, int y) { struct P p; p.x=x; p.y=y; return p; } \
+             int main() { struct P p=mk(3, 4); return p.x*10+p.y; }",
+            34,
+        );
+        program_test_func(
+            "struct L { long a; long b; long c; }; \
+             struct L mk(long x) { struct L l; l.a=x; l.b=x*2; l.c=x*3; return l; } \
+             int main() { struct L l; l=mk(7); return l.a+l.b+l.c; }",
+            42,
+        );
+        program_test_func(
+            "struct T { char c[3]; }; struct T mk() { struct T t; t.c[0]=1; t.c[1]=2; t.c[2]=3; return t; } \
+             int main() { return mk().c[2]*10+mk().c[0]; }",
+            31,
+        );
+        program_test_func(
+            "struct L { long a[4]; }; struct L id(struct L l) { return l; } \
+             int main() { struct L l; l.a[0]=1; l.a[3]=4; return id(id(l)).a[3]*10+id(l).a[0]; }",
+            41,
+        );
+        program_test_func(
+            "union U { char c[12]; int i; }; union U f(union U u) { u.c[11]=u.c[11]+1; return u; } \
+             int main() { union U u; u.i=5; u.c[11]=6; u=f(u); return u.i*10+u.c[11]; }",
+            57,
+        );
+        program_test_func(
+            "struct P { long x; long y; }; struct P (*fp)(struct P); \
+             struct P swap(struct P p) { struct P q; q.x=p.y; q.y=p.x; return q; } \
+             int main() { fp=swap; struct P p; p.x=1; p.y=2; return fp(p).x*10+fp(p).y; }",
+            21,
+        );
+    }
+
+    #[test]
+    fn test_struct_arguments_on_the_stack() {
+        // a struct needing more registers than left goes to the stack,
+        // the next arguments still take the registers left
+        program_test_func(
+            "struct P { long x; long y; }; \
+             long f(long a, long b, long c, long d, long e, struct P p, long g) \
+             { return a+b+c+d+e+p.x*10+p.y*20+g*100; } \
+             int main() { struct P p; p.x=1; p.y=2; return f(1, 2, 3, 4, 5, p, 1) - 100; }",
+            65,
+        );
+        program_test_func(
+            "int f(int a, int b, int c, int d, int e, int f, int g, int h) { return a-b+c-d+e-f+g*h; } \
+             int main() { return f(1, 2, 3, 4, 5, 6, 7, 8); }",
+            53,
+        );
+        program_test_func(
+            "struct L { char s[20]; }; \
+             int f(int a, struct L l, int b, int c, int d, int e, int f, int g, struct L m) \
+             { return a+l.s[19]+b+c+d+e+f+g+m.s[0]; } \
+             int main() { struct L l; l.s[19]=10; struct L m; m.s[0]=20; return f(1, l, 2, 3, 4, 5, 6, 7, m); }",
+            58,
+        );
+        program_test_func(
+            "struct P { int x; int y; }; int sum(int n, struct P p, ...) \
+             { va_list ap; va_start(ap, p); int s=p.x+p.y; while (n) { s=s+va_arg(ap, int); n=n-1; } \
+             va_end(ap); return s; } \
+             int main() { struct P p; p.x=1; p.y=2; return sum(7, p, 1, 2, 3, 4, 5, 6, 7); }",
+            31,
+        );
+    }
+
+    #[test]
+    fn test_struct_va_arg() {
+        // two eightbytes in the register save area while two registers are
+        // left, else in the overflow area, the next long still in a register
+        program_test_func(
+            "struct P { long x; long y; }; \
+             long f(int n, ...) { va_list ap; va_start(ap, n); struct P a=va_arg(ap, struct P); \
+             struct P b=va_arg(ap, struct P); struct P c=va_arg(ap, struct P); long d=va_arg(ap, long); \
+             va_end(ap); return a.x+a.y*2+b.x*3+b.y*4+c.x*5+c.y*6+d*7; } \
+             int main() { struct P p={1, 2}; struct P q={3, 4}; struct P r={5, 6}; \
+             return f(0, p, q, r, 1); }",
+            98,
+        );
+        // more than 16 bytes, always in the overflow area
+        program_test_func(
+            "struct L { long a[3]; }; \
+             long f(int n, ...) { va_list ap; va_start(ap, n); long s=0; while (n) { \
+             struct L l=va_arg(ap, struct L); s=s*10+l.a[0]+l.a[2]; n=n-1; } \
+             s=s*10+va_arg(ap, int); va_end(ap); return s; } \
+             int main() { struct L l={{1, 0, 2}}; struct L m={{3, 0, 1}}; return f(2, l, m, 5) - 300; }",
+            45,
+        );
+        // a struct smaller than an eightbyte, passed by gcc
+        gcc_test_func(
+            "struct C { char a; char b; }; struct P { int x; int y; }; \
+             int f(int n, ...) { va_list ap; va_start(ap, n); int s=0; while (n) { \
+             struct C c=va_arg(ap, struct C); struct P p=va_arg(ap, struct P); \
+             s=s+c.a*c.b+p.x-p.y; n=n-1; } va_end(ap); return s; }",
+            "struct C { char a; char b; }; struct P { int x; int y; }; int f(int n, ...); \
+             int main() { struct C c={2, 3}; struct P p={10, 4}; return f(4, c, p, c, p, c, p, c, p); }",
+            48,
+        );
+    }
+
+    #[test]
+    fn test_struct_sizes_with_gcc_callee() {
+        for n in 1..=32 {
+            let program = format!(
+                "{} int sum(struct S s, int n); struct S make(int seed); int check(int n, struct S s, long x); \
+                 int main() {{ struct S s; int i; for (i=0; i<{n}; i=i+1) s.a[i]=i+1; \
+                 if (sum(s, {n}) != {n}*({n}+1)/2) return 1; \
+                 struct S t=make(3); for (i=0; i<{n}; i=i+1) if (t.a[i] != i+3) return 2; \
+                 if (!check({n}, s, 99)) return 3; return 0; }}",
+                bytes_struct(n),
+                n = n
+            );
+            let gcc_source = format!(
+                "{} int sum(struct S s, int n) {{ int r=0; for (int i=0; i<n; i++) r+=s.a[i]; return r; }} \
+                 struct S make(int seed) {{ struct S s; for (int i=0; i<{n}; i++) s.a[i]=i+seed; return s; }} \
+                 int check(int n, struct S s, long x) {{ return s.a[n-1]==n && x==99; }}",
+                bytes_struct(n),
+                n = n
+            );
+            gcc_test_func(&program, &gcc_source, 0);
+        }
+    }
+
+    #[test]
+    fn test_struct_sizes_with_gcc_caller() {
+        for n in 1..=32 {
+            let program = format!(
+                "{} int sum(struct S s, int n) {{ int r=0; int i; for (i=0; i<n; i=i+1) r=r+s.a[i]; return r; }} \
+                 struct S make(int seed) {{ struct S s; int i; for (i=0; i<{n}; i=i+1) s.a[i]=i+seed; return s; }} \
+                 int check(int n, struct S s, long x) {{ return s.a[n-1]==n && x==99; }}",
+                bytes_struct(n),
+                n = n
+            );
+            let gcc_source = format!(
+                "{} int sum(struct S s, int n); struct S make(int seed); int check(int n, struct S s, long x); \
+                 int main() {{ struct S s; for (int i=0; i<{n}; i++) s.a[i]=i+1; \
+                 if (sum(s, {n}) != {n}*({n}+1)/2) return 1; \
+                 struct S t=make(3); for (int i=0; i<{n}; i++) if (t.a[i] != i+3) return 2; \
+                 if (!check({n}, s, 99)) return 3; return 0; }}",
+                bytes_struct(n),
+                n = n
+            );
+            gcc_test_func(&program, &gcc_source, 0);
+        }
+    }
+
+    #[test]
+    fn test_mixed_members_across_compilers() {
+        let decls = "struct M { char c; int i; long l; }; \
+                     struct Q { short s; int *p; }; \
+                     union U { char c[12]; int i; }; \
+                     struct N { struct M m; char tail; }; ";
+        let gcc_callees = "int g_m(struct M m) { return m.c+m.i+m.l; } \
+             int g_q(struct Q q) { return q.s+*q.p; } \
+             int g_u(union U u) { return u.i+u.c[11]; } \
+             long g_n(struct N n) { return n.m.c+n.m.i+n.m.l+n.tail; } \
+             struct M g_mk_m(char c, int i, long l) { struct M m={c, i, l}; return m; } \
+             union U g_mk_u(int i) { union U u; u.i=i; u.c[11]=i*2; return u; } \
+             struct N g_mk_n(long l) { struct N n; n.m.c=1; n.m.i=2; n.m.l=l; n.tail=4; return n; } \
+             long g_many(long a, long b, long c, long d, long e, struct Q q, struct M m, struct N n, long z) \
+             { return a+b+c+d+e+q.s+m.l+n.tail+z; }";
+        let program = format!(
+            "{} int g_m(struct M m); int g_q(struct Q q); int g_u(union U u); long g_n(struct N n); \
+             struct M g_mk_m(char c, int i, long l); union U g_mk_u(int i); struct N g_mk_n(long l); \
+             long g_many(long a, long b, long c, long d, long e, struct Q q, struct M m, struct N n, long z); \
+             int main() {{ int x=7; struct M m; m.c=1; m.i=2; m.l=3; struct Q q; q.s=4; q.p=&x; \
+             union U u; u.i=5; u.c[11]=6; struct N n; n.m=m; n.tail=8; \
+             if (g_m(m) != 6) return 1; if (g_q(q) != 11) return 2; if (g_u(u) != 11) return 3; \
+             if (g_n(n) != 14) return 4; \
+             struct M r=g_mk_m(10, 20, 30); if (r.c+r.i+r.l != 60) return 5; \
+             union U v=g_mk_u(9); if (v.i != 9 || v.c[11] != 18) return 6; \
+             if (g_mk_n(40).m.l != 40 || g_mk_n(0).tail != 4) return 7; \
+             if (g_many(1, 2, 3, 4, 5, q, m, n, 9) != 39) return 8; \
+             return 0; }}",
+            decls
+        );
+        gcc_test_func(&program, &format!("{}{}", decls, gcc_callees), 0);
+
+        let callees = "int s_m(struct M m) { return m.c+m.i+m.l; } \
+             struct M s_mk_m(char c, int i, long l) { struct M m; m.c=c; m.i=i; m.l=l; return m; } \
+             struct N s_mk_n(struct M m, char tail) { struct N n; n.m=m; n.tail=tail; return n; } \
+             union U s_mk_u(union U u) { u.c[0]=u.c[0]+1; return u; } \
+             long s_many(long a, long b, long c, long d, long e, struct Q q, struct M m, struct N n, long z) \
+             { return a+b+c+d+e+q.s+m.l+n.tail+z; }";
+        let gcc_main = "int s_m(struct M m); struct M s_mk_m(char c, int i, long l); \
+             struct N s_mk_n(struct M m, char tail); union U s_mk_u(union U u); \
+             long s_many(long a, long b, long c, long d, long e, struct Q q, struct M m, struct N n, long z); \
+             int main() { int x=7; struct M m={1, 2, 3}; struct Q q={4, &x}; struct N n={m, 8}; \
+             union U u; u.i=5; \
+             if (s_m(m) != 6) return 1; \
+             struct M r=s_mk_m(10, 20, 30); if (r.c != 10 || r.i != 20 || r.l != 30) return 2; \
+             struct N k=s_mk_n(m, 9); if (k.m.l != 3 || k.tail != 9) return 3; \
+             if (s_mk_u(u).i != 6) return 4; \
+             if (s_many(1, 2, 3, 4, 5, q, m, n, 9) != 39) return 5; \
+             return 0; }";
+        gcc_test_func(
+            &format!("{}{}", decls, callees),
+            &format!("{}{}", decls, gcc_main),
+            0,
+        );
+    }
+}
@@ -0,0 +1,224 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{
+        compile_error_with, error_test_func, program_test_func, warning_test_func,
+    };
+
+    #[test]
+    fn test_valid_conversions() {
+        program_test_func(
+            "int main() { int *p=0; _Bool b=p; int x; p=&x; _Bool c=p; return b*10+c; }",
+            1,
+        );
+        program_test_func(
+            "int main() { int x=3; void *v=&x; int *p=v; char *c=(char *)p; return *p+(c!=0); }",
+            4,
+        );
+        program_test_func(
+            "int main() { int x; int *p=&x; return (p!=0)+(0==p)+(p==p); }",
+            2,
+        );
+        program_test_func(
+            "int main() { char c=300; long l=c; unsigned u=-1; return l+(u>0); }",
+            45,
+        );
+        program_test_func(
+            "int g(); int f(int a, char *s) { return a+s[1]; } int main() { (void)g; return f(1, \"ab\")-g(); } \
+             int g() { return 98; }",
+            1,
+        );
+        program_test_func(
+            "struct S { int a; }; void set(struct S *s, int v) { s->a=v; return; } \
+             int main() { struct S s; set(&s, 5); (void)s; return s.a; }",
+            5,
+        );
+    }
+
+    #[test]
+    fn test_invalid_operands() {
+        error_test_func(
+            "int main() {\n  int x=1;\n  return *x;\n}",
+            "3:10: indirection requires pointer operand (int invalid)",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; return s+1; }",
+            "1:55: invalid operands to binary + (struct S and int)",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; return s*2; }",
+            "1:55: invalid operands to binary * (struct S and int)",
+        );
+        error_test_func(
+            "int main() { int *p; int *q; return p+q; }",
+            "1:38: invalid operands to binary + (int * and int *)",
+        );
+        error_test_func(
+            "int main() { int *p; return 1-p; }",
+            "1:30: invalid operands to binary - (int and int *)",
+        );
+        error_test_func(
+            "int main() { int *p; char *q; return p-q; }",
+            "1:39: invalid operands to binary - (int * and char *)",
+        );
+        error_test_func(
+            "int main() { int *p; return p*2; }",
+            "1:30: invalid operands to binary * (int * and int)",
+        );
+        error_test_func(
+            "void g() {} int main() { return g()+1; }",
+            "1:36: invalid operands to binary + (void and int)",
+        );
+        error_test_func(
+            "int main() { int *p; return -p; }",
+            "1:29: invalid argument type int * to unary -",
+        );
+        error_test_func(
+            "int main() { return &1; }",
+            "1:21: cannot take the address of an rvalue of type int",
+        );
+        error_test_func(
+            "struct B { int x:3; }; int main() { struct B b; int *p=&b.x; return 0; }",
+            "1:56: address of bit-field requested",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; return (int)s; }",
+            "1:54: cannot cast struct S to int",
+        );
+    }
+
+    #[test]
+    fn test_invalid_members_and_calls() {
+        error_test_func(
+            "int main() { int x; return x.a; }",
+            "1:29: member reference base type int is not a structure or union",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; return s.b; }",
+            "1:55: no member named b in struct S",
+        );
+        error_test_func(
+            "struct S; int main() { struct S *p; return p->a; }",
+            "1:45: incomplete definition of type struct S",
+        );
+        error_test_func(
+            "int main() { int x; return x(); }",
+            "1:28: called object type int is not a function or function pointer",
+        );
+        error_test_func(
+            "int f(int a, int b) { return a+b; } int main() { return f(1); }",
+            "1:57: too few arguments to function call, expected 2, have 1",
+        );
+        error_test_func(
+            "int f(int a) { return a; } int main() { return f(1, 2); }",
+            "1:48: too many arguments to function call, expected 1, have 2",
+        );
+        error_test_func(
+            "struct S { int a; }; int f(int a) { return a; } int main() { struct S s; return f(s); }",
+            "1:83: passing struct S to parameter of incompatible type int",
+        );
+    }
+
+    #[test]
+    fn test_incompatible_pointer_types() {
+        // warned about by default, the pointer is converted all the same
+        warning_test_func(
+            &[],
+            "int main() { int x=65; int *p=&x; char *q=p; return *q; }",
+            &["1:43: incompatible pointer types assigning to char * from int * [-Wincompatible-pointer-types]"],
+        );
+        program_test_func(
+            "int main() { int x=65; int *p=&x; char *q=p; return *q; }",
+            65,
+        );
+        warning_test_func(
+            &[],
+            "int f(long *p) { return *p; } int main() { int a[2][3]; int *p=a; f(p); return 0; }\n\
+             char *g(int *p) { return p; }",
+            &[
+                "1:64: incompatible pointer types assigning to int * from int (*)[3]",
+                "1:69: incompatible pointer types passing int * to parameter of type long *",
+                "2:26: incompatible pointer types returning int * from a function with result type char *",
+            ],
+        );
+        // `void *`, compatible pointees and casts are not warned about
+        warning_test_func(
+            &[],
+            "int main() { int x; void *v=&x; int *p=v; int a[3]; int (*q)[]=&a; char *c=(char *)p; \
+             return (v!=0)+(q!=0)+(c!=0); }",
+            &[],
+        );
+        warning_test_func(
+            &["-Wno-incompatible-pointer-types"],
+            "int main() { int *p=0; char *q=p; return q!=0; }",
+            &[],
+        );
+        let message = compile_error_with(
+            &["-Werror"],
+            "int main() { int *p=0; char *q=p; return q!=0; }",
+        );
+        assert!(
+            message.contains("error: 1:32: incompatible pointer types assigning to char * from int * [-Werror=incompatible-pointer-types]"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_invalid_assignments() {
+        error_test_func(
+            "int main() { int a[2]; int b[2]; a=b; return 0; }",
+            "1:35: array type int [2] is not assignable",
+        );
+        error_test_func(
+            "int main() { int x; x+1=2; return 0; }",
+            "1:24: expression is not assignable",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; int x=s; return x; }",
+            "1:53: assigning to int from incompatible type struct S",
+        );
+        error_test_func(
+            "int main() { int *p; int x=p; return x; }",
+            "1:28: assigning to int from incompatible type int *",
+        );
+        error_test_func(
+            "int main() { int *p=5; return 0; }",
+            "1:21: assigning to int * from incompatible type int",
+        );
+        error_test_func(
+            "struct S { int a; }; struct T { int a; }; int main() { struct S s; struct T t; s=t; return 0; }",
+            "1:82: assigning to struct S from incompatible type struct T",
+        );
+    }
+
+    #[test]
+    fn test_invalid_statements() {
+        error_test_func(
+            "void f() { return 1; } int main() { return 0; }",
+            "1:12: void function should not return a value",
+        );
+        error_test_func(
+            "int f() { return; } int main() { return 0; }",
+            "1:11: non-void function should return a value",
+        );
+        error_test_func(
+            "struct S { int a; }; int f() { struct S s; return s; } int main() { return 0; }",
+            "1:51: returning struct S from a function with incompatible result type int",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; if (s) return 1; return 0; }",
+            "1:51: statement requires expression of scalar type (struct S invalid)",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; return !s; }",
+            "1:55: statement requires expression of scalar type (struct S invalid)",
+        );
+        error_test_func(
+            "struct S { int a; }; int main() { struct S s; switch (s) { } return 0; }",
+            "1:55: statement requires expression of integer type (struct S invalid)",
+        );
+    }
+}
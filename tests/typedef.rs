@@ -0,0 +1,94 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::program_test_func;
+
+    #[test]
+    fn test_typedef() {
+        program_test_func("typedef int MyInt; int main() { MyInt x=3; return x; }", 3);
+        program_test_func(
+            "typedef int MyInt, *MyIntPtr; int main() { MyInt x=3; MyIntPtr p=&x; return *p; }",
+            3,
+        );
+        program_test_func("typedef long L; int main() { L x; return sizeof(x); }", 8);
+        program_test_func(
+            "typedef int A[4]; int main() { A x; return sizeof(x); }",
+            16,
+        );
+        program_test_func(
+            "typedef int A[4]; int main() { A x[2]; return sizeof(x); }",
+            32,
+        );
+        program_test_func("int main() { typedef int t; t x=1; return x; }", 1);
+        program_test_func(
+            "typedef int t; typedef t u; int main() { u x=2; return x; }",
+            2,
+        );
+    }
+
+    #[test]
+    fn test_typedef_of_struct() {
+        program_test_func(
+            "typedef struct { int a; long b; } Pair; int main() { Pair p; p.a=3; p.b=4; return p.a+p.b; }",
+            7,
+        );
+        program_test_func(
+            "typedef struct node Node; struct node { int v; Node *next; }; \
+             int main() { Node a; Node b; a.v=1; b.v=2; a.next=&b; return a.next->v; }",
+            2,
+        );
+        program_test_func(
+            "typedef struct { int x; } S; int get(S *s) { return s->x; } S *id(S *s) { return s; } \
+             int main() { S s; s.x=5; return get(id(&s)); }",
+            5,
+        );
+        program_test_func(
+            "typedef enum { red, green } Color; int main() { Color c=green; return c; }",
+            1,
+        );
+    }
+
+    #[test]
+    fn test_typedef_name_disambiguation() {
+        // `T * x` declares a pointer when T is a typedef name ...
+        program_test_func(
+            "typedef int T; int main() { int y=4; T * x=&y; return *x; }",
+            4,
+        );
+        // ... and multiplies when it is a variable
+        program_test_func("int main() { int T=3; int x=2; T * x; return T * x; }", 6);
+    }
+
+    #[test]
+    fn test_typedef_shadowing() {
+        // an inner variable hides the typedef name for the rest of its scope
+        program_test_func("typedef int T; int main() { int T=3; return T; }", 3);
+        program_test_func(
+            "typedef int T; int main() { { int T=3; } T x=5; return x; }",
+            5,
+        );
+        program_test_func(
+            "typedef int T; int main() { T x=1; { T T=2; return T+x; } }",
+            3,
+        );
+        program_test_func("typedef int T; int main() { enum { T=7 }; return T; }", 7);
+        // an inner typedef hides a variable
+        program_test_func(
+            "int main() { int T=3; { typedef long T; T x; return sizeof(x); } }",
+            8,
+        );
+        program_test_func("int main() { int T=3; { typedef long T; } return T; }", 3);
+        // a parameter hides the typedef name inside the function
+        program_test_func(
+            "typedef int T; int f(int T) { return T*2; } int main() { return f(4); }",
+            8,
+        );
+        // struct members don't hide typedef names
+        program_test_func(
+            "typedef int T; int main() { struct { T T; } s; s.T=3; T y=s.T; return y; }",
+            3,
+        );
+    }
+}
@@ -0,0 +1,72 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{program_test_func, warning_test_func};
+
+    #[test]
+    fn test_uninitialized() {
+        let program = "int main() {\n  int x;\n  int y;\n  y = x + 1;\n  return x + y;\n}";
+        warning_test_func(&[], program, &[]);
+        warning_test_func(
+            &["-Wall"],
+            program,
+            &["warning: 4:7: variable x is uninitialized when used here, declared at 2:7 [-Wuninitialized]"],
+        );
+        warning_test_func(
+            &["-Wuninitialized"],
+            "int main() {\n  int x;\n  x = x + 1;\n  return 0;\n}",
+            &["3:7: variable x is uninitialized when used here"],
+        );
+        // initialized, assigned on every path, or through a pointer
+        warning_test_func(
+            &["-Wall"],
+            "int f(int c) {\n  int x = c;\n  int y;\n  if (c) y = 1; else y = 2;\n  int z;\n  int *p = &z;\n  *p = 3;\n  return x + y + z;\n}\n\
+             int g(int c) {\n  int x;\n  switch (c) { case 1: x = 1; break; default: x = 2; }\n  int a[2] = {1};\n  return x + a[1];\n}\n\
+             int main() { return f(1) + g(1); }",
+            &[],
+        );
+        program_test_func(
+            "int main() { int x; int y; x = 3; if (x) y = 4; else y = 5; return x + y; }",
+            7,
+        );
+    }
+
+    #[test]
+    fn test_maybe_uninitialized() {
+        warning_test_func(
+            &["-Wall"],
+            "int f(int c) {\n  int x;\n  if (c) x = 1;\n  return x;\n}\n\
+             int g(int c) {\n  int y;\n  switch (c) { case 1: y = 1; break; }\n  return y;\n}\n\
+             int h() {\n  int i; int s;\n  for (i = 0; i < 3; i = i + 1) {\n    if (i) s = s + i;\n    s = 0;\n  }\n  return 0;\n}\n\
+             int main() { return 0; }",
+            &[
+                "warning: 4:10: variable x may be uninitialized when used here, declared at 2:7 [-Wmaybe-uninitialized]",
+                "9:10: variable y may be uninitialized when used here, declared at 7:7",
+                "14:16: variable s may be uninitialized when used here, declared at 12:14",
+            ],
+        );
+        // the paths through a goto and the operands of `&&`
+        warning_test_func(
+            &["-Wmaybe-uninitialized"],
+            "int f(int c) {\n  int x;\n  if (c) goto set;\n  goto use;\nset:\n  x = 1;\nuse:\n  return x;\n}\n\
+             int g(int c) {\n  int y;\n  if (c && (y = 1)) return y;\n  return y;\n}\n\
+             int main() { return 0; }",
+            &[
+                "8:10: variable x may be uninitialized when used here, declared at 2:7",
+                "13:10: variable y may be uninitialized when used here, declared at 11:7",
+            ],
+        );
+        warning_test_func(
+            &["-Wall", "-Wno-maybe-uninitialized"],
+            "int main(int c) {\n  int x;\n  while (1) { if (c) { x = 1; break; } }\n  int y;\n  if (c) y = 1;\n  return x + y;\n}",
+            &[],
+        );
+        warning_test_func(
+            &["-Wall"],
+            "int main(int c) {\n  int x;\n#pragma GCC diagnostic ignored \"-Wmaybe-uninitialized\"\n  if (c) x = 1;\n  return x;\n}",
+            &[],
+        );
+    }
+}
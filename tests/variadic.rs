@@ -0,0 +1,104 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{gcc_test_func, program_test_func};
+
+    #[test]
+    fn test_variadic_definitions() {
+        program_test_func(
+            "int sum(int n, ...) { va_list ap; va_start(ap, n); int s=0; int i; \
+             for (i=0; i<n; i=i+1) s=s+va_arg(ap, int); va_end(ap); return s; } \
+             int main() { return sum(3, 1, 2, 3); }",
+            6,
+        );
+        program_test_func(
+            "int sum(int n, ...) { va_list ap; va_start(ap, n); int s=0; int i; \
+             for (i=0; i<n; i=i+1) s=s+va_arg(ap, int); va_end(ap); return s; } \
+             int main() { return sum(0)+sum(5, 1, 2, 3, 4, 5); }",
+            15,
+        );
+        program_test_func(
+            "long pick(int a, int b, ...) { va_list ap; va_start(ap, b); va_arg(ap, long); \
+             long x=va_arg(ap, long); va_end(ap); return x+a+b; } \
+             int main() { return pick(1, 2, 10L, 20L); }",
+            23,
+        );
+        program_test_func(
+            "char second(char *fmt, ...) { __builtin_va_list ap; __builtin_va_start(ap, fmt); \
+             __builtin_va_arg(ap, char *); char *s=__builtin_va_arg(ap, char *); \
+             __builtin_va_end(ap); return s[1]; } \
+             int main() { return second(\"\", \"ab\", \"cd\"); }",
+            100,
+        );
+        program_test_func(
+            "int f(int n, ...) { va_list ap; va_start(ap, n); int x=va_arg(ap, int); \
+             va_start(ap, n); int y=va_arg(ap, int); va_end(ap); return x*10+y; } \
+             int main() { return f(1, 4); }",
+            44,
+        );
+    }
+
+    #[test]
+    fn test_va_list_passing_and_copy() {
+        program_test_func(
+            "int vsum(int n, va_list ap) { int s=0; while (n) { s=s+va_arg(ap, int); n=n-1; } return s; } \
+             int sum(int n, ...) { va_list ap; va_start(ap, n); int s=vsum(n, ap); va_end(ap); return s; } \
+             int main() { return sum(4, 10, 20, 30, 40); }",
+            100,
+        );
+        program_test_func(
+            "int f(int n, ...) { va_list ap; va_list aq; va_start(ap, n); va_arg(ap, int); \
+             va_copy(aq, ap); int x=va_arg(ap, int); int y=va_arg(aq, int); \
+             va_end(aq); va_end(ap); return x*10+y; } \
+             int main() { return f(0, 1, 2); }",
+            22,
+        );
+        program_test_func(
+            "int main() { va_list ap; return sizeof(ap)+sizeof(ap[0]); }",
+            48,
+        );
+    }
+
+    #[test]
+    fn test_variadic_interop_with_libc() {
+        gcc_test_func(
+            "int vsprintf(char *buf, char *fmt, va_list ap); int strcmp(char *a, char *b); \
+             int format(char *buf, char *fmt, ...) { va_list ap; va_start(ap, fmt); \
+             int n=vsprintf(buf, fmt, ap); va_end(ap); return n; } \
+             int main() { char buf[64]; int n=format(buf, \"%d-%s-%ld-%c\", 42, \"ab\", -7L, 'z'); \
+             return n == 10 && strcmp(buf, \"42-ab--7-z\") == 0; }",
+            "",
+            1,
+        );
+        gcc_test_func(
+            "int vprintf(char *fmt, va_list ap); int printf(char *fmt, ...); \
+             int log(char *fmt, ...) { va_list ap; va_start(ap, fmt); printf(\"[log] \"); \
+             int n=vprintf(fmt, ap); va_end(ap); return n; } \
+             int main() { return log(\"%d %s\\n\", 7, \"done\"); }",
+            "",
+            7,
+        );
+    }
+
+    #[test]
+    fn test_variadic_called_by_gcc() {
+        gcc_test_func(
+            "int sum(int n, ...) { va_list ap; va_start(ap, n); long s=0; int i; \
+             for (i=0; i<n; i=i+1) s=s+va_arg(ap, long); va_end(ap); return s; } \
+             int call(); int main() { return call(); }",
+            "int sum(int n, ...);\n\
+             int call(void) { return sum(9, 1L, 2L, 3L, 4L, 5L, 6L, 7L, 8L, 9L); }",
+            45,
+        );
+        gcc_test_func(
+            "int count(char *tag, ...) { va_list ap; va_start(ap, tag); int n=0; \
+             while (va_arg(ap, char *)) n=n+1; va_end(ap); return n*10+tag[0]-'a'; } \
+             int call(); int main() { return call(); }",
+            "int count(char *tag, ...);\n\
+             int call(void) { return count(\"c\", \"1\", \"2\", \"3\", \"4\", \"5\", \"6\", \"7\", (char *)0); }",
+            72,
+        );
+    }
+}
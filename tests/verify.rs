@@ -0,0 +1,111 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{compile_ir, compile_ir_error, emit_ir, program_test_func};
+
+    /// The maximum of the two parameters, with a phi.
+    const MAX: &str = "function @max(%0: i32, %1: i32) -> i32 {\n\
+                       bb0:\n\
+                       \x20 %2: i32 = slt i32 %0, %1\n\
+                       \x20 branch i32 %2, bb1, bb2\n\
+                       bb1:\n\
+                       \x20 jump bb2\n\
+                       bb2:\n\
+                       \x20 %3: i32 = phi i32 bb0: %0, bb1: %1\n\
+                       \x20 ret %3\n\
+                       }\n";
+
+    #[test]
+    fn test_verify_valid() {
+        let printed = compile_ir(&["--verify-each", "--emit=ir"], MAX);
+        assert!(
+            printed.contains("\n  %3: i32 = phi i32 bb0: %0, bb1: %1\n"),
+            "{}",
+            printed
+        );
+        // the IR the compiler makes holds together
+        let program =
+            "int f(int n) { int s = 0; while (n > 0) { if (n % 2) s = s + n; else goto next; \
+                       next: n = n - 1; } return s; } int main() { return f(5); }";
+        let text = emit_ir(&["--verify-each"], program);
+        assert_eq!(compile_ir(&["--verify-each", "--emit=ir"], &text), text);
+        program_test_func(program, 9);
+    }
+
+    #[test]
+    fn test_verify_errors() {
+        for (text, expected) in [
+            (
+                "function @f() -> i32 {\nbb0:\n  %0: i32 = add i64 1, 2\n  ret %0\n}",
+                "@f, bb0: %0 has type i32, not i64 in '%0: i32 = add i64 1, 2'",
+            ),
+            (
+                "function @f(%0: i64) -> i32 {\nbb0:\n  %1: i32 = load i32 %0\n  ret %1\n}",
+                "operand %0 has type i64, not ptr in '%1: i32 = load i32 %0'",
+            ),
+            (
+                "function @f() -> i32 {\nbb0:\n  %0: i32 = trunc i32 1\n  ret %0\n}",
+                "trunc can't convert i32 to i32 in '%0: i32 = trunc i32 1'",
+            ),
+            (
+                "function @f() -> i32 {\nbb0:\n  jump bb1\nbb1:\n  ret %0\nbb2:\n  %0: i32 = add i32 1, 2\n  jump bb1\n}",
+                "@f, bb1: %0 is not assigned on every path to its use in 'ret %0'",
+            ),
+            (
+                "function @f() -> i32 {\nbb0:\n  %0: i32 = add i32 %1, 2\n  %1: i32 = add i32 1, 2\n  ret %0\n}",
+                "%1 is not assigned on every path to its use in '%0: i32 = add i32 %1, 2'",
+            ),
+            (
+                "function @f() -> i64 {\nbb0:\n  %1: i64 = add i64 1, 2\n  ret %0\n}",
+                "%0 is never assigned in 'ret %0'",
+            ),
+            (
+                "function @f() -> i32 {\nbb0:\n  jump bb3\n}",
+                "@f, bb0: bb3 is not a block of the function in 'jump bb3'",
+            ),
+            (
+                "function @f() -> i32 {\nbb0:\n  ret %0\n}",
+                "%0 is not a register of the function in 'ret %0'",
+            ),
+            (
+                "function @f(%0: i32) -> i32 {\nbb0:\n  jump bb1\nbb1:\n  %1: i32 = phi i32 bb0: %0, bb0: 1\n  ret %1\n}",
+                "the phi doesn't take one operand from each of the predecessors [bb0]",
+            ),
+            (
+                "function @f(%0: i32) -> i32 {\nbb0:\n  jump bb1\nbb1:\n  %1: i32 = add i32 %0, 1\n  %2: i32 = phi i32 bb0: %1\n  ret %2\n}",
+                "a phi comes after the other instructions of its block in '%2: i32 = phi i32 bb0: %1'",
+            ),
+            (
+                "function @f(%0: i32) -> i32 {\nbb0:\n  %1: i32 = add i32 %0, 1\n  %1: i32 = add i32 %0, 2\n  jump bb1\nbb1:\n  %2: i32 = phi i32 bb0: %1\n  ret %2\n}",
+                "%1 is assigned more than once in SSA form",
+            ),
+            // before out-of-ssa, even without phis
+            (
+                "function @f() -> i32 {\nbb0:\n  %0: i32 = add i32 1, 2\n  %0: i32 = add i32 3, 4\n  ret %0\n}",
+                "@f, bb0: %0 is assigned more than once in SSA form",
+            ),
+            (
+                "function @f() -> i32 {\nbb0:\n  jump bb1\nbb1:\n  jump bb0\n}",
+                "@f, bb1: the entry block bb0 can't be branched to in 'jump bb0'",
+            ),
+            (
+                "function @f() -> void {\nbb0:\n  ret 1\n}",
+                "the function returns void in 'ret 1'",
+            ),
+            (
+                "function @f() -> i64 {\nbb0:\n  ret\n}",
+                "the function returns i64 in 'ret'",
+            ),
+            (
+                "function @f(%0: ptr) -> void {\nbb0:\n  vastart %0\n  ret\n}",
+                "va_start in a function which is not variadic in 'vastart %0'",
+            ),
+        ] {
+            let message = compile_ir_error(&["--verify-each"], text);
+            assert!(message.contains("verify: after irparse: "), "{:?}", message);
+            assert!(message.contains(expected), "{:?} doesn't contain {:?}", message, expected);
+        }
+    }
+}
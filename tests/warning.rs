@@ -0,0 +1,170 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::common::{compile_error_with, program_test_func, warning_test_func};
+
+    #[test]
+    fn test_unused_variables() {
+        let program =
+            "int f(int a, int b) {\n  int x;\n  int y = 1;\n  int z;\n  z = a;\n  return z;\n}";
+        warning_test_func(&[], program, &[]);
+        warning_test_func(
+            &["-Wall"],
+            program,
+            &[
+                "warning: 2:7: unused variable x [-Wunused-variable]",
+                "warning: 3:7: unused variable y [-Wunused-variable]",
+            ],
+        );
+        warning_test_func(
+            &["-Wextra"],
+            program,
+            &["warning: 1:18: unused parameter b [-Wunused-parameter]"],
+        );
+        warning_test_func(
+            &["-Wall", "-Wextra", "-Wno-unused-variable"],
+            program,
+            &["1:18: unused parameter b"],
+        );
+        warning_test_func(
+            &["-Wunused-variable"],
+            "int main() { int a[2]; int n = sizeof(a); static int s; return n; }",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_sign_compare() {
+        warning_test_func(
+            &["-Wsign-compare"],
+            "int main() { int i = -1; unsigned u = 1; char c = 0; \
+             return (i < u) + (u == c) + (u > 0) + (c < 1) + (i < 2u) + (u != -1); }",
+            &[
+                "1:64: comparison of integers of different signs: int and unsigned int [-Wsign-compare]",
+                "1:74: comparison of integers of different signs: unsigned int and char",
+                "1:105: comparison of integers of different signs: int and unsigned int",
+                "1:116: comparison of integers of different signs: unsigned int and int",
+            ],
+        );
+        warning_test_func(
+            &["-Wall"],
+            "int main() { int i = -1; unsigned u = 1; return i < u; }",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_shadow() {
+        warning_test_func(
+            &["-Wshadow"],
+            "int g;\nint f(int p) {\n  int g = p;\n  {\n    int p = 2;\n    int g = p;\n    static int q;\n    {\n      int q = g;\n      return q;\n    }\n  }\n}",
+            &[
+                "warning: 3:7: declaration of g shadows a global declaration, previous declaration at 1:5 [-Wshadow]",
+                "warning: 5:9: declaration of p shadows a parameter, previous declaration at 2:11",
+                "warning: 6:9: declaration of g shadows a previous local, previous declaration at 3:7",
+                "warning: 9:11: declaration of q shadows a previous local, previous declaration at 7:16",
+            ],
+        );
+        warning_test_func(
+            &["-Wall", "-Wextra"],
+            "int x; int main() { int x = 1; return x; }",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_parentheses() {
+        warning_test_func(
+            &["-Wall"],
+            "int main() { int a = 1; int b = 2; int c = 3; \
+             if (a = b) c = 0; while ((a = 0)) c = 1; \
+             c = a && b || c; c = (a && b) || c; c = a || (b && c); \
+             c = a & b == c; c = a ^ (b == c); c = a << b + c; c = a >> (b - c) + 1; \
+             return c; }",
+            &[
+                "1:53: suggest parentheses around assignment used as truth value [-Wparentheses]",
+                "1:94: suggest parentheses around '&&' within '||'",
+                "1:153: suggest parentheses around comparison in operand of '&'",
+                "1:188: suggest parentheses around '+' inside '<<'",
+                "1:210: suggest parentheses around '+' inside '>>'",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_implicit_fallthrough() {
+        warning_test_func(
+            &["-Wextra"],
+            "int main() {\n  int x = 1;\n  int y = 0;\n  switch (x) {\n  case 0:\n  case 1:\n    y = 1;\n  case 2:\n    if (x) break; else return 2;\n  case 3: {\n    y = 3;\n  }\n  default:\n    y = 0;\n  }\n  return y;\n}",
+            &[
+                "warning: 7:5: this statement may fall through [-Wimplicit-fallthrough]",
+                "warning: 11:5: this statement may fall through",
+            ],
+        );
+        // the warned program still compiles as it did
+        program_test_func(
+            "int main() { int y = 0; switch (2) { case 1: y = 1; case 2: y = y + 2; case 3: y = y + 3; } return y; }",
+            5,
+        );
+    }
+
+    #[test]
+    fn test_errors_and_pragmas() {
+        let message = compile_error_with(
+            &["-Wall", "-Werror"],
+            "int main() {\n  int x;\n  return 0;\n}",
+        );
+        assert!(
+            message.contains("error: 2:7: unused variable x [-Werror=unused-variable]"),
+            "{}",
+            message
+        );
+        let message = compile_error_with(
+            &["-Wshadow", "-Werror=shadow"],
+            "int x; int main() { int x = 0; return x; }",
+        );
+        assert!(message.contains("error: 1:25: declaration of x shadows a global declaration"));
+        warning_test_func(
+            &["-Wall", "-Werror", "-Wno-error=unused-variable"],
+            "int main() { int x; return 0; }",
+            &["1:18: unused variable x [-Wunused-variable]"],
+        );
+        warning_test_func(
+            &["-Wshadow"],
+            "int x;\n#pragma GCC diagnostic push\n#pragma GCC diagnostic ignored \"-Wshadow\"\n\
+             int f() { int x = 1; return x; }\n#pragma GCC diagnostic pop\n\
+             int g() { int x = 2; return x; }\n#pragma once\nint main() { return f() + g(); }",
+            &["6:15: declaration of x shadows a global declaration"],
+        );
+        // the level in effect at the comparison holds after parsing
+        warning_test_func(
+            &[],
+            "#pragma GCC diagnostic warning \"-Wsign-compare\"\n\
+             int f(int i, unsigned u) { return i < u; }\n\
+             #pragma GCC diagnostic ignored \"-Wsign-compare\"\n\
+             int g(int i, unsigned u) { return i < u; }",
+            &["2:37: comparison of integers of different signs"],
+        );
+        let message = compile_error_with(
+            &[],
+            "#pragma GCC diagnostic error \"-Wparentheses\"\nint main() { int a = 0; if (a = 1) return 1; return 0; }",
+        );
+        assert!(message.contains(
+            "error: 2:31: suggest parentheses around assignment used as truth value [-Werror=parentheses]"
+        ));
+        let message = compile_error_with(
+            &[],
+            "#pragma GCC diagnostic ignored \"-Wbogus\"\nint main() { return 0; }",
+        );
+        assert!(
+            message.contains("error: 1:1: unknown warning option \"-Wbogus\" in diagnostic pragma")
+        );
+        let message = compile_error_with(&[], "#include <stdio.h>\nint main() { return 0; }");
+        assert!(message.contains("error: 1:1: unsupported preprocessing directive #include"));
+        // errors are rendered like warnings, without a flag
+        let message = compile_error_with(&[], "int main() {\n  return y;\n}");
+        assert_eq!(message, "error: 2:10: use of undeclared identifier y");
+    }
+}